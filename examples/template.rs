@@ -1,36 +1,63 @@
+use std::fmt::Write;
 use std::fs::File;
 use std::io::BufReader;
 use std::path::PathBuf;
 
+use anyhow::Context;
 use clap::Parser;
-use log::debug;
+use tracing::{debug, info_span};
 
+use adventofcode2021::cache::CacheArgs;
+use adventofcode2021::config;
 use adventofcode2021::parse;
+use adventofcode2021::trace::TraceArgs;
 
 ////////////////////////////////////////////////////////////////////////////////
-/// Main
+// Main
 
 #[derive(Parser, Debug)]
 #[clap(author, version, about, long_about = None)]
 struct Args {
-    #[clap(short, long, value_parser, default_value = "inputs/template.txt")]
-    input: PathBuf,
+    /// Input file [default: template.txt in the configured input directory]
+    #[clap(short, long, value_parser)]
+    input: Option<PathBuf>,
+
+    #[clap(flatten)]
+    trace: TraceArgs,
+
+    #[clap(flatten)]
+    cache: CacheArgs,
 }
 
-fn main() {
-    env_logger::init();
+fn main() -> anyhow::Result<()> {
     let args = Args::parse();
+    let _reports = args.trace.init();
+    let input = config::input_path(args.input, env!("CARGO_CRATE_NAME"))?;
+
+    debug!("Using input {}", input.display());
+    let Some(mut answers) = args.cache.open("template", &input)? else {
+        return Ok(());
+    };
+    let nums: Vec<i64> = {
+        let _span = info_span!("parse").entered();
+        let file = File::open(&input).with_context(|| format!("opening {}", input.display()))?;
+        let buf = BufReader::new(file);
+        parse::buffer(buf).with_context(|| format!("parsing {}", input.display()))?
+    };
+
+    writeln!(
+        answers,
+        "Found {length} lines: {nums:?}",
+        length = nums.len()
+    )?;
 
-    debug!("Using input {}", args.input.display());
-    let file = File::open(args.input).unwrap();
-    let buf = BufReader::new(file);
-    let nums: Vec<i64> = parse::buffer(buf).unwrap();
+    answers.save();
 
-    println!("Found {length} lines: {nums:?}", length = nums.len());
+    Ok(())
 }
 
 ////////////////////////////////////////////////////////////////////////////////
-/// Tests
+// Tests
 
 #[cfg(test)]
 mod tests {
@@ -0,0 +1,299 @@
+#[cfg(feature = "serve")]
+use std::net::IpAddr;
+use std::path::PathBuf;
+use std::time::{Duration, Instant, SystemTime};
+
+use anyhow::{bail, Context};
+use clap::builder::{PossibleValuesParser, TypedValueParser};
+use clap::{CommandFactory, Parser, Subcommand, ValueHint};
+use clap_complete::Shell;
+use tracing::{info, info_span, warn};
+
+use adventofcode2021::cancel::{self, TimedOut};
+use adventofcode2021::config::Config;
+use adventofcode2021::gen;
+use adventofcode2021::solution::{Expected, Solution, Solved, SOLUTIONS};
+use adventofcode2021::table::Table;
+use adventofcode2021::trace::{format_duration, TraceArgs};
+
+////////////////////////////////////////////////////////////////////////////////
+// Main
+
+/// Runs the Advent of Code 2021 solutions
+#[derive(Parser, Debug)]
+#[clap(author, version, about, long_about = None)]
+struct Args {
+    #[clap(flatten)]
+    trace: TraceArgs,
+
+    /// Give up on any day that runs longer than this, reporting how far it got
+    #[clap(long, value_parser, value_name = "SECS")]
+    timeout: Option<f64>,
+
+    #[clap(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// List every day with its title, parts, expected runtime, and whether
+    /// its default input exists
+    List,
+    /// Run every day against its default input and print a summary table
+    All,
+    /// Run every day and compare the results against known answers
+    Check {
+        /// TOML file of expected answers, with a [dayNN] section per day
+        #[clap(long, value_parser, default_value = "answers.toml", value_hint = ValueHint::FilePath)]
+        answers: PathBuf,
+    },
+    /// Generate a random but valid input for a day
+    Gen {
+        /// Day to generate an input for
+        #[clap(value_parser = day_parser(), hide_possible_values = true)]
+        day: u8,
+
+        /// How big an input to make; what this means depends on the day
+        #[clap(long, value_parser)]
+        size: Option<usize>,
+
+        /// Random seed [default: based on the current time]
+        #[clap(long, value_parser)]
+        seed: Option<u64>,
+
+        /// File to write the input to [default: standard output]
+        #[clap(short, long, value_parser, value_hint = ValueHint::FilePath)]
+        output: Option<PathBuf>,
+    },
+    /// Answer puzzle inputs over HTTP: `POST /solve/<day>` returns JSON answers
+    #[cfg(feature = "serve")]
+    Serve {
+        /// Port to listen on
+        #[clap(long, value_parser, default_value_t = 8080)]
+        port: u16,
+
+        /// Address to listen on; use 0.0.0.0 to accept outside connections
+        #[clap(long, value_parser, default_value = "127.0.0.1")]
+        bind: IpAddr,
+    },
+    /// Print a shell completion script, e.g. `aoc completions bash > /etc/bash_completion.d/aoc`
+    Completions {
+        /// Shell to generate completions for
+        #[clap(value_enum)]
+        shell: Shell,
+    },
+}
+
+/// Accepts a day from 1 to 25, listing each one for shell completion
+fn day_parser() -> impl TypedValueParser<Value = u8> {
+    const DAYS: [&str; 25] = [
+        "1", "2", "3", "4", "5", "6", "7", "8", "9", "10", "11", "12", "13", "14", "15", "16",
+        "17", "18", "19", "20", "21", "22", "23", "24", "25",
+    ];
+    PossibleValuesParser::new(DAYS).map(|day| day.parse::<u8>().unwrap())
+}
+
+fn main() -> anyhow::Result<()> {
+    let args = Args::parse();
+    let _reports = args.trace.init();
+    let timeout = args.timeout.map(Duration::try_from_secs_f64).transpose()?;
+
+    match args.command {
+        Command::List => list(),
+        Command::All => run_all(timeout),
+        Command::Check { answers } => check(Expected::from_file(&answers)?, timeout),
+        Command::Gen {
+            day,
+            size,
+            seed,
+            output,
+        } => generate(day, size, seed, output),
+        #[cfg(feature = "serve")]
+        Command::Serve { port, bind } => adventofcode2021::serve::serve(bind, port),
+        Command::Completions { shell } => {
+            clap_complete::generate(shell, &mut Args::command(), "aoc", &mut std::io::stdout());
+            Ok(())
+        }
+    }
+}
+
+/// The result of running one day against its default input
+enum Outcome {
+    NoInput,
+    Solved(Solved, String),
+    Failed(anyhow::Error, String),
+    TimedOut(anyhow::Error, String),
+}
+
+/// Runs a solution against its default input, on a worker thread if there is
+/// a `timeout`. Errors are returned as an `Outcome`; a panic is not caught.
+fn run(config: &Config, solution: &Solution, timeout: Option<Duration>) -> Outcome {
+    let name = solution.name();
+    let path = config.default_input(&name);
+    let input = match std::fs::read_to_string(&path) {
+        Ok(input) => input,
+        Err(e) => {
+            warn!("Skipping {name}: could not read {}: {e}", path.display());
+            return Outcome::NoInput;
+        }
+    };
+
+    let span = info_span!("solve", day = solution.day);
+    let solve = solution.solve;
+    let start = Instant::now();
+    let result = match timeout {
+        None => span.in_scope(|| solve(&input)),
+        Some(limit) => cancel::with_timeout(limit, move || span.in_scope(|| solve(&input))),
+    };
+    let elapsed = format_duration(start.elapsed());
+    info!("Solved {name} in {elapsed}");
+
+    match result {
+        Ok(solved) => Outcome::Solved(solved, elapsed),
+        Err(e) if e.is::<TimedOut>() => Outcome::TimedOut(e, elapsed),
+        Err(e) => Outcome::Failed(e, elapsed),
+    }
+}
+
+fn list() -> anyhow::Result<()> {
+    let config = Config::load()?;
+    let mut table = Table::new(["day", "title", "parts", "runtime", "input"]);
+    for solution in SOLUTIONS {
+        let parts = if solution.part2 { "1, 2" } else { "1" };
+        let input = if config.default_input(&solution.name()).is_file() {
+            "yes"
+        } else {
+            "missing"
+        };
+        table.push([
+            solution.name(),
+            solution.title.to_owned(),
+            parts.to_owned(),
+            solution.runtime.to_string(),
+            input.to_owned(),
+        ]);
+    }
+
+    print!("{table}");
+    Ok(())
+}
+
+/// Runs each registered day in turn. Errors are reported in the table and
+/// the remaining days still run; a panic stops everything.
+fn run_all(timeout: Option<Duration>) -> anyhow::Result<()> {
+    let config = Config::load()?;
+    let mut table = Table::new(["day", "part1", "part2", "time"]);
+    let mut failures = Vec::new();
+
+    for solution in SOLUTIONS {
+        let name = solution.name();
+        match run(&config, solution, timeout) {
+            Outcome::NoInput => table.push([name.as_str(), "(no input)"]),
+            Outcome::Solved(solved, elapsed) => {
+                let part2 = solved.part2.unwrap_or_default();
+                table.push([name, solved.part1, part2, elapsed]);
+            }
+            Outcome::Failed(e, elapsed) => {
+                table.push([name.clone(), "(error)".to_owned(), String::new(), elapsed]);
+                failures.push(format!("{name}: {e:#}"));
+            }
+            Outcome::TimedOut(e, elapsed) => {
+                table.push([
+                    name.clone(),
+                    "(timed out)".to_owned(),
+                    String::new(),
+                    elapsed,
+                ]);
+                failures.push(format!("{name}: {e:#}"));
+            }
+        }
+    }
+
+    print!("{table}");
+    report_failures(&failures)
+}
+
+/// Runs each day that has an input and compares against `expected`, printing
+/// pass/fail per day.
+fn check(expected: Expected, timeout: Option<Duration>) -> anyhow::Result<()> {
+    let config = Config::load()?;
+    let mut table = Table::new(["day", "result", "time"]);
+    let mut failures = Vec::new();
+
+    for solution in SOLUTIONS {
+        let name = solution.name();
+        match run(&config, solution, timeout) {
+            Outcome::NoInput => table.push([name.as_str(), "skipped (no input)"]),
+            Outcome::Solved(solved, elapsed) => {
+                let result = match expected.check(solution.day, &solved) {
+                    None => "unchecked (no answers)",
+                    Some(true) => "pass",
+                    Some(false) => {
+                        let want = expected.get(solution.day).cloned().unwrap_or_default();
+                        failures.push(format!(
+                            "{name}: got {}, expected {}",
+                            describe(&solved),
+                            describe(&want)
+                        ));
+                        "FAIL"
+                    }
+                };
+                table.push([name, result.to_owned(), elapsed]);
+            }
+            Outcome::Failed(e, elapsed) => {
+                table.push([name.clone(), "FAIL (error)".to_owned(), elapsed]);
+                failures.push(format!("{name}: {e:#}"));
+            }
+            Outcome::TimedOut(e, elapsed) => {
+                table.push([name.clone(), "FAIL (timed out)".to_owned(), elapsed]);
+                failures.push(format!("{name}: {e:#}"));
+            }
+        }
+    }
+
+    print!("{table}");
+    report_failures(&failures)
+}
+
+fn generate(
+    day: u8,
+    size: Option<usize>,
+    seed: Option<u64>,
+    output: Option<PathBuf>,
+) -> anyhow::Result<()> {
+    let seed = match seed {
+        Some(seed) => seed,
+        None => SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)?
+            .as_nanos() as u64,
+    };
+    let input = gen::generate(day, size, seed)?;
+    eprintln!("Generated day {day} input with seed {seed}");
+
+    match output {
+        Some(path) => {
+            std::fs::write(&path, input).with_context(|| format!("writing {}", path.display()))?
+        }
+        None => print!("{input}"),
+    }
+    Ok(())
+}
+
+fn describe(solved: &Solved) -> String {
+    match &solved.part2 {
+        Some(part2) => format!("{} / {}", solved.part1, part2),
+        None => solved.part1.clone(),
+    }
+}
+
+fn report_failures(failures: &[String]) -> anyhow::Result<()> {
+    if failures.is_empty() {
+        return Ok(());
+    }
+
+    println!();
+    for failure in failures {
+        println!("{failure}");
+    }
+    bail!("{} of {} days failed", failures.len(), SOLUTIONS.len());
+}
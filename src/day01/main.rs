@@ -1,93 +1,184 @@
-use adventofcode2021::parse;
-use clap::Parser;
-use log::debug;
+use std::fmt::Write;
 use std::fs::File;
-use std::io::BufReader;
-use std::path::PathBuf;
-
-pub fn find_increases(depths: &[i64]) -> isize {
-    let mut count = 0;
-    let mut prev = depths.first().copied().unwrap_or_default();
-
-    for &n in &depths[1..] {
-        if prev < n {
-            count += 1;
-        }
-        prev = n;
-    }
-
-    count
-}
-
-pub fn find_window_increases(depths: &[i64], window_size: usize) -> isize {
-    let mut count = 0;
-    for w in depths.windows(window_size + 1) {
-        let n1 = w[0];
-        let n2 = w[w.len() - 1];
-        if n2 > n1 {
-            count += 1;
-        }
-    }
+use std::io::{BufReader, IsTerminal};
+use std::path::{Path, PathBuf};
 
-    count
-}
+use anyhow::Context;
+use clap::builder::RangedU64ValueParser;
+use clap::Parser;
+use itertools::process_results;
+use tracing::{debug, info_span};
+
+use adventofcode2021::cache::CacheArgs;
+use adventofcode2021::config;
+use adventofcode2021::day01::{
+    count_increases_from, increase_positions, read_depths, smoothed_increases, window_increases,
+    Aggregate, ExactDepth, Increases, Smoothing, Sparkline, TrendReport,
+};
+use adventofcode2021::solution;
+use adventofcode2021::trace::TraceArgs;
 
 ////////////////////////////////////////////////////////////////////////////////
-/// Main
+// Main
 
 #[derive(Parser, Debug)]
 #[clap(author, version, about, long_about = None)]
 struct Args {
-    #[clap(short, long, value_parser, default_value = "inputs/day01.txt")]
-    input: PathBuf,
+    /// Input file or directory; repeat to compare several inputs in a table
+    /// [default: day01.txt in the configured input directory]
+    #[clap(short, long, value_parser)]
+    input: Vec<PathBuf>,
+
+    /// Instead of the puzzle answers, count increases between windows of this
+    /// many readings; repeat for several sizes
+    #[clap(long, value_name = "N", value_parser = RangedU64ValueParser::<usize>::new().range(1..))]
+    window: Vec<usize>,
+
+    /// How to combine the readings in each window
+    #[clap(long, value_enum, default_value_t)]
+    aggregate: Aggregate,
+
+    /// Instead of the puzzle answers, count increases after smoothing the
+    /// readings with a moving average (moving:N) or exponentially (exp:ALPHA)
+    #[clap(long, value_parser, value_name = "FILTER", conflicts_with = "window")]
+    smooth: Option<Smoothing>,
+
+    /// Instead of the puzzle answers, report increases, decreases, plateaus,
+    /// the longest run up or down, and the depth range
+    #[clap(long, conflicts_with_all = ["window", "smooth"])]
+    trend: bool,
+
+    /// Print the trend report as JSON
+    #[clap(long, requires = "trend")]
+    json: bool,
+
+    /// Also draw the depths as a sparkline, with increases highlighted
+    #[clap(short, long)]
+    verbose: bool,
+
+    #[clap(flatten)]
+    trace: TraceArgs,
+
+    #[clap(flatten)]
+    cache: CacheArgs,
 }
 
-fn main() {
-    env_logger::init();
+fn main() -> anyhow::Result<()> {
     let args = Args::parse();
+    let _reports = args.trace.init();
+    let mut inputs = config::input_paths(args.input, env!("CARGO_CRATE_NAME"))?;
+    if inputs.len() > 1 {
+        return solution::compare(env!("CARGO_CRATE_NAME"), &inputs);
+    }
+    let input = inputs.remove(0);
 
-    debug!("Using input {}", args.input.display());
-    let file = File::open(args.input).unwrap();
-    let buf = BufReader::new(file);
-    let ns: Vec<i64> = parse::buffer(buf).unwrap();
+    debug!("Using input {}", input.display());
+    if !args.window.is_empty() {
+        return print_windows(&input, &args.window, args.aggregate);
+    }
+    if let Some(smoothing) = args.smooth {
+        return print_smoothed(&input, smoothing);
+    }
+    if args.trend {
+        return print_trend(&input, args.json);
+    }
 
-    let count = find_increases(&ns);
-    let count3 = find_window_increases(&ns, 3);
+    if args.verbose {
+        print_sparkline(&input)?;
+    }
 
-    println!("Found {count} increases, and {count3} sliding increases");
+    let Some(mut answers) = args.cache.open("day01", &input)? else {
+        return Ok(());
+    };
+    // Depths are counted as they are read, so any size of input fits in memory,
+    // unless they are timestamped and need sorting. Whole depths stay exact,
+    // and only fractional ones are read as floats.
+    let Increases { single, windowed } = {
+        let _span = info_span!("count").entered();
+        let file = File::open(&input).with_context(|| format!("opening {}", input.display()))?;
+        let buf = BufReader::new(file);
+        count_increases_from::<ExactDepth, _>(buf, 3)
+            .with_context(|| format!("parsing {}", input.display()))?
+    };
+
+    writeln!(
+        answers,
+        "Found {single} increases, and {windowed} sliding increases"
+    )?;
+
+    answers.save();
+
+    Ok(())
 }
 
-////////////////////////////////////////////////////////////////////////////////
-/// Tests
-
-#[cfg(test)]
-mod tests {
-    use test_log::test;
-
-    #[allow(unused_imports)]
-    use super::*;
-
-    const EXAMPLE: &str = r###"
-        199
-        200
-        208
-        210
-        200
-        207
-        240
-        269
-        260
-        263    
-    "###;
-
-    #[test]
-    fn test_thing() {
-        let ns: Vec<i64> = parse::buffer(EXAMPLE.as_bytes()).unwrap();
-
-        let count = find_increases(&ns);
-        assert_eq!(count, 7);
-
-        let count3 = find_window_increases(&ns, 3);
-        assert_eq!(count3, 5);
+/// Prints the increases for each window size, without touching the answer
+/// cache
+fn print_windows(input: &Path, window_sizes: &[usize], aggregate: Aggregate) -> anyhow::Result<()> {
+    let counts = {
+        let _span = info_span!("count").entered();
+        let file = File::open(input).with_context(|| format!("opening {}", input.display()))?;
+        let depths = read_depths::<ExactDepth, _>(BufReader::new(file));
+        process_results(depths, |depths| {
+            window_increases(depths, window_sizes, aggregate)
+        })
+        .with_context(|| format!("parsing {}", input.display()))?
+    };
+
+    for (size, count) in window_sizes.iter().zip(counts) {
+        println!("Windows of {size} ({aggregate}): {count} increases");
+    }
+    Ok(())
+}
+
+/// Prints the depths as a sparkline, coloring increases on a terminal
+fn print_sparkline(input: &Path) -> anyhow::Result<()> {
+    let file = File::open(input).with_context(|| format!("opening {}", input.display()))?;
+    let depths: Vec<ExactDepth> = read_depths(BufReader::new(file))
+        .collect::<anyhow::Result<_>>()
+        .with_context(|| format!("parsing {}", input.display()))?;
+
+    let increases = increase_positions(&depths);
+    let sparkline = Sparkline {
+        color: std::io::stdout().is_terminal(),
+        ..Sparkline::new(&depths)
+    };
+    println!("{sparkline}");
+    println!(
+        "{} readings, {} deeper than the one before",
+        depths.len(),
+        increases.len()
+    );
+    Ok(())
+}
+
+/// Prints the trend report, as text or JSON, without touching the answer cache
+fn print_trend(input: &Path, json: bool) -> anyhow::Result<()> {
+    let report: TrendReport<ExactDepth> = {
+        let _span = info_span!("trend").entered();
+        let file = File::open(input).with_context(|| format!("opening {}", input.display()))?;
+        read_depths(BufReader::new(file))
+            .collect::<anyhow::Result<_>>()
+            .with_context(|| format!("parsing {}", input.display()))?
+    };
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+    } else {
+        println!("{report}");
     }
+    Ok(())
+}
+
+/// Prints the increases after smoothing, without touching the answer cache
+fn print_smoothed(input: &Path, smoothing: Smoothing) -> anyhow::Result<()> {
+    let count = {
+        let _span = info_span!("count").entered();
+        let file = File::open(input).with_context(|| format!("opening {}", input.display()))?;
+        let depths = read_depths::<ExactDepth, _>(BufReader::new(file));
+        process_results(depths, |depths| smoothed_increases(depths, smoothing))
+            .with_context(|| format!("parsing {}", input.display()))?
+    };
+
+    println!("Found {count} increases after smoothing ({smoothing})");
+    Ok(())
 }
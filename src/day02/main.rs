@@ -1,175 +1,156 @@
+use std::fmt::Write;
 use std::fs::File;
-use std::io::BufReader;
-use std::ops::Add;
-use std::path::PathBuf;
-use std::str::FromStr;
+use std::io::{BufReader, BufWriter};
+use std::path::{Path, PathBuf};
 
-use adventofcode2021::parse;
-use anyhow::anyhow;
+use anyhow::{ensure, Context};
 use clap::Parser;
-use log::debug;
+use tracing::{debug, info_span};
 
-#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
-pub struct Submarine {
-    depth: i64,
-    forward: i64,
-    aim: i64,
-}
-
-impl Add<Command> for Submarine {
-    type Output = Submarine;
-
-    #[allow(clippy::suspicious_arithmetic_impl)]
-    fn add(self, rhs: Command) -> Self::Output {
-        let (depth, forward, aim) = match rhs {
-            Command {
-                depth: 0,
-                forward: n,
-            } => (self.aim * n, n, 0),
-            Command {
-                depth: n,
-                forward: 0,
-            } => (0, 0, n),
-            _ => panic!("Unexpected command {rhs:?}"),
-        };
-
-        Submarine {
-            depth: self.depth + depth,
-            forward: self.forward + forward,
-            aim: self.aim + aim,
-        }
-    }
-}
-
-#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
-pub struct Command {
-    depth: i64,
-    forward: i64,
-}
-
-impl FromStr for Command {
-    type Err = anyhow::Error;
-
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let (d, n) = s.split_once(' ').ok_or(anyhow!("No space in '{s}'"))?;
-        let n: i64 = str::parse(n)?;
-
-        let (depth, forward) = match d {
-            "forward" => (0, n),
-            "down" => (n, 0),
-            "up" => (-n, 0),
-            _ => return Err(anyhow!("Unexpected direction {d}")),
-        };
-
-        Ok(Command { depth, forward })
-    }
-}
-
-impl Add<Command> for Command {
-    type Output = Command;
-
-    fn add(self, rhs: Command) -> Self::Output {
-        Command {
-            depth: self.depth + rhs.depth,
-            forward: self.forward + rhs.forward,
-        }
-    }
-}
+use adventofcode2021::cache::CacheArgs;
+use adventofcode2021::config;
+use adventofcode2021::day02::{
+    answer, navigate_3d, write_trajectory_csv, Command, CommandTable, CostModel, Mode, Navigate,
+    Position, Submarine,
+};
+use adventofcode2021::solution;
+use adventofcode2021::trace::TraceArgs;
 
 ////////////////////////////////////////////////////////////////////////////////
-/// Main
+// Main
 
 #[derive(Parser, Debug)]
 #[clap(author, version, about, long_about = None)]
 struct Args {
-    #[clap(short, long, value_parser, default_value = "inputs/day02.txt")]
-    input: PathBuf,
+    /// Input file or directory; repeat to compare several inputs in a table
+    /// [default: day02.txt in the configured input directory]
+    #[clap(short, long, value_parser)]
+    input: Vec<PathBuf>,
+
+    /// In 3d mode, also follow `port N` and `starboard N`, and print the
+    /// product of all three coordinates instead of the puzzle answers
+    #[clap(long, value_enum, default_value_t)]
+    mode: Mode,
+
+    /// Also write the state after every command, under both models, to this
+    /// CSV file
+    #[clap(long, value_parser, value_name = "CSV")]
+    trajectory: Option<PathBuf>,
+
+    /// Also report the fuel burned, given each verb's cost per unit as e.g.
+    /// `down=3,up=3`; unlisted verbs cost 1
+    #[clap(long, value_parser, value_name = "RATES")]
+    fuel: Option<CostModel>,
+
+    #[clap(flatten)]
+    trace: TraceArgs,
+
+    #[clap(flatten)]
+    cache: CacheArgs,
 }
 
-fn main() {
-    env_logger::init();
+fn main() -> anyhow::Result<()> {
     let args = Args::parse();
+    let _reports = args.trace.init();
+    let mut inputs = config::input_paths(args.input, env!("CARGO_CRATE_NAME"))?;
+    if inputs.len() > 1 {
+        return solution::compare(env!("CARGO_CRATE_NAME"), &inputs);
+    }
+    let input = inputs.remove(0);
 
-    debug!("Using input {}", args.input.display());
-    let file = File::open(args.input).unwrap();
-    let buf = BufReader::new(file);
+    debug!("Using input {}", input.display());
+    if let Some(csv) = &args.trajectory {
+        write_trajectory(&input, csv, args.mode)?;
+    }
+    if let Some(costs) = args.fuel {
+        print_fuel(&input, costs, args.mode)?;
+    }
+    if args.mode == Mode::ThreeD {
+        return print_3d(&input);
+    }
 
-    let directions: Vec<Command> = parse::buffer(buf).unwrap();
-    let sum: Command = directions
-        .iter()
-        .copied()
-        .reduce(Command::add)
-        .unwrap_or_default();
+    let Some(mut answers) = args.cache.open("day02", &input)? else {
+        return Ok(());
+    };
+    let directions: Vec<Command> = {
+        let _span = info_span!("parse").entered();
+        let file = File::open(&input).with_context(|| format!("opening {}", input.display()))?;
+        let buf = BufReader::new(file);
+
+        CommandTable::new()
+            .parse_lines(buf)
+            .with_context(|| format!("parsing {}", input.display()))?
+    };
+    let mul = info_span!("part1").in_scope(|| {
+        let pos = Position::follow(&directions).context("part 1")?;
+        answer(pos.depth, pos.forward)
+    })?;
+
+    writeln!(answers, "Found {mul}")?;
+
+    let (sub, mul) = info_span!("part2").in_scope(|| {
+        let sub = Submarine::follow(&directions).context("part 2")?;
+        anyhow::Ok((sub, answer(sub.depth, sub.forward)?))
+    })?;
+    writeln!(
+        answers,
+        "Submarine landed at {d} * {f} = {mul}",
+        d = sub.depth,
+        f = sub.forward
+    )?;
 
-    let mul = sum.depth * sum.forward;
+    answers.save();
 
-    println!("Found {mul}");
+    Ok(())
+}
 
-    let sub: Submarine = directions
-        .iter()
-        .copied()
-        .fold(Submarine::default(), Submarine::add);
+/// Writes the path taken to `csv`. Sideways moves in 3d mode don't show up in
+/// the 2D models it records.
+fn write_trajectory(input: &Path, csv: &Path, mode: Mode) -> anyhow::Result<()> {
+    let file = File::open(input).with_context(|| format!("opening {}", input.display()))?;
+    let directions = CommandTable::for_mode(mode)
+        .parse_lines(BufReader::new(file))
+        .with_context(|| format!("parsing {}", input.display()))?;
+
+    let out = File::create(csv).with_context(|| format!("creating {}", csv.display()))?;
+    write_trajectory_csv(&directions, BufWriter::new(out))
+        .with_context(|| format!("writing {}", csv.display()))?;
+    eprintln!("Wrote {} steps to {}", directions.len(), csv.display());
+    Ok(())
+}
 
-    let mul = sub.depth * sub.forward;
+/// Prints where the submarine ends up in 3D, without touching the answer
+/// cache
+fn print_3d(input: &Path) -> anyhow::Result<()> {
+    let text =
+        std::fs::read_to_string(input).with_context(|| format!("reading {}", input.display()))?;
+    let sub = navigate_3d(&text).with_context(|| format!("parsing {}", input.display()))?;
     println!(
-        "Submarine landed at {d} * {f} = {mul}",
+        "Submarine reached {f} * {d} * {l} = {product}",
+        f = sub.forward,
         d = sub.depth,
-        f = sub.forward
+        l = sub.lateral,
+        product = sub
+            .product()
+            .context("Overflow multiplying the coordinates")?
     );
+    Ok(())
 }
 
-////////////////////////////////////////////////////////////////////////////////
-/// Tests
-
-#[cfg(test)]
-mod tests {
-    use test_log::test;
-
-    #[allow(unused_imports)]
-    use super::*;
-
-    static EXAMPLE: &str = r###"
-        forward 5
-        down 5
-        forward 8
-        up 3
-        down 8
-        forward 2
-    "###;
-
-    #[test]
-    fn test_part_one() {
-        let directions: Vec<Command> = parse::buffer(EXAMPLE.as_bytes()).unwrap();
-        let sum: Command = directions
-            .iter()
-            .copied()
-            .reduce(Command::add)
-            .unwrap_or_default();
-
-        assert_eq!(
-            sum,
-            Command {
-                depth: 10,
-                forward: 15
-            }
-        )
-    }
-
-    #[test]
-    fn test_part_two() {
-        let directions: Vec<Command> = parse::buffer(EXAMPLE.as_bytes()).unwrap();
-        let sum: Submarine = directions
-            .iter()
-            .copied()
-            .fold(Submarine::default(), Submarine::add);
-
-        assert_eq!(
-            sum,
-            Submarine {
-                depth: 60,
-                forward: 15,
-                aim: 10,
-            }
-        )
-    }
+/// Prints the fuel burned following the commands in `input`
+fn print_fuel(input: &Path, costs: CostModel, mode: Mode) -> anyhow::Result<()> {
+    let file = File::open(input).with_context(|| format!("opening {}", input.display()))?;
+    let directions = CommandTable::for_mode(mode)
+        .parse_lines(BufReader::new(file))
+        .with_context(|| format!("parsing {}", input.display()))?;
+
+    let mut dive = Submarine::simulate(directions.iter().copied()).with_costs(costs);
+    let steps = dive.by_ref().count();
+    ensure!(
+        steps == directions.len(),
+        "Overflow at command {}",
+        steps + 1
+    );
+    println!("Burned {} fuel over {steps} commands", dive.fuel());
+    Ok(())
 }
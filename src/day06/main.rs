@@ -1,118 +1,105 @@
-use std::collections::VecDeque;
-use std::iter::repeat;
-use std::num::ParseIntError;
-use std::path::PathBuf;
-use std::str::FromStr;
+use std::fmt::Write;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::{Path, PathBuf};
 
+use anyhow::Context;
 use clap::Parser;
-use log::debug;
+use tracing::{debug, info_span};
 
-const REFRESH: u8 = 7;
-const INITIAL: u8 = 2;
-
-#[derive(Debug, Clone, PartialEq, Eq)]
-pub struct FishSchool {
-    fish: VecDeque<u64>,
-}
-
-impl FromStr for FishSchool {
-    type Err = ParseIntError;
-
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let ns = s
-            .split(',')
-            .map(str::parse)
-            .collect::<Result<Vec<u8>, _>>()?;
-
-        Ok(FishSchool::from_iter(ns))
-    }
-}
-
-impl FromIterator<u8> for FishSchool {
-    fn from_iter<T: IntoIterator<Item = u8>>(iter: T) -> Self {
-        let mut fish = VecDeque::from_iter(repeat(0).take((REFRESH + INITIAL) as usize));
-        for i in iter {
-            fish[i as usize] += 1;
-        }
-
-        FishSchool { fish }
-    }
-}
-
-impl FishSchool {
-    pub fn step(&mut self) {
-        let birthing = self.fish.pop_front().unwrap();
-        // refresh
-        self.fish[REFRESH as usize - 1] += birthing;
-        // babies
-        self.fish.push_back(birthing);
-    }
-
-    pub fn total(&self) -> u64 {
-        self.fish.iter().sum()
-    }
-}
+use adventofcode2021::cache::{Answers, CacheArgs};
+use adventofcode2021::config;
+use adventofcode2021::day06::{FishSchool, INITIAL, REFRESH};
+use adventofcode2021::solution;
+use adventofcode2021::trace::TraceArgs;
 
 ////////////////////////////////////////////////////////////////////////////////
-/// Main
+// Main
 
 #[derive(Parser, Debug)]
 #[clap(author, version, about, long_about = None)]
 struct Args {
-    #[clap(short, long, value_parser, default_value = "inputs/day06.txt")]
-    input: PathBuf,
+    /// Input file or directory; repeat to compare several inputs in a table
+    /// [default: day06.txt in the configured input directory]
+    #[clap(short, long, value_parser)]
+    input: Vec<PathBuf>,
+
+    /// Days between a grown fish's births
+    #[clap(long, default_value_t = REFRESH, value_parser = clap::value_parser!(u8).range(1..))]
+    refresh: u8,
+
+    /// Extra days a newborn fish waits before its first birth
+    #[clap(long, default_value_t = INITIAL)]
+    initial: u8,
+
+    /// Also print the total after this many days. Beyond about 500 days, build
+    /// with `--features bigint` to avoid overflow.
+    #[clap(long, value_parser)]
+    days: Option<u64>,
+
+    /// Write the number of fish with each timer on each day to a .csv or
+    /// .json file, up to `--days` or else 256 days
+    #[clap(long, value_parser)]
+    series: Option<PathBuf>,
+
+    #[clap(flatten)]
+    trace: TraceArgs,
+
+    #[clap(flatten)]
+    cache: CacheArgs,
 }
 
-fn main() {
-    env_logger::init();
+fn main() -> anyhow::Result<()> {
     let args = Args::parse();
+    let _reports = args.trace.init();
+    let mut inputs = config::input_paths(args.input, env!("CARGO_CRATE_NAME"))?;
+    if inputs.len() > 1 {
+        return solution::compare(env!("CARGO_CRATE_NAME"), &inputs);
+    }
+    let input = inputs.remove(0);
+
+    debug!("Using input {}", input.display());
+    let timers = (args.refresh, args.initial);
+    if let Some(days) = args.days {
+        let school = read_school(&input, timers)?;
+        let total = info_span!("days").in_scope(|| school.total_after(days))?;
+        println!("Total ({days} days): {total}");
+    }
+    if let Some(path) = &args.series {
+        let school = read_school(&input, timers)?;
+        let days = args.days.unwrap_or(256);
+        let series = info_span!("series").in_scope(|| school.try_series(days))?;
+        series
+            .save(path)
+            .with_context(|| format!("writing {}", path.display()))?;
+        eprintln!("Wrote days 0 to {days} to {}", path.display());
+    }
 
-    debug!("Using input {}", args.input.display());
-    let s = std::fs::read_to_string(args.input).unwrap();
-    let mut school: FishSchool = s.parse().unwrap();
+    // Only the puzzle's own timers are cached
+    let mut answers = if timers == (REFRESH, INITIAL) {
+        let Some(answers) = args.cache.open("day06", &input)? else {
+            return Ok(());
+        };
+        answers
+    } else {
+        Answers::at(None)
+    };
+    let school = read_school(&input, timers)?;
 
-    for _ in 0..80 {
-        school.step();
-    }
+    let total = info_span!("part1").in_scope(|| school.population_at(80))?;
+    writeln!(answers, "Total (80 days):  {total}")?;
 
-    println!("Total (80 days):  {}", school.total());
+    let total = info_span!("part2").in_scope(|| school.population_at(256))?;
+    writeln!(answers, "Total (256 days): {total}")?;
 
-    for _ in 80..256 {
-        school.step();
-    }
-    println!("Total (256 days): {}", school.total());
+    answers.save();
+
+    Ok(())
 }
 
-////////////////////////////////////////////////////////////////////////////////
-/// Tests
-
-#[cfg(test)]
-mod tests {
-    use test_log::test;
-
-    #[allow(unused_imports)]
-    use super::*;
-
-    const EXAMPLE: &str = r###"
-        3,4,3,1,2
-    "###;
-
-    #[test]
-    fn test_basic() {
-        let mut school: FishSchool = EXAMPLE.trim().parse().unwrap();
-        for _ in 0..18 {
-            school.step();
-            println!("{:?}", school.fish);
-        }
-        assert_eq!(school.total(), 26);
-        for _ in 18..80 {
-            school.step();
-        }
-        assert_eq!(school.total(), 5934);
-
-        for _ in 80..256 {
-            school.step();
-        }
-        assert_eq!(school.total(), 26984457539);
-    }
+fn read_school(input: &Path, (refresh, initial): (u8, u8)) -> anyhow::Result<FishSchool> {
+    let _span = info_span!("parse").entered();
+    let file = File::open(input).with_context(|| format!("opening {}", input.display()))?;
+    FishSchool::read(BufReader::new(file), refresh, initial)
+        .with_context(|| format!("parsing {}", input.display()))
 }
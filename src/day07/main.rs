@@ -1,130 +1,127 @@
-use std::num::ParseIntError;
-use std::path::PathBuf;
-use std::str::FromStr;
+use std::fmt::Write;
+use std::fs::File;
+use std::io::BufWriter;
+use std::ops::RangeInclusive;
+use std::path::{Path, PathBuf};
 
+use anyhow::Context;
 use clap::Parser;
-use log::debug;
+use tracing::{debug, info_span};
 
-pub struct Crabs {
-    pub locations: Vec<u16>,
-}
-
-impl FromStr for Crabs {
-    type Err = ParseIntError;
-
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let locations = s
-            .split(',')
-            .map(str::parse)
-            .collect::<Result<Vec<u16>, _>>()?;
-        Ok(Crabs { locations })
-    }
-}
-
-impl Crabs {
-    pub fn shortest(&self) -> (u16, u64) {
-        let mut ns = self.locations.clone();
-        ns.sort();
-
-        let mid = (ns.len() + 1) / 2;
-        let mid = ns[mid];
-        let diff_total = ns.iter().map(|&n| n.abs_diff(mid) as u64).sum();
-
-        (mid, diff_total)
-    }
-
-    // fuel cost for distance d is d(d+1)/2
-    // for a crab at position p with goal at x, the fuel cost is
-    //     (|p-x|)(|p-x|+1)/2
-    //     (|p-x|)^2 + |p-x|)/2
-    //     ((p^2 - 2px + x^2) + |p-x|/2
-    // So total is
-    //     Sum_p (p^2 - 2px + x^2 + |p - x|)/2
-    // Pulling out a constant C gives
-    //     C + Sum_p (x^2 - 2px + |p-x|)/2
-    // Setting derivative to 0
-    //     Sum_p (x - p + 1/2 sgn(p-x)) = 0
-    //     N x = Sum_p p
-    //     x = <p> ± ½
-    // So the optimal position is the average of the positions
-    pub fn shortest_linear(&self) -> (u16, u64) {
-        let sum: i64 = self.locations.iter().map(|&n| n as i64).sum();
-
-        let n = self.locations.len() as i64;
-        // avg rounded down
-        let avg = (sum / n) as u16;
-
-        let fuel_func = |x: u16| {
-            self.locations
-                .iter()
-                .map(|&p| {
-                    let d = (p as i64 - x as i64).abs();
-                    d * (d + 1) / 2
-                })
-                .sum::<i64>() as u64
-        };
-
-        let locs: [u16; 3] = [avg - 1, avg, avg + 1];
-        locs.iter()
-            .map(|&x| (x, fuel_func(x)))
-            .min_by_key(|&(_, fuel)| fuel)
-            .unwrap()
-    }
-}
+use adventofcode2021::cache::CacheArgs;
+use adventofcode2021::config;
+use adventofcode2021::day07::{self, CostModel, Crabs, Method};
+use adventofcode2021::parse;
+use adventofcode2021::solution;
+use adventofcode2021::trace::TraceArgs;
 
 ////////////////////////////////////////////////////////////////////////////////
-/// Main
+// Main
 
 #[derive(Parser, Debug)]
 #[clap(author, version, about, long_about = None)]
 struct Args {
-    #[clap(short, long, value_parser, default_value = "inputs/day07.txt")]
-    input: PathBuf,
+    /// Input file or directory; repeat to compare several inputs in a table
+    /// [default: day07.txt in the configured input directory]
+    #[clap(short, long, value_parser)]
+    input: Vec<PathBuf>,
+
+    /// Also print the best position when moving costs fuel by this model
+    #[clap(long, value_enum)]
+    cost: Option<CostModel>,
+
+    /// How to find the best position for --cost
+    #[clap(long, value_enum, default_value_t)]
+    method: Method,
+
+    /// Positions the crabs can't line up at for --cost, like `10..20` or
+    /// `10..=19`; repeat for several ranges
+    #[clap(long, value_name = "A..B", value_parser = day07::parse_range, requires = "cost")]
+    exclude: Vec<RangeInclusive<i64>>,
+
+    /// Write the fuel at every position under the --cost model to this CSV
+    /// file, or print it with `-`
+    #[clap(long, value_parser, requires = "cost")]
+    curve: Option<PathBuf>,
+
+    /// Print a table of this many of the cheapest positions for --cost
+    #[clap(long, value_name = "K", requires = "cost")]
+    top: Option<usize>,
+
+    #[clap(flatten)]
+    trace: TraceArgs,
+
+    #[clap(flatten)]
+    cache: CacheArgs,
 }
 
-fn main() {
-    env_logger::init();
+fn main() -> anyhow::Result<()> {
     let args = Args::parse();
+    let _reports = args.trace.init();
+    let mut inputs = config::input_paths(args.input, env!("CARGO_CRATE_NAME"))?;
+    if inputs.len() > 1 {
+        return solution::compare(env!("CARGO_CRATE_NAME"), &inputs);
+    }
+    let input = inputs.remove(0);
+
+    debug!("Using input {}", input.display());
+    if let Some(model) = args.cost {
+        let crabs = read_crabs(&input)?;
+        let (x, fuel) =
+            info_span!("optimal").in_scope(|| crabs.optimal_with(model, args.method))?;
+        println!("Best position with {model} costs is {x}, using {fuel}");
+        if let Some(k) = args.top {
+            let ranking = info_span!("top").in_scope(|| crabs.best_positions(model, k))?;
+            print!("{ranking}");
+        }
+        if !args.exclude.is_empty() {
+            let allowed = info_span!("excluding")
+                .in_scope(|| crabs.optimal_excluding(model, &args.exclude))?;
+            match allowed {
+                Some((x, fuel)) => {
+                    println!("Best allowed position with {model} costs is {x}, using {fuel}")
+                }
+                None => println!("Every position is excluded"),
+            }
+        }
+
+        if let Some(path) = &args.curve {
+            let curve = info_span!("curve").in_scope(|| crabs.cost_curve(model))?;
+            if path.as_os_str() == "-" {
+                day07::write_curve_csv(&curve, std::io::stdout().lock())?;
+            } else {
+                let file =
+                    File::create(path).with_context(|| format!("creating {}", path.display()))?;
+                day07::write_curve_csv(&curve, BufWriter::new(file))
+                    .with_context(|| format!("writing {}", path.display()))?;
+                eprintln!("Wrote {} positions to {}", curve.len(), path.display());
+            }
+        }
+    }
 
-    debug!("Using input {}", args.input.display());
-    let s = std::fs::read_to_string(args.input).unwrap();
-    let crabs = Crabs::from_str(s.trim()).unwrap();
+    let Some(mut answers) = args.cache.open("day07", &input)? else {
+        return Ok(());
+    };
+    let crabs = read_crabs(&input)?;
 
-    let (mid, fuel) = crabs.shortest();
-    println!("Shortest position {mid} requires {fuel:?}");
+    let (mid, fuel) = info_span!("part1").in_scope(|| crabs.optimal(CostModel::Linear))?;
+    writeln!(answers, "Shortest position {mid} requires {fuel:?}")?;
 
     // 99540639 too high
-    let (mid, fuel) = crabs.shortest_linear();
-    println!("Shortest position {mid} with linear ramp requires {fuel:?}");
-}
-
-////////////////////////////////////////////////////////////////////////////////
-/// Tests
-
-#[cfg(test)]
-mod tests {
-    use test_log::test;
+    let (mid, fuel) = info_span!("part2").in_scope(|| crabs.optimal(CostModel::Quadratic))?;
+    writeln!(
+        answers,
+        "Shortest position {mid} with linear ramp requires {fuel:?}"
+    )?;
 
-    #[allow(unused_imports)]
-    use super::*;
+    answers.save();
 
-    const EXAMPLE: &str = r###"
-        16,1,2,0,4,2,7,1,2,14
-    "###;
-
-    #[test]
-    fn test_basic() {
-        let crabs = Crabs::from_str(EXAMPLE.trim()).unwrap();
-        let (mid, fuel) = crabs.shortest();
-
-        assert_eq!((mid, fuel), (2, 37));
-    }
-
-    #[test]
-    fn test_linear() {
-        let crabs = Crabs::from_str(EXAMPLE.trim()).unwrap();
-        let (mid, fuel) = crabs.shortest_linear();
+    Ok(())
+}
 
-        assert_eq!((mid, fuel), (5, 168));
-    }
+fn read_crabs(input: &Path) -> anyhow::Result<Crabs> {
+    let _span = info_span!("parse").entered();
+    let s =
+        std::fs::read_to_string(input).with_context(|| format!("reading {}", input.display()))?;
+    parse::strict::<Crabs>(&s).with_context(|| format!("parsing {}", input.display()))
 }
@@ -1,241 +1,97 @@
-use std::collections::HashSet;
-use std::fmt::Display;
+use std::fmt::Write;
 use std::path::PathBuf;
-use std::str::FromStr;
 
-use anyhow::{anyhow, Context};
+use anyhow::Context;
 use clap::Parser;
-use log::debug;
+use tracing::{debug, info_span};
 
-#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
-pub enum Fold {
-    Horizontal(i64),
-    Vertical(i64),
-}
-
-#[derive(Debug, Clone, PartialEq, Eq)]
-pub struct Instructions {
-    points: HashSet<(i64, i64)>,
-    folds: Vec<Fold>,
-}
-
-impl FromStr for Instructions {
-    type Err = anyhow::Error;
-
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let mut points = HashSet::new();
-        let mut folds = Vec::new();
-        let trimmed = s.trim();
-        let mut lines = trimmed.lines();
-        for line in lines.by_ref() {
-            let l = line.trim();
-            if l.is_empty() {
-                break;
-            }
-            let (s1, s2) = l.split_once(',').ok_or(anyhow!("Expected comma"))?;
-            let x: i64 = s1.trim().parse().context("parsing x")?;
-            let y: i64 = s2.trim().parse().context("parsing y")?;
-            points.insert((x, y));
-        }
-
-        for line in lines {
-            let l = line.trim();
-            if l.is_empty() {
-                continue;
-            }
-
-            let stripped = l
-                .strip_prefix("fold along ")
-                .ok_or(anyhow!("Expected fold"))?;
-            let (s1, s2) = stripped.split_once('=').ok_or(anyhow!("Expected space"))?;
-            let loc: i64 = s2.trim().parse().context("parsing fold")?;
-            let fold = match s1 {
-                "x" => Fold::Vertical(loc),
-                "y" => Fold::Horizontal(loc),
-                c => return Err(anyhow!("Expected x or y, found '{c}'")),
-            };
-
-            folds.push(fold);
-        }
-
-        // Store them backwards so we can pop them off the back
-        folds.reverse();
-
-        Ok(Self { points, folds })
-    }
-}
-
-impl Instructions {
-    pub fn fold(&mut self, fold: Fold) {
-        let mut new_points = HashSet::new();
-        match fold {
-            Fold::Horizontal(y) => {
-                for &(x2, y2) in &self.points {
-                    if y2 > y {
-                        new_points.insert((x2, 2 * y - y2));
-                    }
-                }
-                self.points.retain(|&(_, y2)| y2 < y);
-            }
-            Fold::Vertical(x) => {
-                for &(x2, y2) in &self.points {
-                    if x2 > x {
-                        new_points.insert((2 * x - x2, y2));
-                    }
-                }
-                self.points.retain(|&(x2, _)| x2 < x);
-            }
-        }
-        self.points.extend(new_points);
-    }
-
-    pub fn step(&mut self) -> bool {
-        if let Some(fold) = self.folds.pop() {
-            self.fold(fold);
-            true
-        } else {
-            false
-        }
-    }
-
-    pub fn fold_all(&mut self) {
-        while self.step() {}
-    }
-
-    pub fn point_count(&self) -> usize {
-        self.points.len()
-    }
-}
-
-impl Display for Instructions {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let mx = self
-            .points
-            .iter()
-            .copied()
-            .map(|(x, _)| x)
-            .max()
-            .unwrap_or_default();
-        let my = self
-            .points
-            .iter()
-            .copied()
-            .map(|(_, y)| y)
-            .max()
-            .unwrap_or_default();
-
-        let pts: HashSet<(i64, i64)> = self.points.iter().copied().collect();
-
-        for y in 0..=my {
-            for x in 0..=mx {
-                if pts.contains(&(x, y)) {
-                    write!(f, "#")?;
-                } else {
-                    write!(f, ".")?;
-                }
-            }
-            writeln!(f)?;
-        }
-
-        // todo: folds
-        Ok(())
-    }
-}
+use adventofcode2021::cache::{Answers, CacheArgs};
+use adventofcode2021::config;
+use adventofcode2021::day13::Instructions;
+use adventofcode2021::parse;
+use adventofcode2021::render::{Palette, Renderer};
+use adventofcode2021::solution;
+use adventofcode2021::trace::TraceArgs;
 
 ////////////////////////////////////////////////////////////////////////////////
-/// Main
+// Main
 
 #[derive(Parser, Debug)]
 #[clap(author, version, about, long_about = None)]
 struct Args {
-    #[clap(short, long, value_parser, default_value = "inputs/day13.txt")]
-    input: PathBuf,
-}
+    /// Input file or directory; repeat to compare several inputs in a table
+    /// [default: day13.txt in the configured input directory]
+    #[clap(short, long, value_parser)]
+    input: Vec<PathBuf>,
 
-fn main() {
-    env_logger::init();
-    let args = Args::parse();
+    /// Render the result to an image file (.png or .svg)
+    #[clap(long, value_parser)]
+    render: Option<PathBuf>,
 
-    debug!("Using input {}", args.input.display());
-    let s = std::fs::read_to_string(&args.input).unwrap();
-    let mut instructions = s.parse::<Instructions>().unwrap();
-    let pcount = instructions.point_count();
-    instructions.step();
-    let pcount1 = instructions.point_count();
+    #[clap(flatten)]
+    trace: TraceArgs,
 
-    instructions.fold_all();
-    let pcount_end = instructions.point_count();
-    println!("Found {pcount} -> {pcount1} -> {pcount_end} points");
-
-    println!("{}", instructions);
+    #[clap(flatten)]
+    cache: CacheArgs,
 }
 
-////////////////////////////////////////////////////////////////////////////////
-/// Tests
-
-#[cfg(test)]
-mod tests {
-    use test_log::test;
-
-    #[allow(unused_imports)]
-    use super::*;
-
-    const EXAMPLE: &str = r###"
-        6,10
-        0,14
-        9,10
-        0,3
-        10,4
-        4,11
-        6,0
-        6,12
-        4,1
-        0,13
-        10,12
-        3,4
-        3,0
-        8,4
-        1,10
-        2,14
-        8,10
-        9,0
-        
-        fold along y=7
-        fold along x=5
-    "###;
-
-    #[test]
-    fn test_parse() {
-        let instructions: Instructions = EXAMPLE.parse().unwrap();
-        println!("{}", instructions);
+fn main() -> anyhow::Result<()> {
+    let args = Args::parse();
+    let _reports = args.trace.init();
+    let mut inputs = config::input_paths(args.input, env!("CARGO_CRATE_NAME"))?;
+    if inputs.len() > 1 {
+        return solution::compare(env!("CARGO_CRATE_NAME"), &inputs);
     }
-
-    #[test]
-    fn test_fold() {
-        let mut instructions: Instructions = EXAMPLE.parse().unwrap();
-        assert_eq!(instructions.point_count(), 18);
-        debug!("{}", instructions);
+    let input = inputs.remove(0);
+
+    debug!("Using input {}", input.display());
+    // A render needs the puzzle solved again, so --cached can't stand in for it
+    let mut answers = if args.render.is_some() {
+        Answers::open("day13", &input)?
+    } else {
+        let Some(answers) = args.cache.open("day13", &input)? else {
+            return Ok(());
+        };
+        answers
+    };
+    let mut instructions = {
+        let _span = info_span!("parse").entered();
+        let s = std::fs::read_to_string(&input)
+            .with_context(|| format!("reading {}", input.display()))?;
+        parse::strict::<Instructions>(&s).with_context(|| format!("parsing {}", input.display()))?
+    };
+    let pcount = instructions.point_count();
+    let pcount1 = info_span!("part1").in_scope(|| {
         instructions.step();
-        debug!("{}", instructions);
-        assert_eq!(instructions.point_count(), 17);
+        instructions.point_count()
+    });
 
+    let pcount_end = info_span!("part2").in_scope(|| {
         instructions.fold_all();
-        debug!("{}", instructions);
-        assert_eq!(instructions.point_count(), 16);
-        let expected_raw = r###"
-            #####
-            #...#
-            #...#
-            #...#
-            #####
-        "###;
+        instructions.point_count()
+    });
+    writeln!(
+        answers,
+        "Found {pcount} -> {pcount1} -> {pcount_end} points"
+    )?;
+
+    writeln!(answers, "{}", instructions)?;
+
+    match info_span!("ocr").in_scope(|| instructions.letters()) {
+        Ok(letters) => writeln!(answers, "Letters: {letters}")?,
+        Err(e) => writeln!(answers, "Could not read letters: {e}")?,
+    }
 
-        // let expected: Instructions = expected_str.parse().unwrap();
-        let expected: String = expected_raw
-            .trim()
-            .lines()
-            .map(|s| format!("{}\n", s.trim_start()))
-            .collect();
-        assert_eq!(format!("{}", instructions), expected);
+    if let Some(path) = args.render {
+        let _span = info_span!("render").entered();
+        let canvas = instructions.canvas();
+        let renderer = Renderer::new(Palette::mono()).fit(&canvas, 1000);
+        renderer
+            .save(&canvas, &path)
+            .with_context(|| format!("rendering {}", path.display()))?;
     }
+
+    answers.save();
+
+    Ok(())
 }
@@ -0,0 +1,76 @@
+use std::fmt::Write;
+use std::path::PathBuf;
+
+use anyhow::{ensure, Context};
+use clap::Parser;
+use tracing::{debug, info_span};
+
+use adventofcode2021::cache::CacheArgs;
+use adventofcode2021::config;
+use adventofcode2021::day24::{to_number, Monad, Program};
+use adventofcode2021::parse;
+use adventofcode2021::solution;
+use adventofcode2021::trace::TraceArgs;
+
+////////////////////////////////////////////////////////////////////////////////
+// Main
+
+#[derive(Parser, Debug)]
+#[clap(author, version, about, long_about = None)]
+struct Args {
+    /// Input file or directory; repeat to compare several inputs in a table
+    /// [default: day24.txt in the configured input directory]
+    #[clap(short, long, value_parser)]
+    input: Vec<PathBuf>,
+
+    #[clap(flatten)]
+    trace: TraceArgs,
+
+    #[clap(flatten)]
+    cache: CacheArgs,
+}
+
+fn main() -> anyhow::Result<()> {
+    let args = Args::parse();
+    let _reports = args.trace.init();
+    let mut inputs = config::input_paths(args.input, env!("CARGO_CRATE_NAME"))?;
+    if inputs.len() > 1 {
+        return solution::compare(env!("CARGO_CRATE_NAME"), &inputs);
+    }
+    let input = inputs.remove(0);
+
+    debug!("Using input {}", input.display());
+    let Some(mut answers) = args.cache.open("day24", &input)? else {
+        return Ok(());
+    };
+    let program = {
+        let _span = info_span!("parse").entered();
+        let s = std::fs::read_to_string(&input)
+            .with_context(|| format!("reading {}", input.display()))?;
+        parse::strict::<Program>(&s).with_context(|| format!("parsing {}", input.display()))?
+    };
+    let monad =
+        info_span!("analyze").in_scope(|| Monad::new(&program).context("analyzing MONAD"))?;
+
+    let largest = info_span!("part1").in_scope(|| monad.largest());
+    let largest = largest.context("No valid model number found")?;
+    ensure!(
+        program.validate(&largest)?,
+        "Model number {} was rejected by the program",
+        to_number(&largest)
+    );
+    writeln!(answers, "Largest model number: {}", to_number(&largest))?;
+
+    let smallest = info_span!("part2").in_scope(|| monad.smallest());
+    let smallest = smallest.context("No valid model number found")?;
+    ensure!(
+        program.validate(&smallest)?,
+        "Model number {} was rejected by the program",
+        to_number(&smallest)
+    );
+    writeln!(answers, "Smallest model number: {}", to_number(&smallest))?;
+
+    answers.save();
+
+    Ok(())
+}
@@ -0,0 +1,80 @@
+use std::fmt::Write;
+use std::path::PathBuf;
+
+use anyhow::Context;
+use clap::Parser;
+use tracing::{debug, info_span};
+
+use adventofcode2021::animate::AnimateArgs;
+use adventofcode2021::cache::{Answers, CacheArgs};
+use adventofcode2021::config;
+use adventofcode2021::day25::Seafloor;
+use adventofcode2021::parse;
+use adventofcode2021::solution;
+use adventofcode2021::trace::TraceArgs;
+
+////////////////////////////////////////////////////////////////////////////////
+// Main
+
+#[derive(Parser, Debug)]
+#[clap(author, version, about, long_about = None)]
+struct Args {
+    /// Input file or directory; repeat to compare several inputs in a table
+    /// [default: day25.txt in the configured input directory]
+    #[clap(short, long, value_parser)]
+    input: Vec<PathBuf>,
+
+    #[clap(flatten)]
+    animate: AnimateArgs,
+
+    #[clap(flatten)]
+    trace: TraceArgs,
+
+    #[clap(flatten)]
+    cache: CacheArgs,
+}
+
+fn main() -> anyhow::Result<()> {
+    let args = Args::parse();
+    let _reports = args.trace.init();
+    let mut inputs = config::input_paths(args.input, env!("CARGO_CRATE_NAME"))?;
+    if inputs.len() > 1 {
+        return solution::compare(env!("CARGO_CRATE_NAME"), &inputs);
+    }
+    let input = inputs.remove(0);
+
+    debug!("Using input {}", input.display());
+    // An animation needs the puzzle solved again, so --cached can't stand in for it
+    let mut answers = if args.animate.animate {
+        Answers::open("day25", &input)?
+    } else {
+        let Some(answers) = args.cache.open("day25", &input)? else {
+            return Ok(());
+        };
+        answers
+    };
+    let mut seafloor = {
+        let _span = info_span!("parse").entered();
+        let s = std::fs::read_to_string(&input)
+            .with_context(|| format!("reading {}", input.display()))?;
+        parse::strict::<Seafloor>(&s).with_context(|| format!("parsing {}", input.display()))?
+    };
+
+    let mut animator = args.animate.animator();
+    let mut drawn = Ok(());
+    let steps = info_span!("part1").in_scope(|| {
+        seafloor.settle_with(|step, seafloor| {
+            if let (Some(animator), Ok(())) = (animator.as_mut(), &drawn) {
+                drawn = animator.frame(format!("Step {step}"), seafloor);
+            }
+        })
+    });
+    drop(animator);
+    drawn.context("drawing animation")?;
+
+    writeln!(answers, "Sea cucumbers stop moving on step {steps}")?;
+
+    answers.save();
+
+    Ok(())
+}
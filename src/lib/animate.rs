@@ -0,0 +1,136 @@
+use std::fmt::Display;
+use std::io::{self, Stdout, Write};
+use std::thread;
+use std::time::Duration;
+
+// ANSI escape sequences
+const HIDE_CURSOR: &str = "\x1b[?25l";
+const SHOW_CURSOR: &str = "\x1b[?25h";
+const HOME: &str = "\x1b[H";
+const CLEAR: &str = "\x1b[2J";
+const CLEAR_TO_END: &str = "\x1b[J";
+const HIGHLIGHT: &str = "\x1b[1;33m";
+const RESET: &str = "\x1b[0m";
+
+// Command-line flags for animating a simulation; use with `#[clap(flatten)]`.
+#[derive(clap::Args, Debug, Clone, PartialEq, Eq)]
+pub struct AnimateArgs {
+    /// Redraw the state in the terminal after each step
+    #[clap(long)]
+    pub animate: bool,
+
+    /// Delay between animation frames, in milliseconds
+    #[clap(long, value_parser, default_value_t = 100)]
+    pub delay: u64,
+}
+
+impl AnimateArgs {
+    /// Returns an animator writing to stdout, if `--animate` was passed
+    pub fn animator(&self) -> Option<Animator<Stdout>> {
+        if self.animate {
+            Some(Animator::new(
+                io::stdout(),
+                Duration::from_millis(self.delay),
+            ))
+        } else {
+            None
+        }
+    }
+}
+
+/// Redraws frames in place in a terminal, pausing between each.
+pub struct Animator<W: Write> {
+    out: W,
+    delay: Duration,
+    frames: usize,
+}
+
+impl<W: Write> Animator<W> {
+    pub fn new(out: W, delay: Duration) -> Self {
+        Animator {
+            out,
+            delay,
+            frames: 0,
+        }
+    }
+
+    /// Number of frames drawn so far
+    pub fn frames(&self) -> usize {
+        self.frames
+    }
+
+    /// Draws a frame with a caption line above the state, replacing the
+    /// previous frame, then waits for the frame delay.
+    pub fn frame(&mut self, caption: impl Display, state: impl Display) -> io::Result<()> {
+        if self.frames == 0 {
+            write!(self.out, "{HIDE_CURSOR}{CLEAR}")?;
+        }
+        write!(self.out, "{HOME}{caption}\n{state}\n{CLEAR_TO_END}")?;
+        self.out.flush()?;
+        self.frames += 1;
+
+        if !self.delay.is_zero() {
+            thread::sleep(self.delay);
+        }
+        Ok(())
+    }
+
+    /// Restores the cursor; called automatically on drop.
+    pub fn finish(&mut self) -> io::Result<()> {
+        if self.frames > 0 {
+            write!(self.out, "{SHOW_CURSOR}")?;
+            self.out.flush()?;
+        }
+        Ok(())
+    }
+}
+
+impl<W: Write> Drop for Animator<W> {
+    fn drop(&mut self) {
+        let _ = self.finish();
+    }
+}
+
+/// Wraps every occurrence of `c` in `text` in bold yellow.
+pub fn highlight(text: &str, c: char) -> String {
+    let mut result = String::with_capacity(text.len());
+    for ch in text.chars() {
+        if ch == c {
+            result.push_str(HIGHLIGHT);
+            result.push(ch);
+            result.push_str(RESET);
+        } else {
+            result.push(ch);
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use test_log::test;
+
+    use super::*;
+
+    #[test]
+    fn test_frames() {
+        let mut out = Vec::new();
+        {
+            let mut animator = Animator::new(&mut out, Duration::ZERO);
+            animator.frame("Step 1", "ab").unwrap();
+            animator.frame("Step 2", "cd").unwrap();
+            assert_eq!(animator.frames(), 2);
+        }
+
+        let s = String::from_utf8(out).unwrap();
+        assert!(s.starts_with(HIDE_CURSOR));
+        assert!(s.contains(&format!("{HOME}Step 2\ncd\n")));
+        assert!(s.ends_with(SHOW_CURSOR));
+    }
+
+    #[test]
+    fn test_highlight() {
+        assert_eq!(highlight("101", '0'), format!("1{HIGHLIGHT}0{RESET}1"));
+        assert_eq!(highlight("111", '0'), "111");
+    }
+}
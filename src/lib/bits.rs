@@ -0,0 +1,196 @@
+use std::str::FromStr;
+
+use anyhow::{anyhow, ensure};
+use bitvec::field::BitField;
+use bitvec::prelude::{BitVec, Msb0};
+
+/// Reads big-endian fields of up to 64 bits, one after another, from a string
+/// of bits, e.g. the hex-encoded transmission of day 16.
+///
+/// The reader only moves forward; `position` counts the bits read so far.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BitReader {
+    bits: BitVec<u8, Msb0>,
+    position: usize,
+}
+
+impl BitReader {
+    /// A reader over `bytes`, most significant bit first
+    pub fn new(bytes: impl Into<Vec<u8>>) -> Self {
+        BitReader {
+            bits: BitVec::from_vec(bytes.into()),
+            position: 0,
+        }
+    }
+
+    /// A reader over 4-bit values, most significant bit first
+    pub fn from_nibbles<I: IntoIterator<Item = u8>>(nibbles: I) -> anyhow::Result<Self> {
+        let mut bits = BitVec::new();
+        for (ix, nibble) in nibbles.into_iter().enumerate() {
+            ensure!(nibble < 16, "Nibble {nibble} at index {ix} is out of range");
+            bits.extend((0..4).rev().map(|b| (nibble >> b) & 1 == 1));
+        }
+
+        Ok(BitReader { bits, position: 0 })
+    }
+
+    /// Total bits, read or not
+    pub fn len(&self) -> usize {
+        self.bits.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.bits.is_empty()
+    }
+
+    /// Number of bits read so far
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    /// Number of bits left to read
+    pub fn remaining(&self) -> usize {
+        self.bits.len() - self.position
+    }
+
+    /// Whether every bit left is 0, as in the padding after a packet
+    pub fn remainder_zero(&self) -> bool {
+        self.bits[self.position..].not_any()
+    }
+
+    pub fn read_bit(&mut self) -> anyhow::Result<bool> {
+        Ok(self.read_bits(1)? == 1)
+    }
+
+    /// Reads the next `n` bits as a big-endian number
+    pub fn read_bits(&mut self, n: usize) -> anyhow::Result<u64> {
+        let value = self.peek_bits(n)?;
+        self.position += n;
+        Ok(value)
+    }
+
+    /// The next `n` bits as a big-endian number, without moving past them
+    pub fn peek_bits(&self, n: usize) -> anyhow::Result<u64> {
+        ensure!(n <= 64, "Can't read {n} bits into a u64");
+        ensure!(
+            n <= self.remaining(),
+            "Not enough bits: wanted {n} at position {}, only {} left",
+            self.position,
+            self.remaining()
+        );
+        if n == 0 {
+            return Ok(0);
+        }
+
+        Ok(self.bits[self.position..self.position + n].load_be())
+    }
+
+    /// Moves past the next `n` bits without reading them
+    pub fn skip(&mut self, n: usize) -> anyhow::Result<()> {
+        ensure!(
+            n <= self.remaining(),
+            "Can't skip {n} bits at position {}, only {} left",
+            self.position,
+            self.remaining()
+        );
+        self.position += n;
+        Ok(())
+    }
+
+    /// Skips ahead to the next multiple of `n` bits, e.g. `align(8)` for the
+    /// next byte boundary. Does nothing if already aligned.
+    pub fn align(&mut self, n: usize) -> anyhow::Result<()> {
+        ensure!(n > 0, "Can't align to 0 bits");
+        let offset = self.position % n;
+        if offset == 0 {
+            return Ok(());
+        }
+        self.skip(n - offset)
+    }
+}
+
+/// Parses a string of hex digits, ignoring surrounding whitespace
+impl FromStr for BitReader {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let nibbles: anyhow::Result<Vec<u8>> = s
+            .trim()
+            .chars()
+            .map(|c| {
+                c.to_digit(16)
+                    .map(|n| n as u8)
+                    .ok_or_else(|| anyhow!("Invalid hex digit: {c:?}"))
+            })
+            .collect();
+
+        BitReader::from_nibbles(nibbles?)
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// Tests
+
+#[cfg(test)]
+mod tests {
+    use test_log::test;
+
+    #[allow(unused_imports)]
+    use super::*;
+
+    #[test]
+    fn test_read() {
+        let mut reader: BitReader = "D2FE28".parse().unwrap();
+        assert_eq!(reader.len(), 24);
+        assert_eq!(reader.read_bits(3).unwrap(), 6);
+        assert_eq!(reader.read_bits(3).unwrap(), 4);
+        assert_eq!(reader.position(), 6);
+
+        assert_eq!(reader.peek_bits(5).unwrap(), 0b10111);
+        assert_eq!(reader.position(), 6);
+        assert!(reader.read_bit().unwrap());
+        assert_eq!(reader.read_bits(4).unwrap(), 0b0111);
+        assert_eq!(reader.read_bits(10).unwrap(), 0b1111000101);
+        assert_eq!(reader.remaining(), 3);
+        assert!(reader.remainder_zero());
+
+        assert!(reader.read_bits(4).is_err());
+        assert_eq!(reader.read_bits(0).unwrap(), 0);
+        assert_eq!(reader.read_bits(3).unwrap(), 0);
+        assert!(reader.read_bit().is_err());
+    }
+
+    #[test]
+    fn test_wide() {
+        let mut reader = BitReader::new([0xFF; 9]);
+        assert!(reader.read_bits(65).is_err());
+        reader.skip(4).unwrap();
+        assert_eq!(reader.read_bits(64).unwrap(), u64::MAX);
+        assert_eq!(reader.remaining(), 4);
+    }
+
+    #[test]
+    fn test_align() {
+        let mut reader = BitReader::new([0b1010_0000, 0b1100_0000]);
+        reader.align(8).unwrap();
+        assert_eq!(reader.position(), 0);
+
+        assert_eq!(reader.read_bits(3).unwrap(), 0b101);
+        reader.align(4).unwrap();
+        assert_eq!(reader.position(), 4);
+        reader.align(8).unwrap();
+        assert_eq!(reader.read_bits(2).unwrap(), 0b11);
+
+        reader.align(8).unwrap();
+        assert_eq!(reader.remaining(), 0);
+        assert!(reader.align(0).is_err());
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!("12G4".parse::<BitReader>().is_err());
+        assert!(BitReader::from_nibbles([1, 2, 16]).is_err());
+        let reader: BitReader = " 0a\n".parse().unwrap();
+        assert_eq!(reader.clone().read_bits(8).unwrap(), 0x0A);
+    }
+}
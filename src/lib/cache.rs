@@ -0,0 +1,161 @@
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use anyhow::Context;
+use sha2::{Digest, Sha256};
+use tracing::{debug, warn};
+
+// Command-line flags for the answer cache; use with `#[clap(flatten)]`.
+#[derive(clap::Args, Debug, Clone, PartialEq, Eq)]
+pub struct CacheArgs {
+    /// Print the stored answer for this input, if there is one, instead of
+    /// solving again
+    #[clap(long)]
+    pub cached: bool,
+}
+
+impl CacheArgs {
+    /// Opens the answers for `day` and this input. With `--cached`, prints the
+    /// stored answers instead if there are any, and returns `None`.
+    pub fn open(&self, day: &str, input: &Path) -> anyhow::Result<Option<Answers>> {
+        let answers = Answers::open(day, input)?;
+        if self.cached {
+            if let Some(cached) = answers.cached()? {
+                print!("{cached}");
+                return Ok(None);
+            }
+            debug!("No cached answers for {day}; solving");
+        }
+        Ok(Some(answers))
+    }
+}
+
+/// The directory answers are stored under: `$XDG_CACHE_HOME/aoc2021`, or
+/// `~/.cache/aoc2021`.
+pub fn cache_dir() -> Option<PathBuf> {
+    let base = match std::env::var_os("XDG_CACHE_HOME") {
+        Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => PathBuf::from(std::env::var_os("HOME")?).join(".cache"),
+    };
+    Some(base.join("aoc2021"))
+}
+
+/// Hex-encoded SHA-256 of some input.
+pub fn input_hash(input: &[u8]) -> String {
+    Sha256::digest(input)
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect()
+}
+
+/// Collects the printed answers for one day and input, so they can be
+/// stored under `<cache dir>/<day>/<input sha>` and printed again later
+/// without solving.
+///
+/// Write answers to it with `writeln!`; everything written is echoed to
+/// stdout.
+#[derive(Debug)]
+pub struct Answers {
+    path: Option<PathBuf>,
+    output: String,
+}
+
+impl Answers {
+    /// Answers for `day`, keyed by the contents of the input file.
+    pub fn open(day: &str, input: &Path) -> anyhow::Result<Self> {
+        let contents = fs::read(input).with_context(|| format!("reading {}", input.display()))?;
+        let path = cache_dir().map(|dir| dir.join(day).join(input_hash(&contents)));
+        Ok(Answers::at(path))
+    }
+
+    /// Answers stored at a specific path, or not stored at all if `None`.
+    pub fn at(path: Option<PathBuf>) -> Self {
+        Answers {
+            path,
+            output: String::new(),
+        }
+    }
+
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+
+    /// Everything written so far
+    pub fn output(&self) -> &str {
+        &self.output
+    }
+
+    /// The previously stored answers, if any.
+    pub fn cached(&self) -> anyhow::Result<Option<String>> {
+        let Some(path) = &self.path else {
+            return Ok(None);
+        };
+        match fs::read_to_string(path) {
+            Ok(s) => {
+                debug!("Loaded cached answers from {}", path.display());
+                Ok(Some(s))
+            }
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e).with_context(|| format!("reading {}", path.display())),
+        }
+    }
+
+    /// Stores everything written so far, replacing any previous answers.
+    ///
+    /// Failing to write the cache is not fatal; it only logs a warning.
+    pub fn save(&self) {
+        let Some(path) = &self.path else {
+            return;
+        };
+        let result = path
+            .parent()
+            .map_or(Ok(()), fs::create_dir_all)
+            .and_then(|()| fs::write(path, &self.output));
+        match result {
+            Ok(()) => debug!("Stored answers in {}", path.display()),
+            Err(e) => warn!("Could not store answers in {}: {e}", path.display()),
+        }
+    }
+}
+
+impl fmt::Write for Answers {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        print!("{s}");
+        self.output.push_str(s);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fmt::Write;
+
+    use test_log::test;
+
+    use super::*;
+
+    #[test]
+    fn test_hash() {
+        assert_eq!(
+            input_hash(b"abc"),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        let dir = std::env::temp_dir().join(format!("aoc2021-cache-{}", std::process::id()));
+        let path = dir.join("day01").join(input_hash(b"1\n2\n"));
+
+        let mut answers = Answers::at(Some(path.clone()));
+        assert_eq!(answers.cached().unwrap(), None);
+        writeln!(answers, "Part 1: {}", 7).unwrap();
+        answers.save();
+
+        let answers = Answers::at(Some(path));
+        assert_eq!(answers.cached().unwrap().as_deref(), Some("Part 1: 7\n"));
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
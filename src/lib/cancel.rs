@@ -0,0 +1,208 @@
+//! Stopping long-running solvers early.
+//!
+//! [`with_timeout`] runs a solver on a worker thread with a [`CancelToken`]
+//! installed. Solvers with long search loops check
+//! [`CancelToken::current()`] as they go, and when it is cancelled return a
+//! [`Cancelled`] error describing how far they got. Solvers that never check
+//! keep running in the background until the process exits.
+
+use std::cell::RefCell;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc};
+use std::time::Duration;
+
+use thiserror::Error;
+
+use crate::trace::format_duration;
+
+/// How long to wait for a cancelled solver to stop and report its progress
+const GRACE: Duration = Duration::from_secs(1);
+
+thread_local! {
+    static CURRENT: RefCell<CancelToken> = RefCell::new(CancelToken::new());
+}
+
+/// A flag a solver can check to see whether it should stop. Clones share the
+/// same flag.
+#[derive(Debug, Clone, Default)]
+pub struct CancelToken(Arc<AtomicBool>);
+
+impl CancelToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The token installed on this thread by [`CancelToken::run`], or one that
+    /// is never cancelled
+    pub fn current() -> Self {
+        CURRENT.with(|current| current.borrow().clone())
+    }
+
+    /// Runs `f` with this as the current token
+    pub fn run<R>(&self, f: impl FnOnce() -> R) -> R {
+        let previous = CURRENT.with(|current| current.replace(self.clone()));
+        let result = f();
+        CURRENT.with(|current| current.replace(previous));
+        result
+    }
+
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+
+    /// Returns an error if cancelled. `progress` describes how far the solver
+    /// got, e.g. `lower bound 1200, 3400 states explored`; it is only called
+    /// when needed.
+    pub fn check(&self, progress: impl FnOnce() -> String) -> Result<(), Cancelled> {
+        if !self.is_cancelled() {
+            return Ok(());
+        }
+        Err(Cancelled {
+            progress: progress(),
+        })
+    }
+}
+
+/// A solver stopped early because its token was cancelled
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+#[error("Cancelled ({progress})")]
+pub struct Cancelled {
+    pub progress: String,
+}
+
+/// A solver ran past its time limit
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub struct TimedOut {
+    pub limit: Duration,
+    /// The solver's report of how far it got, if it stopped when asked
+    pub progress: Option<String>,
+}
+
+impl std::fmt::Display for TimedOut {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Timed out after {}", format_duration(self.limit))?;
+        match &self.progress {
+            Some(progress) => write!(f, " ({progress})"),
+            None => write!(f, " (no progress reported)"),
+        }
+    }
+}
+
+/// Runs `f` on a worker thread, giving up after `limit`.
+///
+/// On timeout, the worker's token is cancelled, and the error is a
+/// [`TimedOut`] with whatever progress the solver reported as it stopped. A
+/// solver that doesn't stop within a second is left running in the
+/// background. A panic in `f` is resumed on this thread.
+pub fn with_timeout<T, F>(limit: Duration, f: F) -> anyhow::Result<T>
+where
+    T: Send + 'static,
+    F: FnOnce() -> anyhow::Result<T> + Send + 'static,
+{
+    let token = CancelToken::new();
+    let (sender, receiver) = mpsc::channel();
+    let worker = {
+        let token = token.clone();
+        std::thread::spawn(move || {
+            let result = token.run(f);
+            // The receiver is gone if we gave up waiting
+            let _ = sender.send(result);
+        })
+    };
+
+    let result = match receiver.recv_timeout(limit) {
+        Ok(result) => return result,
+        Err(mpsc::RecvTimeoutError::Timeout) => {
+            token.cancel();
+            receiver.recv_timeout(GRACE)
+        }
+        Err(mpsc::RecvTimeoutError::Disconnected) => Err(mpsc::RecvTimeoutError::Disconnected),
+    };
+
+    match result {
+        // Finished just as it was cancelled
+        Ok(Ok(value)) => Ok(value),
+        Ok(Err(e)) => match e.downcast_ref::<Cancelled>() {
+            Some(cancelled) => Err(TimedOut {
+                limit,
+                progress: Some(cancelled.progress.clone()),
+            }
+            .into()),
+            None => Err(e),
+        },
+        Err(mpsc::RecvTimeoutError::Timeout) => Err(TimedOut {
+            limit,
+            progress: None,
+        }
+        .into()),
+        Err(mpsc::RecvTimeoutError::Disconnected) => match worker.join() {
+            Err(panic) => std::panic::resume_unwind(panic),
+            Ok(()) => unreachable!("Worker exited without sending a result"),
+        },
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// Tests
+
+#[cfg(test)]
+mod tests {
+    use test_log::test;
+
+    #[allow(unused_imports)]
+    use super::*;
+
+    #[test]
+    fn test_token() {
+        let token = CancelToken::new();
+        assert!(token.check(|| unreachable!()).is_ok());
+        assert!(!CancelToken::current().is_cancelled());
+
+        token.cancel();
+        let current = token.run(|| CancelToken::current().is_cancelled());
+        assert!(current);
+        assert!(!CancelToken::current().is_cancelled());
+
+        let err = token.check(|| "3 steps".to_owned()).unwrap_err();
+        assert_eq!(err.to_string(), "Cancelled (3 steps)");
+    }
+
+    #[test]
+    fn test_finishes() {
+        let value = with_timeout(Duration::from_secs(10), || Ok(42)).unwrap();
+        assert_eq!(value, 42);
+
+        let err = with_timeout::<(), _>(Duration::from_secs(10), || anyhow::bail!("oops"));
+        assert_eq!(err.unwrap_err().to_string(), "oops");
+    }
+
+    #[test]
+    fn test_timeout() {
+        let err = with_timeout::<(), _>(Duration::from_millis(10), || {
+            let token = CancelToken::current();
+            let mut steps = 0;
+            loop {
+                token.check(|| format!("{steps} steps"))?;
+                steps += 1;
+                std::thread::sleep(Duration::from_millis(1));
+            }
+        })
+        .unwrap_err();
+
+        let timed_out = err.downcast_ref::<TimedOut>().unwrap();
+        assert_eq!(timed_out.limit, Duration::from_millis(10));
+        let progress = timed_out.progress.as_deref().unwrap();
+        assert!(progress.ends_with(" steps"), "{progress}");
+        assert!(err.to_string().starts_with("Timed out after 10ms ("));
+    }
+
+    #[test]
+    #[should_panic(expected = "worker panic")]
+    fn test_panic() {
+        let _ = with_timeout::<(), _>(Duration::from_secs(10), || panic!("worker panic"));
+    }
+}
@@ -0,0 +1,176 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{ensure, Context};
+use serde::Deserialize;
+use tracing::debug;
+
+/// Name of the optional config file, looked for in the current directory
+pub const CONFIG_FILE: &str = "aoc.toml";
+/// Environment variable overriding the input directory
+pub const INPUT_DIR_VAR: &str = "AOC_INPUT_DIR";
+/// Input directory used when nothing else is configured
+pub const DEFAULT_INPUT_DIR: &str = "inputs";
+
+/// Settings from `aoc.toml`.
+///
+/// ```toml
+/// input_dir = "../my-inputs"
+/// ```
+///
+/// Relative paths are relative to the directory containing the file.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+pub struct Config {
+    pub input_dir: Option<PathBuf>,
+}
+
+impl Config {
+    /// Loads `aoc.toml` from the current directory, or the default config if
+    /// there is none.
+    pub fn load() -> anyhow::Result<Self> {
+        let path = Path::new(CONFIG_FILE);
+        if !path.exists() {
+            return Ok(Config::default());
+        }
+        Config::from_file(path)
+    }
+
+    pub fn from_file(path: &Path) -> anyhow::Result<Self> {
+        let s =
+            std::fs::read_to_string(path).with_context(|| format!("reading {}", path.display()))?;
+        let mut config: Config =
+            toml::from_str(&s).with_context(|| format!("parsing {}", path.display()))?;
+
+        let base = path.parent().unwrap_or(Path::new(""));
+        config.input_dir = config.input_dir.map(|dir| base.join(dir));
+        debug!("Loaded {}: {:?}", path.display(), config);
+        Ok(config)
+    }
+
+    /// The input directory: `$AOC_INPUT_DIR` if set, then `input_dir` from the
+    /// config file, then `inputs`.
+    pub fn input_dir(&self) -> PathBuf {
+        match std::env::var_os(INPUT_DIR_VAR) {
+            Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+            _ => self
+                .input_dir
+                .clone()
+                .unwrap_or_else(|| PathBuf::from(DEFAULT_INPUT_DIR)),
+        }
+    }
+
+    /// The default input file for a binary, e.g. `inputs/day01.txt` for `day01`
+    pub fn default_input(&self, name: &str) -> PathBuf {
+        self.input_dir().join(format!("{name}.txt"))
+    }
+}
+
+/// Returns `input` if one was given on the command line, and otherwise the
+/// configured default input for `name`.
+pub fn input_path(input: Option<PathBuf>, name: &str) -> anyhow::Result<PathBuf> {
+    match input {
+        Some(path) => Ok(path),
+        None => Ok(Config::load()?.default_input(name)),
+    }
+}
+
+/// Expands a list of `--input` arguments: directories are replaced by the
+/// files directly inside them, sorted by name. With no arguments, returns the
+/// configured default input for `name`.
+pub fn input_paths(inputs: Vec<PathBuf>, name: &str) -> anyhow::Result<Vec<PathBuf>> {
+    if inputs.is_empty() {
+        return Ok(vec![Config::load()?.default_input(name)]);
+    }
+
+    let mut paths = Vec::new();
+    for input in inputs {
+        if !input.is_dir() {
+            paths.push(input);
+            continue;
+        }
+
+        let entries =
+            std::fs::read_dir(&input).with_context(|| format!("reading {}", input.display()))?;
+        let mut files = Vec::new();
+        for entry in entries {
+            let path = entry
+                .with_context(|| format!("reading {}", input.display()))?
+                .path();
+            if path.is_file() {
+                files.push(path);
+            }
+        }
+        ensure!(!files.is_empty(), "No input files in {}", input.display());
+        files.sort();
+        paths.extend(files);
+    }
+
+    Ok(paths)
+}
+
+#[cfg(test)]
+mod tests {
+    use test_log::test;
+
+    use super::*;
+
+    #[test]
+    fn test_config_file() {
+        let dir = std::env::temp_dir().join(format!("aoc2021-config-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(CONFIG_FILE);
+
+        std::fs::write(&path, "input_dir = \"data\"\n").unwrap();
+        let config = Config::from_file(&path).unwrap();
+        assert_eq!(config.input_dir, Some(dir.join("data")));
+
+        std::fs::write(&path, "input_dir = 3\n").unwrap();
+        assert!(Config::from_file(&path).is_err());
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_default_input() {
+        let config = Config {
+            input_dir: Some(PathBuf::from("elsewhere")),
+        };
+        if std::env::var_os(INPUT_DIR_VAR).is_none() {
+            assert_eq!(
+                config.default_input("day07"),
+                Path::new("elsewhere/day07.txt")
+            );
+            assert_eq!(
+                Config::default().default_input("day07"),
+                Path::new("inputs/day07.txt")
+            );
+        }
+
+        let given = Some(PathBuf::from("example.txt"));
+        assert_eq!(
+            input_path(given, "day07").unwrap(),
+            Path::new("example.txt")
+        );
+    }
+
+    #[test]
+    fn test_input_paths() {
+        let dir = std::env::temp_dir().join(format!("aoc2021-inputs-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("empty")).unwrap();
+        std::fs::write(dir.join("b.txt"), "").unwrap();
+        std::fs::write(dir.join("a.txt"), "").unwrap();
+
+        let given = vec![PathBuf::from("example.txt"), dir.clone()];
+        assert_eq!(
+            input_paths(given, "day07").unwrap(),
+            vec![
+                PathBuf::from("example.txt"),
+                dir.join("a.txt"),
+                dir.join("b.txt")
+            ]
+        );
+        assert_eq!(input_paths(vec![], "day07").unwrap().len(), 1);
+        assert!(input_paths(vec![dir.join("empty")], "day07").is_err());
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
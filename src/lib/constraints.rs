@@ -0,0 +1,295 @@
+//! A small constraint-propagation engine: variables, the domain of values each
+//! could still take, and reduction passes that narrow the domains down until
+//! none of them makes progress.
+//!
+//! [`Domains`] holds the variables and their domains, with the reductions
+//! that don't depend on the puzzle: [`Domains::exclude_known`] and
+//! [`Domains::hidden_singles`] for variables that must all take different
+//! values. Puzzle-specific passes are plain functions of the solver's state,
+//! run to a fixed point by [`propagate`]. Day 08's wiring solver is built on
+//! it.
+
+use std::collections::{HashMap, HashSet};
+use std::hash::Hash;
+
+/// Values removed from one variable's domain
+pub type Narrowed<K, V> = (K, HashSet<V>);
+
+/// A reduction pass over a solver's state; returns whether it changed
+/// anything
+pub type Pass<S> = fn(&mut S) -> bool;
+
+/// The values each variable could still take
+#[derive(Debug, Clone)]
+pub struct Domains<K, V> {
+    domains: HashMap<K, HashSet<V>>,
+}
+
+impl<K, V> Default for Domains<K, V> {
+    fn default() -> Self {
+        Domains {
+            domains: HashMap::new(),
+        }
+    }
+}
+
+impl<K: Hash + Eq, V: Hash + Eq> PartialEq for Domains<K, V> {
+    fn eq(&self, other: &Self) -> bool {
+        self.domains == other.domains
+    }
+}
+
+impl<K: Hash + Eq, V: Hash + Eq> Eq for Domains<K, V> {}
+
+impl<K: Hash + Eq + Clone, V: Hash + Eq + Clone> Domains<K, V> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Number of variables
+    pub fn len(&self) -> usize {
+        self.domains.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.domains.is_empty()
+    }
+
+    /// Adds `var`, or replaces its domain, returning the old one
+    pub fn insert(&mut self, var: K, domain: HashSet<V>) -> Option<HashSet<V>> {
+        self.domains.insert(var, domain)
+    }
+
+    pub fn get(&self, var: &K) -> Option<&HashSet<V>> {
+        self.domains.get(var)
+    }
+
+    pub fn vars(&self) -> impl Iterator<Item = &K> {
+        self.domains.keys()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&K, &HashSet<V>)> {
+        self.domains.iter()
+    }
+
+    /// The value of `var`, if its domain is down to one
+    pub fn known(&self, var: &K) -> Option<&V> {
+        let domain = self.domains.get(var)?;
+        if domain.len() != 1 {
+            return None;
+        }
+        domain.iter().next()
+    }
+
+    /// Whether every domain is down to one value
+    pub fn all_known(&self) -> bool {
+        self.domains.values().all(|d| d.len() == 1)
+    }
+
+    /// Keeps only the values of `var` for which `keep` is true, returning the
+    /// rest
+    pub fn retain(&mut self, var: &K, keep: impl FnMut(&V) -> bool) -> HashSet<V> {
+        match self.domains.get_mut(var) {
+            Some(domain) => extract(domain, keep),
+            None => HashSet::new(),
+        }
+    }
+
+    /// Like [`Domains::retain`] for every variable at once, returning what was
+    /// removed from each variable that changed
+    pub fn retain_all(&mut self, mut keep: impl FnMut(&K, &V) -> bool) -> Vec<Narrowed<K, V>> {
+        let mut narrowed = Vec::new();
+        for (var, domain) in &mut self.domains {
+            let removed = extract(domain, |v| keep(var, v));
+            if !removed.is_empty() {
+                narrowed.push((var.clone(), removed));
+            }
+        }
+        narrowed
+    }
+
+    /// Sets `var` to `value`, whether or not it was still possible, returning
+    /// the other values it could have been
+    pub fn assign(&mut self, var: K, value: V) -> HashSet<V> {
+        let mut before = self
+            .domains
+            .insert(var, HashSet::from([value.clone()]))
+            .unwrap_or_default();
+        before.remove(&value);
+        before
+    }
+
+    /// For variables that must all differ: removes each known value from every
+    /// other variable's domain
+    pub fn exclude_known(&mut self) -> Vec<Narrowed<K, V>> {
+        let known: HashSet<V> = self
+            .domains
+            .values()
+            .filter(|d| d.len() == 1)
+            .flatten()
+            .cloned()
+            .collect();
+
+        let mut narrowed = Vec::new();
+        for (var, domain) in &mut self.domains {
+            if domain.len() == 1 {
+                continue;
+            }
+            let removed = extract(domain, |v| !known.contains(v));
+            if !removed.is_empty() {
+                narrowed.push((var.clone(), removed));
+            }
+        }
+        narrowed
+    }
+
+    /// For variables that must all differ: a value only one variable could
+    /// take is that variable's
+    pub fn hidden_singles(&mut self) -> Vec<Narrowed<K, V>> {
+        let mut counts: HashMap<V, usize> = HashMap::new();
+        for value in self.domains.values().flatten() {
+            *counts.entry(value.clone()).or_insert(0) += 1;
+        }
+
+        let mut narrowed = Vec::new();
+        for (value, count) in counts {
+            if count != 1 {
+                continue;
+            }
+            let Some((var, domain)) = self
+                .domains
+                .iter_mut()
+                .find(|(_, d)| d.len() > 1 && d.contains(&value))
+            else {
+                continue;
+            };
+            let removed = extract(domain, |v| *v == value);
+            narrowed.push((var.clone(), removed));
+        }
+        narrowed
+    }
+}
+
+impl<K: Hash + Eq, V> FromIterator<(K, HashSet<V>)> for Domains<K, V> {
+    fn from_iter<I: IntoIterator<Item = (K, HashSet<V>)>>(iter: I) -> Self {
+        Domains {
+            domains: iter.into_iter().collect(),
+        }
+    }
+}
+
+/// Keeps the values in `domain` for which `keep` is true, returning the rest
+fn extract<V: Hash + Eq>(domain: &mut HashSet<V>, mut keep: impl FnMut(&V) -> bool) -> HashSet<V> {
+    let (kept, removed) = std::mem::take(domain).into_iter().partition(|v| keep(v));
+    *domain = kept;
+    removed
+}
+
+/// Runs every pass in `passes` over `state`, round after round, until a round
+/// changes nothing. Then tries `fallbacks` in order — passes too slow or too
+/// specialised to run every round — going back to `passes` as soon as one
+/// makes progress. Returns the number of rounds run.
+pub fn propagate<S>(state: &mut S, passes: &[Pass<S>], fallbacks: &[Pass<S>]) -> usize {
+    let mut rounds = 0;
+    loop {
+        rounds += 1;
+        let mut changed = false;
+        for pass in passes {
+            changed |= pass(state);
+        }
+        if changed {
+            continue;
+        }
+        if !fallbacks.iter().any(|fallback| fallback(state)) {
+            return rounds;
+        }
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// Tests
+
+#[cfg(test)]
+mod tests {
+    use test_log::test;
+
+    #[allow(unused_imports)]
+    use super::*;
+
+    fn domains(vars: &[(&'static str, &[u8])]) -> Domains<&'static str, u8> {
+        vars.iter()
+            .map(|&(var, values)| (var, values.iter().copied().collect()))
+            .collect()
+    }
+
+    #[test]
+    fn test_domains() {
+        let mut ds = domains(&[("x", &[1]), ("y", &[1, 2, 3]), ("z", &[2, 3])]);
+        assert_eq!(ds.len(), 3);
+        assert_eq!(ds.known(&"x"), Some(&1));
+        assert_eq!(ds.known(&"y"), None);
+        assert!(!ds.all_known());
+
+        assert_eq!(ds.exclude_known(), vec![("y", HashSet::from([1]))]);
+        assert!(ds.exclude_known().is_empty());
+        assert_eq!(ds, domains(&[("x", &[1]), ("y", &[2, 3]), ("z", &[2, 3])]));
+
+        assert_eq!(ds.retain(&"z", |&v| v != 2), HashSet::from([2]));
+        assert_eq!(ds.retain(&"w", |_| false), HashSet::new());
+        assert_eq!(ds.exclude_known(), vec![("y", HashSet::from([3]))]);
+        assert!(ds.all_known());
+
+        assert_eq!(ds.assign("x", 4), HashSet::from([1]));
+        assert_eq!(ds.assign("w", 5), HashSet::new());
+        assert_eq!(ds.get(&"w"), Some(&HashSet::from([5])));
+    }
+
+    #[test]
+    fn test_hidden_singles() {
+        // Only y can be 3
+        let mut ds = domains(&[("x", &[1, 2]), ("y", &[1, 2, 3]), ("z", &[1, 2])]);
+        assert_eq!(ds.hidden_singles(), vec![("y", HashSet::from([1, 2]))]);
+        assert_eq!(ds.known(&"y"), Some(&3));
+        assert!(ds.hidden_singles().is_empty());
+
+        let mut narrowed = ds.retain_all(|&var, &v| var != "x" || v != 1);
+        narrowed.sort_by_key(|&(var, _)| var);
+        assert_eq!(narrowed, vec![("x", HashSet::from([1]))]);
+    }
+
+    struct Puzzle {
+        domains: Domains<&'static str, u8>,
+        fallbacks: usize,
+    }
+
+    impl Puzzle {
+        fn all_different(&mut self) -> bool {
+            !self.domains.exclude_known().is_empty() | !self.domains.hidden_singles().is_empty()
+        }
+
+        // Stands in for a slower rule, e.g. a guess
+        fn pick_x(&mut self) -> bool {
+            self.fallbacks += 1;
+            let Some(&x) = self.domains.get(&"x").and_then(|d| d.iter().min()) else {
+                return false;
+            };
+            !self.domains.assign("x", x).is_empty()
+        }
+    }
+
+    #[test]
+    fn test_propagate() {
+        let mut puzzle = Puzzle {
+            domains: domains(&[("x", &[1, 2]), ("y", &[1, 2]), ("z", &[1, 2, 3])]),
+            fallbacks: 0,
+        };
+        let rounds = propagate(&mut puzzle, &[Puzzle::all_different], &[Puzzle::pick_x]);
+        assert_eq!(
+            puzzle.domains,
+            domains(&[("x", &[1]), ("y", &[2]), ("z", &[3])])
+        );
+        // z, then nothing, then x and y, then nothing
+        assert_eq!(rounds, 4);
+        assert_eq!(puzzle.fallbacks, 2);
+    }
+}
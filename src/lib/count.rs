@@ -0,0 +1,74 @@
+//! The integer type used by puzzles that count huge numbers of states: the
+//! lanternfish of day 06, the polymer pairs of day 14 and the Dirac dice
+//! universes of day 21.
+//!
+//! By default this is a `u64`, which is plenty for the real puzzle inputs but
+//! can overflow on larger or adversarial ones — a panic in debug builds, and a
+//! silently wrong answer in release. The `wide` feature switches to `u128`,
+//! and the `bigint` feature to an arbitrary-precision `BigUint`.
+//!
+//! Code using `Count` should work for all three, so it can't rely on `Copy` or
+//! on untyped integer literals; use [`count`] to make one from a number.
+
+use thiserror::Error;
+
+#[cfg(feature = "bigint")]
+pub type Count = num_bigint::BigUint;
+
+#[cfg(all(feature = "wide", not(feature = "bigint")))]
+pub type Count = u128;
+
+#[cfg(not(any(feature = "wide", feature = "bigint")))]
+pub type Count = u64;
+
+/// A calculation needed a larger number than a [`Count`] holds
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Error)]
+#[error("Count overflowed; build with the `wide` or `bigint` feature for larger counts")]
+pub struct Overflow;
+
+/// Converts `n` to a [`Count`]
+pub fn count(n: u64) -> Count {
+    Count::from(n)
+}
+
+/// `a + b`, or `None` if it doesn't fit in a [`Count`]
+#[cfg(feature = "bigint")]
+pub fn checked_add(a: &Count, b: &Count) -> Option<Count> {
+    Some(a + b)
+}
+
+/// `a + b`, or `None` if it doesn't fit in a [`Count`]
+#[cfg(not(feature = "bigint"))]
+pub fn checked_add(a: &Count, b: &Count) -> Option<Count> {
+    a.checked_add(*b)
+}
+
+/// `a * b`, or `None` if it doesn't fit in a [`Count`]
+#[cfg(feature = "bigint")]
+pub fn checked_mul(a: &Count, b: &Count) -> Option<Count> {
+    Some(a * b)
+}
+
+/// `a * b`, or `None` if it doesn't fit in a [`Count`]
+#[cfg(not(feature = "bigint"))]
+pub fn checked_mul(a: &Count, b: &Count) -> Option<Count> {
+    a.checked_mul(*b)
+}
+
+/// `n % m`, as a `u64`
+#[cfg(feature = "bigint")]
+pub fn residue(n: &Count, m: u64) -> u64 {
+    u64::try_from(&(n % m)).unwrap()
+}
+
+/// `n % m`, as a `u64`
+#[cfg(all(feature = "wide", not(feature = "bigint")))]
+pub fn residue(n: &Count, m: u64) -> u64 {
+    (n % m as u128) as u64
+}
+
+/// `n % m`, as a `u64`
+#[cfg(not(any(feature = "wide", feature = "bigint")))]
+pub fn residue(n: &Count, m: u64) -> u64 {
+    n % m
+}
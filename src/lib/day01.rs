@@ -0,0 +1,1094 @@
+use std::cmp::Ordering;
+use std::collections::VecDeque;
+use std::fmt;
+use std::io::BufRead;
+use std::str::FromStr;
+
+use anyhow::{anyhow, bail, ensure, Context};
+use itertools::{process_results, Either, Itertools};
+use parse_display::Display;
+use serde::{Deserialize, Serialize};
+use tracing::{debug, info_span};
+
+use crate::parse;
+use crate::solution::Solved;
+
+/// A depth reading. Puzzle inputs are whole numbers, but some sonar reports
+/// fractional depths, so everything here works with `f64` readings too.
+pub trait Depth: Copy + PartialOrd + fmt::Debug + FromStr {
+    fn to_f64(self) -> f64;
+
+    /// The depth as a whole number, if it was read as one
+    fn whole(self) -> Option<i64> {
+        None
+    }
+
+    /// Whether this is a usable reading, i.e. not NaN or infinite
+    fn is_finite(self) -> bool {
+        true
+    }
+}
+
+impl Depth for i64 {
+    fn to_f64(self) -> f64 {
+        self as f64
+    }
+
+    fn whole(self) -> Option<i64> {
+        Some(self)
+    }
+}
+
+impl Depth for f64 {
+    fn to_f64(self) -> f64 {
+        self
+    }
+
+    fn is_finite(self) -> bool {
+        f64::is_finite(self)
+    }
+}
+
+/// A depth read as exactly as it was written: whole numbers as `i64`, and
+/// only readings with a fraction as `f64`, so large whole depths don't lose
+/// precision. Compares by value whichever way each side was read.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum ExactDepth {
+    Whole(i64),
+    Fractional(f64),
+}
+
+impl Depth for ExactDepth {
+    fn to_f64(self) -> f64 {
+        match self {
+            ExactDepth::Whole(n) => n as f64,
+            ExactDepth::Fractional(x) => x,
+        }
+    }
+
+    fn whole(self) -> Option<i64> {
+        match self {
+            ExactDepth::Whole(n) => Some(n),
+            ExactDepth::Fractional(_) => None,
+        }
+    }
+
+    fn is_finite(self) -> bool {
+        match self {
+            ExactDepth::Whole(_) => true,
+            ExactDepth::Fractional(x) => x.is_finite(),
+        }
+    }
+}
+
+/// Compares a whole number with a float without rounding the whole number
+fn compare_exact(whole: i64, x: f64) -> Option<Ordering> {
+    if x.is_nan() {
+        return None;
+    }
+    // 2^63, just past i64::MAX; infinities fall outside too
+    let limit = 2f64.powi(63);
+    let floor = x.floor();
+    if floor >= limit {
+        return Some(Ordering::Less);
+    }
+    if floor < -limit {
+        return Some(Ordering::Greater);
+    }
+    // In range, the floor converts to i64 exactly
+    let fraction = if x > floor {
+        Ordering::Less
+    } else {
+        Ordering::Equal
+    };
+    Some(whole.cmp(&(floor as i64)).then(fraction))
+}
+
+impl PartialOrd for ExactDepth {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        use ExactDepth::*;
+        match (*self, *other) {
+            (Whole(a), Whole(b)) => Some(a.cmp(&b)),
+            (Fractional(a), Fractional(b)) => a.partial_cmp(&b),
+            (Whole(a), Fractional(b)) => compare_exact(a, b),
+            (Fractional(a), Whole(b)) => compare_exact(b, a).map(Ordering::reverse),
+        }
+    }
+}
+
+impl PartialEq for ExactDepth {
+    fn eq(&self, other: &Self) -> bool {
+        self.partial_cmp(other) == Some(Ordering::Equal)
+    }
+}
+
+impl FromStr for ExactDepth {
+    type Err = std::num::ParseFloatError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.parse() {
+            Ok(n) => Ok(ExactDepth::Whole(n)),
+            Err(_) => s.parse().map(ExactDepth::Fractional),
+        }
+    }
+}
+
+impl fmt::Display for ExactDepth {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ExactDepth::Whole(n) => write!(f, "{n}"),
+            ExactDepth::Fractional(x) => write!(f, "{x}"),
+        }
+    }
+}
+
+/// A value worked out from several depths, such as a window's mean: an exact
+/// fraction while every depth is whole, and an `f64` once any is fractional
+#[derive(Debug, Clone, Copy)]
+pub enum Combined {
+    /// `numerator / denominator`, with a positive denominator
+    Exact(i128, i128),
+    Float(f64),
+}
+
+impl Combined {
+    /// A single depth, exact if it's whole
+    pub fn of<T: Depth>(depth: T) -> Self {
+        match depth.whole() {
+            Some(n) => Combined::Exact(n.into(), 1),
+            None => Combined::Float(depth.to_f64()),
+        }
+    }
+
+    pub fn to_f64(self) -> f64 {
+        match self {
+            Combined::Exact(n, d) => n as f64 / d as f64,
+            Combined::Float(x) => x,
+        }
+    }
+}
+
+impl PartialOrd for Combined {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        use Combined::*;
+        match (*self, *other) {
+            (Exact(a, b), Exact(c, d)) => Some((a * d).cmp(&(c * b))),
+            (Exact(n, d), Float(x)) => compare_fraction(n, d, x),
+            (Float(_), Exact(..)) => other.partial_cmp(self).map(Ordering::reverse),
+            (Float(a), Float(b)) => a.partial_cmp(&b),
+        }
+    }
+}
+
+/// Compares `n / d`, for a positive `d`, with a float: the whole part
+/// exactly, as [`compare_exact`] does, and only what's left under 1 as `f64`
+fn compare_fraction(n: i128, d: i128, x: f64) -> Option<Ordering> {
+    let (whole, rest) = (n.div_euclid(d), n.rem_euclid(d));
+    let Ok(whole) = i64::try_from(whole) else {
+        // Past any i64, where floats are whole numbers anyway
+        return (n as f64 / d as f64).partial_cmp(&x);
+    };
+    let floor = x.floor();
+    match compare_exact(whole, floor)? {
+        Ordering::Equal => (rest as f64 / d as f64).partial_cmp(&(x - floor)),
+        unequal => Some(unequal),
+    }
+}
+
+impl PartialEq for Combined {
+    fn eq(&self, other: &Self) -> bool {
+        self.partial_cmp(other) == Some(Ordering::Equal)
+    }
+}
+
+pub fn find_increases<T: Depth>(depths: &[T]) -> isize {
+    depths.iter().tuple_windows().filter(|(a, b)| a < b).count() as isize
+}
+
+/// Indices of the readings deeper than the one before
+pub fn increase_positions<T: Depth>(depths: &[T]) -> Vec<usize> {
+    (1..depths.len())
+        .filter(|&ix| depths[ix - 1] < depths[ix])
+        .collect()
+}
+
+/// Draws depths as a unicode sparkline, one bar per reading from shallowest
+/// `▁` to deepest `█`, wrapped every `width` readings. With `color`, readings
+/// deeper than the one before are highlighted.
+#[derive(Debug, Clone)]
+pub struct Sparkline<'a, T> {
+    pub depths: &'a [T],
+    pub width: usize,
+    pub color: bool,
+}
+
+impl<'a, T: Depth> Sparkline<'a, T> {
+    const BARS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+    const HIGHLIGHT: &'static str = "\x1b[1;33m";
+    const RESET: &'static str = "\x1b[0m";
+
+    /// An uncolored sparkline, 80 readings wide
+    pub fn new(depths: &'a [T]) -> Self {
+        Sparkline {
+            depths,
+            width: 80,
+            color: false,
+        }
+    }
+
+    fn bar(depth: f64, min: f64, max: f64) -> char {
+        if max <= min {
+            return Self::BARS[0];
+        }
+        let level = ((depth - min) / (max - min) * (Self::BARS.len() - 1) as f64).round();
+        Self::BARS[level as usize]
+    }
+}
+
+impl<T: Depth> fmt::Display for Sparkline<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let values: Vec<f64> = self.depths.iter().map(|d| d.to_f64()).collect();
+        let min = values.iter().copied().fold(f64::INFINITY, f64::min);
+        let max = values.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+
+        for (ix, &value) in values.iter().enumerate() {
+            if ix > 0 && ix % self.width.max(1) == 0 {
+                writeln!(f)?;
+            }
+            let bar = Self::bar(value, min, max);
+            if self.color && ix > 0 && values[ix - 1] < value {
+                write!(f, "{}{bar}{}", Self::HIGHLIGHT, Self::RESET)?;
+            } else {
+                write!(f, "{bar}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// How to combine the readings in a window into one value to compare
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Display, clap::ValueEnum)]
+#[display(style = "lowercase")]
+pub enum Aggregate {
+    #[default]
+    Sum,
+    Mean,
+    /// The middle reading, or the mean of the middle two
+    Median,
+    Max,
+}
+
+impl Aggregate {
+    /// Combines `readings`, which must not be empty; exactly, if they're all
+    /// whole
+    pub fn of<T: Depth>(self, readings: &[T]) -> Combined {
+        assert!(!readings.is_empty(), "Can't aggregate an empty window");
+        let whole: Option<Vec<i128>> = readings.iter().map(|r| r.whole().map(i128::from)).collect();
+        let Some(mut whole) = whole else {
+            return Combined::Float(self.of_floats(readings));
+        };
+        let n = whole.len() as i128;
+        match self {
+            Aggregate::Sum => Combined::Exact(whole.iter().sum(), 1),
+            Aggregate::Mean => Combined::Exact(whole.iter().sum(), n),
+            Aggregate::Median => {
+                whole.sort_unstable();
+                let mid = whole.len() / 2;
+                if whole.len() % 2 == 1 {
+                    Combined::Exact(whole[mid], 1)
+                } else {
+                    Combined::Exact(whole[mid - 1] + whole[mid], 2)
+                }
+            }
+            Aggregate::Max => Combined::Exact(*whole.iter().max().unwrap(), 1),
+        }
+    }
+
+    /// Like [`Aggregate::of`], for readings that aren't all whole
+    fn of_floats<T: Depth>(self, readings: &[T]) -> f64 {
+        let values = readings.iter().map(|r| r.to_f64());
+        match self {
+            Aggregate::Sum => values.sum(),
+            Aggregate::Mean => values.sum::<f64>() / readings.len() as f64,
+            Aggregate::Median => {
+                let mut sorted: Vec<f64> = values.collect();
+                sorted.sort_by(f64::total_cmp);
+                let mid = sorted.len() / 2;
+                if sorted.len() % 2 == 1 {
+                    sorted[mid]
+                } else {
+                    (sorted[mid - 1] + sorted[mid]) / 2.0
+                }
+            }
+            Aggregate::Max => values.fold(f64::NEG_INFINITY, f64::max),
+        }
+    }
+}
+
+/// Counts how often the `aggregate` of `window_size` consecutive readings is
+/// larger than that of the window one reading earlier
+pub fn find_window_increases<T: Depth>(
+    depths: &[T],
+    window_size: usize,
+    aggregate: Aggregate,
+) -> isize {
+    window_increases(depths.iter().copied(), &[window_size], aggregate)[0]
+}
+
+/// Like [`find_window_increases`], for several window sizes in one pass over
+/// `depths`, keeping only the largest window in memory. Returns a count for
+/// each of `window_sizes`, in the same order; sizes must be at least 1.
+pub fn window_increases<T: Depth, I: IntoIterator<Item = T>>(
+    depths: I,
+    window_sizes: &[usize],
+    aggregate: Aggregate,
+) -> Vec<isize> {
+    assert!(
+        !window_sizes.contains(&0),
+        "Window sizes must be at least 1"
+    );
+    let largest = window_sizes.iter().copied().max().unwrap_or_default();
+
+    let mut counts = vec![0; window_sizes.len()];
+    let mut previous: Vec<Option<Combined>> = vec![None; window_sizes.len()];
+    let mut window = VecDeque::with_capacity(largest);
+    for n in depths {
+        window.push_back(n);
+        if window.len() > largest {
+            window.pop_front();
+        }
+
+        let readings = window.make_contiguous();
+        for (ix, &size) in window_sizes.iter().enumerate() {
+            if readings.len() < size {
+                continue;
+            }
+            let value = aggregate.of(&readings[readings.len() - size..]);
+            if previous[ix].is_some_and(|prev| prev < value) {
+                counts[ix] += 1;
+            }
+            previous[ix] = Some(value);
+        }
+    }
+
+    counts
+}
+
+/// A filter for noisy readings, applied before counting increases
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Smoothing {
+    /// The mean of each `n` consecutive readings. The first `n - 1` readings
+    /// only fill the window, so there are `n - 1` fewer outputs than inputs.
+    MovingAverage(usize),
+    /// `alpha * reading + (1 - alpha) * previous`, starting from the first
+    /// reading. Alpha should be in `(0, 1]`; 1 leaves the readings as they are.
+    /// Unless it's 1, the smoothed values are floats even for whole readings.
+    Exponential(f64),
+}
+
+impl Smoothing {
+    /// Smooths `readings` lazily, keeping at most a window of them in memory.
+    /// A moving average of whole readings is exact.
+    pub fn apply<T: Depth, I: IntoIterator<Item = T>>(self, readings: I) -> Smoothed<I::IntoIter> {
+        Smoothed {
+            readings: readings.into_iter(),
+            smoothing: self,
+            window: VecDeque::new(),
+            previous: None,
+        }
+    }
+}
+
+/// Parses `moving:N` or `exp:ALPHA`
+impl FromStr for Smoothing {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (kind, value) = s
+            .split_once(':')
+            .ok_or_else(|| anyhow!("Expected moving:N or exp:ALPHA, found {s:?}"))?;
+        match kind {
+            "moving" => {
+                let n: usize = value
+                    .parse()
+                    .with_context(|| format!("parsing window size {value:?}"))?;
+                ensure!(n > 0, "Moving average window must be at least 1");
+                Ok(Smoothing::MovingAverage(n))
+            }
+            "exp" => {
+                let alpha: f64 = value
+                    .parse()
+                    .with_context(|| format!("parsing alpha {value:?}"))?;
+                ensure!(
+                    alpha > 0.0 && alpha <= 1.0,
+                    "Alpha must be in (0, 1], found {alpha}"
+                );
+                Ok(Smoothing::Exponential(alpha))
+            }
+            _ => bail!("Unknown smoothing {kind:?}; expected moving or exp"),
+        }
+    }
+}
+
+impl fmt::Display for Smoothing {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Smoothing::MovingAverage(n) => write!(f, "moving:{n}"),
+            Smoothing::Exponential(alpha) => write!(f, "exp:{alpha}"),
+        }
+    }
+}
+
+/// Iterator returned by [`Smoothing::apply`]
+#[derive(Debug, Clone)]
+pub struct Smoothed<I: Iterator> {
+    readings: I,
+    smoothing: Smoothing,
+    window: VecDeque<I::Item>,
+    previous: Option<f64>,
+}
+
+impl<T: Depth, I: Iterator<Item = T>> Iterator for Smoothed<I> {
+    type Item = Combined;
+
+    fn next(&mut self) -> Option<Combined> {
+        match self.smoothing {
+            Smoothing::MovingAverage(n) => loop {
+                self.window.push_back(self.readings.next()?);
+                if self.window.len() > n {
+                    self.window.pop_front();
+                }
+                if self.window.len() == n {
+                    return Some(Aggregate::Mean.of(self.window.make_contiguous()));
+                }
+            },
+            // Leaves the readings as they are, and as exact
+            Smoothing::Exponential(1.0) => self.readings.next().map(Combined::of),
+            Smoothing::Exponential(alpha) => {
+                let reading = self.readings.next()?.to_f64();
+                let smoothed = match self.previous {
+                    None => reading,
+                    Some(prev) => alpha * reading + (1.0 - alpha) * prev,
+                };
+                self.previous = Some(smoothed);
+                Some(Combined::Float(smoothed))
+            }
+        }
+    }
+}
+
+/// Counts how often the smoothed depths increase
+pub fn smoothed_increases<T: Depth, I: IntoIterator<Item = T>>(
+    depths: I,
+    smoothing: Smoothing,
+) -> isize {
+    let smoothed = smoothing.apply(depths);
+    smoothed.tuple_windows().filter(|(a, b)| a < b).count() as isize
+}
+
+/// How often depth readings increased, counted in one pass
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Increases {
+    /// Readings larger than the one before
+    pub single: isize,
+    /// Sums of `window_size` readings larger than the sum before
+    pub windowed: isize,
+}
+
+/// Keeps running counts of increases as readings arrive one at a time, e.g.
+/// live from a socket. Holds only the last `window_size + 1` readings.
+#[derive(Debug, Clone)]
+pub struct DepthTracker<T> {
+    window_size: usize,
+    window: VecDeque<T>,
+    readings: usize,
+    increases: Increases,
+}
+
+impl<T: Depth> DepthTracker<T> {
+    /// A tracker comparing sums of `window_size` readings
+    pub fn new(window_size: usize) -> Self {
+        DepthTracker {
+            window_size,
+            window: VecDeque::with_capacity(window_size + 1),
+            readings: 0,
+            increases: Increases::default(),
+        }
+    }
+
+    pub fn push(&mut self, reading: T) {
+        if self.window.back().is_some_and(|&prev| prev < reading) {
+            self.increases.single += 1;
+        }
+
+        self.window.push_back(reading);
+        self.readings += 1;
+        if self.window.len() > self.window_size + 1 {
+            self.window.pop_front();
+        }
+        // Consecutive windows share all but their first and last readings
+        if self.window.len() == self.window_size + 1 && self.window.front() < self.window.back() {
+            self.increases.windowed += 1;
+        }
+    }
+
+    /// The increases so far
+    pub fn increases(&self) -> Increases {
+        self.increases
+    }
+
+    /// Number of readings pushed so far
+    pub fn readings(&self) -> usize {
+        self.readings
+    }
+
+    /// The most recent reading
+    pub fn last(&self) -> Option<T> {
+        self.window.back().copied()
+    }
+}
+
+impl<T: Depth> Extend<T> for DepthTracker<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, readings: I) {
+        for reading in readings {
+            self.push(reading);
+        }
+    }
+}
+
+/// Counts increases as [`find_increases`] and [`find_window_increases`] do
+/// for sums, keeping only the last `window_size + 1` readings in memory.
+pub fn count_increases<T: Depth, I: IntoIterator<Item = T>>(
+    depths: I,
+    window_size: usize,
+) -> Increases {
+    let mut tracker = DepthTracker::new(window_size);
+    tracker.extend(depths);
+    tracker.increases()
+}
+
+/// A summary of how depths changed from reading to reading, computed in one
+/// pass by collecting the depths into it.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct TrendReport<T> {
+    pub readings: usize,
+    /// Readings deeper than the one before
+    pub increases: usize,
+    /// Readings shallower than the one before
+    pub decreases: usize,
+    /// Readings the same depth as the one before
+    pub plateaus: usize,
+    /// The most readings in a row that each went deeper, or each went
+    /// shallower, than the one before
+    pub longest_run: usize,
+    pub min: Option<T>,
+    pub max: Option<T>,
+}
+
+impl<T> Default for TrendReport<T> {
+    fn default() -> Self {
+        TrendReport {
+            readings: 0,
+            increases: 0,
+            decreases: 0,
+            plateaus: 0,
+            longest_run: 0,
+            min: None,
+            max: None,
+        }
+    }
+}
+
+impl<T: Depth> FromIterator<T> for TrendReport<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(depths: I) -> Self {
+        let mut report = TrendReport::default();
+        let mut last: Option<T> = None;
+        // The direction and length of the current run
+        let mut run: (Option<Ordering>, usize) = (None, 0);
+
+        for depth in depths {
+            report.readings += 1;
+            if report.min.is_none_or(|min| depth < min) {
+                report.min = Some(depth);
+            }
+            if report.max.is_none_or(|max| depth > max) {
+                report.max = Some(depth);
+            }
+
+            let direction = last.and_then(|last| depth.partial_cmp(&last));
+            match direction {
+                Some(Ordering::Greater) => report.increases += 1,
+                Some(Ordering::Less) => report.decreases += 1,
+                Some(Ordering::Equal) => report.plateaus += 1,
+                None => {}
+            }
+            run = match direction {
+                Some(Ordering::Equal) | None => (None, 1),
+                d if d == run.0 => (d, run.1 + 1),
+                d => (d, 2),
+            };
+            report.longest_run = report.longest_run.max(run.1);
+            last = Some(depth);
+        }
+        report
+    }
+}
+
+impl<T: Depth + fmt::Display> fmt::Display for TrendReport<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{} readings: {} increases, {} decreases, {} plateaus",
+            self.readings, self.increases, self.decreases, self.plateaus
+        )?;
+        write!(f, "Longest run up or down: {} readings", self.longest_run)?;
+        if let (Some(min), Some(max)) = (self.min, self.max) {
+            write!(f, "\nDepths from {min} to {max}")?;
+        }
+        Ok(())
+    }
+}
+
+/// One line of input: a depth, optionally with the time it was taken, as
+/// `timestamp,depth`. Timestamps are whole numbers, e.g. seconds since the
+/// epoch.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Reading<T> {
+    pub timestamp: Option<i64>,
+    pub depth: T,
+}
+
+impl<T> FromStr for Reading<T>
+where
+    T: Depth,
+    T::Err: Into<anyhow::Error> + fmt::Display,
+{
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (timestamp, depth) = match s.split_once(',') {
+            Some((timestamp, depth)) => {
+                let timestamp = timestamp
+                    .trim()
+                    .parse()
+                    .with_context(|| format!("Invalid timestamp {timestamp:?}"))?;
+                (Some(timestamp), depth.trim())
+            }
+            None => (None, s),
+        };
+        let depth: T = depth
+            .parse()
+            .map_err(Into::into)
+            .with_context(|| format!("Invalid depth {depth:?}"))?;
+        ensure!(depth.is_finite(), "Depth {depth:?} is not a finite number");
+        Ok(Reading { timestamp, depth })
+    }
+}
+
+/// The depths of `readings` in time order. Either all readings have
+/// timestamps or none do; untimed readings are taken to be in order already.
+/// Readings with the same timestamp keep their order.
+pub fn time_ordered<T: Depth>(mut readings: Vec<Reading<T>>) -> anyhow::Result<Vec<T>> {
+    let timed = readings.iter().filter(|r| r.timestamp.is_some()).count();
+    ensure!(
+        timed == 0 || timed == readings.len(),
+        "{timed} of {} readings have timestamps; expected all or none",
+        readings.len()
+    );
+
+    if !readings
+        .iter()
+        .tuple_windows()
+        .all(|(a, b)| a.timestamp <= b.timestamp)
+    {
+        debug!("Sorting {} readings by timestamp", readings.len());
+        readings.sort_by_key(|r| r.timestamp);
+    }
+    Ok(readings.into_iter().map(|r| r.depth).collect())
+}
+
+/// Parses readings one per line from `buf`, rejecting NaN and infinite
+/// depths, and returns their depths in time order.
+///
+/// Plain depths are parsed as they are needed. Timestamped readings may be
+/// out of order, so they are all read in and sorted before the first is
+/// returned.
+pub fn read_depths<T, B>(buf: B) -> impl Iterator<Item = anyhow::Result<T>>
+where
+    T: Depth,
+    T::Err: Into<anyhow::Error> + fmt::Display,
+    B: BufRead,
+{
+    let mut readings = parse::lines::<B, Reading<T>>(buf).peekable();
+    let timed = matches!(
+        readings.peek(),
+        Some(Ok(Reading {
+            timestamp: Some(_),
+            ..
+        }))
+    );
+    if !timed {
+        return Either::Left(readings.map(|reading| {
+            let reading = reading?;
+            ensure!(
+                reading.timestamp.is_none(),
+                "Reading {reading:?} has a timestamp, but earlier readings do not"
+            );
+            Ok(reading.depth)
+        }));
+    }
+
+    let depths = match readings
+        .collect::<anyhow::Result<_>>()
+        .and_then(time_ordered)
+    {
+        Ok(depths) => depths.into_iter().map(Ok).collect(),
+        Err(e) => vec![Err(e)],
+    };
+    Either::Right(depths.into_iter())
+}
+
+/// Counts increases in depths read one per line from `buf`, without reading
+/// them all into memory unless they are timestamped
+pub fn count_increases_from<T, B>(buf: B, window_size: usize) -> anyhow::Result<Increases>
+where
+    T: Depth,
+    T::Err: Into<anyhow::Error> + fmt::Display,
+    B: BufRead,
+{
+    process_results(read_depths::<T, B>(buf), |depths| {
+        count_increases(depths, window_size)
+    })
+}
+
+/// Solves both parts of the puzzle for the given input. Depths are read as
+/// [`ExactDepth`], so whole numbers stay exact at any size.
+pub fn solve(input: &str) -> anyhow::Result<Solved> {
+    let increases = info_span!("count")
+        .in_scope(|| count_increases_from::<ExactDepth, _>(input.as_bytes(), 3))?;
+    Ok(Solved::new(increases.single, increases.windowed))
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// Tests
+
+#[cfg(test)]
+mod tests {
+    use test_log::test;
+
+    use crate::parse;
+
+    #[allow(unused_imports)]
+    use super::*;
+
+    const EXAMPLE: &str = r###"
+        199
+        200
+        208
+        210
+        200
+        207
+        240
+        269
+        260
+        263    
+    "###;
+
+    #[test]
+    fn test_thing() {
+        let ns: Vec<i64> = parse::buffer(EXAMPLE.as_bytes()).unwrap();
+
+        let count = find_increases(&ns);
+        assert_eq!(count, 7);
+
+        let count3 = find_window_increases(&ns, 3, Aggregate::Sum);
+        assert_eq!(count3, 5);
+    }
+
+    #[test]
+    fn test_aggregates() {
+        assert_eq!(Aggregate::Sum.of(&[3, 1, 2, 10]), Combined::Exact(16, 1));
+        assert_eq!(Aggregate::Mean.of(&[3, 1, 2, 10]), Combined::Exact(4, 1));
+        assert_eq!(Aggregate::Median.of(&[3, 1, 2, 10]), Combined::Exact(5, 2));
+        assert_eq!(Aggregate::Median.of(&[3, 1, 10]), Combined::Exact(3, 1));
+        assert_eq!(Aggregate::Max.of(&[3, 1, 2, 10]), Combined::Exact(10, 1));
+        assert_eq!(Aggregate::Mean.of(&[1.0, 2.0]), Combined::Float(1.5));
+        assert_eq!(Aggregate::Mean.of(&[1.0, 2.0]).to_f64(), 1.5);
+
+        let ns: Vec<i64> = parse::buffer(EXAMPLE.as_bytes()).unwrap();
+        // Windows of 1 are the readings themselves
+        assert_eq!(find_window_increases(&ns, 1, Aggregate::Max), 7);
+        // Same-sized windows have larger means exactly when they have larger sums
+        assert_eq!(find_window_increases(&ns, 3, Aggregate::Mean), 5);
+        assert_eq!(find_window_increases(&ns, 3, Aggregate::Max), 3);
+        assert_eq!(find_window_increases(&ns, 3, Aggregate::Median), 4);
+
+        let counts = window_increases(ns.iter().copied(), &[1, 3, 20], Aggregate::Sum);
+        assert_eq!(counts, vec![7, 5, 0]);
+    }
+
+    #[test]
+    fn test_smoothing() {
+        let readings = [1.0, 3.0, 2.0, 6.0];
+        let smoothed = |smoothing: Smoothing| -> Vec<f64> {
+            smoothing.apply(readings).map(Combined::to_f64).collect()
+        };
+        assert_eq!(smoothed(Smoothing::MovingAverage(2)), vec![2.0, 2.5, 4.0]);
+        assert_eq!(
+            smoothed(Smoothing::Exponential(0.5)),
+            vec![1.0, 2.0, 2.0, 4.0]
+        );
+        assert_eq!(Smoothing::MovingAverage(5).apply(readings).count(), 0);
+
+        let ns: Vec<i64> = parse::buffer(EXAMPLE.as_bytes()).unwrap();
+        // A moving average compares the same sums as the window trick
+        let moving = smoothed_increases(ns.iter().copied(), Smoothing::MovingAverage(3));
+        assert_eq!(moving, 5);
+        let unsmoothed = smoothed_increases(ns.iter().copied(), Smoothing::Exponential(1.0));
+        assert_eq!(unsmoothed, 7);
+        // Heavy smoothing hides the dip at 260, but not the deeper one at 200
+        let heavy = smoothed_increases(ns.iter().copied(), Smoothing::Exponential(0.2));
+        assert_eq!(heavy, 8);
+    }
+
+    #[test]
+    fn test_positions() {
+        let ns: Vec<i64> = parse::buffer(EXAMPLE.as_bytes()).unwrap();
+        assert_eq!(increase_positions(&ns), vec![1, 2, 3, 5, 6, 7, 9]);
+        assert!(increase_positions::<i64>(&[]).is_empty());
+
+        let sparkline = Sparkline::new(&ns).to_string();
+        assert_eq!(sparkline, "▁▁▂▂▁▂▅█▇▇");
+
+        let wrapped = Sparkline {
+            width: 4,
+            ..Sparkline::new(&ns)
+        };
+        assert_eq!(wrapped.to_string(), "▁▁▂▂\n▁▂▅█\n▇▇");
+
+        // Scaled to the shallowest and deepest readings shown
+        let colored = Sparkline {
+            color: true,
+            ..Sparkline::new(&ns[..3])
+        };
+        assert_eq!(colored.to_string(), "▁\x1b[1;33m▂\x1b[0m\x1b[1;33m█\x1b[0m");
+        assert_eq!(Sparkline::new(&[5, 5]).to_string(), "▁▁");
+    }
+
+    #[test]
+    fn test_tracker() {
+        let ns: Vec<i64> = parse::buffer(EXAMPLE.as_bytes()).unwrap();
+        let mut tracker = DepthTracker::new(3);
+        assert_eq!(tracker.last(), None);
+
+        // Counts are available after every reading
+        let mut singles = vec![];
+        for &n in &ns {
+            tracker.push(n);
+            singles.push(tracker.increases().single);
+        }
+        assert_eq!(singles, vec![0, 1, 2, 3, 3, 4, 5, 6, 6, 7]);
+        assert_eq!(tracker.readings(), 10);
+        assert_eq!(tracker.last(), Some(263));
+        assert_eq!(
+            tracker.increases(),
+            Increases {
+                single: 7,
+                windowed: 5
+            }
+        );
+
+        let mut tracker = DepthTracker::new(1);
+        tracker.extend([1.5, 1.0, 2.5]);
+        assert_eq!(tracker.increases().single, 1);
+        assert_eq!(tracker.increases().windowed, 1);
+    }
+
+    #[test]
+    fn test_fractional() {
+        let input = "199.5\n199.25\n200\n200.75\n1e2\n";
+        let solved = solve(input).unwrap();
+        assert_eq!(solved, Solved::new(2, 1));
+        assert!(count_increases_from::<i64, _>(input.as_bytes(), 3).is_err());
+
+        let depths = [1.5, 1.25, 2.0];
+        assert_eq!(find_increases(&depths), 1);
+        assert_eq!(find_window_increases(&depths, 2, Aggregate::Mean), 1);
+        assert_eq!(smoothed_increases(depths, Smoothing::Exponential(0.5)), 1);
+
+        // Integer inputs give the same answers either way
+        let solved = solve(EXAMPLE).unwrap();
+        assert_eq!(solved, Solved::new(7, 5));
+
+        for bad in ["1\nNaN\n2\n", "1\ninf\n"] {
+            assert!(solve(bad).is_err(), "{bad:?}");
+        }
+    }
+
+    #[test]
+    fn test_exact_depths() {
+        // As f64, these are all the same number
+        let big = 1i64 << 60;
+        let input = format!("{big}\n{}\n{}\n{}\n", big + 1, big + 2, big + 1);
+        assert_eq!(solve(&input).unwrap(), Solved::new(2, 1));
+
+        let parse = |s: &str| s.parse::<ExactDepth>().unwrap();
+        assert_eq!(parse("200"), ExactDepth::Whole(200));
+        assert_eq!(parse("200.5"), ExactDepth::Fractional(200.5));
+        assert!("deep".parse::<ExactDepth>().is_err());
+
+        // Whole and fractional readings compare by value
+        assert_eq!(parse("200"), parse("200.0"));
+        assert!(parse("200") < parse("200.5"));
+        assert!(parse("-3.5") < parse("-3"));
+        assert!(parse("201") > parse("200.5"));
+        assert!(ExactDepth::Whole(big + 1) > ExactDepth::Fractional(big as f64));
+        assert!(ExactDepth::Whole(i64::MAX) < ExactDepth::Fractional(1e19));
+        assert!(ExactDepth::Whole(i64::MIN) > ExactDepth::Fractional(-1e19));
+        assert_eq!(
+            ExactDepth::Whole(1).partial_cmp(&ExactDepth::Fractional(f64::NAN)),
+            None
+        );
+
+        // Windows and smoothing keep whole depths exact too
+        let depths: Vec<ExactDepth> = input.lines().map(parse).collect();
+        for aggregate in [Aggregate::Sum, Aggregate::Mean, Aggregate::Median] {
+            assert_eq!(
+                find_window_increases(&depths, 2, aggregate),
+                1,
+                "{aggregate}"
+            );
+        }
+        let moving = Smoothing::MovingAverage(2);
+        assert_eq!(smoothed_increases(depths.iter().copied(), moving), 1);
+        let unsmoothed = Smoothing::Exponential(1.0);
+        assert_eq!(smoothed_increases(depths.iter().copied(), unsmoothed), 2);
+        assert!(Combined::Exact(2 * big as i128 + 1, 2) > Combined::Float(big as f64));
+        assert!(Combined::Exact(big as i128 + 1, 1) > Combined::Float(big as f64));
+
+        let report: TrendReport<ExactDepth> =
+            [parse("3"), parse("2.5"), parse("4")].into_iter().collect();
+        assert_eq!(
+            report.to_string().lines().last(),
+            Some("Depths from 2.5 to 4")
+        );
+        let json = serde_json::to_string(&report).unwrap();
+        assert!(json.contains(r#""min":2.5,"max":4"#), "{json}");
+        let reloaded: TrendReport<ExactDepth> = serde_json::from_str(&json).unwrap();
+        assert_eq!(reloaded, report);
+    }
+
+    #[test]
+    fn test_parse_smoothing() {
+        for s in ["moving:3", "exp:0.25", "exp:1"] {
+            let smoothing: Smoothing = s.parse().unwrap();
+            assert_eq!(smoothing.to_string(), s);
+        }
+        for bad in [
+            "moving", "moving:0", "exp:0", "exp:1.5", "median:3", "moving:x",
+        ] {
+            assert!(bad.parse::<Smoothing>().is_err(), "{bad}");
+        }
+    }
+
+    #[test]
+    fn test_streaming() {
+        let increases = count_increases_from::<i64, _>(EXAMPLE.as_bytes(), 3).unwrap();
+        assert_eq!(
+            increases,
+            Increases {
+                single: 7,
+                windowed: 5
+            }
+        );
+        assert!(count_increases_from::<i64, _>("1\n2\nthree\n".as_bytes(), 3).is_err());
+        assert_eq!(count_increases::<i64, _>([], 3), Increases::default());
+
+        // A long descent and climb, never held in memory
+        let depths = (0..1_000_000).rev().chain(0..1_000_000);
+        let increases = count_increases(depths, 3);
+        assert_eq!(increases.single, 999_999);
+        assert_eq!(increases.windowed, 1_000_000 - 2);
+    }
+
+    #[test]
+    fn test_timestamped() {
+        let reading: Reading<i64> = "1638316800, 199".parse().unwrap();
+        assert_eq!(
+            reading,
+            Reading {
+                timestamp: Some(1638316800),
+                depth: 199
+            }
+        );
+        let reading: Reading<f64> = "199.5".parse().unwrap();
+        assert_eq!(reading.timestamp, None);
+        for bad in ["noon,199", "10,", "10,deep", "10,NaN"] {
+            assert!(bad.parse::<Reading<f64>>().is_err(), "{bad}");
+        }
+
+        let ns: Vec<i64> = parse::buffer(EXAMPLE.as_bytes()).unwrap();
+        // The example, with timestamps counting down and the lines reversed,
+        // so it is only right once sorted
+        let input: String = ns
+            .iter()
+            .enumerate()
+            .rev()
+            .map(|(ix, n)| format!("{},{n}\n", 100 + ix * 10))
+            .collect();
+        assert_eq!(solve(&input).unwrap(), Solved::new(7, 5));
+
+        // Equal timestamps keep their input order
+        let depths: Vec<i64> = read_depths("5,3\n5,1\n2,2\n".as_bytes())
+            .collect::<anyhow::Result<_>>()
+            .unwrap();
+        assert_eq!(depths, vec![2, 3, 1]);
+
+        assert!(solve("199\n10,200\n").is_err());
+        assert!(solve("10,199\n200\n").is_err());
+    }
+
+    #[test]
+    fn test_trend() {
+        let ns: Vec<i64> = parse::buffer(EXAMPLE.as_bytes()).unwrap();
+        let report: TrendReport<i64> = ns.iter().copied().collect();
+        assert_eq!(
+            report,
+            TrendReport {
+                readings: 10,
+                increases: 7,
+                decreases: 2,
+                plateaus: 0,
+                longest_run: 4,
+                min: Some(199),
+                max: Some(269),
+            }
+        );
+        assert_eq!(
+            report.to_string(),
+            "10 readings: 7 increases, 2 decreases, 0 plateaus\n\
+             Longest run up or down: 4 readings\n\
+             Depths from 199 to 269"
+        );
+
+        // Plateaus break runs; falling runs count too
+        let report: TrendReport<f64> = [3.0, 3.0, 2.5, 2.0, 1.0, 1.0, 4.0].into_iter().collect();
+        assert_eq!(
+            (report.increases, report.decreases, report.plateaus),
+            (1, 3, 2)
+        );
+        assert_eq!(report.longest_run, 4);
+        assert_eq!((report.min, report.max), (Some(1.0), Some(4.0)));
+
+        let empty: TrendReport<i64> = std::iter::empty().collect();
+        assert_eq!(empty, TrendReport::default());
+        assert_eq!(empty.to_string().lines().count(), 2);
+
+        let json = serde_json::to_string(&report).unwrap();
+        let reloaded: TrendReport<f64> = serde_json::from_str(&json).unwrap();
+        assert_eq!(reloaded, report);
+        let json = serde_json::to_value(TrendReport::<i64>::default()).unwrap();
+        assert_eq!(json["min"], serde_json::Value::Null);
+    }
+}
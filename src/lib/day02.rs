@@ -0,0 +1,1112 @@
+use std::fmt;
+use std::io::{BufRead, Write};
+use std::ops::Add;
+use std::str::FromStr;
+
+use anyhow::{anyhow, ensure, Context};
+use parse_display::Display;
+
+use tracing::info_span;
+
+use crate::solution::Solved;
+
+/// Where the submarine is under part 1's rules, where `down` and `up` change
+/// depth directly
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Position {
+    pub depth: i64,
+    pub forward: i64,
+}
+
+impl Position {
+    /// The fewest of the puzzle's commands taking a submarine from the start
+    /// to `target` under part 1's rules, or `None` if it is behind the start
+    pub fn plan(target: Position) -> Option<Vec<Command>> {
+        if target.forward < 0 {
+            return None;
+        }
+        let mut commands = Vec::new();
+        if target.forward > 0 {
+            commands.push(Command {
+                verb: FORWARD,
+                n: target.forward,
+            });
+        }
+        if target.depth != 0 {
+            commands.push(dive(target.depth));
+        }
+        Some(commands)
+    }
+}
+
+impl Navigate for Position {
+    fn step(self, command: Command) -> Option<Self> {
+        (command.verb.simple)(self, command.n)
+    }
+}
+
+/// Panics on overflow; use [`Navigate::apply`] to handle it
+impl Add<Command> for Position {
+    type Output = Position;
+
+    fn add(self, rhs: Command) -> Self::Output {
+        self.step(rhs)
+            .unwrap_or_else(|| panic!("Overflow applying {rhs} at {self:?}"))
+    }
+}
+
+/// Where the submarine is under part 2's rules, where `down` and `up` change
+/// aim, and moving forward changes depth by aim
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Submarine {
+    pub depth: i64,
+    pub forward: i64,
+    pub aim: i64,
+}
+
+impl Submarine {
+    /// The fewest of the puzzle's commands taking a submarine from the start
+    /// to `target`'s depth and distance under part 2's rules, ending at
+    /// whatever aim that needs. Returns `None` if the target is behind the
+    /// start, or at a different depth with no distance to dive in.
+    pub fn plan(target: Position) -> Option<Vec<Command>> {
+        let Position { depth, forward } = target;
+        let forward_by = |n| Command { verb: FORWARD, n };
+        match (depth, forward) {
+            (_, f) if f < 0 => None,
+            (0, 0) => Some(vec![]),
+            (_, 0) => None,
+            (0, f) => Some(vec![forward_by(f)]),
+            // One aim the whole way
+            (d, f) if d % f == 0 => Some(vec![dive(d / f), forward_by(f)]),
+            // Otherwise the last step makes up the difference; one aim can't
+            // reach it, so this is as short as it gets
+            (d, f) => Some(vec![forward_by(f - 1), dive(d), forward_by(1)]),
+        }
+    }
+}
+
+impl Navigate for Submarine {
+    fn step(self, command: Command) -> Option<Self> {
+        (command.verb.aimed)(self, command.n)
+    }
+}
+
+/// Panics on overflow; use [`Navigate::apply`] to handle it
+impl Add<Command> for Submarine {
+    type Output = Submarine;
+
+    fn add(self, rhs: Command) -> Self::Output {
+        self.step(rhs)
+            .unwrap_or_else(|| panic!("Overflow applying {rhs} at {self:?}"))
+    }
+}
+
+/// Where the submarine is in three dimensions: part 2's rules, plus `port`
+/// and `starboard` moving it sideways. Starboard is positive.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Submarine3d {
+    pub depth: i64,
+    pub forward: i64,
+    pub lateral: i64,
+    pub aim: i64,
+}
+
+impl Submarine3d {
+    /// The product of all three coordinates, the 3D answer, or `None` if it
+    /// overflows
+    pub fn product(&self) -> Option<i64> {
+        self.forward
+            .checked_mul(self.depth)?
+            .checked_mul(self.lateral)
+    }
+}
+
+impl Navigate for Submarine3d {
+    fn step(self, command: Command) -> Option<Self> {
+        (command.verb.spatial)(self, command.n)
+    }
+}
+
+/// Panics on overflow; use [`Navigate::apply`] to handle it
+impl Add<Command> for Submarine3d {
+    type Output = Submarine3d;
+
+    fn add(self, rhs: Command) -> Self::Output {
+        self.step(rhs)
+            .unwrap_or_else(|| panic!("Overflow applying {rhs} at {self:?}"))
+    }
+}
+
+/// A submarine state that commands move, under one of the puzzle's rules.
+///
+/// Inputs can be crafted to overflow an `i64`, so every step is checked:
+/// `apply` and `follow` return an error rather than a wrong answer.
+pub trait Navigate: Copy + Default + fmt::Debug {
+    /// The state after `command`, or `None` if a coordinate overflows
+    fn step(self, command: Command) -> Option<Self>;
+
+    /// Moves by `command`. On overflow, returns an error and leaves the state
+    /// as it was.
+    fn apply(&mut self, command: Command) -> anyhow::Result<()> {
+        *self = self
+            .step(command)
+            .ok_or_else(|| anyhow!("Overflow applying {command} at {self:?}"))?;
+        Ok(())
+    }
+
+    /// The state after each of `commands` in turn, from the start, so a dive
+    /// can be inspected or stopped part way. Stops early if a coordinate
+    /// overflows; [`Navigate::follow`] reports where.
+    fn simulate<I>(commands: I) -> Simulation<Self, I::IntoIter>
+    where
+        I: IntoIterator<Item = Command>,
+    {
+        Simulation::new(Self::default(), commands)
+    }
+
+    /// The state after following `commands` from the start
+    fn follow(commands: &[Command]) -> anyhow::Result<Self> {
+        let mut state = Self::default();
+        for (ix, &command) in commands.iter().enumerate() {
+            state
+                .apply(command)
+                .with_context(|| format!("command {}", ix + 1))?;
+        }
+        Ok(state)
+    }
+}
+
+/// Fuel burned by each verb per unit of distance, e.g. `down=3,up=3` for
+/// depth changes costing three times as much as moving forward. Verbs not
+/// listed burn 1 per unit, and verbs without a distance burn their rate once.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct CostModel {
+    rates: Vec<(String, u64)>,
+}
+
+impl CostModel {
+    /// A model where every verb burns 1 per unit
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the fuel `verb` burns per unit
+    pub fn set(&mut self, verb: &str, rate: u64) -> &mut Self {
+        match self.rates.iter_mut().find(|(v, _)| v == verb) {
+            Some((_, r)) => *r = rate,
+            None => self.rates.push((verb.to_owned(), rate)),
+        }
+        self
+    }
+
+    pub fn rate(&self, verb: &str) -> u64 {
+        self.rates
+            .iter()
+            .find(|(v, _)| v == verb)
+            .map_or(1, |&(_, rate)| rate)
+    }
+
+    /// Fuel burned by `command`, saturating at `u64::MAX`
+    pub fn cost(&self, command: Command) -> u64 {
+        let units = if command.verb.takes_argument {
+            command.n.unsigned_abs()
+        } else {
+            1
+        };
+        self.rate(command.verb.name).saturating_mul(units)
+    }
+}
+
+impl FromStr for CostModel {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut model = CostModel::new();
+        for rate in s.split(',').map(str::trim).filter(|r| !r.is_empty()) {
+            let (verb, n) = rate
+                .split_once('=')
+                .ok_or_else(|| anyhow!("Expected verb=rate, got {rate:?}"))?;
+            let n = n
+                .trim()
+                .parse()
+                .with_context(|| format!("Invalid rate {n:?} for {verb}"))?;
+            model.set(verb.trim(), n);
+        }
+        Ok(model)
+    }
+}
+
+impl fmt::Display for CostModel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (ix, (verb, rate)) in self.rates.iter().enumerate() {
+            if ix > 0 {
+                write!(f, ",")?;
+            }
+            write!(f, "{verb}={rate}")?;
+        }
+        Ok(())
+    }
+}
+
+/// An iterator over the states a submarine passes through; see
+/// [`Navigate::simulate`]. With a [`CostModel`], it also adds up the fuel
+/// burned so far.
+#[derive(Debug, Clone)]
+pub struct Simulation<S, I> {
+    /// `None` once a step overflows
+    state: Option<S>,
+    commands: I,
+    costs: Option<CostModel>,
+    fuel: u64,
+}
+
+impl<S: Navigate, I: Iterator<Item = Command>> Simulation<S, I> {
+    pub fn new(start: S, commands: impl IntoIterator<IntoIter = I>) -> Self {
+        Simulation {
+            state: Some(start),
+            commands: commands.into_iter(),
+            costs: None,
+            fuel: 0,
+        }
+    }
+
+    /// Burns fuel according to `costs` for each command from here on
+    pub fn with_costs(self, costs: CostModel) -> Self {
+        Simulation {
+            costs: Some(costs),
+            ..self
+        }
+    }
+
+    /// Fuel burned by the commands so far, or 0 without a cost model. A
+    /// command that overflows burns nothing.
+    pub fn fuel(&self) -> u64 {
+        self.fuel
+    }
+}
+
+impl<S: Navigate, I: Iterator<Item = Command>> Iterator for Simulation<S, I> {
+    type Item = S;
+
+    fn next(&mut self) -> Option<S> {
+        let command = self.commands.next()?;
+        self.state = self.state?.step(command);
+        if let (Some(_), Some(costs)) = (self.state, &self.costs) {
+            self.fuel = self.fuel.saturating_add(costs.cost(command));
+        }
+        self.state
+    }
+}
+
+/// A command word and what it does under each of the puzzle's rules, or
+/// `None` on overflow. Verbs with an argument are written like `forward 5`,
+/// and those without like `surface`; verbs without an argument are passed 0.
+#[derive(Clone, Copy)]
+pub struct Verb {
+    pub name: &'static str,
+    pub takes_argument: bool,
+    pub simple: fn(Position, i64) -> Option<Position>,
+    pub aimed: fn(Submarine, i64) -> Option<Submarine>,
+    pub spatial: fn(Submarine3d, i64) -> Option<Submarine3d>,
+}
+
+impl fmt::Debug for Verb {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Verb({})", self.name)
+    }
+}
+
+/// Verbs are told apart by name; a table holds one verb per name
+impl PartialEq for Verb {
+    fn eq(&self, other: &Self) -> bool {
+        self.name == other.name
+    }
+}
+
+impl Eq for Verb {}
+
+pub const FORWARD: Verb = Verb {
+    name: "forward",
+    takes_argument: true,
+    simple: |p, n| {
+        Some(Position {
+            forward: p.forward.checked_add(n)?,
+            ..p
+        })
+    },
+    aimed: |s, n| {
+        Some(Submarine {
+            forward: s.forward.checked_add(n)?,
+            depth: s.depth.checked_add(s.aim.checked_mul(n)?)?,
+            ..s
+        })
+    },
+    spatial: |s, n| {
+        Some(Submarine3d {
+            forward: s.forward.checked_add(n)?,
+            depth: s.depth.checked_add(s.aim.checked_mul(n)?)?,
+            ..s
+        })
+    },
+};
+
+pub const DOWN: Verb = Verb {
+    name: "down",
+    takes_argument: true,
+    simple: |p, n| {
+        Some(Position {
+            depth: p.depth.checked_add(n)?,
+            ..p
+        })
+    },
+    aimed: |s, n| {
+        Some(Submarine {
+            aim: s.aim.checked_add(n)?,
+            ..s
+        })
+    },
+    spatial: |s, n| {
+        Some(Submarine3d {
+            aim: s.aim.checked_add(n)?,
+            ..s
+        })
+    },
+};
+
+pub const UP: Verb = Verb {
+    name: "up",
+    takes_argument: true,
+    simple: |p, n| {
+        Some(Position {
+            depth: p.depth.checked_sub(n)?,
+            ..p
+        })
+    },
+    aimed: |s, n| {
+        Some(Submarine {
+            aim: s.aim.checked_sub(n)?,
+            ..s
+        })
+    },
+    spatial: |s, n| {
+        Some(Submarine3d {
+            aim: s.aim.checked_sub(n)?,
+            ..s
+        })
+    },
+};
+
+/// `back N` undoes `forward N`
+pub const BACK: Verb = Verb {
+    name: "back",
+    takes_argument: true,
+    simple: |p, n| {
+        Some(Position {
+            forward: p.forward.checked_sub(n)?,
+            ..p
+        })
+    },
+    aimed: |s, n| {
+        Some(Submarine {
+            forward: s.forward.checked_sub(n)?,
+            depth: s.depth.checked_sub(s.aim.checked_mul(n)?)?,
+            ..s
+        })
+    },
+    spatial: |s, n| {
+        Some(Submarine3d {
+            forward: s.forward.checked_sub(n)?,
+            depth: s.depth.checked_sub(s.aim.checked_mul(n)?)?,
+            ..s
+        })
+    },
+};
+
+/// `surface` rises to depth 0, and levels off
+pub const SURFACE: Verb = Verb {
+    name: "surface",
+    takes_argument: false,
+    simple: |p, _| Some(Position { depth: 0, ..p }),
+    aimed: |s, _| {
+        Some(Submarine {
+            depth: 0,
+            aim: 0,
+            ..s
+        })
+    },
+    spatial: |s, _| {
+        Some(Submarine3d {
+            depth: 0,
+            aim: 0,
+            ..s
+        })
+    },
+};
+
+/// `hold N` moves forward N while holding depth, whatever the aim
+pub const HOLD: Verb = Verb {
+    name: "hold",
+    takes_argument: true,
+    simple: FORWARD.simple,
+    aimed: |s, n| {
+        Some(Submarine {
+            forward: s.forward.checked_add(n)?,
+            ..s
+        })
+    },
+    spatial: |s, n| {
+        Some(Submarine3d {
+            forward: s.forward.checked_add(n)?,
+            ..s
+        })
+    },
+};
+
+/// `port N` moves N to the left. Only [`Submarine3d`] tracks sideways
+/// movement; the 2D models stay where they are.
+pub const PORT: Verb = Verb {
+    name: "port",
+    takes_argument: true,
+    simple: |p, _| Some(p),
+    aimed: |s, _| Some(s),
+    spatial: |s, n| {
+        Some(Submarine3d {
+            lateral: s.lateral.checked_sub(n)?,
+            ..s
+        })
+    },
+};
+
+/// `starboard N` moves N to the right, like [`PORT`]
+pub const STARBOARD: Verb = Verb {
+    name: "starboard",
+    takes_argument: true,
+    simple: |p, _| Some(p),
+    aimed: |s, _| Some(s),
+    spatial: |s, n| {
+        Some(Submarine3d {
+            lateral: s.lateral.checked_add(n)?,
+            ..s
+        })
+    },
+};
+
+/// `down n`, or `up -n` for negative `n`
+fn dive(n: i64) -> Command {
+    if n >= 0 {
+        Command { verb: DOWN, n }
+    } else {
+        Command { verb: UP, n: -n }
+    }
+}
+
+/// The verbs in the puzzle
+pub const STANDARD_VERBS: [Verb; 3] = [FORWARD, DOWN, UP];
+
+/// Which way the submarine can move
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Display, clap::ValueEnum)]
+pub enum Mode {
+    /// Forward, down and up, as in the puzzle
+    #[default]
+    #[display("2d")]
+    #[value(name = "2d")]
+    TwoD,
+    /// Also `port` and `starboard`, tracked by [`Submarine3d`]
+    #[display("3d")]
+    #[value(name = "3d")]
+    ThreeD,
+}
+
+/// The largest distance accepted in a command. Puzzle inputs only go up to
+/// 9; anything much bigger is a corrupt input, and risks overflow.
+pub const MAX_DISTANCE: i64 = 1_000_000;
+
+fn parse_command(verbs: &[Verb], s: &str) -> anyhow::Result<Command> {
+    let mut tokens = s.split_whitespace();
+    let name = tokens.next().ok_or_else(|| anyhow!("Empty command"))?;
+    let verb = *verbs
+        .iter()
+        .find(|v| v.name == name)
+        .ok_or_else(|| anyhow!("Unknown direction {name:?}"))?;
+
+    let n = if verb.takes_argument {
+        let arg = tokens
+            .next()
+            .ok_or_else(|| anyhow!("No distance given for {name}"))?;
+        let n: i64 = arg
+            .parse()
+            .with_context(|| format!("Invalid distance {arg:?} for {name}"))?;
+        ensure!(n >= 0, "Negative distance {n} for {name}");
+        ensure!(
+            n <= MAX_DISTANCE,
+            "Distance {n} for {name} is larger than {MAX_DISTANCE}"
+        );
+        n
+    } else {
+        0
+    };
+
+    let extra = tokens.collect::<Vec<_>>();
+    ensure!(
+        extra.is_empty(),
+        "Unexpected {:?} after {name}",
+        extra.join(" ")
+    );
+    Ok(Command { verb, n })
+}
+
+/// The verbs commands can use, starting with those in the puzzle. More can
+/// be added with [`CommandTable::register`]:
+///
+/// ```
+/// # use adventofcode2021::day02::{CommandTable, BACK, SURFACE};
+/// let mut table = CommandTable::new();
+/// table.register(BACK).register(SURFACE);
+/// let command = table.parse("back 3").unwrap();
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CommandTable {
+    verbs: Vec<Verb>,
+}
+
+impl Default for CommandTable {
+    fn default() -> Self {
+        CommandTable {
+            verbs: STANDARD_VERBS.to_vec(),
+        }
+    }
+}
+
+impl CommandTable {
+    /// A table of the puzzle's verbs: `forward`, `down` and `up`
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The verbs available in `mode`
+    pub fn for_mode(mode: Mode) -> Self {
+        let mut table = Self::new();
+        if mode == Mode::ThreeD {
+            table.register(PORT).register(STARBOARD);
+        }
+        table
+    }
+
+    /// Adds `verb`, replacing any verb with the same name
+    pub fn register(&mut self, verb: Verb) -> &mut Self {
+        match self.verbs.iter_mut().find(|v| v.name == verb.name) {
+            Some(existing) => *existing = verb,
+            None => self.verbs.push(verb),
+        }
+        self
+    }
+
+    pub fn get(&self, name: &str) -> Option<Verb> {
+        self.verbs.iter().find(|v| v.name == name).copied()
+    }
+
+    /// Parses one command, like `forward 5`
+    pub fn parse(&self, s: &str) -> anyhow::Result<Command> {
+        parse_command(&self.verbs, s.trim())
+    }
+
+    /// Parses one command per line, skipping blank lines. Errors give the
+    /// line number and text.
+    pub fn parse_lines<B: BufRead>(&self, buf: B) -> anyhow::Result<Vec<Command>> {
+        let mut commands = Vec::new();
+        for (ix, line) in buf.lines().enumerate() {
+            let line = line.with_context(|| format!("reading line {}", ix + 1))?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let command = self
+                .parse(&line)
+                .with_context(|| format!("line {}: {:?}", ix + 1, line.trim()))?;
+            commands.push(command);
+        }
+        Ok(commands)
+    }
+}
+
+/// A verb and its distance, e.g. `forward 5`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Command {
+    pub verb: Verb,
+    pub n: i64,
+}
+
+impl fmt::Display for Command {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.verb.takes_argument {
+            write!(f, "{} {}", self.verb.name, self.n)
+        } else {
+            write!(f, "{}", self.verb.name)
+        }
+    }
+}
+
+/// Parses the puzzle's own commands; use a [`CommandTable`] for others
+impl FromStr for Command {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        parse_command(&STANDARD_VERBS, s)
+    }
+}
+
+/// The state after each command in turn, starting from `start`, and stopping
+/// early if a coordinate overflows. Works with any model, e.g. [`Position`]
+/// or [`Submarine`].
+pub fn trajectory<'a, S: Navigate + 'a>(
+    start: S,
+    commands: &'a [Command],
+) -> impl Iterator<Item = S> + 'a {
+    Simulation::new(start, commands.iter().copied())
+}
+
+/// Writes the path under both models as CSV, one row per command after a
+/// row for the start, for plotting the dive profile
+pub fn write_trajectory_csv<W: Write>(commands: &[Command], mut out: W) -> anyhow::Result<()> {
+    writeln!(
+        out,
+        "step,command,depth,forward,aimed_depth,aimed_forward,aim"
+    )?;
+    writeln!(out, "0,,0,0,0,0,0")?;
+    let mut pos = Position::default();
+    let mut sub = Submarine::default();
+    for (step, &command) in commands.iter().enumerate() {
+        pos.apply(command)
+            .and_then(|()| sub.apply(command))
+            .with_context(|| format!("command {}", step + 1))?;
+        writeln!(
+            out,
+            "{},{command},{},{},{},{},{}",
+            step + 1,
+            pos.depth,
+            pos.forward,
+            sub.depth,
+            sub.forward,
+            sub.aim
+        )?;
+    }
+    out.flush()?;
+    Ok(())
+}
+
+/// Follows commands, including `port` and `starboard`, in three dimensions
+pub fn navigate_3d(input: &str) -> anyhow::Result<Submarine3d> {
+    let directions = info_span!("parse")
+        .in_scope(|| CommandTable::for_mode(Mode::ThreeD).parse_lines(input.as_bytes()))?;
+    info_span!("navigate").in_scope(|| Submarine3d::follow(&directions))
+}
+
+/// `depth * forward`, the puzzle's answer, checking for overflow
+pub fn answer(depth: i64, forward: i64) -> anyhow::Result<i64> {
+    depth
+        .checked_mul(forward)
+        .ok_or_else(|| anyhow!("Overflow multiplying depth {depth} by distance {forward}"))
+}
+
+/// Solves both parts of the puzzle for the given input
+pub fn solve(input: &str) -> anyhow::Result<Solved> {
+    let directions =
+        info_span!("parse").in_scope(|| CommandTable::new().parse_lines(input.as_bytes()))?;
+    let part1 = info_span!("part1").in_scope(|| {
+        let pos = Position::follow(&directions).context("part 1")?;
+        answer(pos.depth, pos.forward)
+    })?;
+    let part2 = info_span!("part2").in_scope(|| {
+        let sub = Submarine::follow(&directions).context("part 2")?;
+        answer(sub.depth, sub.forward)
+    })?;
+    Ok(Solved::new(part1, part2))
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// Tests
+
+#[cfg(test)]
+mod tests {
+    use itertools::Itertools;
+    use test_log::test;
+
+    use crate::parse;
+
+    #[allow(unused_imports)]
+    use super::*;
+
+    static EXAMPLE: &str = r###"
+        forward 5
+        down 5
+        forward 8
+        up 3
+        down 8
+        forward 2
+    "###;
+
+    #[test]
+    fn test_part_one() {
+        let directions: Vec<Command> = parse::buffer(EXAMPLE.as_bytes()).unwrap();
+        let pos: Position = directions
+            .iter()
+            .copied()
+            .fold(Position::default(), Position::add);
+
+        assert_eq!(
+            pos,
+            Position {
+                depth: 10,
+                forward: 15
+            }
+        )
+    }
+
+    #[test]
+    fn test_part_two() {
+        let directions: Vec<Command> = parse::buffer(EXAMPLE.as_bytes()).unwrap();
+        let sum: Submarine = directions
+            .iter()
+            .copied()
+            .fold(Submarine::default(), Submarine::add);
+
+        assert_eq!(
+            sum,
+            Submarine {
+                depth: 60,
+                forward: 15,
+                aim: 10,
+            }
+        )
+    }
+
+    #[test]
+    fn test_table() {
+        let command: Command = "forward 5".parse().unwrap();
+        assert_eq!(
+            command,
+            Command {
+                verb: FORWARD,
+                n: 5
+            }
+        );
+        for bad in ["back 3", "forward", "forward x", "sideways 2", ""] {
+            assert!(bad.parse::<Command>().is_err(), "{bad:?}");
+        }
+
+        let mut table = CommandTable::new();
+        table.register(BACK).register(SURFACE).register(HOLD);
+        assert_eq!(table.get("up"), Some(UP));
+        assert_eq!(table.get("dive"), None);
+        assert!(table.parse("surface 3").is_err());
+
+        let commands = table
+            .parse_lines("down 4\nforward 3\n\nback 1\nhold 5\nsurface\nforward 2\n".as_bytes())
+            .unwrap();
+        assert_eq!(commands.len(), 6);
+
+        let pos = commands
+            .iter()
+            .copied()
+            .fold(Position::default(), Position::add);
+        assert_eq!(
+            pos,
+            Position {
+                depth: 0,
+                forward: 9
+            }
+        );
+
+        let sub = commands
+            .iter()
+            .copied()
+            .fold(Submarine::default(), Submarine::add);
+        // 12 deep after forward 3, 8 after back 1, held there, then surfaced
+        // and levelled off before the last forward
+        assert_eq!(
+            sub,
+            Submarine {
+                depth: 0,
+                forward: 9,
+                aim: 0
+            }
+        );
+
+        // Registering a verb again replaces it
+        table.register(Verb { name: "up", ..DOWN });
+        let sub = Submarine::default() + table.parse("up 2").unwrap();
+        assert_eq!(sub.aim, 2);
+    }
+
+    #[test]
+    fn test_trajectory() {
+        let directions: Vec<Command> = parse::buffer(EXAMPLE.as_bytes()).unwrap();
+        let depths: Vec<i64> = trajectory(Position::default(), &directions)
+            .map(|p| p.depth)
+            .collect();
+        assert_eq!(depths, vec![0, 5, 5, 2, 10, 10]);
+
+        let subs: Vec<Submarine> = trajectory(Submarine::default(), &directions).collect();
+        assert_eq!(subs.len(), 6);
+        assert_eq!(
+            subs[2],
+            Submarine {
+                depth: 40,
+                forward: 13,
+                aim: 5
+            }
+        );
+        assert_eq!(
+            subs.last().copied(),
+            Some(
+                directions
+                    .iter()
+                    .copied()
+                    .fold(Submarine::default(), Submarine::add)
+            )
+        );
+
+        let mut csv = Vec::new();
+        write_trajectory_csv(&directions[..2], &mut csv).unwrap();
+        assert_eq!(
+            String::from_utf8(csv).unwrap(),
+            "step,command,depth,forward,aimed_depth,aimed_forward,aim\n\
+             0,,0,0,0,0,0\n\
+             1,forward 5,0,5,0,5,0\n\
+             2,down 5,5,5,0,5,5\n"
+        );
+
+        let mut table = CommandTable::new();
+        table.register(SURFACE);
+        assert_eq!(table.parse("surface").unwrap().to_string(), "surface");
+    }
+
+    #[test]
+    fn test_validation() {
+        for bad in [
+            "forward -2",
+            "down 1000001",
+            "up 99999999999999999999",
+            "forward 5 6",
+            "forward 5 then stop",
+            "backward 3",
+            "Forward 3",
+            "down 2.5",
+        ] {
+            assert!(bad.parse::<Command>().is_err(), "{bad:?}");
+        }
+        let command: Command = "  down   1000000 ".parse().unwrap();
+        assert_eq!(command.n, MAX_DISTANCE);
+        let command: Command = "up 0".parse().unwrap();
+        assert_eq!(command, Command { verb: UP, n: 0 });
+
+        let err = CommandTable::new()
+            .parse_lines("forward 5\n\ndown 5\nforward 8 2\n".as_bytes())
+            .unwrap_err();
+        assert_eq!(
+            format!("{err:#}"),
+            "line 4: \"forward 8 2\": Unexpected \"2\" after forward"
+        );
+
+        let err = solve("forward 1\nsideways 4\n").unwrap_err();
+        assert_eq!(
+            format!("{err:#}"),
+            "line 2: \"sideways 4\": Unknown direction \"sideways\""
+        );
+        let err = solve("up -3\n").unwrap_err();
+        assert_eq!(
+            format!("{err:#}"),
+            "line 1: \"up -3\": Negative distance -3 for up"
+        );
+    }
+
+    #[test]
+    fn test_3d() {
+        let input = format!("{EXAMPLE}\nstarboard 4\nport 1\nforward 1\n");
+        let sub = navigate_3d(&input).unwrap();
+        assert_eq!(
+            sub,
+            Submarine3d {
+                depth: 70,
+                forward: 16,
+                lateral: 3,
+                aim: 10
+            }
+        );
+        assert_eq!(sub.product(), Some(16 * 70 * 3));
+
+        // Without sideways moves, 3D matches part 2
+        let sub = navigate_3d(EXAMPLE).unwrap();
+        assert_eq!((sub.depth, sub.forward, sub.lateral), (60, 15, 0));
+
+        // The 2D table doesn't know the new verbs, and the 2D models ignore them
+        assert!(solve(&input).is_err());
+        let port = CommandTable::for_mode(Mode::ThreeD)
+            .parse("port 3")
+            .unwrap();
+        assert_eq!(Submarine::default() + port, Submarine::default());
+        assert_eq!(Mode::ThreeD.to_string(), "3d");
+    }
+
+    #[test]
+    fn test_plan() {
+        for depth in -5..=12 {
+            for forward in 0..=6 {
+                let target = Position { depth, forward };
+                let plan = Position::plan(target).unwrap();
+                let pos = plan
+                    .iter()
+                    .copied()
+                    .fold(Position::default(), Position::add);
+                assert_eq!(pos, target);
+
+                let Some(plan) = Submarine::plan(target) else {
+                    assert!(forward == 0 && depth != 0, "{target:?}");
+                    continue;
+                };
+                assert!(plan.len() <= 3, "{target:?}: {plan:?}");
+                let sub = plan
+                    .iter()
+                    .copied()
+                    .fold(Submarine::default(), Submarine::add);
+                assert_eq!((sub.depth, sub.forward), (depth, forward));
+            }
+        }
+
+        let plan = |depth, forward| Submarine::plan(Position { depth, forward }).unwrap();
+        let names = |plan: Vec<Command>| plan.iter().map(|c| c.to_string()).join(", ");
+        assert_eq!(names(plan(60, 15)), "down 4, forward 15");
+        assert_eq!(names(plan(-30, 15)), "up 2, forward 15");
+        assert_eq!(names(plan(61, 15)), "forward 14, down 61, forward 1");
+        assert_eq!(names(plan(0, 15)), "forward 15");
+        assert!(plan(0, 0).is_empty());
+
+        assert_eq!(
+            Position::plan(Position {
+                depth: 2,
+                forward: -1
+            }),
+            None
+        );
+        assert_eq!(
+            Submarine::plan(Position {
+                depth: 0,
+                forward: -1
+            }),
+            None
+        );
+    }
+
+    #[test]
+    fn test_overflow() {
+        let mut sub = Submarine {
+            aim: i64::MAX / 2,
+            ..Submarine::default()
+        };
+        let before = sub;
+        let forward: Command = "forward 3".parse().unwrap();
+        assert_eq!(sub.step(forward), None);
+        let err = sub.apply(forward).unwrap_err();
+        assert!(err
+            .to_string()
+            .starts_with("Overflow applying forward 3 at Submarine"));
+        assert_eq!(sub, before);
+        assert!(sub.apply("down 3".parse().unwrap()).is_ok());
+        assert_eq!(sub.aim, i64::MAX / 2 + 3);
+
+        // Aim builds up to 1e10, and each forward adds 1e16 to the depth
+        let input = "down 1000000\n".repeat(10_000) + &"forward 1000000\n".repeat(1_000);
+        let commands = CommandTable::new().parse_lines(input.as_bytes()).unwrap();
+        let err = Submarine::follow(&commands).unwrap_err();
+        let message = format!("{err:#}");
+        assert!(
+            message.starts_with("command 10923: Overflow applying forward 1000000"),
+            "{message}"
+        );
+        assert_eq!(trajectory(Submarine::default(), &commands).count(), 10922);
+        assert!(write_trajectory_csv(&commands, std::io::sink()).is_err());
+        assert!(solve(&input).is_err());
+
+        // Each coordinate fits, but their product doesn't
+        let input = "forward 1000000\n".repeat(10_000) + &"down 1000000\n".repeat(10_000);
+        let err = solve(&input).unwrap_err();
+        assert_eq!(
+            format!("{err:#}"),
+            "Overflow multiplying depth 10000000000 by distance 10000000000"
+        );
+    }
+
+    #[test]
+    fn test_simulate() {
+        let directions: Vec<Command> = parse::buffer(EXAMPLE.as_bytes()).unwrap();
+        let forwards: Vec<i64> = Submarine::simulate(directions.iter().copied())
+            .map(|s| s.forward)
+            .collect();
+        assert_eq!(forwards, vec![5, 5, 13, 13, 13, 15]);
+
+        // Stop as soon as the dive passes 30
+        let deep = Submarine::simulate(directions.iter().copied())
+            .find(|s| s.depth > 30)
+            .unwrap();
+        assert_eq!(
+            deep,
+            Submarine {
+                depth: 40,
+                forward: 13,
+                aim: 5
+            }
+        );
+
+        // Commands can be produced lazily, and the dive can be endless
+        let zigzag = ["down 1", "forward 1", "up 1", "forward 1"]
+            .map(|s| s.parse::<Command>().unwrap())
+            .into_iter()
+            .cycle();
+        let pos = Position::simulate(zigzag).nth(4_000 - 1).unwrap();
+        assert_eq!(
+            pos,
+            Position {
+                depth: 0,
+                forward: 2_000
+            }
+        );
+
+        // Overflow ends the simulation
+        let start = Submarine {
+            aim: i64::MAX,
+            ..Submarine::default()
+        };
+        let states: Vec<_> = Simulation::new(start, directions.iter().copied()).collect();
+        assert!(states.is_empty());
+    }
+
+    #[test]
+    fn test_fuel() {
+        let costs: CostModel = "down=3, up=3,surface=10".parse().unwrap();
+        assert_eq!(costs.to_string(), "down=3,up=3,surface=10");
+        assert_eq!((costs.rate("down"), costs.rate("forward")), (3, 1));
+        for bad in ["down", "down=x", "down=-1"] {
+            assert!(bad.parse::<CostModel>().is_err(), "{bad}");
+        }
+        assert_eq!("".parse::<CostModel>().unwrap(), CostModel::new());
+
+        let directions: Vec<Command> = parse::buffer(EXAMPLE.as_bytes()).unwrap();
+        let mut dive = Submarine::simulate(directions.iter().copied()).with_costs(costs.clone());
+        // forward 5, down 5
+        dive.nth(1).unwrap();
+        assert_eq!(dive.fuel(), 5 + 15);
+        // forward 8, up 3, down 8, forward 2
+        assert_eq!(dive.by_ref().count(), 4);
+        assert_eq!(dive.fuel(), 20 + 8 + 9 + 24 + 2);
+
+        let mut table = CommandTable::new();
+        table.register(SURFACE);
+        let surface = table.parse("surface").unwrap();
+        assert_eq!(costs.cost(surface), 10);
+
+        // No cost model, no fuel
+        let mut dive = Position::simulate(directions.iter().copied());
+        assert_eq!(dive.by_ref().count(), 6);
+        assert_eq!(dive.fuel(), 0);
+    }
+}
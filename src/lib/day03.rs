@@ -0,0 +1,904 @@
+use std::any::type_name;
+use std::fmt;
+use std::io::BufRead;
+use std::str::FromStr;
+
+use anyhow::{anyhow, bail, ensure, Context};
+use serde::{Deserialize, Serialize};
+use tracing::{debug, info_span};
+
+use crate::parse;
+use crate::solution::Solved;
+use crate::table::Table;
+
+/// The most bits an [`Observation`] can hold
+pub const MAX_BITS: usize = 128;
+
+/// Observations that all have the same width
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct DiagnosticReport {
+    observations: Vec<Observation>,
+    width: usize,
+}
+
+impl DiagnosticReport {
+    /// A report of `observations`, or an error if they aren't all as wide as
+    /// the first
+    pub fn new(observations: Vec<Observation>) -> anyhow::Result<Self> {
+        let width = observations.first().map_or(0, Observation::width);
+        for (ix, obs) in observations.iter().enumerate() {
+            ensure!(
+                obs.width() == width,
+                "Observation {} has {} bits, expected {width}",
+                ix + 1,
+                obs.width()
+            );
+        }
+        Ok(DiagnosticReport {
+            observations,
+            width,
+        })
+    }
+
+    /// Bits in each observation, or 0 for an empty report
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    /// The gamma and epsilon rates, as any integer type wide enough for the
+    /// report, e.g. `power::<u32>()`
+    pub fn power<T: TryFrom<u128>>(&self) -> anyhow::Result<(T, T)> {
+        self.accumulate().power()
+    }
+
+    /// How many ones and zeros each column has, which decide the power
+    pub fn bit_histogram(&self) -> BitHistogram {
+        self.accumulate().histogram()
+    }
+
+    fn accumulate(&self) -> PowerAccumulator {
+        let mut power = PowerAccumulator::with_width(self.width);
+        for &obs in &self.observations {
+            power.add(obs);
+        }
+        power
+    }
+
+    /// Which bit to keep in column `ix`, given how many `ones` and `zeros` it
+    /// has: the most common if `most_common`, or else the least, with ties
+    /// settled by `tie`
+    fn keep_bit(
+        ones: usize,
+        zeros: usize,
+        ix: usize,
+        most_common: bool,
+        tie: TieBreak,
+    ) -> anyhow::Result<bool> {
+        if ones == zeros {
+            return match tie {
+                TieBreak::PreferOne => Ok(true),
+                TieBreak::PreferZero => Ok(false),
+                TieBreak::Error => {
+                    bail!("Bit {ix} is 1 in {ones} of {} observations", ones + zeros)
+                }
+            };
+        }
+        Ok((ones > zeros) == most_common)
+    }
+
+    /// The oxygen generator and CO2 scrubber ratings, as any integer type
+    /// wide enough for the report. Ties go to 1 for oxygen and 0 for CO2, as
+    /// in the puzzle.
+    pub fn life<T: TryFrom<u128>>(&self) -> anyhow::Result<(T, T)> {
+        self.life_with(TieBreak::PreferOne, TieBreak::PreferZero)
+    }
+
+    /// The oxygen generator and CO2 scrubber ratings, settling ties in the
+    /// most and least common bits with `oxygen_tie` and `co2_tie`
+    pub fn life_with<T: TryFrom<u128>>(
+        &self,
+        oxygen_tie: TieBreak,
+        co2_tie: TieBreak,
+    ) -> anyhow::Result<(T, T)> {
+        let mut sorted = self.observations.clone();
+        sorted.sort_unstable();
+
+        let oxygen = self.rating(&sorted, true, oxygen_tie).context("oxygen")?;
+        let co2 = self.rating(&sorted, false, co2_tie).context("CO2")?;
+        Ok((oxygen.to_int()?, co2.to_int()?))
+    }
+
+    /// Filters `sorted` down to one observation, bit by bit.
+    ///
+    /// The observations left after each bit share a prefix, so they are a
+    /// range of `sorted` with those having a 0 in the next column before
+    /// those having a 1, and each filter is a binary search.
+    fn rating(
+        &self,
+        sorted: &[Observation],
+        most_common: bool,
+        tie: TieBreak,
+    ) -> anyhow::Result<Observation> {
+        let mut range = 0..sorted.len();
+        for ix in 0..self.width {
+            if range.len() <= 1 {
+                break;
+            }
+            let split = range.start + sorted[range.clone()].partition_point(|o| !o.bit(ix));
+            let (zeros, ones) = (split - range.start, range.end - split);
+            range = if Self::keep_bit(ones, zeros, ix, most_common, tie)? {
+                split..range.end
+            } else {
+                range.start..split
+            };
+        }
+
+        match range.len() {
+            1 => Ok(sorted[range.start]),
+            n => bail!("Expected 1 rating, found {n}"),
+        }
+    }
+}
+
+/// How to pick a bit when a column has as many ones as zeros
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TieBreak {
+    PreferOne,
+    PreferZero,
+    /// Give up, e.g. for inputs that promise there are no ties
+    Error,
+}
+
+/// Counts the ones in each column as observations arrive, so the power can
+/// be found without holding the whole report in memory. The first
+/// observation sets the width.
+///
+/// Observations are counted in blocks of 64: each block is transposed with
+/// word operations into one `u64` per column, and counted with a popcount per
+/// column rather than bit by bit.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct PowerAccumulator {
+    width: usize,
+    /// Ones in each column of the blocks already counted
+    ones: Vec<usize>,
+    /// The values of the observations not yet counted, fewer than 64
+    block: Vec<u128>,
+    total: usize,
+}
+
+impl PowerAccumulator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn with_width(width: usize) -> Self {
+        PowerAccumulator {
+            width,
+            ones: vec![0; width],
+            block: Vec::with_capacity(64),
+            total: 0,
+        }
+    }
+
+    /// Counts `obs`, or errors if it isn't as wide as the observations before
+    /// it
+    pub fn push(&mut self, obs: Observation) -> anyhow::Result<()> {
+        if self.total == 0 {
+            *self = Self::with_width(obs.width());
+        }
+        ensure!(
+            obs.width() == self.width,
+            "Observation {} has {} bits, expected {}",
+            self.total + 1,
+            obs.width(),
+            self.width
+        );
+        self.add(obs);
+        Ok(())
+    }
+
+    /// Counts `obs`, which must be `self.width` bits wide
+    fn add(&mut self, obs: Observation) {
+        self.block.push(obs.value());
+        self.total += 1;
+        if self.block.len() == 64 {
+            count_block(&self.block, &mut self.ones);
+            self.block.clear();
+        }
+    }
+
+    /// Ones in each column so far, including the block not yet counted
+    fn counts(&self) -> Vec<usize> {
+        let mut counts = self.ones.clone();
+        count_block(&self.block, &mut counts);
+        counts
+    }
+
+    /// Bits in each observation, or 0 before the first
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    /// Number of observations so far
+    pub fn len(&self) -> usize {
+        self.total
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.total == 0
+    }
+
+    pub fn histogram(&self) -> BitHistogram {
+        let columns = self
+            .counts()
+            .iter()
+            .map(|&ones| BitCount {
+                ones,
+                zeros: self.total - ones,
+            })
+            .collect();
+        BitHistogram { columns }
+    }
+
+    /// The gamma and epsilon rates of the observations so far: gamma has the
+    /// bits set in more than half of them, and epsilon the rest
+    pub fn power<T: TryFrom<u128>>(&self) -> anyhow::Result<(T, T)> {
+        let half = self.total / 2;
+        let counts = self.counts();
+        let gamma: Observation = counts.iter().map(|&cnt| cnt > half).collect();
+        let epsilon: Observation = counts.iter().map(|&cnt| cnt <= half).collect();
+
+        Ok((gamma.to_int()?, epsilon.to_int()?))
+    }
+}
+
+/// Adds the ones in each column of up to 64 `values` to `counts`, which has
+/// an entry per column, most significant first. Each 64-bit half of the
+/// values is transposed as a bit matrix, so a column is one word.
+fn count_block(values: &[u128], counts: &mut [usize]) {
+    let width = counts.len();
+    for shift in [0, 64] {
+        if shift >= width {
+            break;
+        }
+        let mut rows = [0u64; 64];
+        for (row, &value) in rows.iter_mut().zip(values) {
+            *row = (value >> shift) as u64;
+        }
+        transpose(&mut rows);
+        // rows[bit] now holds bit `shift + bit` of every value
+        for (bit, row) in rows.iter().enumerate().take(width - shift) {
+            counts[width - 1 - shift - bit] += row.count_ones() as usize;
+        }
+    }
+}
+
+/// Transposes a 64x64 bit matrix in place, so bit `j` of `rows[i]` becomes
+/// bit `i` of `rows[j]`. Swaps the off-diagonal 32x32 blocks, then the 16x16
+/// blocks within each of those, and so on down to single bits (Hacker's
+/// Delight, 7-3).
+fn transpose(rows: &mut [u64; 64]) {
+    let mut j = 32;
+    let mut mask: u64 = 0x0000_0000_ffff_ffff;
+    while j != 0 {
+        let mut k = 0;
+        while k < 64 {
+            let t = ((rows[k] >> j) ^ rows[k + j]) & mask;
+            rows[k + j] ^= t;
+            rows[k] ^= t << j;
+            k = (k + j + 1) & !j;
+        }
+        j >>= 1;
+        mask ^= mask << j;
+    }
+}
+
+/// How many observations have a one or a zero in one column
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct BitCount {
+    pub ones: usize,
+    pub zeros: usize,
+}
+
+impl BitCount {
+    /// The column's bit in gamma: set when more than half the observations
+    /// have a one. Epsilon has the opposite bit.
+    pub fn gamma_bit(&self) -> bool {
+        self.ones > (self.ones + self.zeros) / 2
+    }
+}
+
+/// The [`BitCount`] of each column, most significant first. Displays as a
+/// table, with the gamma bit each column gives.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct BitHistogram {
+    pub columns: Vec<BitCount>,
+}
+
+impl fmt::Display for BitHistogram {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut table = Table::new(["bit", "ones", "zeros", "gamma"]);
+        for (ix, count) in self.columns.iter().enumerate() {
+            table.push([
+                ix.to_string(),
+                count.ones.to_string(),
+                count.zeros.to_string(),
+                (count.gamma_bit() as u8).to_string(),
+            ]);
+        }
+        write!(f, "{table}")
+    }
+}
+
+/// The gamma and epsilon rates of observations read one per line from `buf`,
+/// without reading them all into memory
+pub fn stream_power<T, B>(buf: B) -> anyhow::Result<(T, T)>
+where
+    T: TryFrom<u128>,
+    B: BufRead,
+{
+    let mut power = PowerAccumulator::new();
+    for obs in parse::lines(buf) {
+        power.push(obs?)?;
+    }
+    power.power()
+}
+
+/// One line of a diagnostic report: up to [`MAX_BITS`] bits, most
+/// significant first
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Observation {
+    /// Compared first, so observations of one width sort by value
+    value: u128,
+    width: usize,
+}
+
+impl Observation {
+    /// The lowest `width` bits of `value`
+    pub fn from_value(value: u128, width: usize) -> Self {
+        if width > MAX_BITS {
+            panic!("Width {width} is over {MAX_BITS} bits");
+        }
+        // Shifting by all 128 bits overflows, leaving no bits for width 0
+        let mask = u128::MAX
+            .checked_shr((MAX_BITS - width) as u32)
+            .unwrap_or(0);
+        Observation {
+            value: value & mask,
+            width,
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    /// Column `ix`, counting from the most significant bit
+    pub fn bit(&self, ix: usize) -> bool {
+        (self.value >> (self.width - 1 - ix)) & 1 == 1
+    }
+
+    pub fn bools(&self) -> impl Iterator<Item = bool> + '_ {
+        (0..self.width).map(|ix| self.bit(ix))
+    }
+
+    /// The bits as a number
+    pub fn value(&self) -> u128 {
+        self.value
+    }
+
+    /// The bits as a number of type `T`, or an error if it doesn't fit
+    pub fn to_int<T: TryFrom<u128>>(&self) -> anyhow::Result<T> {
+        let value = self.value;
+        T::try_from(value).map_err(|_| anyhow!("{value} does not fit in a {}", type_name::<T>()))
+    }
+}
+
+impl FromIterator<bool> for Observation {
+    fn from_iter<T: IntoIterator<Item = bool>>(iter: T) -> Self {
+        let (mut value, mut width) = (0u128, 0);
+        for b in iter {
+            if width == MAX_BITS {
+                panic!("More than {MAX_BITS} bits");
+            }
+            value = (value << 1) | b as u128;
+            width += 1;
+        }
+
+        Observation { value, width }
+    }
+}
+
+impl FromStr for Observation {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.len() > MAX_BITS {
+            return Err(anyhow::anyhow!("Width {} is over {MAX_BITS} bits", s.len()));
+        }
+
+        let mut value = 0u128;
+        for c in s.bytes() {
+            let bit = match c {
+                b'0' => 0,
+                b'1' => 1,
+                _ => return Err(anyhow::anyhow!("Unexpected char '{c}'")),
+            };
+            value = (value << 1) | bit;
+        }
+        let obs = Observation {
+            value,
+            width: s.len(),
+        };
+
+        debug!("{s} -> {n} = {n:b}", n = obs.value());
+
+        Ok(obs)
+    }
+}
+
+/// The four ratings of a report, whatever its width, and the bit counts
+/// behind gamma and epsilon
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Ratings {
+    pub width: usize,
+    pub gamma: u128,
+    pub epsilon: u128,
+    pub oxygen: u128,
+    pub co2: u128,
+    pub histogram: BitHistogram,
+}
+
+impl Ratings {
+    /// Part 1's answer, gamma times epsilon
+    pub fn power(&self) -> anyhow::Result<u128> {
+        self.gamma
+            .checked_mul(self.epsilon)
+            .ok_or_else(|| anyhow!("Power {} * {} overflows", self.gamma, self.epsilon))
+    }
+
+    /// Part 2's answer, the oxygen rating times the CO2 rating
+    pub fn life(&self) -> anyhow::Result<u128> {
+        self.oxygen
+            .checked_mul(self.co2)
+            .ok_or_else(|| anyhow!("Life {} * {} overflows", self.oxygen, self.co2))
+    }
+
+    /// The ratings and both answers, or an error if an answer overflows
+    pub fn summary(&self) -> anyhow::Result<DiagnosticSummary> {
+        Ok(DiagnosticSummary {
+            gamma: self.gamma,
+            epsilon: self.epsilon,
+            power: self.power()?,
+            oxygen: self.oxygen,
+            co2: self.co2,
+            life_support: self.life()?,
+        })
+    }
+}
+
+/// Both parts' answers and the ratings they come from. Displays as two lines,
+/// one per part.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct DiagnosticSummary {
+    pub gamma: u128,
+    pub epsilon: u128,
+    pub power: u128,
+    pub oxygen: u128,
+    pub co2: u128,
+    pub life_support: u128,
+}
+
+impl fmt::Display for DiagnosticSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "Power: gamma {} * epsilon {} = {}",
+            self.gamma, self.epsilon, self.power
+        )?;
+        write!(
+            f,
+            "Life support: oxygen {} * CO2 {} = {}",
+            self.oxygen, self.co2, self.life_support
+        )
+    }
+}
+
+/// Finds the ratings of a report of any width up to [`MAX_BITS`], so the
+/// 5-bit example and 12-bit puzzle inputs both work
+pub fn ratings(input: &str) -> anyhow::Result<Ratings> {
+    let observations: Vec<Observation> =
+        info_span!("parse").in_scope(|| parse::buffer(input.as_bytes()))?;
+    let diagnostics = DiagnosticReport::new(observations)?;
+    ensure!(diagnostics.width() > 0, "Empty report");
+    debug!("Report is {} bits wide", diagnostics.width());
+
+    let (gamma, epsilon) = info_span!("part1").in_scope(|| diagnostics.power())?;
+    let (oxygen, co2) = info_span!("part2").in_scope(|| diagnostics.life())?;
+    Ok(Ratings {
+        width: diagnostics.width(),
+        gamma,
+        epsilon,
+        oxygen,
+        co2,
+        histogram: diagnostics.bit_histogram(),
+    })
+}
+
+/// Solves both parts of the puzzle for the given input
+pub fn solve(input: &str) -> anyhow::Result<Solved> {
+    let ratings = ratings(input)?;
+    Ok(Solved::new(ratings.power()?, ratings.life()?))
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// Tests
+
+#[cfg(test)]
+mod tests {
+    use crate::parse;
+    use test_log::test;
+
+    #[allow(unused_imports)]
+    use super::*;
+
+    #[test]
+    fn test_observation() {
+        let obs: Observation = "1".parse().unwrap();
+        assert_eq!((obs.value(), obs.width()), (0b1, 1));
+
+        let obs: Observation = "11".parse().unwrap();
+        assert_eq!(obs.bools().collect::<Vec<bool>>(), vec![true, true]);
+        assert_eq!(obs.value(), 0b11);
+
+        let obs: Observation = "11001".parse().unwrap();
+        assert_eq!((obs.value(), obs.width()), (0b11001, 5));
+        assert_eq!(obs, Observation::from_value(0b11001, 5));
+        assert_eq!(obs, Observation::from_value(0b111001, 5));
+        let expected = [true, true, false, false, true];
+        assert_eq!(obs, Observation::from_iter(expected));
+        assert!(obs.bit(0) && !obs.bit(2) && obs.bit(4));
+
+        // Leading zeros still count towards the width
+        let obs: Observation = "00110".parse().unwrap();
+        assert_eq!((obs.value(), obs.width()), (0b110, 5));
+        assert_ne!(obs, Observation::from_value(0b110, 3));
+
+        let obs: Observation = "1110100100010111".parse().unwrap();
+        assert_eq!(obs.to_int::<u16>().unwrap(), 0b1110100100010111);
+        assert!("10201".parse::<Observation>().is_err());
+    }
+
+    static EXAMPLE: &str = r###"
+        00100
+        11110
+        10110
+        10111
+        10101
+        01111
+        00111
+        11100
+        10000
+        11001
+        00010
+        01010
+    "###;
+
+    #[test]
+    fn test_parse() {
+        let observations: Vec<Observation> = parse::buffer(EXAMPLE.as_bytes()).unwrap();
+
+        let first = observations[0];
+        let refs: Vec<bool> = first.bools().collect();
+        assert_eq!(refs, vec![false, false, true, false, false]);
+
+        let first = observations[1];
+        let refs: Vec<bool> = first.bools().collect();
+        assert_eq!(refs, vec![true, true, true, true, false]);
+        assert_eq!(first.value(), 0b11110);
+    }
+
+    #[test]
+    fn test_diagnostics() {
+        let observations: Vec<Observation> = parse::buffer(EXAMPLE.as_bytes()).unwrap();
+        let diagnostics = DiagnosticReport::new(observations.clone()).unwrap();
+        assert_eq!(diagnostics.width(), 5);
+
+        let (g, e) = diagnostics.power::<u16>().unwrap();
+        assert_eq!((g, e), (22, 9));
+
+        let mut mixed = observations;
+        mixed.push("0010".parse().unwrap());
+        let err = DiagnosticReport::new(mixed).unwrap_err();
+        assert_eq!(err.to_string(), "Observation 13 has 4 bits, expected 5");
+    }
+
+    #[test]
+    fn test_life() {
+        let observations: Vec<Observation> = parse::buffer(EXAMPLE.as_bytes()).unwrap();
+        let diagnostics = DiagnosticReport::new(observations).unwrap();
+
+        let (ox, co) = diagnostics.life::<u16>().unwrap();
+        assert_eq!((ox, co), (23, 10));
+    }
+
+    #[test]
+    fn test_wide() {
+        let line = "110100101011110000110101";
+        let obs: Observation = line.parse().unwrap();
+        assert_eq!(obs.value(), 0b110100101011110000110101);
+        assert_eq!(obs.to_int::<u64>().unwrap(), 0b110100101011110000110101);
+        assert_eq!(Observation::from_value(0b110100101011110000110101, 24), obs);
+
+        let line = "1".repeat(64);
+        let obs: Observation = line.parse().unwrap();
+        assert_eq!(obs.to_int::<u64>().unwrap(), u64::MAX);
+        assert_eq!(obs.bools().count(), 64);
+
+        let obs: Observation = format!("1{}", "0".repeat(99)).parse().unwrap();
+        assert_eq!(obs.value(), 1 << 99);
+        let obs: Observation = "1".repeat(128).parse().unwrap();
+        assert_eq!(obs, Observation::from_value(u128::MAX, 128));
+        assert!("1".repeat(129).parse::<Observation>().is_err());
+
+        // The example with each line repeated 6 times, so the answers repeat
+        // too
+        let observations: Vec<Observation> = EXAMPLE
+            .split_whitespace()
+            .map(|line| line.repeat(6).parse().unwrap())
+            .collect();
+        let diagnostics = DiagnosticReport::new(observations).unwrap();
+        let repeat = |n: u64| (0..6).fold(0, |acc, _| (acc << 5) | n);
+        assert_eq!(diagnostics.power::<u64>().unwrap(), (repeat(22), repeat(9)));
+        assert_eq!(diagnostics.life::<u64>().unwrap(), (repeat(23), repeat(10)));
+        let (g, _) = diagnostics.power::<u32>().unwrap();
+        assert_eq!(g as u64, repeat(22));
+
+        // Too narrow for 30 bits
+        let err = diagnostics.life::<u16>().unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            format!("{} does not fit in a u16", repeat(23))
+        );
+
+        // Repeated 25 times, the example fills 125 bits
+        let observations: Vec<Observation> = EXAMPLE
+            .split_whitespace()
+            .map(|line| line.repeat(25).parse().unwrap())
+            .collect();
+        let diagnostics = DiagnosticReport::new(observations).unwrap();
+        let (ox, co) = diagnostics.life::<u128>().unwrap();
+        assert_eq!(ox, (0..25).fold(0, |acc, _| (acc << 5) | 23));
+        assert_eq!(co, (0..25).fold(0, |acc, _| (acc << 5) | 10));
+        assert!(diagnostics.life::<u64>().is_err());
+    }
+
+    #[test]
+    fn test_inferred_width() {
+        let example = ratings(EXAMPLE).unwrap();
+        assert_eq!(
+            (
+                example.width,
+                example.gamma,
+                example.epsilon,
+                example.oxygen,
+                example.co2
+            ),
+            (5, 22, 9, 23, 10)
+        );
+        assert_eq!(example.histogram.columns.len(), 5);
+        assert_eq!(solve(EXAMPLE).unwrap(), Solved::new(198, 230));
+
+        let wide: String = EXAMPLE
+            .split_whitespace()
+            .map(|line| line.repeat(24) + "\n")
+            .collect();
+        let wide = ratings(&wide).unwrap();
+        assert_eq!(wide.width, 120);
+        assert!(wide.power().is_err());
+
+        assert!(solve("").is_err());
+        // Every line must match the first
+        assert!(solve("00100\n1111\n").is_err());
+        assert!(solve(&"1".repeat(129)).is_err());
+    }
+
+    #[test]
+    fn test_streaming() {
+        let (g, e) = stream_power::<u16, _>(EXAMPLE.as_bytes()).unwrap();
+        assert_eq!((g, e), (22, 9));
+        let err = stream_power::<u16, _>("00100\n0010\n".as_bytes()).unwrap_err();
+        assert_eq!(err.to_string(), "Observation 2 has 4 bits, expected 5");
+
+        let mut power = PowerAccumulator::new();
+        assert!(power.is_empty());
+        power.push("11000".parse().unwrap()).unwrap();
+        power.push("10100".parse().unwrap()).unwrap();
+        power.push("00111".parse().unwrap()).unwrap();
+        assert!(power.push("0011".parse().unwrap()).is_err());
+        assert_eq!((power.len(), power.width()), (3, 5));
+        assert_eq!(power.power::<u8>().unwrap(), (0b10100, 0b01011));
+
+        // Never held in memory: two thirds of the numbers set the top bit,
+        // and the low bits are set as often as not
+        let mut power = PowerAccumulator::new();
+        for n in 0..300_000u128 {
+            let obs = Observation::from_value(((n % 3).min(1) << 19) | (n % 2), 20);
+            power.push(obs).unwrap();
+        }
+        assert_eq!(power.len(), 300_000);
+        assert_eq!(power.power::<u32>().unwrap(), (1 << 19, (1 << 19) - 1));
+    }
+
+    #[test]
+    fn test_packed_counts() {
+        // 1000 is not a multiple of 64, so the last block is partly full, and
+        // 70 bits spill over one word
+        let mut state = 7u128;
+        let observations: Vec<Observation> = (0..1000)
+            .map(|_| {
+                state = state.wrapping_mul(0x2545f4914f6cdd1d).wrapping_add(1);
+                Observation::from_value(state >> 40, 70)
+            })
+            .collect();
+
+        let mut power = PowerAccumulator::new();
+        for &obs in &observations {
+            power.push(obs).unwrap();
+        }
+        let histogram = power.histogram();
+        for (ix, column) in histogram.columns.iter().enumerate() {
+            let ones = observations.iter().filter(|o| o.bit(ix)).count();
+            assert_eq!((column.ones, column.zeros), (ones, 1000 - ones), "bit {ix}");
+        }
+
+        for obs in &observations[..5] {
+            let value = obs.bools().fold(0, |n, b| (n << 1) | b as u128);
+            assert_eq!(obs.value(), value);
+        }
+    }
+
+    #[test]
+    fn test_transpose() {
+        let mut state = 99u64;
+        let mut rows = [0u64; 64];
+        for row in &mut rows {
+            state = state.wrapping_mul(6364136223846793005).wrapping_add(1);
+            *row = state;
+        }
+        let mut transposed = rows;
+        transpose(&mut transposed);
+        for (i, row) in rows.iter().enumerate() {
+            for (j, column) in transposed.iter().enumerate() {
+                assert_eq!(column >> i & 1, row >> j & 1, "({i}, {j})");
+            }
+        }
+        transpose(&mut transposed);
+        assert_eq!(transposed, rows);
+    }
+
+    #[test]
+    fn test_histogram() {
+        let observations: Vec<Observation> = parse::buffer(EXAMPLE.as_bytes()).unwrap();
+        let diagnostics = DiagnosticReport::new(observations).unwrap();
+        let histogram = diagnostics.bit_histogram();
+        let ones: Vec<usize> = histogram.columns.iter().map(|c| c.ones).collect();
+        assert_eq!(ones, vec![7, 5, 8, 7, 5]);
+        assert!(histogram.columns.iter().all(|c| c.ones + c.zeros == 12));
+
+        // Gamma is 10110, or 22
+        assert_eq!(
+            histogram.to_string(),
+            "\
+bit  ones  zeros  gamma
+---  ----  -----  -----
+0    7     5      1
+1    5     7      0
+2    8     4      1
+3    7     5      1
+4    5     7      0
+"
+        );
+
+        let json = serde_json::to_string(&histogram).unwrap();
+        assert!(
+            json.starts_with(r#"{"columns":[{"ones":7,"zeros":5}"#),
+            "{json}"
+        );
+        let reloaded: BitHistogram = serde_json::from_str(&json).unwrap();
+        assert_eq!(reloaded, histogram);
+    }
+
+    #[test]
+    fn test_tie_break() {
+        let observations: Vec<Observation> = parse::buffer(EXAMPLE.as_bytes()).unwrap();
+        let diagnostics = DiagnosticReport::new(observations).unwrap();
+        let puzzle = diagnostics.life_with::<u16>(TieBreak::PreferOne, TieBreak::PreferZero);
+        assert_eq!(puzzle.unwrap(), (23, 10));
+
+        // Oxygen ties at the last bit between 10110 and 10111, and CO2 ties
+        // at the third between 01111 and 01010
+        let flipped = diagnostics.life_with::<u16>(TieBreak::PreferZero, TieBreak::PreferOne);
+        assert_eq!(flipped.unwrap(), (22, 15));
+
+        let err = diagnostics
+            .life_with::<u16>(TieBreak::Error, TieBreak::PreferZero)
+            .unwrap_err();
+        assert_eq!(
+            format!("{err:#}"),
+            "oxygen: Bit 4 is 1 in 1 of 2 observations"
+        );
+        let err = diagnostics
+            .life_with::<u16>(TieBreak::PreferOne, TieBreak::Error)
+            .unwrap_err();
+        assert!(format!("{err:#}").starts_with("CO2: Bit "), "{err:#}");
+
+        // Malformed reports are errors, not panics
+        let empty = DiagnosticReport::new(vec![]).unwrap();
+        assert!(empty.life::<u16>().is_err());
+    }
+
+    #[test]
+    fn test_summary() {
+        let summary = ratings(EXAMPLE).unwrap().summary().unwrap();
+        assert_eq!((summary.power, summary.life_support), (198, 230));
+        assert_eq!(
+            summary.to_string(),
+            "Power: gamma 22 * epsilon 9 = 198\nLife support: oxygen 23 * CO2 10 = 230"
+        );
+
+        let json = serde_json::to_string(&summary).unwrap();
+        assert_eq!(
+            json,
+            r#"{"gamma":22,"epsilon":9,"power":198,"oxygen":23,"co2":10,"life_support":230}"#
+        );
+        let reloaded: DiagnosticSummary = serde_json::from_str(&json).unwrap();
+        assert_eq!(reloaded, summary);
+    }
+
+    /// The puzzle's filter, written out directly
+    fn filter_rating(mut values: Vec<u32>, bits: usize, most_common: bool) -> u32 {
+        for ix in (0..bits).rev() {
+            if values.len() == 1 {
+                break;
+            }
+            let ones = values.iter().filter(|&&v| v >> ix & 1 == 1).count();
+            let zeros = values.len() - ones;
+            let bit = if ones == zeros {
+                most_common
+            } else {
+                (ones > zeros) == most_common
+            };
+            values.retain(|&v| (v >> ix & 1 == 1) == bit);
+        }
+        values[0]
+    }
+
+    #[test]
+    fn test_large_life() {
+        // A simple LCG, taking the high bits. The numbers must be distinct,
+        // or a filter could end with two the same
+        let mut state = 12345u64;
+        let mut values: Vec<u32> = (0..200_000)
+            .map(|_| {
+                state = state
+                    .wrapping_mul(6364136223846793005)
+                    .wrapping_add(1442695040888963407);
+                (state >> 44) as u32
+            })
+            .collect();
+        values.sort_unstable();
+        values.dedup();
+
+        let observations = values
+            .iter()
+            .map(|&v| Observation::from_value(v.into(), 20))
+            .collect();
+        let diagnostics = DiagnosticReport::new(observations).unwrap();
+        let expected = (
+            filter_rating(values.clone(), 20, true),
+            filter_rating(values, 20, false),
+        );
+        assert_eq!(diagnostics.life::<u32>().unwrap(), expected);
+    }
+}
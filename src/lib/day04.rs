@@ -0,0 +1,961 @@
+use std::cmp::Reverse;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::io::BufRead;
+use std::str::FromStr;
+
+use anyhow::{anyhow, ensure, Context};
+use itertools::Itertools;
+use parse_display::Display;
+use thiserror::Error;
+use tracing::info_span;
+
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+
+use crate::nom::{preceded, strict, ws};
+use crate::parallel;
+use crate::solution::Solved;
+
+pub mod parser {
+    use crate::nom::*;
+
+    pub fn number(input: &str) -> IResult<'_, u16> {
+        map_res(digit1, str::parse)(input)
+    }
+
+    /// Spaces within a line
+    fn spaces(input: &str) -> IResult<'_, &str> {
+        recognize(many0(char(' ')))(input)
+    }
+
+    /// The numbers drawn, separated by commas
+    pub fn draws(input: &str) -> IResult<'_, Vec<u16>> {
+        delimited(spaces, separated_list1(char(','), number), spaces)(input)
+    }
+
+    /// A row of a board, its numbers separated and maybe indented by spaces
+    pub fn row(input: &str) -> IResult<'_, Vec<u16>> {
+        delimited(spaces, separated_list1(many1(char(' ')), number), spaces)(input)
+    }
+
+    /// The end of a line and any blank lines after it, as the number of line
+    /// breaks
+    pub fn line_breaks(input: &str) -> IResult<'_, usize> {
+        let line_break = preceded(opt(char('\r')), char('\n'));
+        map(many1(pair(line_break, spaces)), |breaks| breaks.len())(input)
+    }
+}
+
+/// An error for unexpected text at `rest`, the unparsed end of `input`, while
+/// parsing `what`
+fn unexpected(input: &str, rest: &str, what: &str, expected: &str) -> anyhow::Error {
+    let offset = input.len() - rest.len();
+    let line = input[..offset].matches('\n').count() + 1;
+    let found = rest.lines().next().unwrap_or_default().trim();
+    anyhow!("Line {line}, {what}: expected {expected}, found {found:?}")
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BingoGame {
+    instructions: Vec<u16>,
+    pub boards: Vec<Board>,
+    /// Where each number is: the board, row and column of every cell holding
+    /// it, in the order of the boards
+    cells: HashMap<u16, Vec<(usize, usize, usize)>>,
+    played: usize,
+    pub winners: Vec<usize>,
+    playing: HashSet<usize>,
+    tie_order: TieOrder,
+}
+
+impl BingoGame {
+    pub fn parse(mut buf: impl BufRead) -> anyhow::Result<Self> {
+        let mut input = String::new();
+        buf.read_to_string(&mut input)?;
+        input.parse()
+    }
+
+    /// Parses the numbers drawn and the boards, with blank lines between
+    /// boards. Errors say which line, board and row they are on.
+    fn parse_str(input: &str) -> anyhow::Result<Self> {
+        let (mut rest, instructions) = preceded(ws, parser::draws)(input).map_err(|_| {
+            unexpected(
+                input,
+                input.trim_start(),
+                "numbers drawn",
+                "numbers separated by commas",
+            )
+        })?;
+
+        let mut boards: Vec<Board> = Vec::new();
+        let mut rows: Vec<Vec<u16>> = Vec::new();
+        loop {
+            let what = match rows.len() {
+                0 => "numbers drawn".to_owned(),
+                n => format!("board {}, row {n}", boards.len() + 1),
+            };
+            let (after, breaks) = match parser::line_breaks(rest) {
+                Ok(parsed) => parsed,
+                Err(_) if rest.trim().is_empty() => (rest, 0),
+                Err(_) => {
+                    return Err(unexpected(
+                        input,
+                        rest,
+                        &what,
+                        "a number or the end of the line",
+                    ))
+                }
+            };
+
+            // A blank line ends a board, as does the end of the input
+            let at_end = after.trim().is_empty();
+            if (at_end || breaks > 1) && !rows.is_empty() {
+                let board = Board::from_rows(std::mem::take(&mut rows))
+                    .with_context(|| format!("board {}", boards.len() + 1))?;
+                boards.push(board);
+            }
+            if at_end {
+                break;
+            }
+            if boards.is_empty() && rows.is_empty() && breaks < 2 {
+                return Err(unexpected(
+                    input,
+                    after,
+                    "board 1",
+                    "a blank line before it",
+                ));
+            }
+
+            let (after_row, row) = parser::row(after).map_err(|_| {
+                let what = format!("board {}, row {}", boards.len() + 1, rows.len() + 1);
+                unexpected(input, after, &what, "a row of numbers")
+            })?;
+            rows.push(row);
+            rest = after_row;
+        }
+        let board_count = boards.len();
+
+        let mut cells: HashMap<u16, Vec<_>> = HashMap::new();
+        for (ix, board) in boards.iter().enumerate() {
+            for (cell, &value) in board.values.iter().enumerate() {
+                let (row, column) = (cell / board.width, cell % board.width);
+                cells.entry(value).or_default().push((ix, row, column));
+            }
+        }
+
+        let game = BingoGame {
+            instructions,
+            boards,
+            cells,
+            played: 0,
+            winners: Default::default(),
+            playing: HashSet::from_iter(0..board_count),
+            tie_order: TieOrder::default(),
+        };
+        game.validate()?;
+        Ok(game)
+    }
+
+    /// Checks that every board is the same shape as the first, with no number
+    /// twice, and that every number on a board gets drawn; otherwise a board
+    /// might never be able to win
+    pub fn validate(&self) -> Result<(), InvalidGame> {
+        let drawn: HashSet<u16> = self.instructions.iter().copied().collect();
+        let Some(first) = self.boards.first() else {
+            return Ok(());
+        };
+        let expected = (first.rows(), first.columns());
+
+        for (ix, board) in self.boards.iter().enumerate() {
+            let board_number = ix + 1;
+            let shape = (board.rows(), board.columns());
+            if shape != expected {
+                return Err(InvalidGame::Shape {
+                    board: board_number,
+                    shape,
+                    expected,
+                });
+            }
+
+            let mut seen = HashSet::new();
+            for &number in &board.values {
+                if !seen.insert(number) {
+                    return Err(InvalidGame::Duplicate {
+                        board: board_number,
+                        number,
+                    });
+                }
+                if !drawn.contains(&number) {
+                    return Err(InvalidGame::NeverDrawn {
+                        board: board_number,
+                        number,
+                    });
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Orders boards winning on the same draw by `order`, in
+    /// [`BingoGame::winners`] and everything built on it
+    pub fn with_tie_order(mut self, order: TieOrder) -> Self {
+        self.tie_order = order;
+        self
+    }
+
+    /// Returns the value of the drawn instruction, and the number of winning boards
+    pub fn draw(&mut self) -> Option<(u16, usize)> {
+        let &value = self.instructions.get(self.played)?;
+
+        let mut won = self.cross_off(value);
+        for ix in &won {
+            self.playing.remove(ix);
+        }
+
+        let boards = &self.boards;
+        self.tie_order
+            .sort(&mut won, |ix| boards[ix].unmarked_sum());
+        let count = won.len();
+        self.winners.extend(won);
+
+        self.played += 1;
+        Some((value, count))
+    }
+
+    /// Crosses `value` off the boards still playing, returning those that won
+    /// with it
+    #[cfg(not(feature = "parallel"))]
+    fn cross_off(&mut self, value: u16) -> Vec<usize> {
+        // Only the boards with this number can change
+        let mut won = Vec::new();
+        let cells = self.cells.get(&value).map_or(&[][..], Vec::as_slice);
+        for &(ix, row, column) in cells {
+            if !self.playing.contains(&ix) {
+                continue;
+            }
+            let board = &mut self.boards[ix];
+            board.cross(row, column);
+            if board.won() && !won.contains(&ix) {
+                won.push(ix);
+            }
+        }
+        won
+    }
+
+    /// Crosses `value` off the boards still playing, returning those that won
+    /// with it, checking the boards across rayon's thread pool
+    #[cfg(feature = "parallel")]
+    fn cross_off(&mut self, value: u16) -> Vec<usize> {
+        let playing = &self.playing;
+        self.boards
+            .par_iter_mut()
+            .enumerate()
+            .filter(|(ix, _)| playing.contains(ix))
+            .filter_map(|(ix, board)| {
+                board.draw(value);
+                board.won().then_some(ix)
+            })
+            .collect()
+    }
+
+    /// Plays the game from the start, whatever has been drawn already, with
+    /// an event for each number drawn. The game itself is left as it is, so
+    /// it can be played again.
+    pub fn play(&self) -> impl Iterator<Item = DrawEvent> {
+        let mut game = self.clone();
+        game.restart();
+        std::iter::from_fn(move || {
+            let (value, won) = game.draw()?;
+            let winners = game.winners[game.winners.len() - won..]
+                .iter()
+                .map(|&index| Win::new(index, &game.boards[index], value))
+                .collect();
+            Some(DrawEvent { value, winners })
+        })
+    }
+
+    /// The draws that made at least one board win. Those with several are
+    /// ties, ordered by the game's [`TieOrder`].
+    pub fn winning_draws(&self) -> impl Iterator<Item = DrawEvent> {
+        self.play().filter(|event| !event.winners.is_empty())
+    }
+
+    /// When each board wins, as an index into the numbers drawn, or `None`
+    /// if it never does. Each board is checked on its own, so with the
+    /// `parallel` feature they are split across threads.
+    pub fn win_turns(&self) -> Vec<Option<usize>> {
+        let mut turns: HashMap<u16, usize> = HashMap::new();
+        for (turn, &value) in self.instructions.iter().enumerate() {
+            turns.entry(value).or_insert(turn);
+        }
+        parallel::map(&self.boards, |board| board.win_turn(&turns))
+    }
+
+    /// The boards winning on `turn` in the game's [`TieOrder`], as
+    /// `(board_index, draw, score)`
+    fn scores_on(&self, turns: &[Option<usize>], turn: usize) -> Vec<(usize, u16, u64)> {
+        let value = self.instructions[turn];
+        let drawn: HashSet<u16> = self.instructions[..=turn].iter().copied().collect();
+        let unmarked_sum = |ix: usize| -> u32 {
+            let values = self.boards[ix].values.iter();
+            values
+                .filter(|v| !drawn.contains(v))
+                .map(|&v| v as u32)
+                .sum()
+        };
+
+        let mut boards: Vec<usize> = (0..turns.len())
+            .filter(|&ix| turns[ix] == Some(turn))
+            .collect();
+        self.tie_order.sort(&mut boards, unmarked_sum);
+        boards
+            .into_iter()
+            .map(|ix| (ix, value, u64::from(unmarked_sum(ix)) * u64::from(value)))
+            .collect()
+    }
+
+    /// The first board to win, the number that made it win, and its score:
+    /// part 1's answer. `None` if no board ever wins.
+    pub fn first_winner_score(&self) -> Option<(usize, u16, u64)> {
+        let turns = self.win_turns();
+        let &first = turns.iter().flatten().min()?;
+        self.scores_on(&turns, first).first().copied()
+    }
+
+    /// The last board to win, the number that made it win, and its score:
+    /// part 2's answer. `None` if no board ever wins. If several boards win
+    /// on the last draw, this is the last of them by the game's [`TieOrder`].
+    pub fn last_winner_score(&self) -> Option<(usize, u16, u64)> {
+        let turns = self.win_turns();
+        let &last = turns.iter().flatten().max()?;
+        self.scores_on(&turns, last).last().copied()
+    }
+
+    /// Clears every board, ready to draw the first number again
+    fn restart(&mut self) {
+        for board in &mut self.boards {
+            board.clear();
+        }
+        self.played = 0;
+        self.winners.clear();
+        self.playing = HashSet::from_iter(0..self.boards.len());
+    }
+}
+
+/// Why a parsed game can't be played properly. Boards are numbered from 1,
+/// as in parse errors.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum InvalidGame {
+    #[error("Board {board} is {}x{}, but board 1 is {}x{}", shape.0, shape.1, expected.0, expected.1)]
+    Shape {
+        board: usize,
+        /// Rows and columns
+        shape: (usize, usize),
+        expected: (usize, usize),
+    },
+    #[error("Board {board} has {number} more than once")]
+    Duplicate { board: usize, number: u16 },
+    #[error("Board {board} has {number}, which is never drawn")]
+    NeverDrawn { board: usize, number: u16 },
+}
+
+/// How to order boards that win on the same draw. All of them are on the same
+/// number, so their scores only differ by their unmarked sums.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Display, clap::ValueEnum)]
+#[display(style = "kebab-case")]
+pub enum TieOrder {
+    /// In the order of the boards in the input
+    #[default]
+    BoardOrder,
+    /// Highest score first; equal scores in board order
+    HighestScore,
+    /// Lowest score first; equal scores in board order
+    LowestScore,
+}
+
+impl TieOrder {
+    /// Sorts board indices, which start in board order
+    fn sort(self, boards: &mut [usize], unmarked_sum: impl Fn(usize) -> u32) {
+        match self {
+            TieOrder::BoardOrder => {}
+            TieOrder::HighestScore => boards.sort_by_key(|&ix| Reverse(unmarked_sum(ix))),
+            TieOrder::LowestScore => boards.sort_by_key(|&ix| unmarked_sum(ix)),
+        }
+    }
+}
+
+/// A number drawn in [`BingoGame::play`], and the boards it made win
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DrawEvent {
+    pub value: u16,
+    /// In the game's [`TieOrder`]
+    pub winners: Vec<Win>,
+}
+
+impl DrawEvent {
+    /// Whether more than one board won on this draw
+    pub fn is_tie(&self) -> bool {
+        self.winners.len() > 1
+    }
+}
+
+/// A board winning, as it was when it won
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Win {
+    /// Where the board is in [`BingoGame::boards`]
+    pub index: usize,
+    pub board: Board,
+    pub unmarked_sum: u32,
+    /// The unmarked sum times the number just drawn
+    pub score: u64,
+}
+
+impl Win {
+    fn new(index: usize, board: &Board, value: u16) -> Self {
+        let unmarked_sum = board.unmarked_sum();
+        Win {
+            index,
+            board: board.clone(),
+            unmarked_sum,
+            score: u64::from(unmarked_sum) * u64::from(value),
+        }
+    }
+}
+
+/// A bingo board of any size: one or more rows, each with the same number of
+/// columns
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Board {
+    width: usize,
+    /// The numbers, row by row
+    values: Vec<u16>,
+    crossed: Vec<bool>,
+    /// How many numbers are crossed in each row and column, so a win can be
+    /// spotted without looking at the whole board
+    row_marks: Vec<usize>,
+    column_marks: Vec<usize>,
+    has_won: bool,
+}
+
+impl Board {
+    /// Parses a board from its rows, taking its size from them
+    pub fn from_lines(lines: &[impl AsRef<str>]) -> anyhow::Result<Self> {
+        let rows = lines
+            .iter()
+            .enumerate()
+            .map(|(ix, line)| {
+                strict(line.as_ref(), parser::row).with_context(|| format!("row {}", ix + 1))
+            })
+            .collect::<anyhow::Result<_>>()?;
+        Board::from_rows(rows)
+    }
+
+    /// A board with these rows, which must all be the same length
+    pub fn from_rows(rows: Vec<Vec<u16>>) -> anyhow::Result<Self> {
+        ensure!(!rows.is_empty(), "Expected at least one row");
+
+        let width = rows[0].len();
+        ensure!(width > 0, "Expected numbers in row 1");
+        let mut values = Vec::with_capacity(width * rows.len());
+        for (ix, row) in rows.into_iter().enumerate() {
+            ensure!(
+                row.len() == width,
+                "Expected {width} numbers in row {}, found {}",
+                ix + 1,
+                row.len()
+            );
+            values.extend(row);
+        }
+
+        let rows = values.len() / width;
+        Ok(Board {
+            width,
+            crossed: vec![false; values.len()],
+            values,
+            row_marks: vec![0; rows],
+            column_marks: vec![0; width],
+            has_won: false,
+        })
+    }
+
+    pub fn rows(&self) -> usize {
+        self.values.len() / self.width
+    }
+
+    pub fn columns(&self) -> usize {
+        self.width
+    }
+
+    /// Crosses `n` wherever it is on the board. Without the `parallel`
+    /// feature, [`BingoGame`] looks up where numbers are instead of searching
+    /// each board.
+    pub fn draw(&mut self, n: u16) {
+        for cell in 0..self.values.len() {
+            if self.values[cell] == n {
+                self.cross(cell / self.width, cell % self.width);
+            }
+        }
+    }
+
+    fn cross(&mut self, row: usize, column: usize) {
+        let cell = row * self.width + column;
+        if self.crossed[cell] {
+            return;
+        }
+        self.crossed[cell] = true;
+        self.row_marks[row] += 1;
+        self.column_marks[column] += 1;
+        if self.row_marks[row] == self.width || self.column_marks[column] == self.rows() {
+            self.has_won = true;
+        }
+    }
+
+    /// When this board wins, given when each number is drawn: when the last
+    /// number of its first complete row or column is drawn
+    fn win_turn(&self, turns: &HashMap<u16, usize>) -> Option<usize> {
+        let width = self.width;
+        let rows =
+            (0..self.rows()).map(|row| self.complete_turn(turns, row * width..(row + 1) * width));
+        let columns = (0..width)
+            .map(|column| self.complete_turn(turns, (column..self.values.len()).step_by(width)));
+        rows.chain(columns).flatten().min()
+    }
+
+    /// When the last of `cells` is drawn, if they all are
+    fn complete_turn(
+        &self,
+        turns: &HashMap<u16, usize>,
+        mut cells: impl Iterator<Item = usize>,
+    ) -> Option<usize> {
+        cells.try_fold(0, |latest, cell| {
+            Some(latest.max(*turns.get(&self.values[cell])?))
+        })
+    }
+
+    fn clear(&mut self) {
+        self.crossed.fill(false);
+        self.row_marks.fill(0);
+        self.column_marks.fill(0);
+        self.has_won = false;
+    }
+
+    /// Whether any whole row or column is crossed
+    pub fn won(&self) -> bool {
+        self.has_won
+    }
+
+    pub fn unmarked_sum(&self) -> u32 {
+        self.values
+            .iter()
+            .zip(&self.crossed)
+            .filter(|&(_, &crossed)| !crossed)
+            .map(|(&value, _)| value as u32)
+            .sum()
+    }
+}
+
+/// Each row on a line, with crossed numbers in brackets:
+///
+/// ```text
+/// [14] [21] [17] [24] [ 4]
+///  10   16   15  [ 9]  19
+/// ```
+impl fmt::Display for Board {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let digits = self
+            .values
+            .iter()
+            .map(|n| n.to_string().len())
+            .max()
+            .unwrap_or(1);
+        let cells = self.values.iter().zip(&self.crossed);
+        for row in &cells.chunks(self.width) {
+            let row = row
+                .map(|(n, &crossed)| match crossed {
+                    true => format!("[{n:>digits$}]"),
+                    false => format!(" {n:>digits$} "),
+                })
+                .join(" ");
+            writeln!(f, "{}", row.trim_end())?;
+        }
+        Ok(())
+    }
+}
+
+/// The numbers drawn so far, then each board, marking those that have won
+impl fmt::Display for BingoGame {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let drawn = &self.instructions[..self.played];
+        writeln!(
+            f,
+            "Drawn {} of {}: {}",
+            drawn.len(),
+            self.instructions.len(),
+            drawn.iter().join(",")
+        )?;
+        for (ix, board) in self.boards.iter().enumerate() {
+            let won = if self.playing.contains(&ix) {
+                ""
+            } else {
+                " (won)"
+            };
+            write!(f, "\nBoard {ix}{won}:\n{board}")?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for BingoGame {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        BingoGame::parse_str(s)
+    }
+}
+
+/// Solves both parts of the puzzle for the given input
+pub fn solve(input: &str) -> anyhow::Result<Solved> {
+    let game = info_span!("parse").in_scope(|| BingoGame::parse(input.as_bytes()))?;
+
+    // Both parts come from the same game: the first and last boards to win
+    let (_, _, first) = info_span!("part1")
+        .in_scope(|| game.first_winner_score())
+        .context("No board won")?;
+    let (_, _, last) = info_span!("part2")
+        .in_scope(|| game.last_winner_score())
+        .context("No board won")?;
+    Ok(Solved::new(first, last))
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// Tests
+
+#[cfg(test)]
+mod tests {
+    use test_log::test;
+
+    #[allow(unused_imports)]
+    use super::*;
+
+    const EXAMPLE: &str = r###"
+        7,4,9,5,11,17,23,2,0,14,21,24,10,16,13,6,15,25,12,22,18,20,8,19,3,26,1
+
+        22 13 17 11  0
+         8  2 23  4 24
+        21  9 14 16  7
+         6 10  3 18  5
+         1 12 20 15 19
+        
+         3 15  0  2 22
+         9 18 13 17  5
+        19  8  7 25 23
+        20 11 10 24  4
+        14 21 16 12  6
+        
+        14 21 17 24  4
+        10 16 15  9 19
+        18  8 23 26 20
+        22 11 13  6  5
+         2  0 12  3  7
+    "###;
+
+    #[test]
+    fn test_parse() {
+        let game = BingoGame::parse(EXAMPLE.as_bytes()).unwrap();
+        assert_eq!(&game.instructions[..3], vec![7, 4, 9]);
+        assert_eq!(game.instructions.len(), 27);
+        assert_eq!(&game.instructions[24..], vec![3, 26, 1]);
+    }
+
+    #[test]
+    fn test_games() {
+        let mut game = BingoGame::parse(EXAMPLE.as_bytes()).unwrap();
+
+        assert_eq!(game.draw(), Some((7, 0)));
+        assert_eq!(game.draw(), Some((4, 0)));
+        assert_eq!(game.draw(), Some((9, 0)));
+        assert_eq!(game.draw(), Some((5, 0)));
+        assert_eq!(game.draw(), Some((11, 0)));
+        assert_eq!(game.draw(), Some((17, 0)));
+        assert_eq!(game.draw(), Some((23, 0)));
+        assert_eq!(game.draw(), Some((2, 0)));
+        assert_eq!(game.draw(), Some((0, 0)));
+        assert_eq!(game.draw(), Some((14, 0)));
+        assert_eq!(game.draw(), Some((21, 0)));
+        assert_eq!(game.draw(), Some((24, 1)));
+
+        let winner = game.winners[0];
+        assert_eq!(winner, 2);
+
+        let mut last_value = None;
+        for _ in 0..100 {
+            let (value, _) = game.draw().unwrap();
+            if !game.playing.is_empty() {
+                continue;
+            }
+
+            last_value = Some(value);
+            break;
+        }
+
+        assert_eq!(game.winners, vec![2, 0, 1]);
+        assert_eq!(last_value, Some(13));
+
+        let &last_winner = game.winners.last().unwrap();
+        assert_eq!(game.boards[last_winner].unmarked_sum(), 148);
+    }
+
+    #[test]
+    fn test_play() {
+        let mut game = BingoGame::parse(EXAMPLE.as_bytes()).unwrap();
+        let events: Vec<DrawEvent> = game.play().collect();
+        assert_eq!(events.len(), 27);
+        assert_eq!(events[0].value, 7);
+
+        let wins: Vec<(u16, usize, u64)> = events
+            .iter()
+            .flat_map(|e| e.winners.iter().map(|w| (e.value, w.index, w.score)))
+            .collect();
+        assert_eq!(wins, vec![(24, 2, 4512), (16, 0, 2192), (13, 1, 1924)]);
+        let first = &events[11].winners[0];
+        assert_eq!(first.unmarked_sum, 188);
+        assert!(first.board.won());
+
+        // Playing doesn't touch the game, even one already under way
+        for _ in 0..14 {
+            game.draw();
+        }
+        let replayed: Vec<DrawEvent> = game.play().collect();
+        assert_eq!(replayed, events);
+        assert_eq!(game.winners, vec![2, 0]);
+    }
+
+    #[test]
+    fn test_winner_scores() {
+        let game = BingoGame::parse(EXAMPLE.as_bytes()).unwrap();
+        assert_eq!(game.first_winner_score(), Some((2, 24, 4512)));
+        assert_eq!(game.last_winner_score(), Some((1, 13, 1924)));
+
+        // A board with one number wins as soon as it is drawn
+        let game = BingoGame::parse("2,1\n\n1\n".as_bytes()).unwrap();
+        assert_eq!(game.first_winner_score(), Some((0, 1, 0)));
+        assert_eq!(game.last_winner_score(), Some((0, 1, 0)));
+
+        let game = BingoGame::parse("1,2\n".as_bytes()).unwrap();
+        assert_eq!(game.first_winner_score(), None);
+        assert!(solve("1,2\n").is_err());
+    }
+
+    #[test]
+    fn test_large_game() {
+        // 2000 boards of 5x5 over the numbers 0..5000, each board's rows
+        // taken from a different stride, and every number drawn
+        let draws: Vec<String> = (0..5000).rev().map(|n| n.to_string()).collect();
+        let mut input = draws.join(",");
+        for board in 0..2000 {
+            input.push('\n');
+            for row in 0..5 {
+                let numbers: Vec<String> = (0..5)
+                    .map(|col| ((board * 7 + row * 5 + col) * 13 % 5000).to_string())
+                    .collect();
+                input.push_str(&format!("\n{}", numbers.join(" ")));
+            }
+        }
+        input.push('\n');
+
+        let game: BingoGame = input.parse().unwrap();
+        let mut slow = game.clone();
+        let mut fast = game.clone();
+        // Board::draw on each board, as the game did before it had an index
+        while let Some(value) = slow.instructions.get(slow.played).copied() {
+            for (ix, board) in slow.boards.iter_mut().enumerate() {
+                if slow.playing.contains(&ix) {
+                    board.draw(value);
+                    if board.won() {
+                        slow.playing.remove(&ix);
+                        slow.winners.push(ix);
+                    }
+                }
+            }
+            slow.played += 1;
+        }
+        while fast.draw().is_some() {}
+
+        assert_eq!(fast.winners.len(), 2000);
+        assert_eq!(fast.winners, slow.winners);
+        assert_eq!(fast.boards, slow.boards);
+
+        // Each board's win turn, found on its own, matches playing it out
+        let turns = game.win_turns();
+        for (turn, event) in game.play().enumerate() {
+            for win in event.winners {
+                assert_eq!(turns[win.index], Some(turn), "board {}", win.index);
+            }
+        }
+        let (first, last) = (fast.winners[0], fast.winners[1999]);
+        assert_eq!(game.first_winner_score().unwrap().0, first);
+        assert_eq!(game.last_winner_score().unwrap().0, last);
+    }
+
+    #[test]
+    fn test_ties() {
+        // Each board wins on its own draw
+        let input = "1,2,3,4,5,6,9\n\n1 2\n4 5\n\n3 1\n9 6\n\n5 6\n9 4\n";
+        let game: BingoGame = input.parse().unwrap();
+        let draws: Vec<DrawEvent> = game.winning_draws().collect();
+        let value_and_boards = |draws: &[DrawEvent]| -> Vec<(u16, Vec<usize>)> {
+            draws
+                .iter()
+                .map(|e| (e.value, e.winners.iter().map(|w| w.index).collect()))
+                .collect()
+        };
+        assert_eq!(
+            value_and_boards(&draws),
+            vec![(2, vec![0]), (3, vec![1]), (6, vec![2])]
+        );
+        assert!(!draws.iter().any(DrawEvent::is_tie));
+
+        // Drawing 3 completes the top rows of the last two boards, with
+        // unmarked sums 9 + 6 and 9 + 4
+        let input = "1,3,2,4,5,6,9\n\n1 2\n4 5\n\n3 1\n9 6\n\n1 3\n9 4\n";
+        let game: BingoGame = input.parse().unwrap();
+        let draws: Vec<DrawEvent> = game.winning_draws().collect();
+        assert!(draws[0].is_tie());
+        assert_eq!(value_and_boards(&draws)[0], (3, vec![1, 2]));
+        let scores: Vec<u64> = draws[0].winners.iter().map(|w| w.score).collect();
+        assert_eq!(scores, vec![(6 + 9) * 3, (9 + 4) * 3]);
+
+        let lowest = game.clone().with_tie_order(TieOrder::LowestScore);
+        let draws: Vec<DrawEvent> = lowest.winning_draws().collect();
+        assert_eq!(value_and_boards(&draws)[0], (3, vec![2, 1]));
+        assert_eq!(lowest.first_winner_score(), Some((2, 3, 39)));
+
+        let highest = game.with_tie_order(TieOrder::HighestScore);
+        assert_eq!(highest.first_winner_score(), Some((1, 3, 45)));
+        assert_eq!(TieOrder::HighestScore.to_string(), "highest-score");
+    }
+
+    #[test]
+    fn test_board_sizes() {
+        let input = "5,1,6,2,3,4\n\n1 2 3\n4 5 6\n\n6 5 4\n3 2 1\n";
+        let mut game = BingoGame::parse(input.as_bytes()).unwrap();
+        let sizes: Vec<(usize, usize)> = game
+            .boards
+            .iter()
+            .map(|b| (b.rows(), b.columns()))
+            .collect();
+        assert_eq!(sizes, vec![(2, 3), (2, 3)]);
+
+        // The middle column of both
+        assert_eq!(game.draw(), Some((5, 0)));
+        assert_eq!(game.draw(), Some((1, 0)));
+        assert_eq!(game.draw(), Some((6, 0)));
+        assert_eq!(game.draw(), Some((2, 2)));
+        assert_eq!(game.winners, vec![0, 1]);
+        assert_eq!(game.boards[0].unmarked_sum(), 3 + 4);
+        assert_eq!(game.boards[1].unmarked_sum(), 3 + 4);
+
+        let err = BingoGame::parse("1,2\n\n1 2\n3\n".as_bytes()).unwrap_err();
+        assert_eq!(
+            format!("{err:#}"),
+            "board 1: Expected 2 numbers in row 2, found 1"
+        );
+    }
+
+    #[test]
+    fn test_validate() {
+        let invalid = |input: &str| {
+            let err = BingoGame::parse(input.as_bytes()).unwrap_err();
+            err.downcast::<InvalidGame>().unwrap()
+        };
+
+        let shape = invalid("1,2,3,4\n\n1 2\n3 4\n\n1 2 3 4\n");
+        assert_eq!(
+            shape,
+            InvalidGame::Shape {
+                board: 2,
+                shape: (1, 4),
+                expected: (2, 2)
+            }
+        );
+        assert_eq!(shape.to_string(), "Board 2 is 1x4, but board 1 is 2x2");
+
+        let duplicate = invalid("1,2,3\n\n1 2\n3 1\n");
+        assert_eq!(
+            duplicate,
+            InvalidGame::Duplicate {
+                board: 1,
+                number: 1
+            }
+        );
+
+        let never = invalid("1,2,3\n\n1 2\n3 4\n");
+        assert_eq!(never.to_string(), "Board 1 has 4, which is never drawn");
+
+        let game = BingoGame::parse(EXAMPLE.as_bytes()).unwrap();
+        assert_eq!(game.validate(), Ok(()));
+    }
+
+    #[test]
+    fn test_display() {
+        let mut game = BingoGame::parse(EXAMPLE.as_bytes()).unwrap();
+        while game.winners.is_empty() {
+            game.draw();
+        }
+
+        assert_eq!(
+            game.boards[2].to_string(),
+            "\
+[14] [21] [17] [24] [ 4]
+ 10   16   15  [ 9]  19
+ 18    8  [23]  26   20
+ 22  [11]  13    6  [ 5]
+[ 2] [ 0]  12    3  [ 7]
+"
+        );
+
+        let shown = game.to_string();
+        assert!(
+            shown.starts_with("Drawn 12 of 27: 7,4,9,5,11,17,23,2,0,14,21,24\n\nBoard 0:\n"),
+            "{shown}"
+        );
+        assert!(shown.contains("\nBoard 2 (won):\n[14] [21]"), "{shown}");
+        assert_eq!(shown.lines().count(), 1 + 3 * 7);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let error = |input: &str| format!("{:#}", BingoGame::parse(input.as_bytes()).unwrap_err());
+
+        assert_eq!(
+            error("1,2,x\n\n1 2\n"),
+            r#"Line 1, numbers drawn: expected a number or the end of the line, found ",x""#
+        );
+        assert_eq!(
+            error("\n  \n1,2\n1 2\n"),
+            r#"Line 4, board 1: expected a blank line before it, found "1 2""#
+        );
+        assert_eq!(
+            error("1,2\n\n1 2\n3 4\n\n5 6\n7 x\n"),
+            r#"Line 7, board 2, row 2: expected a number or the end of the line, found "x""#
+        );
+        assert_eq!(
+            error("1,2\n\n1 2\n3 4\n\n5 6\nx 7\n"),
+            r#"Line 7, board 2, row 2: expected a row of numbers, found "x 7""#
+        );
+        assert_eq!(
+            error("1,2\n\n1 2\n3 99999\n"),
+            r#"Line 4, board 1, row 2: expected a number or the end of the line, found "99999""#
+        );
+        let crlf = "1,2,3,4\r\n\r\n1 2\r\n3 4\r\n";
+        assert_eq!(BingoGame::parse(crlf.as_bytes()).unwrap().boards.len(), 1);
+        assert_eq!(
+            error(",1\n"),
+            r#"Line 1, numbers drawn: expected numbers separated by commas, found ",1""#
+        );
+
+        let board = Board::from_lines(&["1 2", "3 y"]).unwrap_err();
+        assert!(format!("{board:#}").starts_with("row 2: "), "{board:#}");
+    }
+}
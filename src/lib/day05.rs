@@ -0,0 +1,940 @@
+use std::collections::{HashMap, HashSet};
+use std::fs::File;
+use std::io::{BufRead, BufWriter, Write};
+use std::path::Path;
+use std::str::FromStr;
+
+use anyhow::{bail, ensure, Context};
+use itertools::Either;
+use parse_display::Display;
+use serde::{Deserialize, Serialize};
+use tracing::{debug, info_span};
+
+use crate::nom::strict;
+use crate::parse;
+use crate::render::{Canvas, Palette, Renderer};
+use crate::solution::Solved;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Line {
+    pub start: (i64, i64),
+    pub end: (i64, i64),
+}
+
+impl Line {
+    pub fn horizontal(&self) -> bool {
+        self.start.1 == self.end.1
+    }
+
+    pub fn vertical(&self) -> bool {
+        self.start.0 == self.end.0
+    }
+
+    pub fn diagonal(&self) -> bool {
+        (self.start.1 - self.end.1).abs() == (self.start.0 - self.end.0).abs()
+    }
+
+    /// Whether the line is horizontal, vertical or at 45°, so that it passes
+    /// exactly through each point on it
+    pub fn is_straight(&self) -> bool {
+        self.horizontal() || self.vertical() || self.diagonal()
+    }
+
+    /// Errors if `raster` can't draw this line
+    pub fn check(&self, raster: Raster) -> anyhow::Result<()> {
+        ensure!(
+            raster == Raster::Bresenham || self.is_straight(),
+            "{},{} -> {},{} is not horizontal, vertical or diagonal",
+            self.start.0,
+            self.start.1,
+            self.end.0,
+            self.end.1
+        );
+        Ok(())
+    }
+
+    /// The points on the line, as a set. [`Line::iter_points`] gives the same
+    /// points without building a set.
+    pub fn points(&self) -> HashSet<(i64, i64)> {
+        self.iter_points().collect()
+    }
+
+    /// Each point on the line in turn, from start to end, without allocating.
+    /// Panics if the line isn't [straight](Line::is_straight).
+    pub fn iter_points(&self) -> impl Iterator<Item = (i64, i64)> {
+        let (x1, x2) = (self.start.0, self.end.0);
+        let (y1, y2) = (self.start.1, self.end.1);
+
+        let sign1 = (x2 - x1).signum();
+        let sign2 = (y2 - y1).signum();
+
+        let magnitude1 = (x2 - x1).abs();
+        let magnitude2 = (y2 - y1).abs();
+        let magnitude = match (magnitude1, magnitude2) {
+            (0, m) => m,
+            (m, 0) => m,
+            (m1, m2) if m1 == m2 => m1,
+            _ => panic!("Not a line: {magnitude1}, {magnitude2}"),
+        };
+
+        (0..=magnitude).map(move |d| (x1 + d * sign1, y1 + d * sign2))
+    }
+
+    /// The points nearest the line, from start to end, by Bresenham's
+    /// algorithm: one per step along the longer axis. For straight lines,
+    /// these are the same as [`Line::iter_points`].
+    pub fn bresenham(&self) -> impl Iterator<Item = (i64, i64)> {
+        let ((x0, y0), (x1, y1)) = (self.start, self.end);
+        let (dx, dy) = ((x1 - x0).abs(), -(y1 - y0).abs());
+        let (sx, sy) = ((x1 - x0).signum(), (y1 - y0).signum());
+        let mut error = dx + dy;
+        let mut next = Some((x0, y0));
+        std::iter::from_fn(move || {
+            let (x, y) = next?;
+            next = if (x, y) == (x1, y1) {
+                None
+            } else {
+                let (mut x, mut y) = (x, y);
+                let doubled = 2 * error;
+                if doubled >= dy {
+                    error += dy;
+                    x += sx;
+                }
+                if doubled <= dx {
+                    error += dx;
+                    y += sy;
+                }
+                Some((x, y))
+            };
+            Some((x, y))
+        })
+    }
+
+    /// The part of the line inside the rectangle `((x0, x1), (y0, y1))`,
+    /// bounds included, or `None` if it misses the rectangle. Straight lines
+    /// are cut exactly where they cross its edges; other lines are cut at the
+    /// first and last points Bresenham's algorithm draws inside, so the points
+    /// between may shift slightly when redrawn.
+    pub fn clipped(&self, ((x0, x1), (y0, y1)): ((i64, i64), (i64, i64))) -> Option<Line> {
+        let inside = |&(x, y): &(i64, i64)| (x0..=x1).contains(&x) && (y0..=y1).contains(&y);
+        if !self.is_straight() {
+            let first = self.bresenham().find(inside)?;
+            let last = self.bresenham().filter(inside).last()?;
+            return Some(Line {
+                start: first,
+                end: last,
+            });
+        }
+
+        // Steps from the start, along each axis, that stay within the bounds
+        let steps = |from: i64, to: i64, lo: i64, hi: i64| match (to - from).signum() {
+            0 if (lo..=hi).contains(&from) => Some((0, i64::MAX)),
+            0 => None,
+            1 => Some((lo - from, hi - from)),
+            _ => Some((from - hi, from - lo)),
+        };
+        let (x_first, x_last) = steps(self.start.0, self.end.0, x0, x1)?;
+        let (y_first, y_last) = steps(self.start.1, self.end.1, y0, y1)?;
+        let len = (self.end.0 - self.start.0)
+            .abs()
+            .max((self.end.1 - self.start.1).abs());
+        let first = x_first.max(y_first).max(0);
+        let last = x_last.min(y_last).min(len);
+        if first > last {
+            return None;
+        }
+
+        let (dx, dy) = (
+            (self.end.0 - self.start.0).signum(),
+            (self.end.1 - self.start.1).signum(),
+        );
+        let at = |d: i64| (self.start.0 + d * dx, self.start.1 + d * dy);
+        Some(Line {
+            start: at(first),
+            end: at(last),
+        })
+    }
+
+    /// The points `raster` draws for this line. Panics if it can't; see
+    /// [`Line::check`].
+    pub fn raster(&self, raster: Raster) -> impl Iterator<Item = (i64, i64)> {
+        match raster {
+            Raster::Straight => Either::Left(self.iter_points()),
+            Raster::Bresenham => Either::Right(self.bresenham()),
+        }
+    }
+}
+
+/// How to turn lines into points
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Display, clap::ValueEnum)]
+#[display(style = "kebab-case")]
+pub enum Raster {
+    /// Only horizontal, vertical and 45° lines, as in the puzzle; others are
+    /// errors
+    #[default]
+    Straight,
+    /// Lines at any angle, drawn with Bresenham's algorithm
+    Bresenham,
+}
+
+impl FromStr for Line {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        strict(s, parser::line)
+    }
+}
+
+pub mod parser {
+    use super::Line;
+    use crate::nom::*;
+
+    /// Spaces within a line
+    fn spaces(input: &str) -> IResult<'_, &str> {
+        recognize(many0(char(' ')))(input)
+    }
+
+    /// A point `x,y`, maybe with spaces around the comma
+    pub fn point(input: &str) -> IResult<'_, (i64, i64)> {
+        let comma = delimited(spaces, char(','), spaces);
+        pair(context("x", int), preceded(comma, context("y", int)))(input)
+    }
+
+    /// A line `x1,y1 -> x2,y2`, maybe with more or fewer spaces around the
+    /// arrow
+    pub fn line(input: &str) -> IResult<'_, Line> {
+        let arrow = context("arrow", delimited(spaces, tag("->"), spaces));
+        map(
+            delimited(
+                spaces,
+                tuple((context("start", point), arrow, context("end", point))),
+                spaces,
+            ),
+            |(start, _, end)| Line { start, end },
+        )(input)
+    }
+}
+
+/// The ways a line can run, each with x never decreasing
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+enum Direction {
+    Horizontal,
+    Vertical,
+    /// y rising with x
+    Diagonal,
+    /// y falling as x rises
+    AntiDiagonal,
+}
+
+impl Direction {
+    /// `(a, b)` such that the points on a line in this direction all have the
+    /// same `a * x + b * y`, its carrier
+    fn normal(self) -> (i64, i64) {
+        match self {
+            Direction::Horizontal => (0, 1),
+            Direction::Vertical => (1, 0),
+            Direction::Diagonal => (-1, 1),
+            Direction::AntiDiagonal => (1, 1),
+        }
+    }
+
+    fn carrier(self, (x, y): (i64, i64)) -> i64 {
+        let (a, b) = self.normal();
+        a * x + b * y
+    }
+
+    /// How far along its line a point is
+    fn position(self, (x, y): (i64, i64)) -> i64 {
+        match self {
+            Direction::Vertical => y,
+            _ => x,
+        }
+    }
+}
+
+/// A line as a range of positions along a carrier, so that overlaps can be
+/// worked out without listing points
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+struct Span {
+    direction: Direction,
+    carrier: i64,
+    from: i64,
+    to: i64,
+}
+
+impl Span {
+    fn of(line: &Line) -> Self {
+        let (mut start, mut end) = (line.start, line.end);
+        if (end.0, end.1) < (start.0, start.1) {
+            std::mem::swap(&mut start, &mut end);
+        }
+        let (dx, dy) = (end.0 - start.0, end.1 - start.1);
+        // A single point counts as horizontal
+        let direction = match (dx, dy) {
+            (_, 0) => Direction::Horizontal,
+            (0, _) => Direction::Vertical,
+            _ if dx == dy => Direction::Diagonal,
+            _ if dx == -dy => Direction::AntiDiagonal,
+            _ => panic!("Not a line: {dx}, {dy}"),
+        };
+        Span {
+            direction,
+            carrier: direction.carrier(start),
+            from: direction.position(start),
+            to: direction.position(end),
+        }
+    }
+
+    fn len(&self) -> i64 {
+        self.to - self.from + 1
+    }
+
+    fn contains(&self, point: (i64, i64)) -> bool {
+        let position = self.direction.position(point);
+        self.direction.carrier(point) == self.carrier && (self.from..=self.to).contains(&position)
+    }
+
+    /// The part shared with `other`, if they run along the same carrier
+    fn overlap(&self, other: &Span) -> Option<Span> {
+        if (self.direction, self.carrier) != (other.direction, other.carrier) {
+            return None;
+        }
+        let (from, to) = (self.from.max(other.from), self.to.min(other.to));
+        (from <= to).then_some(Span { from, to, ..*self })
+    }
+
+    /// The point where this crosses `other`, if they cross at all and do so
+    /// at a whole point. Lines running the same way never cross.
+    fn crossing(&self, other: &Span) -> Option<(i64, i64)> {
+        let (a1, b1) = self.direction.normal();
+        let (a2, b2) = other.direction.normal();
+        let det = a1 * b2 - a2 * b1;
+        if det == 0 {
+            return None;
+        }
+        let x = self.carrier * b2 - other.carrier * b1;
+        let y = a1 * other.carrier - a2 * self.carrier;
+        if x % det != 0 || y % det != 0 {
+            return None;
+        }
+        let point = (x / det, y / det);
+        (self.contains(point) && other.contains(point)).then_some(point)
+    }
+}
+
+/// The widest overlap field [`Lines::heatmap`] draws
+pub const HEATMAP_MAX_WIDTH: usize = 120;
+
+/// The largest bounding box, in points, that [`Lines::count_overlaps`] counts
+/// on a dense grid: 32MiB of `u16`s
+pub const DENSE_AREA_LIMIT: u64 = 1 << 24;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Lines {
+    pub lines: Vec<Line>,
+    raster: Raster,
+}
+
+impl Lines {
+    /// Parses straight lines, erroring on any others
+    pub fn parse(buf: impl BufRead) -> anyhow::Result<Self> {
+        Lines::parse_with(buf, Raster::Straight)
+    }
+
+    /// Parses lines that `raster` can draw
+    pub fn parse_with(buf: impl BufRead, raster: Raster) -> anyhow::Result<Self> {
+        let mut lines = Vec::new();
+        for (ix, line) in parse::lines(buf).enumerate() {
+            let line: Line = line.with_context(|| format!("line {}", ix + 1))?;
+            line.check(raster)
+                .with_context(|| format!("line {}", ix + 1))?;
+            lines.push(line);
+        }
+
+        for line in &mut lines {
+            if line.start.0 > line.end.0 {
+                std::mem::swap(&mut line.start, &mut line.end);
+            }
+        }
+
+        lines.sort_by_key(|l| (l.start.0, l.end.0, l.start.1, l.end.1));
+
+        Ok(Lines { lines, raster })
+    }
+
+    pub fn all_points(&self) -> HashMap<(i64, i64), usize> {
+        let mut points = HashMap::new();
+
+        for line in &self.lines {
+            for point in line.raster(self.raster) {
+                *points.entry(point).or_default() += 1;
+            }
+        }
+
+        points
+    }
+
+    pub fn overlap_count(&self) -> usize {
+        self.all_points().values().map(|n| n - 1).sum()
+    }
+
+    pub fn overlaps(&self) -> usize {
+        self.all_points().values().filter(|&&n| n > 1).count()
+    }
+
+    /// The points covered by at least `k` lines, in order. `k = 2` gives the
+    /// points [`Lines::overlaps`] counts.
+    pub fn points_with_at_least(&self, k: usize) -> Vec<(i64, i64)> {
+        let mut points: Vec<(i64, i64)> = self
+            .all_points()
+            .into_iter()
+            .filter(|&(_, n)| n >= k)
+            .map(|(point, _)| point)
+            .collect();
+        points.sort_unstable();
+        points
+    }
+
+    /// The lines clipped to the rectangle `((x0, x1), (y0, y1))`, as
+    /// [`Line::clipped`] does, dropping any outside it. Counting overlaps on
+    /// these covers just that part of the field.
+    pub fn clipped(&self, bounds: ((i64, i64), (i64, i64))) -> Lines {
+        Lines {
+            lines: self
+                .lines
+                .iter()
+                .filter_map(|line| line.clipped(bounds))
+                .collect(),
+            raster: self.raster,
+        }
+    }
+
+    /// The smallest and largest x and y of any line's ends, or `None` if
+    /// there are no lines
+    pub fn bounds(&self) -> Option<((i64, i64), (i64, i64))> {
+        let ends = self.lines.iter().flat_map(|l| [l.start, l.end]);
+        let (xs, ys): (Vec<i64>, Vec<i64>) = ends.unzip();
+        let min_max = |vs: &[i64]| Some((*vs.iter().min()?, *vs.iter().max()?));
+        Some((min_max(&xs)?, min_max(&ys)?))
+    }
+
+    /// The number of points in the bounding box, or `None` if it overflows
+    fn area(&self) -> Option<u64> {
+        let ((x0, x1), (y0, y1)) = self.bounds()?;
+        let width = u64::try_from(x1.checked_sub(x0)?).ok()?.checked_add(1)?;
+        let height = u64::try_from(y1.checked_sub(y0)?).ok()?.checked_add(1)?;
+        width.checked_mul(height)
+    }
+
+    /// The number of points covered by more than one line, counting every
+    /// point in the bounding box on a flat grid. Much faster than a hash map
+    /// when the lines are packed into a small area, as in the puzzle.
+    pub fn dense_overlaps(&self) -> usize {
+        let Some(((x0, x1), (y0, _))) = self.bounds() else {
+            return 0;
+        };
+        let width = (x1 - x0 + 1) as usize;
+        let area = self.area().expect("Bounding box too large") as usize;
+
+        // Only whether a count is over 1 matters, so saturating is fine
+        let mut grid = vec![0u16; area];
+        for line in &self.lines {
+            for (x, y) in line.raster(self.raster) {
+                let ix = (y - y0) as usize * width + (x - x0) as usize;
+                grid[ix] = grid[ix].saturating_add(1);
+            }
+        }
+        grid.iter().filter(|&&n| n > 1).count()
+    }
+
+    /// The number of points covered by more than one line, on a dense grid
+    /// if the bounding box has at most [`DENSE_AREA_LIMIT`] points, or else
+    /// from pairs of lines
+    pub fn count_overlaps(&self) -> usize {
+        match self.area() {
+            Some(area) if area <= DENSE_AREA_LIMIT => {
+                debug!(area, "Counting overlaps on a dense grid");
+                self.dense_overlaps()
+            }
+            area => {
+                debug!(?area, "Counting overlaps pairwise");
+                self.pairwise_overlaps()
+            }
+        }
+    }
+
+    /// The number of points covered by more than one line, as
+    /// [`Lines::overlaps`] finds, but worked out from each pair of lines
+    /// without listing the points they cover, so long lines cost no more than
+    /// short ones. Lines drawn by Bresenham's algorithm don't fit this, so
+    /// are counted with [`Lines::overlaps`].
+    pub fn pairwise_overlaps(&self) -> usize {
+        if self.raster == Raster::Bresenham {
+            return self.overlaps();
+        }
+        let spans: Vec<Span> = self.lines.iter().map(Span::of).collect();
+
+        // Lines along the same carrier share a range of it; others cross at
+        // most once
+        let mut shared: Vec<Span> = Vec::new();
+        let mut crossings = HashSet::new();
+        for (ix, a) in spans.iter().enumerate() {
+            for b in &spans[ix + 1..] {
+                match a.overlap(b) {
+                    Some(overlap) => shared.push(overlap),
+                    None => crossings.extend(a.crossing(b)),
+                }
+            }
+        }
+
+        // Merge the shared ranges on each carrier, so none of them overlap
+        shared.sort();
+        let mut merged: HashMap<(Direction, i64), Vec<Span>> = HashMap::new();
+        for span in shared {
+            let on_carrier = merged.entry((span.direction, span.carrier)).or_default();
+            match on_carrier.last_mut() {
+                Some(last) if span.from <= last.to + 1 => last.to = last.to.max(span.to),
+                _ => on_carrier.push(span),
+            }
+        }
+        let merged: Vec<Span> = merged.into_values().flatten().collect();
+        let covering = |point: (i64, i64)| merged.iter().filter(|s| s.contains(point)).count();
+
+        // Points where merged ranges cross are in more than one of them
+        let mut multiple = HashSet::new();
+        for (ix, a) in merged.iter().enumerate() {
+            for b in &merged[ix + 1..] {
+                multiple.extend(a.crossing(b));
+            }
+        }
+
+        let total: usize = merged.iter().map(|span| span.len() as usize).sum();
+        let repeats: usize = multiple.into_iter().map(|p| covering(p) - 1).sum();
+        let lone_crossings = crossings.into_iter().filter(|&p| covering(p) == 0).count();
+        total - repeats + lone_crossings
+    }
+
+    /// Every point covered by a line with how many lines cover it, along with
+    /// the overlap count, for export
+    pub fn overlap_map(&self) -> OverlapMap {
+        let mut points: Vec<PointCount> = self
+            .all_points()
+            .into_iter()
+            .map(|((x, y), lines)| PointCount { x, y, lines })
+            .collect();
+        points.sort_unstable_by_key(|p| (p.y, p.x));
+        OverlapMap {
+            bounds: self.bounds(),
+            overlaps: self.count_overlaps(),
+            points,
+        }
+    }
+
+    /// A canvas where each point's value is the number of lines covering it
+    pub fn canvas(&self) -> Canvas {
+        Canvas::from_points(self.all_points())
+    }
+
+    /// Writes the overlap field to an image (.png or .svg), brighter where
+    /// more lines overlap
+    pub fn render(&self, path: impl AsRef<Path>) -> anyhow::Result<()> {
+        let canvas = self.canvas();
+        let renderer = Renderer::new(Palette::heat()).fit(&canvas, 1000);
+        renderer.save(&canvas, path)
+    }
+
+    /// Draws the overlap field as text, like the puzzle's diagrams: `.` where
+    /// there are no lines, or else how many there are, with `+` for 10 or
+    /// more. Errors if it is wider than [`HEATMAP_MAX_WIDTH`].
+    pub fn heatmap(&self) -> anyhow::Result<String> {
+        let canvas = self.canvas();
+        ensure!(
+            canvas.width() <= HEATMAP_MAX_WIDTH,
+            "The overlap field is {} wide, too wide to print; render it to an image instead",
+            canvas.width()
+        );
+
+        let mut s = String::with_capacity((canvas.width() + 1) * canvas.height());
+        for y in 0..canvas.height() {
+            for x in 0..canvas.width() {
+                s.push(match canvas.get(x, y) {
+                    0 => '.',
+                    n => char::from_digit(n as u32, 10).unwrap_or('+'),
+                });
+            }
+            s.push('\n');
+        }
+        Ok(s)
+    }
+}
+
+/// A point and the number of lines covering it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PointCount {
+    pub x: i64,
+    pub y: i64,
+    pub lines: usize,
+}
+
+/// The overlap field, as [`Lines::overlap_map`] makes it: the points covered
+/// by any line, row by row, and the totals
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OverlapMap {
+    /// The smallest and largest x and y of any line's ends
+    pub bounds: Option<((i64, i64), (i64, i64))>,
+    /// The number of points covered by more than one line
+    pub overlaps: usize,
+    pub points: Vec<PointCount>,
+}
+
+impl OverlapMap {
+    /// Writes the map as JSON, totals included
+    pub fn write_json<W: Write>(&self, mut out: W) -> anyhow::Result<()> {
+        serde_json::to_writer(&mut out, self)?;
+        writeln!(out)?;
+        out.flush()?;
+        Ok(())
+    }
+
+    /// Writes the points as CSV, one row per point, without the totals
+    pub fn write_csv<W: Write>(&self, mut out: W) -> anyhow::Result<()> {
+        writeln!(out, "x,y,lines")?;
+        for PointCount { x, y, lines } in &self.points {
+            writeln!(out, "{x},{y},{lines}")?;
+        }
+        out.flush()?;
+        Ok(())
+    }
+
+    /// Saves the map to a file, choosing the format from the extension
+    /// (`.json` or `.csv`)
+    pub fn save(&self, path: impl AsRef<Path>) -> anyhow::Result<()> {
+        let path = path.as_ref();
+        let extension = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(|e| e.to_ascii_lowercase());
+        let write = match extension.as_deref() {
+            Some("json") => Self::write_json,
+            Some("csv") => Self::write_csv,
+            _ => bail!(
+                "Unknown format for {}; expected .json or .csv",
+                path.display()
+            ),
+        };
+
+        let file = File::create(path).with_context(|| format!("creating {}", path.display()))?;
+        write(self, BufWriter::new(file))
+    }
+}
+
+/// Solves both parts of the puzzle for the given input
+pub fn solve(input: &str) -> anyhow::Result<Solved> {
+    let lines = info_span!("parse").in_scope(|| Lines::parse(input.as_bytes()))?;
+    let hv_overlaps = info_span!("part1").in_scope(|| {
+        let mut hvlines = lines.clone();
+        hvlines.lines.retain(|l| l.horizontal() || l.vertical());
+        hvlines.count_overlaps()
+    });
+    let overlaps = info_span!("part2").in_scope(|| lines.count_overlaps());
+    Ok(Solved::new(hv_overlaps, overlaps))
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// Tests
+
+#[cfg(test)]
+mod tests {
+    use test_log::test;
+
+    #[allow(unused_imports)]
+    use super::*;
+
+    const EXAMPLE: &str = r###"
+        0,9 -> 5,9
+        8,0 -> 0,8
+        9,4 -> 3,4
+        2,2 -> 2,1
+        7,0 -> 7,4
+        6,4 -> 2,0
+        0,9 -> 2,9
+        3,4 -> 1,4
+        0,0 -> 8,8
+        5,5 -> 8,2
+    "###;
+
+    #[test]
+    fn test_basic() {
+        let lines = Lines::parse(EXAMPLE.as_bytes()).unwrap();
+        let mut hvlines = lines; //.clone();
+        hvlines.lines.retain(|l| l.horizontal() || l.vertical());
+
+        let all_points = hvlines.all_points();
+        let x1 = all_points.keys().map(|&(x, _)| x).min().unwrap();
+        let x2 = all_points.keys().map(|&(x, _)| x).max().unwrap();
+        let y1 = all_points.keys().map(|&(_, y)| y).min().unwrap();
+        let y2 = all_points.keys().map(|&(_, y)| y).max().unwrap();
+
+        for y in y1..=y2 {
+            let row: String = (x1..=x2)
+                .map(|x| {
+                    all_points
+                        .get(&(x, y))
+                        .map(|n| n.to_string().chars().last().unwrap())
+                        .unwrap_or('.')
+                })
+                .collect();
+            debug!("{row}");
+        }
+
+        assert_eq!(hvlines.all_points().len(), 21);
+        assert_eq!(hvlines.overlaps(), 5);
+    }
+
+    #[test]
+    fn test_diagonals() {
+        let lines = Lines::parse(EXAMPLE.as_bytes()).unwrap();
+        assert_eq!(lines.all_points().len(), 39);
+        assert_eq!(lines.overlaps(), 12);
+        assert_eq!(lines.pairwise_overlaps(), 12);
+        assert_eq!(lines.dense_overlaps(), 12);
+        assert_eq!(lines.bounds(), Some(((0, 9), (0, 9))));
+    }
+
+    #[test]
+    fn test_clipped() {
+        let line = Line::from_str("0,0 -> 8,8").unwrap();
+        let clip = |line: Line, bounds| line.clipped(bounds).map(|l| (l.start, l.end));
+        assert_eq!(clip(line, ((2, 5), (3, 9))), Some(((3, 3), (5, 5))));
+        assert_eq!(clip(line, ((-5, 20), (-5, 20))), Some(((0, 0), (8, 8))));
+        assert_eq!(clip(line, ((9, 12), (0, 20))), None);
+        // Both ends outside, passing a corner
+        let line = Line::from_str("8,0 -> 0,8").unwrap();
+        assert_eq!(clip(line, ((0, 3), (0, 5))), Some(((3, 5), (3, 5))));
+        assert_eq!(clip(line, ((0, 2), (0, 5))), None);
+        let line = Line::from_str("3,4 -> 3,-10").unwrap();
+        assert_eq!(clip(line, ((0, 5), (0, 5))), Some(((3, 4), (3, 0))));
+        assert_eq!(clip(line, ((4, 5), (0, 5))), None);
+
+        // Clipping gives the same counts as filtering the full map
+        let lines = Lines::parse(EXAMPLE.as_bytes()).unwrap();
+        for bounds in [((2, 6), (1, 5)), ((0, 9), (4, 4)), ((5, 20), (-3, 2))] {
+            let ((x0, x1), (y0, y1)) = bounds;
+            let expected: HashMap<(i64, i64), usize> = lines
+                .all_points()
+                .into_iter()
+                .filter(|&((x, y), _)| (x0..=x1).contains(&x) && (y0..=y1).contains(&y))
+                .collect();
+            let clipped = lines.clipped(bounds);
+            assert_eq!(clipped.all_points(), expected, "{bounds:?}");
+            assert_eq!(clipped.count_overlaps(), clipped.overlaps());
+        }
+
+        let far = lines.clipped(((100, 200), (100, 200)));
+        assert!(far.lines.is_empty());
+        assert_eq!(far.count_overlaps(), 0);
+    }
+
+    #[test]
+    fn test_parse_line() {
+        let line = Line {
+            start: (0, 9),
+            end: (5, 9),
+        };
+        for s in ["0,9 -> 5,9", "0, 9 ->  5,9", " 0 ,9->5 , 9 ", "0,9->5,9"] {
+            assert_eq!(Line::from_str(s).unwrap(), line, "{s:?}");
+        }
+        assert_eq!(
+            Line::from_str("-3,4 -> 5,-6").unwrap(),
+            Line {
+                start: (-3, 4),
+                end: (5, -6)
+            }
+        );
+
+        for (s, problem) in [
+            ("0,x -> 5,9", "in y"),
+            ("0,9 -> ,9", "in end"),
+            ("0,9 - 5,9", "in arrow"),
+            ("0,9 5,9", "in arrow"),
+        ] {
+            let message = format!("{:#}", Line::from_str(s).unwrap_err());
+            assert!(message.contains(problem), "{s:?}: {message}");
+            assert!(message.contains(s), "{s:?}: {message}");
+        }
+
+        let err = Line::from_str("0,9 -> 5,9,2").unwrap_err();
+        assert!(err.to_string().contains("\",2\""), "{err}");
+    }
+
+    #[test]
+    fn test_overlap_map() {
+        let lines = Lines::parse(EXAMPLE.as_bytes()).unwrap();
+        let map = lines.overlap_map();
+        assert_eq!(map.points.len(), 39);
+        assert_eq!(map.overlaps, 12);
+        assert_eq!(map.bounds, Some(((0, 9), (0, 9))));
+        assert_eq!(
+            map.points[0],
+            PointCount {
+                x: 0,
+                y: 0,
+                lines: 1
+            }
+        );
+        assert_eq!(
+            map.points[1],
+            PointCount {
+                x: 2,
+                y: 0,
+                lines: 1
+            }
+        );
+
+        let mut json = Vec::new();
+        map.write_json(&mut json).unwrap();
+        let reloaded: OverlapMap = serde_json::from_slice(&json).unwrap();
+        assert_eq!(reloaded, map);
+
+        let mut csv = Vec::new();
+        map.write_csv(&mut csv).unwrap();
+        let csv = String::from_utf8(csv).unwrap();
+        assert!(csv.starts_with("x,y,lines\n0,0,1\n2,0,1\n7,0,1\n"), "{csv}");
+        assert_eq!(csv.lines().count(), 40);
+
+        let dir = std::env::temp_dir();
+        let path = dir.join(format!("aoc2021-day05-map-{}.csv", std::process::id()));
+        map.save(&path).unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), csv);
+        std::fs::remove_file(&path).unwrap();
+        assert!(map.save(dir.join("map.txt")).is_err());
+    }
+
+    #[test]
+    fn test_points_with_at_least() {
+        let lines = Lines::parse(EXAMPLE.as_bytes()).unwrap();
+        assert_eq!(lines.points_with_at_least(0).len(), 39);
+        assert_eq!(lines.points_with_at_least(1).len(), 39);
+        assert_eq!(lines.points_with_at_least(2).len(), lines.overlaps());
+        assert_eq!(lines.points_with_at_least(3), vec![(4, 4), (6, 4)]);
+        assert_eq!(lines.points_with_at_least(4), vec![]);
+    }
+
+    #[test]
+    fn test_dense_overlaps() {
+        let parse = |s: &str| Lines::parse(s.as_bytes()).unwrap();
+
+        // Away from the origin, with negative coordinates
+        let offset = parse("-5,-5 -> -1,-1\n-5,-1 -> -1,-5\n-3,-7 -> -3,0\n");
+        assert_eq!(offset.dense_overlaps(), offset.overlaps());
+        assert_eq!(offset.count_overlaps(), 1);
+
+        // Too spread out for a grid, so counted pairwise
+        let spread = parse("0,0 -> 0,10\n0,5 -> 0,20\n100000000,0 -> 100000000,1\n");
+        assert!(spread.area().unwrap() > DENSE_AREA_LIMIT);
+        assert_eq!(spread.count_overlaps(), 6);
+
+        assert_eq!(parse("").count_overlaps(), 0);
+        for seed in 0..3 {
+            let input = crate::gen::find(5)
+                .unwrap()
+                .generate(Some(200), seed)
+                .unwrap();
+            let lines = parse(&input);
+            assert_eq!(lines.dense_overlaps(), lines.overlaps(), "seed {seed}");
+        }
+    }
+
+    #[test]
+    fn test_iter_points() {
+        let line = Line::from_str("5,5 -> 8,2").unwrap();
+        let points: Vec<(i64, i64)> = line.iter_points().collect();
+        assert_eq!(points, vec![(5, 5), (6, 4), (7, 3), (8, 2)]);
+        assert_eq!(line.points(), points.into_iter().collect());
+
+        // Nothing is built up front, so a huge line costs only what is used
+        let long = Line::from_str("0,0 -> 1000000000000,0").unwrap();
+        let mut points = long.iter_points();
+        assert_eq!(points.nth(2), Some((2, 0)));
+        assert!(long.iter_points().any(|(x, _)| x == 10));
+    }
+
+    #[test]
+    fn test_heatmap() {
+        let lines = Lines::parse(EXAMPLE.as_bytes()).unwrap();
+        assert_eq!(
+            lines.heatmap().unwrap(),
+            "\
+1.1....11.
+.111...2..
+..2.1.111.
+...1.2.2..
+.112313211
+...1.2....
+..1...1...
+.1.....1..
+1.......1.
+222111....
+"
+        );
+
+        let wide = Lines::parse("0,0 -> 200,0\n".as_bytes()).unwrap();
+        assert!(wide.heatmap().is_err());
+
+        let path = std::env::temp_dir().join(format!("aoc2021-day05-{}.svg", std::process::id()));
+        lines.render(&path).unwrap();
+        let svg = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert!(svg.starts_with("<svg "), "{svg}");
+    }
+
+    #[test]
+    fn test_raster() {
+        let steep = "0,0 -> 4,2\n0,2 -> 4,0\n2,0 -> 2,4\n";
+        let err = Lines::parse(steep.as_bytes()).unwrap_err();
+        assert_eq!(
+            format!("{err:#}"),
+            "line 1: 0,0 -> 4,2 is not horizontal, vertical or diagonal"
+        );
+
+        let shallow = Line::from_str("0,0 -> 4,2").unwrap();
+        let points: Vec<(i64, i64)> = shallow.bresenham().collect();
+        assert_eq!(points, vec![(0, 0), (1, 1), (2, 1), (3, 2), (4, 2)]);
+
+        // Straight lines come out the same either way
+        let lines = Lines::parse(EXAMPLE.as_bytes()).unwrap();
+        for line in &lines.lines {
+            assert!(line.iter_points().eq(line.bresenham()), "{line:?}");
+        }
+
+        // The first two share (1, 1) and (2, 1), which the third also crosses
+        let lines = Lines::parse_with(steep.as_bytes(), Raster::Bresenham).unwrap();
+        assert_eq!(lines.overlaps(), 2);
+        assert_eq!(lines.dense_overlaps(), 2);
+        assert_eq!(lines.pairwise_overlaps(), 2);
+    }
+
+    #[test]
+    fn test_pairwise_overlaps() {
+        let parse = |s: &str| Lines::parse(s.as_bytes()).unwrap();
+
+        // Three lines through one point, and a point on its own
+        let star = parse("0,0 -> 4,4\n0,4 -> 4,0\n2,0 -> 2,9\n2,2 -> 2,2\n7,7 -> 7,7\n");
+        assert_eq!(star.pairwise_overlaps(), star.overlaps());
+        // Diagonals crossing between whole points don't overlap
+        let between = parse("0,0 -> 3,3\n0,1 -> 3,-2\n");
+        assert_eq!(between.pairwise_overlaps(), 0);
+        // Collinear lines overlapping in a chain, crossed by another
+        let chain = parse("0,5 -> 10,5\n4,5 -> 20,5\n15,5 -> 30,5\n12,0 -> 12,9\n8,0 -> 8,20\n");
+        assert_eq!(chain.pairwise_overlaps(), chain.overlaps());
+
+        for seed in 0..5 {
+            let input = crate::gen::find(5)
+                .unwrap()
+                .generate(Some(150), seed)
+                .unwrap();
+            let lines = parse(&input);
+            assert_eq!(lines.pairwise_overlaps(), lines.overlaps(), "seed {seed}");
+        }
+    }
+}
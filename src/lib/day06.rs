@@ -0,0 +1,1061 @@
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{BufRead, BufWriter, Write};
+use std::ops::{Add, AddAssign, RangeInclusive};
+use std::path::Path;
+use std::str::FromStr;
+
+use anyhow::{bail, ensure, Context};
+use tracing::info_span;
+
+use crate::count::{checked_add, checked_mul, count, residue, Count, Overflow};
+use crate::parse;
+use crate::solution::Solved;
+
+/// Days between a grown fish's births, in the puzzle
+pub const REFRESH: u8 = 7;
+/// Extra days before a newborn fish's first birth, in the puzzle
+pub const INITIAL: u8 = 2;
+
+/// A square matrix, as a list of rows
+type Matrix<T> = Vec<Vec<T>>;
+
+/// `a * b`, where `mul_add(sum, x, y)` is `sum + x * y`
+fn matrix_product<T: Clone>(
+    a: &Matrix<T>,
+    b: &Matrix<T>,
+    zero: &T,
+    mul_add: &impl Fn(T, &T, &T) -> T,
+) -> Matrix<T> {
+    let n = a.len();
+    (0..n)
+        .map(|i| {
+            (0..n)
+                .map(|j| (0..n).fold(zero.clone(), |sum, k| mul_add(sum, &a[i][k], &b[k][j])))
+                .collect()
+        })
+        .collect()
+}
+
+/// `m` to the power `exponent`, by repeated squaring
+fn matrix_power<T: Clone>(
+    m: &Matrix<T>,
+    mut exponent: u64,
+    (zero, one): (&T, &T),
+    mul_add: &impl Fn(T, &T, &T) -> T,
+) -> Matrix<T> {
+    let n = m.len();
+    let mut result: Matrix<T> = (0..n)
+        .map(|i| {
+            (0..n)
+                .map(|j| if i == j { one } else { zero }.clone())
+                .collect()
+        })
+        .collect();
+    let mut square = m.clone();
+    while exponent > 0 {
+        if exponent & 1 == 1 {
+            result = matrix_product(&result, &square, zero, mul_add);
+        }
+        exponent >>= 1;
+        if exponent > 0 {
+            square = matrix_product(&square, &square, zero, mul_add);
+        }
+    }
+    result
+}
+
+/// The number of fish with each timer value, for fish that give birth every
+/// `refresh` days, and whose young wait `initial` days longer the first time.
+///
+/// The puzzle's schools outgrow a `u64` after about 500 days; build with the
+/// `bigint` feature to simulate thousands.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FishSchool {
+    fish: VecDeque<Count>,
+    refresh: u8,
+    /// Days simulated so far
+    day: u64,
+}
+
+impl FromStr for FishSchool {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        FishSchool::parse_with(s, REFRESH, INITIAL)
+    }
+}
+
+/// Panics on timers too long for the puzzle's fish; see [`FishSchool::insert`]
+impl FromIterator<u8> for FishSchool {
+    fn from_iter<T: IntoIterator<Item = u8>>(iter: T) -> Self {
+        let mut school = FishSchool::new(REFRESH, INITIAL);
+        for timer in iter {
+            school.insert(timer).unwrap();
+        }
+        school
+    }
+}
+
+/// Combines schools as [`FishSchool::merge`] does, panicking if their cycles
+/// differ
+impl AddAssign<&FishSchool> for FishSchool {
+    fn add_assign(&mut self, other: &FishSchool) {
+        self.merge(other).unwrap();
+    }
+}
+
+impl AddAssign for FishSchool {
+    fn add_assign(&mut self, other: FishSchool) {
+        *self += &other;
+    }
+}
+
+impl Add for FishSchool {
+    type Output = FishSchool;
+
+    fn add(mut self, other: FishSchool) -> FishSchool {
+        self += &other;
+        self
+    }
+}
+
+impl Extend<FishSchool> for FishSchool {
+    fn extend<T: IntoIterator<Item = FishSchool>>(&mut self, iter: T) {
+        for school in iter {
+            *self += &school;
+        }
+    }
+}
+
+/// Combines the schools, or makes an empty school of the puzzle's fish if
+/// there are none
+impl FromIterator<FishSchool> for FishSchool {
+    fn from_iter<T: IntoIterator<Item = FishSchool>>(iter: T) -> Self {
+        let mut iter = iter.into_iter();
+        let mut school = iter
+            .next()
+            .unwrap_or_else(|| FishSchool::new(REFRESH, INITIAL));
+        school.extend(iter);
+        school
+    }
+}
+
+impl FishSchool {
+    /// A school with no fish. Panics if `refresh` is 0.
+    pub fn new(refresh: u8, initial: u8) -> Self {
+        assert!(refresh > 0, "Fish must wait at least a day between births");
+        let timers = refresh as usize + initial as usize;
+        FishSchool {
+            fish: VecDeque::from_iter(std::iter::repeat_n(Count::default(), timers)),
+            refresh,
+            day: 0,
+        }
+    }
+
+    /// The number of days simulated so far
+    pub fn day(&self) -> u64 {
+        self.day
+    }
+
+    /// Adds the fish in `other` to this school. If one school is behind, it is
+    /// first advanced to the other's day. Errors if the fish have different
+    /// cycles.
+    pub fn merge(&mut self, other: &FishSchool) -> anyhow::Result<()> {
+        let (refresh, initial) = self.cycle();
+        ensure!(
+            other.cycle() == (refresh, initial),
+            "Can't merge schools with cycles {:?} and {:?}",
+            (refresh, initial),
+            other.cycle()
+        );
+
+        let mut other = other.clone();
+        if self.day < other.day {
+            self.try_step_n(other.day - self.day)?;
+        } else {
+            other.try_step_n(self.day - other.day)?;
+        }
+        for (n, more) in self.fish.iter_mut().zip(other.fish) {
+            *n += more;
+        }
+        Ok(())
+    }
+
+    /// Days between births, and the extra days newborns wait
+    fn cycle(&self) -> (u8, u8) {
+        (
+            self.refresh,
+            (self.fish.len() - self.refresh as usize) as u8,
+        )
+    }
+
+    /// Parses comma-separated timers for fish with the given cycle lengths
+    pub fn parse_with(s: &str, refresh: u8, initial: u8) -> anyhow::Result<Self> {
+        FishSchool::read(s.as_bytes(), refresh, initial)
+    }
+
+    /// Reads comma-separated timers for fish with the given cycle lengths one
+    /// at a time, so the whole list never needs to be in memory
+    pub fn read(mut buf: impl BufRead, refresh: u8, initial: u8) -> anyhow::Result<Self> {
+        let mut school = FishSchool::new(refresh, initial);
+        let mut piece = Vec::new();
+        let mut fish = 0;
+        let mut after_comma = false;
+        loop {
+            piece.clear();
+            let read = buf.read_until(b',', &mut piece)?;
+            if read == 0 && !after_comma {
+                break;
+            }
+            after_comma = piece.last() == Some(&b',');
+            if after_comma {
+                piece.pop();
+            }
+
+            fish += 1;
+            let timer = std::str::from_utf8(&piece)
+                .map_err(anyhow::Error::from)
+                .and_then(|s| Ok(s.trim().parse()?))
+                .with_context(|| format!("fish {fish}: {:?}", String::from_utf8_lossy(&piece)))?;
+            school
+                .insert(timer)
+                .with_context(|| format!("fish {fish}"))?;
+        }
+        ensure!(fish > 0, "No fish");
+        Ok(school)
+    }
+
+    /// Adds a fish with `timer` days until it gives birth. Errors if that is
+    /// longer than a newborn fish waits.
+    pub fn insert(&mut self, timer: u8) -> anyhow::Result<()> {
+        let longest = self.fish.len() - 1;
+        ensure!(
+            (timer as usize) <= longest,
+            "Timer {timer} is longer than a newborn's ({longest})"
+        );
+        self.fish[timer as usize] += count(1);
+        Ok(())
+    }
+
+    /// Steps a day, or errors instead of overflowing, leaving the school as it
+    /// was
+    // The clone is needed when Count is a BigUint.
+    #[allow(clippy::clone_on_copy)]
+    pub fn try_step(&mut self) -> Result<(), Overflow> {
+        let refresh = self.refresh as usize;
+        // Those moving into the refreshed fish's timer, which are the babies
+        // if they share it
+        let joining = self.fish.get(refresh).unwrap_or(&self.fish[0]);
+        let refreshed = checked_add(joining, &self.fish[0]).ok_or(Overflow)?;
+
+        let birthing = self.fish.pop_front().unwrap();
+        self.fish.push_back(birthing.clone());
+        self.fish[refresh - 1] = refreshed;
+        self.day += 1;
+        Ok(())
+    }
+
+    /// Advances `n` days at once, in `O(log n)` matrix products rather than
+    /// `n` steps, or errors instead of overflowing, leaving the school as it
+    /// was. Only the number of steps is small: the totals still grow
+    /// exponentially, so for very large `n` see [`FishSchool::total_after_mod`].
+    pub fn try_step_n(&mut self, n: u64) -> Result<(), Overflow> {
+        // `None` is a number too large to count. Multiplying it by 0 is still
+        // 0, so that descendants of fish the school doesn't have can't
+        // overflow the total.
+        let mul_add = |sum: Option<Count>, a: &Option<Count>, b: &Option<Count>| {
+            let zero = Some(count(0));
+            if *a == zero || *b == zero {
+                return sum;
+            }
+            checked_add(&sum?, &checked_mul(a.as_ref()?, b.as_ref()?)?)
+        };
+        let fish: Vec<Option<Count>> = self.fish.iter().cloned().map(Some).collect();
+        let advanced = self.advanced(n, (&Some(count(0)), &Some(count(1))), &fish, &mul_add);
+        self.fish = advanced
+            .into_iter()
+            .collect::<Option<_>>()
+            .ok_or(Overflow)?;
+        self.day += n;
+        Ok(())
+    }
+
+    /// The number of fish with each timer `n` days after `fish`, where
+    /// `mul_add(sum, a, b)` is `sum + a * b`
+    fn advanced<T: Clone>(
+        &self,
+        n: u64,
+        (zero, one): (&T, &T),
+        fish: &[T],
+        mul_add: &impl Fn(T, &T, &T) -> T,
+    ) -> Vec<T> {
+        let transition = self.transition((zero, one), mul_add);
+        let power = matrix_power(&transition, n, (zero, one), mul_add);
+        power
+            .iter()
+            .map(|row| {
+                row.iter()
+                    .zip(fish)
+                    .fold(zero.clone(), |sum, (a, b)| mul_add(sum, a, b))
+            })
+            .collect()
+    }
+
+    /// The matrix taking the fish with each timer to those the next day
+    fn transition<T: Clone>(
+        &self,
+        (zero, one): (&T, &T),
+        mul_add: &impl Fn(T, &T, &T) -> T,
+    ) -> Matrix<T> {
+        let n = self.fish.len();
+        let mut m = vec![vec![zero.clone(); n]; n];
+        for (timer, row) in m.iter_mut().enumerate().take(n - 1) {
+            row[timer + 1] = one.clone();
+        }
+        // babies
+        m[n - 1][0] = one.clone();
+        // refresh, which may be the babies' timer too
+        let refreshed = &mut m[self.refresh as usize - 1][0];
+        *refreshed = mul_add(refreshed.clone(), one, one);
+        m
+    }
+
+    /// The total, or an error if it doesn't fit in a [`Count`]
+    pub fn try_total(&self) -> Result<Count, Overflow> {
+        self.fish
+            .iter()
+            .try_fold(count(0), |sum, n| checked_add(&sum, n))
+            .ok_or(Overflow)
+    }
+
+    /// The number of fish with each timer value, from 0 up to a newborn's
+    pub fn timers(&self) -> Vec<Count> {
+        self.fish.iter().cloned().collect()
+    }
+
+    /// The number of fish with each timer on each of the next `days` days,
+    /// starting from today, or an error if a count overflows
+    pub fn try_series(&self, days: u64) -> Result<AgeSeries, Overflow> {
+        let mut school = self.clone();
+        let mut days_timers = vec![school.timers()];
+        for _ in 0..days {
+            school.try_step()?;
+            days_timers.push(school.timers());
+        }
+        Ok(AgeSeries { days: days_timers })
+    }
+
+    /// The total on `day`, counting from when the school was parsed, leaving
+    /// the school as it is. Errors if the school has already passed that day,
+    /// or if the total overflows.
+    pub fn population_at(&self, day: u64) -> anyhow::Result<Count> {
+        let Some(days) = day.checked_sub(self.day) else {
+            bail!("The school is on day {}, already past day {day}", self.day);
+        };
+        let mut school = self.clone();
+        school.try_step_n(days)?;
+        let total = school.try_total()?;
+        Ok(total)
+    }
+
+    /// The total after `days` more days, or an error if it overflows
+    pub fn total_after(&self, days: u64) -> Result<Count, Overflow> {
+        let mut school = self.clone();
+        school.try_step_n(days)?;
+        school.try_total()
+    }
+
+    /// The total after `days` more days, modulo `modulus`. This works for any
+    /// number of days, even where the total itself would not fit in memory.
+    pub fn total_after_mod(&self, days: u64, modulus: u64) -> u64 {
+        let mul_add = |sum: u64, &a: &u64, &b: &u64| {
+            ((sum as u128 + a as u128 * b as u128) % modulus as u128) as u64
+        };
+        let fish: Vec<u64> = self.fish.iter().map(|n| residue(n, modulus)).collect();
+        let advanced = self.advanced(days, (&0, &(1 % modulus)), &fish, &mul_add);
+        advanced.iter().fold(0, |sum, n| mul_add(sum, n, &1))
+    }
+}
+
+/// The fish with each timer value over time, from [`FishSchool::try_series`], for
+/// plotting how the population is made up
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AgeSeries {
+    /// For each day, the number of fish with each timer value
+    pub days: Vec<Vec<Count>>,
+}
+
+impl AgeSeries {
+    /// Writes one row per day: the day, the number of fish with each timer
+    /// value, and the total
+    pub fn write_csv<W: Write>(&self, mut out: W) -> anyhow::Result<()> {
+        let timers = self.days.first().map_or(0, Vec::len);
+        write!(out, "day")?;
+        for timer in 0..timers {
+            write!(out, ",timer{timer}")?;
+        }
+        writeln!(out, ",total")?;
+
+        for (day, fish) in self.days.iter().enumerate() {
+            write!(out, "{day}")?;
+            for n in fish {
+                write!(out, ",{n}")?;
+            }
+            writeln!(out, ",{}", fish.iter().sum::<Count>())?;
+        }
+        out.flush()?;
+        Ok(())
+    }
+
+    /// Writes a JSON list with an object per day, like
+    /// `{"day": 0, "timers": [0, 1, ...], "total": 5}`. Counts are written
+    /// out in full, even past what a `u64` holds.
+    pub fn write_json<W: Write>(&self, mut out: W) -> anyhow::Result<()> {
+        writeln!(out, "[")?;
+        for (day, fish) in self.days.iter().enumerate() {
+            let timers = fish.iter().map(Count::to_string).collect::<Vec<_>>();
+            let total = fish.iter().sum::<Count>();
+            let comma = if day + 1 < self.days.len() { "," } else { "" };
+            writeln!(
+                out,
+                "  {{\"day\": {day}, \"timers\": [{}], \"total\": {total}}}{comma}",
+                timers.join(", ")
+            )?;
+        }
+        writeln!(out, "]")?;
+        out.flush()?;
+        Ok(())
+    }
+
+    /// Saves the series to a file, choosing the format from the extension
+    /// (`.csv` or `.json`)
+    pub fn save(&self, path: impl AsRef<Path>) -> anyhow::Result<()> {
+        let path = path.as_ref();
+        let extension = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(|e| e.to_ascii_lowercase());
+        let write = match extension.as_deref() {
+            Some("csv") => Self::write_csv,
+            Some("json") => Self::write_json,
+            _ => bail!(
+                "Unknown format for {}; expected .csv or .json",
+                path.display()
+            ),
+        };
+
+        let file = File::create(path).with_context(|| format!("creating {}", path.display()))?;
+        write(self, BufWriter::new(file))
+    }
+}
+
+/// Finds starting schools that grow to a given total, for the puzzle in
+/// reverse. The total is the sum over timers of the number of fish starting
+/// with that timer times the number of descendants one such fish has, so for
+/// each number of starting fish, this solves for how many have each timer.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Ancestry {
+    total: u128,
+    refresh: u8,
+    initial: u8,
+    /// Each timer starting fish may have, and the number of fish one of them
+    /// grows to, from most to fewest, leaving out those with more than the
+    /// total
+    weights: Vec<(u8, u128)>,
+}
+
+impl Ancestry {
+    /// Starting schools of fish with the given cycle lengths and `timers`
+    /// that have `total` fish after `days` days. For the puzzle's inputs, the
+    /// timers are `1..=5`.
+    pub fn new(
+        total: u64,
+        days: u64,
+        (refresh, initial): (u8, u8),
+        timers: RangeInclusive<u8>,
+    ) -> anyhow::Result<Self> {
+        let school = FishSchool::new(refresh, initial);
+        let longest = school.fish.len() - 1;
+        ensure!(
+            *timers.end() as usize <= longest,
+            "Timers {timers:?} are longer than a newborn's ({longest})"
+        );
+
+        // As in `FishSchool::try_step_n`, with `None` for too many to count
+        let mul_add = |sum: Option<u128>, &a: &Option<u128>, &b: &Option<u128>| {
+            if a == Some(0) || b == Some(0) {
+                return sum;
+            }
+            sum?.checked_add(a?.checked_mul(b?)?)
+        };
+        let transition = school.transition((&Some(0), &Some(1)), &mul_add);
+        let power = matrix_power(&transition, days, (&Some(0), &Some(1)), &mul_add);
+        let total = total as u128;
+        let weights = timers
+            .filter_map(|timer| {
+                let mut column = power.iter().map(|row| row[timer as usize]);
+                let descendants = column.try_fold(0u128, |sum, n| sum.checked_add(n?))?;
+                Some((timer, descendants)).filter(|&(_, n)| n <= total)
+            })
+            .collect();
+
+        Ok(Ancestry {
+            total,
+            refresh,
+            initial,
+            weights,
+        })
+    }
+
+    /// The numbers of starting fish that could reach the total, going by how
+    /// many fish one fish can grow to. Not all of them are possible.
+    pub fn fish_range(&self) -> RangeInclusive<u64> {
+        let (Some(&(_, most)), Some(&(_, fewest))) = (self.weights.first(), self.weights.last())
+        else {
+            return 0..=0;
+        };
+        let lo = self.total.div_ceil(most) as u64;
+        let hi = (self.total / fewest) as u64;
+        lo..=hi
+    }
+
+    /// A starting school of `fish` fish that reaches the total, if there is
+    /// one
+    pub fn find(&self, fish: u64) -> Option<FishSchool> {
+        let mut counts = Vec::with_capacity(self.weights.len());
+        if !search(&self.weights, fish as u128, self.total, &mut counts) {
+            return None;
+        }
+
+        let mut school = FishSchool::new(self.refresh, self.initial);
+        for (&(timer, _), n) in self.weights.iter().zip(counts) {
+            school.fish[timer as usize] = count(n as u64);
+        }
+        Some(school)
+    }
+
+    /// A starting school with as few fish as possible, if any reaches the
+    /// total
+    pub fn fewest(&self) -> Option<FishSchool> {
+        self.fish_range().find_map(|fish| self.find(fish))
+    }
+
+    /// A starting school with as many fish as possible, if any reaches the
+    /// total
+    pub fn most(&self) -> Option<FishSchool> {
+        self.fish_range().rev().find_map(|fish| self.find(fish))
+    }
+}
+
+/// Finds how many of `fish` fish have each weight, from most to fewest, so
+/// that their weights add up to `total`, pushing the counts onto `counts`.
+/// Tries each count for all but the last three weights, and solves for those.
+fn search(weights: &[(u8, u128)], fish: u128, total: u128, counts: &mut Vec<u128>) -> bool {
+    let w = |ix: usize| weights[ix].1;
+    let solved = match weights.len() {
+        0 => (fish == 0 && total == 0).then(Vec::new),
+        1 => (fish * w(0) == total).then(|| vec![fish]),
+        2 => solve_two(fish, total, (w(0), w(1))).map(|(a, b)| vec![a, b]),
+        3 => solve_three(fish, total, (w(0), w(1), w(2))).map(|(a, b, c)| vec![a, b, c]),
+        _ => None,
+    };
+    if weights.len() <= 3 {
+        let Some(solved) = solved else {
+            return false;
+        };
+        counts.extend(solved);
+        return true;
+    }
+
+    // The rest of the fish must make up the rest of the total, with between
+    // the fewest and most descendants each
+    let (most, rest_most, fewest) = (w(0), w(1), w(weights.len() - 1));
+    let Some(spare) = total.checked_sub(fish * fewest) else {
+        return false;
+    };
+    let hi = match most - fewest {
+        0 if spare == 0 => fish,
+        0 => return false,
+        diff => fish.min(spare / diff),
+    };
+    let lo = match total.checked_sub(fish * rest_most) {
+        None | Some(0) => 0,
+        Some(short) if most > rest_most => short.div_ceil(most - rest_most),
+        Some(_) => return false,
+    };
+    for n in lo..=hi {
+        counts.push(n);
+        if search(&weights[1..], fish - n, total - n * most, counts) {
+            return true;
+        }
+        counts.pop();
+    }
+    false
+}
+
+/// `(a, b)` with `a + b = fish` and `a * wa + b * wb = total`, for `wa >= wb`
+fn solve_two(fish: u128, total: u128, (wa, wb): (u128, u128)) -> Option<(u128, u128)> {
+    let extra = total.checked_sub(fish * wb)?;
+    let a = match wa - wb {
+        0 if extra == 0 => 0,
+        0 => return None,
+        diff if extra % diff == 0 => extra / diff,
+        _ => return None,
+    };
+    Some((a, fish.checked_sub(a)?))
+}
+
+/// `(a, b, c)` with `a + b + c = fish` and `a * wa + b * wb + c * wc = total`,
+/// for `wa >= wb >= wc`
+fn solve_three(
+    fish: u128,
+    total: u128,
+    (wa, wb, wc): (u128, u128, u128),
+) -> Option<(u128, u128, u128)> {
+    // Every fish has at least `wc` descendants, and `a` and `b` fish have
+    // `da` and `db` more: `a * da + b * db = extra`, with `a + b <= fish`
+    let extra = total.checked_sub(fish * wc)?;
+    let (da, db) = (wa - wc, wb - wc);
+    let (a, b) = match (da, db) {
+        (0, _) if extra == 0 => (0, 0),
+        (0, _) => return None,
+        (_, 0) if extra % da == 0 => (extra / da, 0),
+        (_, 0) => return None,
+        _ => {
+            // `a` is fixed modulo `db / g`; use the largest `a` that leaves `b`
+            // non-negative, as that also uses the fewest fish
+            let (g, x, _) = extended_gcd(da as i128, db as i128);
+            let g = g as u128;
+            if extra % g != 0 {
+                return None;
+            }
+            let step = db / g;
+            let inverse = x.rem_euclid(step as i128) as u128;
+            let smallest = (inverse * ((extra / g) % step)) % step;
+            let largest = extra / da;
+            if largest < smallest {
+                return None;
+            }
+            let a = smallest + (largest - smallest) / step * step;
+            (a, (extra - a * da) / db)
+        }
+    };
+    Some((a, b, fish.checked_sub(a + b)?))
+}
+
+/// `(g, x, y)` with `g = gcd(a, b) = a * x + b * y`
+fn extended_gcd(a: i128, b: i128) -> (i128, i128, i128) {
+    if b == 0 {
+        return (a, 1, 0);
+    }
+    let (g, x, y) = extended_gcd(b, a % b);
+    (g, y, x - (a / b) * y)
+}
+
+/// Solves both parts of the puzzle for the given input
+pub fn solve(input: &str) -> anyhow::Result<Solved> {
+    let school: FishSchool = info_span!("parse").in_scope(|| parse::strict(input))?;
+    let part1 = info_span!("part1").in_scope(|| school.population_at(80))?;
+    let part2 = info_span!("part2").in_scope(|| school.population_at(256))?;
+    Ok(Solved::new(part1, part2))
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// Tests
+
+#[cfg(test)]
+mod tests {
+    use test_log::test;
+
+    #[allow(unused_imports)]
+    use super::*;
+
+    const EXAMPLE: &str = r###"
+        3,4,3,1,2
+    "###;
+
+    #[test]
+    fn test_basic() {
+        let mut school: FishSchool = EXAMPLE.trim().parse().unwrap();
+        for _ in 0..18 {
+            school.try_step().unwrap();
+            println!("{:?}", school.fish);
+        }
+        assert_eq!(school.try_total().unwrap(), count(26));
+        for _ in 18..80 {
+            school.try_step().unwrap();
+        }
+        assert_eq!(school.try_total().unwrap(), count(5934));
+
+        for _ in 80..256 {
+            school.try_step().unwrap();
+        }
+        assert_eq!(school.try_total().unwrap(), count(26984457539));
+    }
+
+    #[test]
+    fn test_timers() {
+        let school = FishSchool::parse_with("3,4,3,1,2", REFRESH, INITIAL).unwrap();
+        assert_eq!(school, EXAMPLE.trim().parse().unwrap());
+
+        // With no wait for newborns, the school doubles every `refresh` days
+        let mut school = FishSchool::parse_with("0, 1,2", 3, 0).unwrap();
+        for _ in 0..9 {
+            school.try_step().unwrap();
+        }
+        assert_eq!(school.try_total().unwrap(), count(3 * 8));
+
+        // Fish that give birth every day double every day
+        let mut school = FishSchool::parse_with("0", 1, 0).unwrap();
+        for _ in 0..10 {
+            school.try_step().unwrap();
+        }
+        assert_eq!(school.try_total().unwrap(), count(1024));
+
+        // Newborns wait 1 + 2 days, so the school grows by 1, 1, 1, 2, 3, 4, 6, ...
+        let mut school = FishSchool::parse_with("0", 1, 2).unwrap();
+        let totals: Vec<Count> = (0..7)
+            .map(|_| {
+                school.try_step().unwrap();
+                school.try_total().unwrap()
+            })
+            .collect();
+        assert_eq!(totals, [2, 3, 4, 6, 9, 13, 19].map(count));
+
+        let err = FishSchool::parse_with("3,9", REFRESH, INITIAL).unwrap_err();
+        assert_eq!(
+            format!("{err:#}"),
+            "fish 2: Timer 9 is longer than a newborn's (8)"
+        );
+        assert!(FishSchool::parse_with("3,x", REFRESH, INITIAL).is_err());
+        assert!(FishSchool::parse_with("11", 10, 2).is_ok());
+    }
+
+    /// `fish` copies of `1,`, ending in a newline instead of the last comma,
+    /// made up as they are read
+    struct ManyFish {
+        fish: u64,
+        pending: &'static [u8],
+    }
+
+    impl std::io::Read for ManyFish {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            let mut written = 0;
+            while written < buf.len() {
+                if self.pending.is_empty() {
+                    if self.fish == 0 {
+                        break;
+                    }
+                    self.fish -= 1;
+                    self.pending = if self.fish == 0 { b"1\n" } else { b"1," };
+                }
+                buf[written] = self.pending[0];
+                self.pending = &self.pending[1..];
+                written += 1;
+            }
+            Ok(written)
+        }
+    }
+
+    #[test]
+    fn test_read() {
+        let read = |s: &str| FishSchool::read(s.as_bytes(), REFRESH, INITIAL);
+        let school: FishSchool = EXAMPLE.trim().parse().unwrap();
+        assert_eq!(read("3,4,3,1,2\n").unwrap(), school);
+        assert_eq!(read(" 3, 4,3 ,1,2").unwrap(), school);
+
+        let errors = [
+            ("", "No fish"),
+            ("\n", "fish 1: \"\\n\""),
+            ("3,4,", "fish 3: \"\""),
+            ("3,,4", "fish 2: \"\""),
+            ("3,x4", "fish 2: \"x4\""),
+        ];
+        for (s, expected) in errors {
+            let err = read(s).unwrap_err();
+            assert!(err.to_string().starts_with(expected), "{s:?}: {err:#}");
+        }
+
+        let many = ManyFish {
+            fish: 1_000_000,
+            pending: b"",
+        };
+        let buf = std::io::BufReader::new(many);
+        let school = FishSchool::read(buf, REFRESH, INITIAL).unwrap();
+        assert_eq!(school.timers()[1], count(1_000_000));
+        assert_eq!(school.try_total().unwrap(), count(1_000_000));
+    }
+
+    #[test]
+    fn test_step_n() {
+        for (refresh, initial) in [(REFRESH, INITIAL), (3, 0), (1, 0), (1, 2), (5, 4)] {
+            let mut school = FishSchool::new(refresh, initial);
+            for timer in [3, 0, 1, 0, 0] {
+                school.insert(timer % (refresh + initial)).unwrap();
+            }
+            let mut stepped = school.clone();
+            for n in 0..60 {
+                let mut jumped = school.clone();
+                jumped.try_step_n(n).unwrap();
+                assert_eq!(jumped, stepped, "{refresh}, {initial}: {n} days");
+                stepped.try_step().unwrap();
+            }
+        }
+
+        let school: FishSchool = EXAMPLE.trim().parse().unwrap();
+        assert_eq!(school.total_after(80).unwrap(), count(5934));
+        assert_eq!(school.total_after(256).unwrap(), count(26984457539));
+    }
+
+    #[test]
+    fn test_population_at() {
+        let mut school: FishSchool = EXAMPLE.trim().parse().unwrap();
+        assert_eq!(school.population_at(256).unwrap(), count(26984457539));
+        assert_eq!(school.population_at(18).unwrap(), count(26));
+        assert_eq!(school.population_at(80).unwrap(), count(5934));
+        assert_eq!(school.population_at(0).unwrap(), count(5));
+        assert_eq!(school.day(), 0);
+
+        school.try_step_n(10).unwrap();
+        school.try_step().unwrap();
+        assert_eq!(school.day(), 11);
+        assert_eq!(school.population_at(80).unwrap(), count(5934));
+        assert_eq!(
+            school.population_at(11).unwrap(),
+            school.try_total().unwrap()
+        );
+        let err = school.population_at(10).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "The school is on day 11, already past day 10"
+        );
+    }
+
+    #[test]
+    fn test_merge() {
+        let school: FishSchool = EXAMPLE.trim().parse().unwrap();
+        let parts: Vec<FishSchool> = ["3,4", "3", "1,2"]
+            .into_iter()
+            .map(|s| s.parse().unwrap())
+            .collect();
+        assert_eq!(parts.iter().cloned().collect::<FishSchool>(), school);
+        assert_eq!(
+            parts[0].clone() + parts[1].clone() + parts[2].clone(),
+            school
+        );
+
+        // Schools on different days are lined up first
+        let mut ahead = parts[0].clone();
+        ahead.try_step_n(20).unwrap();
+        let mut merged = parts[1].clone();
+        merged.extend([ahead, parts[2].clone()]);
+        assert_eq!(merged.day(), 20);
+        assert_eq!(merged.population_at(80).unwrap(), count(5934));
+
+        let empty: FishSchool = std::iter::empty::<FishSchool>().collect();
+        assert_eq!(empty.try_total().unwrap(), count(0));
+        assert_eq!(empty + school.clone(), school);
+
+        let mut other = FishSchool::parse_with("3", 6, 2).unwrap();
+        let err = other.merge(&school).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Can't merge schools with cycles (6, 2) and (7, 2)"
+        );
+    }
+
+    #[test]
+    fn test_ancestry() {
+        let cycle = (REFRESH, INITIAL);
+        let ancestry = Ancestry::new(26984457539, 256, cycle, 1..=5).unwrap();
+        let fewest = ancestry.fewest().unwrap();
+        let most = ancestry.most().unwrap();
+        assert!(fewest.try_total().unwrap() <= count(5), "{fewest:?}");
+        assert!(most.try_total().unwrap() >= count(5), "{most:?}");
+        for school in [&fewest, &most] {
+            assert_eq!(school.population_at(256).unwrap(), count(26984457539));
+            assert_eq!(school.timers()[0], count(0));
+            assert!(school.timers()[6..].iter().all(|n| *n == count(0)));
+        }
+        let range = ancestry.fish_range();
+        assert!(
+            range.contains(&5) && range.end() - range.start() < 5,
+            "{range:?}"
+        );
+        let found = ancestry.find(5).unwrap();
+        assert_eq!(found.population_at(256).unwrap(), count(26984457539));
+
+        // Every starting school of a few fish is found again from its total
+        for timers in [[1, 1, 1], [2, 5, 5], [0, 4, 8], [3, 3, 6]] {
+            let school = FishSchool::from_iter(timers);
+            for days in [0, 1, 10, 40] {
+                let total = residue(&school.total_after(days).unwrap(), u64::MAX);
+                let ancestry = Ancestry::new(total, days, cycle, 0..=8).unwrap();
+                let found = ancestry.find(3).unwrap();
+                assert_eq!(found.population_at(days).unwrap(), count(total));
+                assert_eq!(found.try_total().unwrap(), count(3));
+                let fewest = ancestry.fewest().unwrap().try_total().unwrap();
+                let most = ancestry.most().unwrap().try_total().unwrap();
+                assert!(fewest <= count(3) && most >= count(3), "{timers:?}, {days}");
+            }
+        }
+
+        let grown: Vec<u64> = (1..=5)
+            .map(|t| {
+                residue(
+                    &FishSchool::from_iter([t]).total_after(10).unwrap(),
+                    u64::MAX,
+                )
+            })
+            .collect();
+        assert_eq!(grown, [3, 3, 2, 2, 2]);
+        let ancestry = Ancestry::new(8, 10, cycle, 1..=5).unwrap();
+        assert_eq!(ancestry.fewest().unwrap().try_total().unwrap(), count(3));
+        assert_eq!(ancestry.most().unwrap().try_total().unwrap(), count(4));
+        // Fish with these timers only ever make even totals in 10 days
+        let ancestry = Ancestry::new(7, 10, cycle, 3..=5).unwrap();
+        assert_eq!(ancestry.fewest(), None);
+        assert!(ancestry.fish_range().is_empty());
+
+        // Far too many descendants for any fish
+        let ancestry = Ancestry::new(1000, 5000, cycle, 1..=5).unwrap();
+        assert_eq!(ancestry.fish_range(), 0..=0);
+        assert_eq!(ancestry.most(), None);
+        assert!(Ancestry::new(1000, 10, cycle, 1..=9).is_err());
+
+        // 256 timers, one more than a u8 holds
+        let ancestry = Ancestry::new(3, 10, (200, 56), 250..=255).unwrap();
+        let found = ancestry.fewest().unwrap();
+        assert_eq!(found.try_total().unwrap(), count(3));
+        assert_eq!(found.population_at(10).unwrap(), count(3));
+    }
+
+    #[test]
+    fn test_total_after_mod() {
+        let school: FishSchool = EXAMPLE.trim().parse().unwrap();
+        let modulus = 1_000_000_007;
+        for days in [0, 1, 18, 80, 256] {
+            let total = residue(&school.total_after(days).unwrap(), modulus);
+            assert_eq!(school.total_after_mod(days, modulus), total, "{days} days");
+        }
+        assert_eq!(school.total_after_mod(256, 1000), 26984457539 % 1000);
+        assert_eq!(school.total_after_mod(256, 1), 0);
+
+        // Far too many fish to count exactly, but quick modulo a prime
+        let total = school.total_after_mod(1_000_000_000_000, modulus);
+        assert!(total < modulus);
+        // Splitting the days between the starting school and the count gives
+        // the same answer
+        let mut later = school.clone();
+        later.try_step_n(100).unwrap();
+        assert_eq!(later.total_after_mod(999_999_999_900, modulus), total);
+    }
+
+    #[test]
+    fn test_series() {
+        let school: FishSchool = EXAMPLE.trim().parse().unwrap();
+        let series = school.try_series(18).unwrap();
+        assert_eq!(series.days.len(), 19);
+        assert_eq!(series.days[0], [0, 1, 1, 2, 1, 0, 0, 0, 0].map(count));
+        let mut later = school.clone();
+        later.try_step_n(18).unwrap();
+        assert_eq!(series.days[18], later.timers());
+        assert_eq!(series.days[18].iter().sum::<Count>(), count(26));
+
+        let mut csv = Vec::new();
+        school.try_series(2).unwrap().write_csv(&mut csv).unwrap();
+        assert_eq!(
+            String::from_utf8(csv).unwrap(),
+            "day,timer0,timer1,timer2,timer3,timer4,timer5,timer6,timer7,timer8,total\n\
+             0,0,1,1,2,1,0,0,0,0,5\n\
+             1,1,1,2,1,0,0,0,0,0,5\n\
+             2,1,2,1,0,0,0,1,0,1,6\n"
+        );
+
+        let mut json = Vec::new();
+        school.try_series(1).unwrap().write_json(&mut json).unwrap();
+        let parsed: serde_json::Value = serde_json::from_slice(&json).unwrap();
+        assert_eq!(
+            parsed,
+            serde_json::json!([
+                {"day": 0, "timers": [0, 1, 1, 2, 1, 0, 0, 0, 0], "total": 5},
+                {"day": 1, "timers": [1, 1, 2, 1, 0, 0, 0, 0, 0], "total": 5},
+            ])
+        );
+
+        let dir = std::env::temp_dir();
+        let path = dir.join(format!("aoc2021-day06-series-{}.json", std::process::id()));
+        school.try_series(1).unwrap().save(&path).unwrap();
+        assert_eq!(std::fs::read(&path).unwrap(), json);
+        std::fs::remove_file(&path).unwrap();
+        assert!(school
+            .try_series(1)
+            .unwrap()
+            .save(dir.join("series.txt"))
+            .is_err());
+    }
+
+    #[cfg(not(feature = "bigint"))]
+    #[test]
+    fn test_overflow() {
+        let school: FishSchool = EXAMPLE.trim().parse().unwrap();
+        let mut stepped = school.clone();
+        let mut last = 0;
+        while stepped.try_step().is_ok() && stepped.try_total().is_ok() {
+            last = stepped.day();
+        }
+        assert!(last > 256, "{last}");
+        assert!(school.population_at(last).is_ok());
+
+        // Counting past the overflow fails cleanly instead of wrapping or
+        // panicking
+        let before = stepped.clone();
+        if stepped.try_step().is_err() {
+            assert_eq!(stepped, before);
+        }
+        let mut jumped = school.clone();
+        assert_eq!(jumped.try_step_n(100_000), Err(Overflow));
+        assert_eq!(jumped, school);
+        let err = school.population_at(100_000).unwrap_err();
+        assert!(err.is::<Overflow>(), "{err}");
+        assert_eq!(school.total_after(100_000), Err(Overflow));
+        assert_eq!(school.try_series(100_000), Err(Overflow));
+        assert!(school.try_series(last).is_ok());
+    }
+
+    // Too many fish for a u64
+    #[cfg(any(feature = "wide", feature = "bigint"))]
+    #[test]
+    fn test_wide() {
+        let mut school: FishSchool = EXAMPLE.trim().parse().unwrap();
+        for _ in 0..512 {
+            school.try_step().unwrap();
+        }
+        assert!(school.try_total().unwrap() > count(u64::MAX));
+    }
+
+    #[cfg(feature = "bigint")]
+    #[test]
+    fn test_thousands_of_days() {
+        // A fish with timer 0 on day d becomes fish with timers 0 on days
+        // d + 7 and d + 9; before its first birth it is just one fish
+        let days = 5000;
+        let mut totals: Vec<Count> = Vec::with_capacity(days + 1);
+        for d in 0..=days {
+            let before = |n: usize| d.checked_sub(n).map_or(count(1), |d| totals[d].clone());
+            let total = if d == 0 {
+                count(1)
+            } else {
+                before(7) + before(9)
+            };
+            totals.push(total);
+        }
+
+        let school = FishSchool::from_iter([0]);
+        assert_eq!(school.total_after(days as u64).unwrap(), totals[days]);
+        assert_eq!(school.total_after(256).unwrap(), totals[256]);
+        // Around 190 digits
+        assert!(totals[days].bits() > 600, "{}", totals[days].bits());
+    }
+}
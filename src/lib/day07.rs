@@ -0,0 +1,1126 @@
+use std::fmt;
+use std::io::Write;
+use std::ops::RangeInclusive;
+use std::str::FromStr;
+
+use anyhow::{ensure, Context};
+use parse_display::Display;
+use thiserror::Error;
+use tracing::info_span;
+
+use crate::solution::Solved;
+use crate::table::Table;
+use crate::{parallel, parse};
+
+/// Crab positions, with how many crabs are at each
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Crabs {
+    /// Each position with any crabs, in order, and the number there
+    groups: Vec<(i64, u64)>,
+}
+
+/// Parses positions separated by commas or newlines, each either a single
+/// crab's position or `position:count` for several crabs, like `16,1:3,2`.
+/// Whitespace around each entry is ignored, as is a trailing comma, but an
+/// empty entry anywhere else is an error.
+impl FromStr for Crabs {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let tokens: Vec<&str> = s.split([',', '\n']).map(str::trim).collect();
+        // Skip the empty tokens after a trailing comma or newline
+        let end = tokens
+            .iter()
+            .rposition(|t| !t.is_empty())
+            .map_or(0, |ix| ix + 1);
+        ensure!(end > 0, "No crabs");
+
+        let mut groups = Vec::with_capacity(end);
+        for (ix, &token) in tokens[..end].iter().enumerate() {
+            let group =
+                parse_group(token).with_context(|| format!("crab {}: {token:?}", ix + 1))?;
+            groups.push(group);
+        }
+        Ok(Crabs::from_counts(groups))
+    }
+}
+
+/// Parses `position` or `position:count`
+fn parse_group(token: &str) -> anyhow::Result<(i64, u64)> {
+    ensure!(!token.is_empty(), "Empty entry");
+    let (position, count) = match token.split_once(':') {
+        Some((position, count)) => (position.trim(), Some(count.trim())),
+        None => (token, None),
+    };
+    let position = position
+        .parse()
+        .with_context(|| format!("Bad position {position:?}"))?;
+    let count = match count {
+        Some(count) => count
+            .parse()
+            .with_context(|| format!("Bad count {count:?}"))?,
+        None => 1,
+    };
+    Ok((position, count))
+}
+
+/// The fuel a crab burns to move some distance
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Display, clap::ValueEnum)]
+#[display(style = "kebab-case")]
+pub enum CostModel {
+    /// 1 for any move at all
+    Constant,
+    /// 1 per step, as in part 1
+    Linear,
+    /// 1 for the first step, 2 for the second, and so on: `d(d+1)/2` for
+    /// distance `d`, as in part 2
+    Quadratic,
+}
+
+impl CostModel {
+    /// The fuel to move `distance`. A `u128`, as the quadratic cost of a long
+    /// move doesn't fit in a `u64`.
+    pub fn cost(self, distance: u64) -> u128 {
+        let d = distance as u128;
+        match self {
+            CostModel::Constant => (distance > 0) as u128,
+            CostModel::Linear => d,
+            // d(d + 1) is at most 2^128 - 2^64, so this can't overflow
+            CostModel::Quadratic => d * (d + 1) / 2,
+        }
+    }
+}
+
+/// The fuel at a position doesn't fit in a `u64`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Error)]
+#[error("Fuel overflowed a u64")]
+pub struct FuelOverflow;
+
+/// A fuel total as a `u64`, or an error if it doesn't fit.
+///
+/// Totals are added up in `u128`, saturating, so that positions whose fuel
+/// doesn't fit in a `u64` still compare as costing more than any that does,
+/// and the search for the best position can go past them.
+fn narrow(total: u128) -> Result<u64, FuelOverflow> {
+    u64::try_from(total).map_err(|_| FuelOverflow)
+}
+
+/// The fuel all the crabs in `groups` burn moving to `x`, where `cost` gives
+/// the fuel to move a distance, saturating at `u128::MAX`
+fn total_fuel(groups: &[(i64, u64)], cost: impl Fn(u64) -> u128, x: i64) -> u128 {
+    groups.iter().fold(0, |total: u128, &(p, n)| {
+        total.saturating_add((n as u128).saturating_mul(cost(p.abs_diff(x))))
+    })
+}
+
+/// How to find the best position
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Display, clap::ValueEnum)]
+#[display(style = "kebab-case")]
+pub enum Method {
+    /// A closed form for each model: the most common position, the median,
+    /// or near the average
+    #[default]
+    Fast,
+    /// Search for where the total fuel stops falling, taking `O(log range)`
+    /// fuel evaluations
+    Search,
+    /// Try every position from the leftmost crab to the rightmost
+    Scan,
+}
+
+impl Crabs {
+    /// Crabs from pairs of a position and the number of crabs there, which
+    /// may repeat positions
+    pub fn from_counts(counts: impl IntoIterator<Item = (i64, u64)>) -> Self {
+        let mut counts: Vec<(i64, u64)> = counts.into_iter().filter(|&(_, n)| n > 0).collect();
+        counts.sort_unstable();
+        let mut groups: Vec<(i64, u64)> = Vec::with_capacity(counts.len());
+        for (position, n) in counts {
+            match groups.last_mut() {
+                Some((last, total)) if *last == position => *total += n,
+                _ => groups.push((position, n)),
+            }
+        }
+        Crabs { groups }
+    }
+
+    /// Each position with any crabs, in order, and the number there
+    pub fn groups(&self) -> &[(i64, u64)] {
+        &self.groups
+    }
+
+    /// The number of crabs
+    pub fn len(&self) -> u128 {
+        self.groups.iter().map(|&(_, n)| n as u128).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.groups.is_empty()
+    }
+
+    /// The fuel all the crabs burn moving to `x` under `model`, or an error
+    /// if it doesn't fit in a `u64`
+    pub fn fuel(&self, model: CostModel, x: i64) -> Result<u64, FuelOverflow> {
+        narrow(total_fuel(&self.groups, |d| model.cost(d), x))
+    }
+
+    /// The fuel all the crabs burn moving to `x`, where `cost` gives the fuel
+    /// to move a distance, or an error if it doesn't fit in a `u64`
+    pub fn fuel_by(&self, cost: impl Fn(u64) -> u64, x: i64) -> Result<u64, FuelOverflow> {
+        narrow(total_fuel(&self.groups, |d| cost(d) as u128, x))
+    }
+
+    /// The position costing the least fuel under `model`, and that fuel; the
+    /// leftmost if several tie. Errors if that fuel doesn't fit in a `u64`,
+    /// and panics if there are no crabs.
+    pub fn optimal(&self, model: CostModel) -> Result<(i64, u64), FuelOverflow> {
+        self.optimal_with(model, Method::Fast)
+    }
+
+    /// Like [`Crabs::optimal`], but finding the position with `method`; all
+    /// three give the same answer.
+    pub fn optimal_with(
+        &self,
+        model: CostModel,
+        method: Method,
+    ) -> Result<(i64, u64), FuelOverflow> {
+        let (x, total) = self.best(model, method);
+        Ok((x, narrow(total)?))
+    }
+
+    /// The best position under `model` found with `method`, and its total fuel
+    fn best(&self, model: CostModel, method: Method) -> (i64, u128) {
+        match (method, model) {
+            (Method::Fast, CostModel::Constant) => self.most_common(),
+            (Method::Fast, CostModel::Linear) => self.median(),
+            (Method::Fast, CostModel::Quadratic) => self.near_average(),
+            // The total fuel for a constant cost is only lower at positions
+            // with crabs, so it isn't convex; but then trying just those
+            // positions is enough.
+            (Method::Search, CostModel::Constant) => {
+                let table = self.fuel_table();
+                self.groups
+                    .iter()
+                    .map(|&(x, _)| (x, table.total(model, x)))
+                    .min_by_key(|&(_, fuel)| fuel)
+                    .unwrap()
+            }
+            (Method::Search, _) => {
+                let table = self.fuel_table();
+                self.convex_search(|x| table.total(model, x))
+            }
+            (Method::Scan, _) => {
+                let (lo, hi) = self.span().expect("No crabs");
+                let table = self.fuel_table();
+                best_in(lo..=hi, |chunk| {
+                    table.totals(model, chunk).min_by_key(|&(_, fuel)| fuel)
+                })
+                .unwrap()
+            }
+        }
+    }
+
+    /// The position costing the least fuel for any cost function, trying
+    /// every position from the leftmost crab to the rightmost. Errors if
+    /// that fuel doesn't fit in a `u64`, and panics if there are no crabs.
+    pub fn optimal_by(&self, cost: impl Fn(u64) -> u64 + Sync) -> Result<(i64, u64), FuelOverflow> {
+        let (lo, hi) = self.span().expect("No crabs");
+        let (x, total) = best_in(lo..=hi, |chunk| {
+            chunk
+                .map(|x| (x, total_fuel(&self.groups, |d| cost(d) as u128, x)))
+                .min_by_key(|&(_, fuel)| fuel)
+        })
+        .unwrap();
+        Ok((x, narrow(total)?))
+    }
+
+    /// The leftmost and rightmost crab positions, if there are any crabs
+    fn span(&self) -> Option<(i64, i64)> {
+        let (lo, _) = *self.groups.first()?;
+        let (hi, _) = *self.groups.last()?;
+        Some((lo, hi))
+    }
+
+    /// The `k` cheapest positions under `model`, cheapest first, with ties
+    /// broken leftmost first. Errors if any of their fuel doesn't fit in a
+    /// `u64`, and panics if there are no crabs.
+    ///
+    /// For the convex models these are a run of positions around the best
+    /// one. With constant costs, every position without crabs costs the
+    /// same, so only those between the leftmost and rightmost crabs are
+    /// listed, and there may be fewer than `k`.
+    pub fn best_positions(&self, model: CostModel, k: usize) -> Result<Ranking, FuelOverflow> {
+        let table = self.fuel_table();
+        let mut positions: Vec<(i64, u128)> = Vec::with_capacity(k);
+        match model {
+            CostModel::Constant => {
+                let (lo, hi) = self.span().expect("No crabs");
+                let mut occupied: Vec<(i64, u128)> = self
+                    .groups
+                    .iter()
+                    .map(|&(x, _)| (x, table.total(model, x)))
+                    .collect();
+                occupied.sort_by_key(|&(x, fuel)| (fuel, x));
+                positions.extend(occupied.into_iter().take(k));
+
+                let empty = (lo..=hi)
+                    .filter(|x| self.groups.binary_search_by_key(x, |&(p, _)| p).is_err())
+                    .map(|x| (x, self.len()));
+                positions.extend(empty.take(k - positions.len()));
+            }
+            CostModel::Linear | CostModel::Quadratic => {
+                let best = self.best(model, Method::Fast);
+                positions.push(best);
+                // The fuel only rises moving away from the best position, so
+                // take whichever neighbor on either side is cheaper
+                let (mut left, mut right) = (best.0.checked_sub(1), best.0.checked_add(1));
+                while positions.len() < k {
+                    let fuel = |x: Option<i64>| x.map(|x| (x, table.total(model, x)));
+                    let next = match (fuel(left), fuel(right)) {
+                        (None, None) => break,
+                        (Some(l), Some(r)) if l.1 <= r.1 => l,
+                        (Some(l), None) => l,
+                        (_, Some(r)) => r,
+                    };
+                    if Some(next.0) == left {
+                        left = next.0.checked_sub(1);
+                    } else {
+                        right = next.0.checked_add(1);
+                    }
+                    positions.push(next);
+                }
+            }
+        }
+
+        let least = positions.first().map_or(Ok(0), |&(_, fuel)| narrow(fuel))?;
+        let candidates = positions
+            .into_iter()
+            .take(k)
+            .map(|(position, fuel)| {
+                let fuel = narrow(fuel)?;
+                Ok(Candidate {
+                    position,
+                    fuel,
+                    extra: fuel - least,
+                })
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Ranking { candidates })
+    }
+
+    /// Prefix sums for finding the fuel under the built-in models at any
+    /// position, without visiting every crab
+    pub fn fuel_table(&self) -> FuelTable<'_> {
+        FuelTable::new(self)
+    }
+
+    /// The best position under `model` that isn't in any of the `excluded`
+    /// ranges, and its fuel, or `None` if every position is excluded. Errors
+    /// if that fuel doesn't fit in a `u64`, and panics if there are no crabs.
+    ///
+    /// For the convex models, the fuel only rises moving away from the best
+    /// position, so if that is excluded the answer is just past one end of
+    /// the excluded block around it. With constant costs, it is the most
+    /// common allowed crab position, if there is one; otherwise every allowed
+    /// position costs the same, and this picks one next to the first crab.
+    pub fn optimal_excluding(
+        &self,
+        model: CostModel,
+        excluded: &[RangeInclusive<i64>],
+    ) -> Result<Option<(i64, u64)>, FuelOverflow> {
+        let blocks = merge_ranges(excluded);
+        let block = |x: i64| {
+            let ix = blocks.partition_point(|&(start, _)| start <= x);
+            blocks[..ix].last().filter(|&&(_, end)| end >= x).copied()
+        };
+        // x itself if it's allowed, or the closest allowed positions on each
+        // side
+        let nearest = |x: i64| match block(x) {
+            None => vec![x],
+            Some((start, end)) => [start.checked_sub(1), end.checked_add(1)]
+                .into_iter()
+                .flatten()
+                .collect(),
+        };
+
+        let candidates = match model {
+            CostModel::Constant => {
+                let (first, _) = *self.groups.first().expect("No crabs");
+                let mut allowed: Vec<i64> = self
+                    .groups
+                    .iter()
+                    .map(|&(x, _)| x)
+                    .filter(|&x| block(x).is_none())
+                    .collect();
+                allowed.extend(nearest(first));
+                allowed
+            }
+            _ => nearest(self.best(model, Method::Fast).0),
+        };
+        candidates
+            .into_iter()
+            .map(|x| (x, total_fuel(&self.groups, |d| model.cost(d), x)))
+            .min_by_key(|&(x, fuel)| (fuel, x))
+            .map(|(x, fuel)| Ok((x, narrow(fuel)?)))
+            .transpose()
+    }
+
+    /// The fuel under `model` at each position from the leftmost crab to the
+    /// rightmost, in order; empty if there are no crabs. Errors if any of it
+    /// doesn't fit in a `u64`.
+    pub fn cost_curve(&self, model: CostModel) -> Result<Vec<(i64, u64)>, FuelOverflow> {
+        let Some((lo, hi)) = self.span() else {
+            return Ok(Vec::new());
+        };
+        self.fuel_table()
+            .fuels(model, lo..=hi)
+            .map(|(x, fuel)| Ok((x, fuel?)))
+            .collect()
+    }
+
+    /// The position costing the least fuel for a convex cost function, i.e.
+    /// one where each step costs at least as much as the one before, like
+    /// `d²`; the leftmost if several tie. Errors if that fuel doesn't fit in
+    /// a `u64`, and panics if there are no crabs.
+    ///
+    /// The total fuel is then a sum of convex functions and so convex too:
+    /// the change in fuel from `x` to `x + 1` never decreases with `x`. This
+    /// is a binary search for the first `x` where it stops being negative,
+    /// the discrete version of a ternary search.
+    pub fn convex_optimal_by(&self, cost: impl Fn(u64) -> u64) -> Result<(i64, u64), FuelOverflow> {
+        let (x, total) = self.convex_search(|x| total_fuel(&self.groups, |d| cost(d) as u128, x));
+        Ok((x, narrow(total)?))
+    }
+
+    /// The leftmost position from the leftmost crab to the rightmost with
+    /// the least `fuel`, for a convex `fuel`
+    fn convex_search(&self, fuel: impl Fn(i64) -> u128) -> (i64, u128) {
+        let (mut lo, mut hi) = self.span().expect("No crabs");
+        while lo < hi {
+            // Rounded down, so that mid < hi even for negative positions
+            let mid = lo + (hi.abs_diff(lo) / 2) as i64;
+            if fuel(mid + 1) >= fuel(mid) {
+                hi = mid;
+            } else {
+                lo = mid + 1;
+            }
+        }
+        (lo, fuel(lo))
+    }
+
+    /// With a constant cost, the best position is wherever the most crabs
+    /// already are
+    fn most_common(&self) -> (i64, u128) {
+        let (x, _) = self.groups.iter().fold(
+            (0, 0),
+            |best, &(x, n)| if n > best.1 { (x, n) } else { best },
+        );
+        (
+            x,
+            total_fuel(&self.groups, |d| CostModel::Constant.cost(d), x),
+        )
+    }
+
+    /// With a linear cost, moving one step right from `x` saves 1 for each
+    /// crab to the right and costs 1 for each to the left, so the best
+    /// position is the median
+    fn median(&self) -> (i64, u128) {
+        // The lower median, for an even number of crabs: the position of the
+        // crab with `(len - 1) / 2` crabs before it
+        let before = (self.len() - 1) / 2;
+        let mut seen = 0;
+        let &(mid, _) = self
+            .groups
+            .iter()
+            .find(|&&(_, n)| {
+                seen += n as u128;
+                seen > before
+            })
+            .unwrap();
+        (
+            mid,
+            total_fuel(&self.groups, |d| CostModel::Linear.cost(d), mid),
+        )
+    }
+
+    // fuel cost for distance d is d(d+1)/2
+    // for a crab at position p with goal at x, the fuel cost is
+    //     (|p-x|)(|p-x|+1)/2
+    //     (|p-x|)^2 + |p-x|)/2
+    //     ((p^2 - 2px + x^2) + |p-x|/2
+    // So total is
+    //     Sum_p (p^2 - 2px + x^2 + |p - x|)/2
+    // Pulling out a constant C gives
+    //     C + Sum_p (x^2 - 2px + |p-x|)/2
+    // Setting derivative to 0
+    //     Sum_p (x - p + 1/2 sgn(p-x)) = 0
+    //     N x = Sum_p p
+    //     x = <p> ± ½
+    // So the optimal position is the average of the positions, give or take
+    // one
+    fn near_average(&self) -> (i64, u128) {
+        let sum = self.groups.iter().try_fold(0i128, |sum, &(p, n)| {
+            sum.checked_add((p as i128).checked_mul(n as i128)?)
+        });
+        let Some(sum) = sum else {
+            // Too many crabs too far out to average, but the fuel is still
+            // convex
+            let table = self.fuel_table();
+            return self.convex_search(|x| table.total(CostModel::Quadratic, x));
+        };
+
+        // avg rounded down, even for negative positions
+        let avg = sum.div_euclid(self.len() as i128) as i64;
+
+        [avg.saturating_sub(1), avg, avg.saturating_add(1)]
+            .into_iter()
+            .map(|x| {
+                (
+                    x,
+                    total_fuel(&self.groups, |d| CostModel::Quadratic.cost(d), x),
+                )
+            })
+            .min_by_key(|&(_, fuel)| fuel)
+            .unwrap()
+    }
+}
+
+/// A position the crabs could line up at
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Candidate {
+    pub position: i64,
+    pub fuel: u64,
+    /// How much more fuel this takes than the best position
+    pub extra: u64,
+}
+
+/// Positions ranked by fuel, cheapest first. Displays as a table.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Ranking {
+    pub candidates: Vec<Candidate>,
+}
+
+impl fmt::Display for Ranking {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut table = Table::new(["rank", "position", "fuel", "extra"]);
+        for (ix, c) in self.candidates.iter().enumerate() {
+            table.push([
+                (ix + 1).to_string(),
+                c.position.to_string(),
+                c.fuel.to_string(),
+                format!("+{}", c.extra),
+            ]);
+        }
+        write!(f, "{table}")
+    }
+}
+
+/// Running totals over crab groups, for finding the fuel at a position under
+/// the built-in models in `O(log groups)` time, or `O(1)` each when going
+/// through positions in order.
+///
+/// With `L` crabs left of `x` at positions summing to `Sₗ`, and `R` crabs
+/// right of it summing to `Sᵣ`, the linear fuel is `(Lx - Sₗ) + (Sᵣ - Rx)`.
+/// The quadratic fuel is half the sum of `d² + d`, and `Σ d²` over all the
+/// crabs is `Σp² - 2xΣp + Nx²`, which needs only totals. Where those
+/// overflow an `i128`, the fuel is added up crab group by crab group instead.
+#[derive(Debug, Clone)]
+pub struct FuelTable<'a> {
+    groups: &'a [(i64, u64)],
+    /// The number of crabs, and the sum of their positions, in the groups
+    /// before each index; one longer than `groups`. A sum is `None` once it
+    /// overflows.
+    before: Vec<(i128, Option<i128>)>,
+    /// The sum of the squares of all the positions, unless it overflows
+    squares: Option<i128>,
+}
+
+impl<'a> FuelTable<'a> {
+    pub fn new(crabs: &'a Crabs) -> Self {
+        let mut before = Vec::with_capacity(crabs.groups.len() + 1);
+        let (mut count, mut sum, mut squares) = (0i128, Some(0i128), Some(0i128));
+        before.push((count, sum));
+        for &(p, n) in &crabs.groups {
+            let (p, n) = (p as i128, n as i128);
+            count += n;
+            sum = sum.and_then(|sum| sum.checked_add(n.checked_mul(p)?));
+            squares =
+                squares.and_then(|squares| squares.checked_add(n.checked_mul(p.checked_mul(p)?)?));
+            before.push((count, sum));
+        }
+        FuelTable {
+            groups: &crabs.groups,
+            before,
+            squares,
+        }
+    }
+
+    /// The fuel all the crabs burn moving to `x` under `model`, or an error
+    /// if it doesn't fit in a `u64`
+    pub fn fuel(&self, model: CostModel, x: i64) -> Result<u64, FuelOverflow> {
+        narrow(self.total(model, x))
+    }
+
+    /// The fuel under `model` at each of `positions`, in order, or an error
+    /// for each that doesn't fit in a `u64`
+    pub fn fuels(
+        &self,
+        model: CostModel,
+        positions: RangeInclusive<i64>,
+    ) -> impl Iterator<Item = (i64, Result<u64, FuelOverflow>)> + '_ {
+        self.totals(model, positions)
+            .map(|(x, total)| (x, narrow(total)))
+    }
+
+    /// Like [`FuelTable::fuel`], as a saturating `u128`
+    fn total(&self, model: CostModel, x: i64) -> u128 {
+        let ix = self.groups.partition_point(|&(p, _)| p < x);
+        self.total_at(model, x, ix)
+    }
+
+    /// Like [`FuelTable::fuels`], as saturating `u128`s
+    fn totals(
+        &self,
+        model: CostModel,
+        positions: RangeInclusive<i64>,
+    ) -> impl Iterator<Item = (i64, u128)> + '_ {
+        let start = *positions.start();
+        let mut ix = self.groups.partition_point(|&(p, _)| p < start);
+        positions.map(move |x| {
+            while ix < self.groups.len() && self.groups[ix].0 < x {
+                ix += 1;
+            }
+            (x, self.total_at(model, x, ix))
+        })
+    }
+
+    /// The fuel at `x`, where `ix` is the first group not left of `x`
+    fn total_at(&self, model: CostModel, x: i64, ix: usize) -> u128 {
+        if model == CostModel::Constant {
+            let (count, _) = *self.before.last().unwrap();
+            let here = match self.groups.get(ix) {
+                Some(&(p, n)) if p == x => n as i128,
+                _ => 0,
+            };
+            return (count - here) as u128;
+        }
+        match self.by_totals(model, x, ix) {
+            Some(fuel) => fuel as u128,
+            None => total_fuel(self.groups, |d| model.cost(d), x),
+        }
+    }
+
+    /// The linear or quadratic fuel at `x` from the running totals, or
+    /// `None` if any step overflows
+    fn by_totals(&self, model: CostModel, x: i64, ix: usize) -> Option<i128> {
+        let (count, sum) = *self.before.last().unwrap();
+        let (left, left_sum) = self.before[ix];
+        let (sum, left_sum) = (sum?, left_sum?);
+        let (right, right_sum) = (count - left, sum - left_sum);
+        let x = x as i128;
+        let linear =
+            (left.checked_mul(x)? - left_sum).checked_add(right_sum - right.checked_mul(x)?)?;
+
+        match model {
+            CostModel::Quadratic => {
+                let squares = self
+                    .squares?
+                    .checked_sub(x.checked_mul(sum)?.checked_mul(2)?)?
+                    .checked_add(count.checked_mul(x)?.checked_mul(x)?)?;
+                Some(squares.checked_add(linear)? / 2)
+            }
+            _ => Some(linear),
+        }
+    }
+}
+
+/// The best of `best(chunk)` over chunks covering `positions`; the leftmost
+/// if several tie. Splits the positions evenly across the threads.
+fn best_in(
+    positions: RangeInclusive<i64>,
+    best: impl Fn(RangeInclusive<i64>) -> Option<(i64, u128)> + Sync + Send,
+) -> Option<(i64, u128)> {
+    let (lo, hi) = positions.into_inner();
+    if lo > hi {
+        return None;
+    }
+    let threads = parallel::threads() as i128;
+    let (lo, hi) = (lo as i128, hi as i128);
+    // Rounded up, so the chunks cover everything
+    let chunk_size = (hi - lo + threads) / threads;
+    let chunks: Vec<RangeInclusive<i64>> = (lo..=hi)
+        .step_by(chunk_size as usize)
+        .map(|start| start as i64..=(start + chunk_size - 1).min(hi) as i64)
+        .collect();
+
+    // min_by_key keeps the first of equal minimums, and the chunks are in order
+    parallel::map(&chunks, |chunk| best(chunk.clone()))
+        .into_iter()
+        .flatten()
+        .min_by_key(|&(_, fuel)| fuel)
+}
+
+/// Sorts `ranges` and joins any that overlap or touch, giving `(start, end)`
+/// pairs, both inclusive
+fn merge_ranges(ranges: &[RangeInclusive<i64>]) -> Vec<(i64, i64)> {
+    let mut sorted: Vec<(i64, i64)> = ranges
+        .iter()
+        .filter(|r| !r.is_empty())
+        .map(|r| (*r.start(), *r.end()))
+        .collect();
+    sorted.sort_unstable();
+
+    let mut merged: Vec<(i64, i64)> = Vec::with_capacity(sorted.len());
+    for (start, end) in sorted {
+        match merged.last_mut() {
+            Some((_, last)) if start <= last.saturating_add(1) => *last = (*last).max(end),
+            _ => merged.push((start, end)),
+        }
+    }
+    merged
+}
+
+/// Parses a range of positions, either `a..b` (not including `b`) or
+/// `a..=b`
+pub fn parse_range(s: &str) -> anyhow::Result<RangeInclusive<i64>> {
+    let (start, end) = s
+        .split_once("..")
+        .with_context(|| format!("Expected a range like 3..7 or 3..=6, found {s:?}"))?;
+    // Clap only shows the outermost error, so put it all in one message
+    let number = |n: &str| {
+        n.trim()
+            .parse::<i64>()
+            .map_err(|e| anyhow::anyhow!("Bad position {n:?} in range {s:?}: {e}"))
+    };
+    let start = number(start)?;
+    let range = match end.strip_prefix('=') {
+        Some(end) => start..=number(end)?,
+        None => {
+            let end = number(end)?;
+            match end.checked_sub(1) {
+                Some(last) => start..=last,
+                // Nothing is below i64::MIN
+                None => RangeInclusive::new(1, 0),
+            }
+        }
+    };
+    Ok(range)
+}
+
+/// Writes a cost curve as CSV, with the header `position,fuel`
+pub fn write_curve_csv<W: Write>(curve: &[(i64, u64)], mut out: W) -> anyhow::Result<()> {
+    writeln!(out, "position,fuel")?;
+    for (x, fuel) in curve {
+        writeln!(out, "{x},{fuel}")?;
+    }
+    out.flush()?;
+    Ok(())
+}
+
+/// Solves both parts of the puzzle for the given input
+pub fn solve(input: &str) -> anyhow::Result<Solved> {
+    let crabs = info_span!("parse").in_scope(|| parse::strict::<Crabs>(input))?;
+    let (_, fuel) = info_span!("part1").in_scope(|| crabs.optimal(CostModel::Linear))?;
+    let (_, fuel_ramp) = info_span!("part2").in_scope(|| crabs.optimal(CostModel::Quadratic))?;
+    Ok(Solved::new(fuel, fuel_ramp))
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// Tests
+
+#[cfg(test)]
+mod tests {
+    use test_log::test;
+
+    #[allow(unused_imports)]
+    use super::*;
+
+    const EXAMPLE: &str = r###"
+        16,1,2,0,4,2,7,1,2,14
+    "###;
+
+    #[test]
+    fn test_basic() {
+        let crabs = Crabs::from_str(EXAMPLE.trim()).unwrap();
+        let (mid, fuel) = crabs.optimal(CostModel::Linear).unwrap();
+
+        assert_eq!((mid, fuel), (2, 37));
+        assert_eq!(crabs.fuel(CostModel::Linear, 1).unwrap(), 41);
+        assert_eq!(crabs.fuel(CostModel::Linear, 10).unwrap(), 71);
+    }
+
+    #[test]
+    fn test_linear() {
+        let crabs = Crabs::from_str(EXAMPLE.trim()).unwrap();
+        let (mid, fuel) = crabs.optimal(CostModel::Quadratic).unwrap();
+
+        assert_eq!((mid, fuel), (5, 168));
+        assert_eq!(crabs.fuel(CostModel::Quadratic, 2).unwrap(), 206);
+    }
+
+    #[test]
+    fn test_weighted() {
+        let crabs = Crabs::from_str(EXAMPLE.trim()).unwrap();
+        let weighted = Crabs::from_str("16,1:2,2:3,0\n4, 7,14").unwrap();
+        assert_eq!(weighted, crabs);
+        assert_eq!(crabs.len(), 10);
+        assert_eq!(crabs.groups()[..3], [(0, 1), (1, 2), (2, 3)]);
+
+        let pairs = Crabs::from_counts([(2, 1), (16, 1), (2, 2), (1, 2), (0, 1), (4, 1)]);
+        let pairs =
+            Crabs::from_counts(
+                pairs
+                    .groups()
+                    .iter()
+                    .copied()
+                    .chain([(7, 1), (14, 1), (9, 0)]),
+            );
+        assert_eq!(pairs, crabs);
+
+        // Billions of crabs, without listing each one
+        let fleet = Crabs::from_counts([(0, 3_000_000_000), (10, 1_000_000_000), (1000, 1)]);
+        assert_eq!(
+            fleet.optimal(CostModel::Linear).unwrap(),
+            (0, 10_000_001_000)
+        );
+        assert_eq!(
+            fleet.optimal(CostModel::Constant).unwrap(),
+            (0, 1_000_000_001)
+        );
+        let (x, fuel) = fleet.optimal(CostModel::Quadratic).unwrap();
+        assert_eq!(x, 2);
+        assert_eq!(fuel, fleet.optimal_by(|d| d * (d + 1) / 2).unwrap().1);
+
+        for bad in ["1,x", "1,2:y", "3:4:5", "1,,2"] {
+            assert!(Crabs::from_str(bad).is_err(), "{bad}");
+        }
+        let err = Crabs::from_str("1,2:y").unwrap_err();
+        assert_eq!(err.to_string(), "crab 2: \"2:y\"");
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let crabs = Crabs::from_str(EXAMPLE.trim()).unwrap();
+        let messy = Crabs::from_str(" 16 ,1, 2,0 ,4,2\n7, 1 : 1,2,\t14,\n").unwrap();
+        assert_eq!(messy, crabs);
+
+        let error = |s: &str| format!("{:#}", Crabs::from_str(s).unwrap_err());
+        assert_eq!(error("1,,2"), "crab 2: \"\": Empty entry");
+        assert_eq!(
+            error("1, 2x"),
+            "crab 2: \"2x\": Bad position \"2x\": invalid digit found in string"
+        );
+        assert_eq!(
+            error("1\n3:-4"),
+            "crab 2: \"3:-4\": Bad count \"-4\": invalid digit found in string"
+        );
+        assert_eq!(
+            error("3: 4:5"),
+            "crab 1: \"3: 4:5\": Bad count \"4:5\": invalid digit found in string"
+        );
+        assert_eq!(error(""), "No crabs");
+        assert_eq!(error(" ,\n"), "No crabs");
+    }
+
+    #[test]
+    fn test_wide_positions() {
+        // Around and past the largest u16
+        let crabs = Crabs::from_str("65534,65535,65536,65536,70000").unwrap();
+        assert_eq!(
+            crabs.optimal(CostModel::Linear).unwrap(),
+            (65536, 1 + 2 + 4464)
+        );
+        assert_eq!(crabs.optimal(CostModel::Constant).unwrap(), (65536, 3));
+        let (x, fuel) = crabs.optimal(CostModel::Quadratic).unwrap();
+        assert_eq!((x, fuel), (66428, 7_977_170));
+        assert_eq!(crabs.optimal_by(|d| d * (d + 1) / 2).unwrap(), (x, fuel));
+
+        // Negative positions, with an average that rounds down below zero
+        let crabs = Crabs::from_str("-70000:2,-1,3,1").unwrap();
+        assert_eq!(
+            crabs.optimal(CostModel::Linear).unwrap(),
+            (-1, 69999 * 2 + 4 + 2)
+        );
+        let (x, fuel) = crabs.optimal(CostModel::Quadratic).unwrap();
+        assert_eq!(crabs.optimal_by(|d| d * (d + 1) / 2).unwrap(), (x, fuel));
+
+        let far = Crabs::from_counts([(-(1 << 40), 1), (1 << 40, 3)]);
+        assert_eq!(far.optimal(CostModel::Linear).unwrap(), (1 << 40, 1 << 41));
+    }
+
+    #[test]
+    fn test_fuel_overflow() {
+        // The linear fuel fits, but the quadratic fuel is around 1.6e37
+        let crabs = Crabs::from_str("-4000000000000000000,4000000000000000000").unwrap();
+        assert_eq!(
+            crabs.optimal(CostModel::Linear),
+            Ok((-4_000_000_000_000_000_000, 8_000_000_000_000_000_000))
+        );
+        // Too wide a span to scan
+        for method in [Method::Fast, Method::Search] {
+            let found = crabs.optimal_with(CostModel::Quadratic, method);
+            assert_eq!(found, Err(FuelOverflow), "{method:?}");
+        }
+        assert_eq!(crabs.fuel(CostModel::Quadratic, 0), Err(FuelOverflow));
+        assert_eq!(
+            crabs.best_positions(CostModel::Quadratic, 2),
+            Err(FuelOverflow)
+        );
+        assert!(solve("-4000000000000000000,4000000000000000000").is_err());
+
+        // Sums of positions that overflow an i128, around an optimum that
+        // still fits
+        let crabs = Crabs::from_counts([(i64::MAX - 1, u64::MAX), (i64::MAX, u64::MAX)]);
+        for model in [CostModel::Constant, CostModel::Linear, CostModel::Quadratic] {
+            for method in [Method::Fast, Method::Search, Method::Scan] {
+                let found = crabs.optimal_with(model, method);
+                assert_eq!(found, Ok((i64::MAX - 1, u64::MAX)), "{model} {method:?}");
+            }
+            assert_eq!(
+                crabs.fuel_table().fuel(model, i64::MAX - 2),
+                Err(FuelOverflow)
+            );
+        }
+    }
+
+    #[test]
+    fn test_methods() {
+        let crabs = [
+            EXAMPLE.trim(),
+            "0,3,3,8,9,9,9,40,41,100,2,7",
+            "0,0,1",
+            "5",
+            "-8:3,-2,0:2,1,6:4,30",
+            // Ties: every position from 0 to 10 is optimal for linear costs
+            "0,10",
+        ];
+        for crabs in crabs.map(|s| Crabs::from_str(s).unwrap()) {
+            for model in [CostModel::Constant, CostModel::Linear, CostModel::Quadratic] {
+                let scanned = crabs.optimal_with(model, Method::Scan).unwrap();
+                assert_eq!(
+                    crabs.optimal_with(model, Method::Fast).unwrap(),
+                    scanned,
+                    "{model}"
+                );
+                assert_eq!(
+                    crabs.optimal_with(model, Method::Search).unwrap(),
+                    scanned,
+                    "{model}"
+                );
+            }
+            let cost = |d: u64| d.pow(4) + 3 * d;
+            assert_eq!(
+                crabs.convex_optimal_by(cost).unwrap(),
+                crabs.optimal_by(cost).unwrap()
+            );
+        }
+
+        // Searching is fast even over a huge range
+        let far = Crabs::from_counts([(-(1 << 40), 2), (1 << 40, 3), (7, 1)]);
+        assert_eq!(
+            far.optimal_with(CostModel::Linear, Method::Search).unwrap(),
+            far.optimal(CostModel::Linear).unwrap()
+        );
+        assert_eq!(Method::Search.to_string(), "search");
+    }
+
+    #[test]
+    fn test_cost_curve() {
+        let crabs = Crabs::from_str(EXAMPLE.trim()).unwrap();
+        let curve = crabs.cost_curve(CostModel::Linear).unwrap();
+        assert_eq!(curve.len(), 17);
+        assert_eq!(curve[..3], [(0, 49), (1, 41), (2, 37)]);
+        assert_eq!(curve.iter().min_by_key(|&(_, fuel)| fuel), Some(&(2, 37)));
+
+        let crabs = Crabs::from_str("-1,1:2").unwrap();
+        let curve = crabs.cost_curve(CostModel::Quadratic).unwrap();
+        assert_eq!(curve, [(-1, 6), (0, 3), (1, 3)]);
+
+        let mut out = Vec::new();
+        write_curve_csv(&curve, &mut out).unwrap();
+        let csv = String::from_utf8(out).unwrap();
+        assert_eq!(csv, "position,fuel\n-1,6\n0,3\n1,3\n");
+
+        assert!(Crabs::from_counts([])
+            .cost_curve(CostModel::Linear)
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_excluded() {
+        let crabs = Crabs::from_str(EXAMPLE.trim()).unwrap();
+        let best = |model, excluded: &[RangeInclusive<i64>]| {
+            let found = crabs.optimal_excluding(model, excluded).unwrap();
+            let (x, fuel) = found.unwrap();
+            assert!(!excluded.iter().any(|r| r.contains(&x)));
+            assert_eq!(crabs.fuel(model, x).unwrap(), fuel);
+            // Compare with every allowed position in and around the crabs
+            let expected = (-20..40)
+                .filter(|x| !excluded.iter().any(|r| r.contains(x)))
+                .map(|x| crabs.fuel(model, x).unwrap())
+                .min();
+            assert_eq!(Some(fuel), expected, "{model} excluding {excluded:?}");
+            (x, fuel)
+        };
+
+        assert_eq!(best(CostModel::Linear, &[]), (2, 37));
+        assert_eq!(best(CostModel::Linear, &[2..=2]), (3, 39));
+        assert_eq!(best(CostModel::Linear, &[0..=3, 4..=6]), (7, 53));
+        assert_eq!(best(CostModel::Quadratic, &[3..=7, 1..=4]), (8, 223));
+        assert_eq!(best(CostModel::Constant, &[2..=2]), (1, 8));
+        assert_eq!(best(CostModel::Constant, &[0..=20]), (-1, 10));
+        for excluded in [vec![5..=5], vec![-3..=1, 10..=12], vec![0..=16]] {
+            for model in [CostModel::Constant, CostModel::Linear, CostModel::Quadratic] {
+                best(model, &excluded);
+            }
+        }
+
+        let everything = [i64::MIN..=0, 1..=i64::MAX];
+        assert_eq!(
+            crabs
+                .optimal_excluding(CostModel::Linear, &everything)
+                .unwrap(),
+            None
+        );
+
+        assert_eq!(parse_range("3..7").unwrap(), 3..=6);
+        assert_eq!(parse_range("-5..=-2").unwrap(), -5..=-2);
+        assert!(parse_range("4..4").unwrap().is_empty());
+        assert!(parse_range("4").is_err());
+        assert!(parse_range("a..3").is_err());
+        assert_eq!(
+            merge_ranges(&[5..=8, 1..=2, 3..=3, 7..=9, parse_range("4..4").unwrap()]),
+            [(1, 3), (5, 9)]
+        );
+    }
+
+    #[test]
+    fn test_fuel_table() {
+        let fleets = [
+            EXAMPLE.trim(),
+            "-8:3,-2,0:2,1,6:4,30",
+            "65534,65535,65536:7,70000",
+            "5",
+        ];
+        for crabs in fleets.map(|s| Crabs::from_str(s).unwrap()) {
+            let table = crabs.fuel_table();
+            let (lo, hi) = crabs.span().unwrap();
+            for model in [CostModel::Constant, CostModel::Linear, CostModel::Quadratic] {
+                let positions = lo - 5..=hi + 5;
+                let swept: Vec<_> = table.fuels(model, positions.clone()).collect();
+                let direct: Vec<_> = positions.map(|x| (x, crabs.fuel(model, x))).collect();
+                assert_eq!(swept, direct, "{model}");
+                for &(x, fuel) in &direct {
+                    assert_eq!(table.fuel(model, x), fuel);
+                }
+            }
+        }
+
+        // A million positions, with tens of millions of crabs
+        let crabs =
+            Crabs::from_counts((0..100_000).map(|i| (i * 10 - 300_000, 100 + i as u64 % 400)));
+        for model in [CostModel::Constant, CostModel::Linear, CostModel::Quadratic] {
+            let fast = crabs.optimal(model).unwrap();
+            assert_eq!(
+                crabs.optimal_with(model, Method::Scan).unwrap(),
+                fast,
+                "{model}"
+            );
+            assert_eq!(
+                crabs.optimal_with(model, Method::Search).unwrap(),
+                fast,
+                "{model}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_best_positions() {
+        let crabs = Crabs::from_str(EXAMPLE.trim()).unwrap();
+        for model in [CostModel::Constant, CostModel::Linear, CostModel::Quadratic] {
+            let ranking = crabs.best_positions(model, 6).unwrap();
+            assert_eq!(ranking.candidates.len(), 6, "{model}");
+
+            // The same as sorting every position between the crabs
+            let mut expected: Vec<(u64, i64)> = (0..=16)
+                .map(|x| (crabs.fuel(model, x).unwrap(), x))
+                .collect();
+            expected.sort();
+            let found: Vec<(u64, i64)> = ranking
+                .candidates
+                .iter()
+                .map(|c| (c.fuel, c.position))
+                .collect();
+            assert_eq!(found, expected[..6], "{model}");
+        }
+
+        let ranking = crabs.best_positions(CostModel::Linear, 3).unwrap();
+        assert_eq!(
+            ranking.candidates,
+            [
+                Candidate {
+                    position: 2,
+                    fuel: 37,
+                    extra: 0
+                },
+                Candidate {
+                    position: 3,
+                    fuel: 39,
+                    extra: 2
+                },
+                Candidate {
+                    position: 1,
+                    fuel: 41,
+                    extra: 4
+                },
+            ]
+        );
+        assert_eq!(
+            ranking.to_string(),
+            "rank  position  fuel  extra\n\
+             ----  --------  ----  -----\n\
+             1     2         37    +0\n\
+             2     3         39    +2\n\
+             3     1         41    +4\n"
+        );
+
+        // Only 3 positions between the crabs
+        let few = Crabs::from_str("0,2:2").unwrap();
+        assert_eq!(
+            few.best_positions(CostModel::Constant, 5)
+                .unwrap()
+                .candidates
+                .len(),
+            3
+        );
+        assert!(few
+            .best_positions(CostModel::Linear, 0)
+            .unwrap()
+            .candidates
+            .is_empty());
+    }
+
+    #[test]
+    fn test_cost_models() {
+        let crabs = Crabs::from_str(EXAMPLE.trim()).unwrap();
+        assert_eq!(crabs.optimal(CostModel::Constant).unwrap(), (2, 7));
+
+        // The fast paths agree with trying every position
+        let many = Crabs::from_str("0,3,3,8,9,9,9,40,41,100,2,7").unwrap();
+        for crabs in [&crabs, &many, &Crabs::from_str("0,0,1").unwrap()] {
+            for model in [CostModel::Constant, CostModel::Linear, CostModel::Quadratic] {
+                let (x, fuel) = crabs.optimal(model).unwrap();
+                assert_eq!(crabs.fuel(model, x).unwrap(), fuel);
+                let (_, best) = crabs.optimal_by(|d| model.cost(d) as u64).unwrap();
+                assert_eq!(fuel, best, "{model}");
+            }
+        }
+
+        // Cubic costs have no fast path
+        let (x, fuel) = crabs.optimal_by(|d| d * d * d).unwrap();
+        assert_eq!((x, fuel), (6, 2179));
+        assert_eq!(crabs.convex_optimal_by(|d| d * d * d).unwrap(), (x, fuel));
+        assert_eq!(CostModel::Quadratic.to_string(), "quadratic");
+    }
+}
@@ -0,0 +1,1439 @@
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::fmt;
+use std::io::BufRead;
+use std::iter;
+use std::str::FromStr;
+
+use anyhow::{ensure, Context};
+use itertools::Itertools;
+use parse_display::Display;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use tracing::{debug, info, info_span};
+
+use crate::constraints::{self, Domains, Narrowed};
+use crate::solution::Solved;
+use crate::{nom, parallel, stats};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Connections {
+    patterns: Vec<String>,
+    outputs: Vec<String>,
+}
+
+impl Connections {
+    /// The number of outputs that can only be one digit, just from how many
+    /// segments they light: 1, 4, 7 and 8
+    pub fn simples(&self) -> usize {
+        self.simples_with(&Encoding::decimal())
+    }
+
+    /// Like [`Connections::simples`], for another display
+    pub fn simples_with(&self, encoding: &Encoding) -> usize {
+        let unique = encoding.unique_lengths();
+        self.outputs
+            .iter()
+            .filter(|s| unique.contains(&s.chars().count()))
+            .count()
+    }
+
+    /// Parses an entry for a display showing digits with `encoding`: a
+    /// different pattern for each of some or all of the digits, then `|` and
+    /// 4 outputs, each using the encoding's segments at most once
+    pub fn parse_with(s: &str, encoding: &Encoding) -> anyhow::Result<Self> {
+        let segments: String = encoding.segments.iter().collect();
+        let (patterns, outputs) = nom::strict(s.trim(), parser::entry(&segments))?;
+
+        let digits = encoding.digits().len();
+        ensure!(
+            patterns.len() <= digits,
+            "Expected at most {digits} patterns, found {}",
+            patterns.len()
+        );
+        ensure!(
+            outputs.len() == 4,
+            "Expected 4 outputs, found {}",
+            outputs.len()
+        );
+
+        let mut seen = HashSet::new();
+        for (field, lit) in iter::repeat("Pattern")
+            .zip(&patterns)
+            .chain(iter::repeat("Output").zip(&outputs))
+        {
+            let mut sorted: Vec<char> = lit.chars().collect();
+            sorted.sort();
+            ensure!(
+                sorted.windows(2).all(|w| w[0] != w[1]),
+                "{field} {lit:?} repeats a segment"
+            );
+            ensure!(
+                field != "Pattern" || seen.insert(sorted),
+                "Pattern {lit:?} appears twice"
+            );
+        }
+
+        let owned = |lits: Vec<&str>| lits.into_iter().map(str::to_owned).collect();
+        Ok(Connections {
+            patterns: owned(patterns),
+            outputs: owned(outputs),
+        })
+    }
+
+    /// Works out the wiring and reads the outputs, as [`Possibilities::decode`]
+    /// does
+    pub fn decode(&self) -> Result<Decoded, Undecodable> {
+        self.decode_with(&Encoding::decimal())
+    }
+
+    /// Like [`Connections::decode`], for another display
+    pub fn decode_with(&self, encoding: &Encoding) -> Result<Decoded, Undecodable> {
+        Possibilities::with_encoding(self, encoding).decode()
+    }
+
+    /// Reads an entry per line, as [`Connections::parse_with`] does, skipping
+    /// blank lines
+    pub fn read(buf: impl BufRead, encoding: &Encoding) -> anyhow::Result<Vec<Self>> {
+        let mut entries = Vec::new();
+        for (ix, line) in buf.lines().enumerate() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let entry = Connections::parse_with(&line, encoding)
+                .with_context(|| format!("line {}", ix + 1))?;
+            entries.push(entry);
+        }
+        Ok(entries)
+    }
+}
+
+impl FromStr for Connections {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Connections::parse_with(s, &Encoding::decimal())
+    }
+}
+
+pub mod parser {
+    use crate::nom::*;
+
+    /// Spaces between fields
+    fn spaces(input: &str) -> IResult<'_, &str> {
+        recognize(many1(char(' ')))(input)
+    }
+
+    /// The segments lit by a pattern or output, each one of `segments`.
+    /// Anything else up to the next space is an error, not the end of the
+    /// list.
+    pub fn lit<'a>(segments: &'a str) -> impl FnMut(&'a str) -> IResult<'a, &'a str> {
+        let word = recognize(many1(none_of(" |")));
+        preceded(
+            peek(none_of("|")),
+            cut(verify(word, |w: &str| {
+                w.chars().all(|c| segments.contains(c))
+            })),
+        )
+    }
+
+    /// An entry `patterns | outputs`, each a list of segments from
+    /// `segments` separated by spaces
+    #[allow(clippy::type_complexity)]
+    pub fn entry<'a>(
+        segments: &'a str,
+    ) -> impl FnMut(&'a str) -> IResult<'a, (Vec<&'a str>, Vec<&'a str>)> {
+        let patterns = separated_list1(spaces, context("pattern", lit(segments)));
+        let separator = delimited(many0(char(' ')), char('|'), many0(char(' ')));
+        let outputs = separated_list1(spaces, context("output", lit(segments)));
+        map(
+            tuple((
+                context("patterns", patterns),
+                context("separator", separator),
+                context("outputs", outputs),
+            )),
+            |(patterns, _, outputs)| (patterns, outputs),
+        )
+    }
+}
+
+// Segments used for each digit
+// e.g. SEGMENTS[3] = "acdeg" - the number 3 uses segments a, c, d, e, and g
+const SEGMENTS: [&str; 10] = [
+    "abcefg", "cf", "acdeg", "acdfg", "bcdf", "abdfg", "abdefg", "acf", "abcdefg",
+    "abcdfg",
+    // "abcefg", "cf", "acdeg", "acdfg", "bcdf", "abdfg", "abdefg", "acf", "abcdefg", "abcdfg",
+];
+
+// Segments for the hex digits A, b, C, d, E and F, after 0 to 9
+const HEX_SEGMENTS: [&str; 6] = ["abcdef", "bdefg", "abeg", "cdefg", "abdeg", "abde"];
+
+/// Which segments each digit of a display lights up. Segments are named by
+/// letters, and digit `n` is the `n`th pattern.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Encoding {
+    // All the segments, in order
+    segments: Vec<char>,
+    // The segments of each digit, in order
+    digits: Vec<Vec<char>>,
+}
+
+impl Encoding {
+    /// An encoding from the segments lit for each digit, in order. Errors if
+    /// two digits look the same, or a digit lights nothing.
+    pub fn new<S: AsRef<str>>(digits: impl IntoIterator<Item = S>) -> anyhow::Result<Self> {
+        let mut seen: HashSet<Vec<char>> = HashSet::new();
+        let mut encoded = Vec::new();
+        for (d, segments) in digits.into_iter().enumerate() {
+            let segments = segments.as_ref();
+            let mut lit: Vec<char> = segments.chars().collect();
+            lit.sort();
+            lit.dedup();
+            ensure!(
+                !lit.is_empty() && lit.iter().all(char::is_ascii_lowercase),
+                "Digit {d} has invalid segments {segments:?}"
+            );
+            ensure!(
+                seen.insert(lit.clone()),
+                "Digit {d} looks like an earlier one"
+            );
+            encoded.push(lit);
+        }
+        ensure!(encoded.len() >= 2, "Expected at least 2 digits");
+        ensure!(encoded.len() <= u8::MAX as usize, "Too many digits");
+
+        let mut segments: Vec<char> = encoded.iter().flatten().copied().collect();
+        segments.sort();
+        segments.dedup();
+        Ok(Encoding {
+            segments,
+            digits: encoded,
+        })
+    }
+
+    /// The usual 0 to 9 on a 7-segment display, as in the puzzle
+    pub fn decimal() -> Self {
+        Encoding::new(SEGMENTS).unwrap()
+    }
+
+    /// 0 to 9 and then A, b, C, d, E and F on a 7-segment display
+    pub fn hex() -> Self {
+        Encoding::new(SEGMENTS.iter().chain(&HEX_SEGMENTS)).unwrap()
+    }
+
+    pub fn segments(&self) -> &[char] {
+        &self.segments
+    }
+
+    /// The segments lit for each digit, in order
+    pub fn digits(&self) -> &[Vec<char>] {
+        &self.digits
+    }
+
+    /// Numbers of segments that only one digit lights
+    pub fn unique_lengths(&self) -> Vec<usize> {
+        let lengths = self.digits.iter().map(Vec::len).counts();
+        let mut unique: Vec<usize> = lengths
+            .into_iter()
+            .filter_map(|(len, n)| (n == 1).then_some(len))
+            .collect();
+        unique.sort();
+        unique
+    }
+}
+
+impl Default for Encoding {
+    fn default() -> Self {
+        Encoding::decimal()
+    }
+}
+
+/// Displays with a built-in [`Encoding`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Display, clap::ValueEnum)]
+#[display(style = "kebab-case")]
+pub enum Digits {
+    /// 0 to 9, as in the puzzle
+    #[default]
+    Decimal,
+    /// 0 to F
+    Hex,
+}
+
+impl Digits {
+    pub fn encoding(self) -> Encoding {
+        match self {
+            Digits::Decimal => Encoding::decimal(),
+            Digits::Hex => Encoding::hex(),
+        }
+    }
+}
+
+/// An entry worked out: how it's wired, and what its outputs read
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Decoded {
+    /// The segment each wire lights. An entry missing some patterns may
+    /// leave some wires unsettled; they're left out.
+    pub wiring: HashMap<char, char>,
+    /// The digit each output shows
+    pub digits: Vec<u8>,
+    /// The outputs read as a number, in base the number of digits
+    pub value: u64,
+}
+
+impl Decoded {
+    /// The outputs drawn as a seven-segment display of `encoding`'s digits,
+    /// as [`render`] does
+    pub fn render(&self, encoding: &Encoding) -> String {
+        let lit: Vec<Vec<char>> = self
+            .digits
+            .iter()
+            .map(|&d| encoding.digits[d as usize].clone())
+            .collect();
+        render(&lit)
+    }
+}
+
+/// Why an entry's outputs couldn't be read
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum Undecodable {
+    #[error("No wiring makes every pattern a digit")]
+    NoWiring,
+    /// Too few patterns to tell the wirings apart; these are every value the
+    /// outputs could read, in order
+    #[error("The outputs could read {}", .0.iter().join(" or "))]
+    Ambiguous(Vec<u64>),
+}
+
+/// The rules [`Possibilities`] uses to narrow down the wiring
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Display)]
+pub enum Rule {
+    /// A pattern's wires can only light segments its possible digits use
+    #[display("segments in pattern")]
+    PatternSegments,
+    /// A wire known to light one segment can't light it for another wire
+    #[display("known wire")]
+    KnownWire,
+    /// A pattern that can only be one digit is the only pattern for it, and
+    /// a digit only one pattern could be is that pattern's
+    #[display("single digit")]
+    SingleDigit,
+    /// A segment only one wire could light is that wire's
+    #[display("single wire")]
+    SingleWire,
+    /// A pattern whose wires are all known is the digit they light
+    #[display("known wires in pattern")]
+    KnownPattern,
+    /// Of the five-segment patterns, 3 is the one sharing a missing segment
+    /// with neither of the others; likewise for other encodings' digits
+    #[display("finding 3")]
+    Three,
+    /// What every wiring left that makes every pattern a different digit
+    /// agrees on
+    #[display("brute force")]
+    BruteForce,
+}
+
+/// Possibilities removed for a pattern or wire
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Removed {
+    /// Digits the pattern can't be
+    Digits { pattern: String, digits: Vec<u8> },
+    /// Segments the wire can't light
+    Segments { wire: char, segments: Vec<char> },
+}
+
+/// One step in working out the wiring: a rule, and what it ruled out
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DeductionStep {
+    pub rule: Rule,
+    pub removed: Removed,
+}
+
+impl DeductionStep {
+    /// The step removing `digits` from `pattern`'s, if there are any
+    fn digits(rule: Rule, pattern: &[char], digits: HashSet<u8>) -> Option<Self> {
+        Some(DeductionStep {
+            rule,
+            removed: Removed::Digits {
+                pattern: pattern.iter().collect(),
+                digits: sorted(digits)?,
+            },
+        })
+    }
+
+    /// The step removing `segments` from `wire`'s, if there are any
+    fn segments(rule: Rule, wire: char, segments: HashSet<char>) -> Option<Self> {
+        Some(DeductionStep {
+            rule,
+            removed: Removed::Segments {
+                wire,
+                segments: sorted(segments)?,
+            },
+        })
+    }
+}
+
+/// The values in order, or `None` if there are none
+fn sorted<T: Ord>(values: HashSet<T>) -> Option<Vec<T>> {
+    let mut values: Vec<T> = values.into_iter().collect();
+    values.sort();
+    (!values.is_empty()).then_some(values)
+}
+
+impl fmt::Display for DeductionStep {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.removed {
+            Removed::Digits { pattern, digits } => {
+                write!(
+                    f,
+                    "{}: {pattern} isn't {}",
+                    self.rule,
+                    digits.iter().join(" or ")
+                )
+            }
+            Removed::Segments { wire, segments } => write!(
+                f,
+                "{}: wire {wire} doesn't light {}",
+                self.rule,
+                segments.iter().join(" or ")
+            ),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Possibilities {
+    // Known pattern, possible numeric matches
+    patterns: Domains<Vec<char>, u8>,
+
+    // Segments to possible input wires
+    rewiring: Domains<char, char>,
+
+    // Outputs for this connection set
+    outputs: Vec<Vec<char>>,
+
+    // Everything ruled out so far, in order
+    steps: Vec<DeductionStep>,
+
+    // The digits the display can show
+    encoding: Encoding,
+}
+
+impl Possibilities {
+    pub fn new(connections: &Connections) -> Self {
+        Self::with_encoding(connections, &Encoding::decimal())
+    }
+
+    /// Possibilities for a display showing digits with `encoding`
+    pub fn with_encoding(connections: &Connections, encoding: &Encoding) -> Self {
+        let patterns = connections
+            .patterns
+            .iter()
+            .map(|pattern| {
+                let mut pattern = pattern.chars().collect::<Vec<char>>();
+                pattern.sort();
+
+                let ns: HashSet<u8> = (0..encoding.digits.len() as u8)
+                    .filter(|&n| encoding.digits[n as usize].len() == pattern.len())
+                    .collect();
+                (pattern, ns)
+            })
+            .collect();
+
+        let outputs = connections
+            .outputs
+            .iter()
+            .map(|s| {
+                let mut cs = s.chars().collect::<Vec<char>>();
+                cs.sort();
+                cs
+            })
+            .collect::<Vec<Vec<char>>>();
+
+        let rewiring = encoding
+            .segments
+            .iter()
+            .map(|&c| (c, HashSet::from_iter(encoding.segments.iter().copied())))
+            .collect();
+        Self {
+            patterns,
+            outputs,
+            rewiring,
+            steps: Vec::new(),
+            encoding: encoding.clone(),
+        }
+    }
+
+    /// Each step taken so far in working out the wiring, in order
+    pub fn explain(&self) -> Vec<DeductionStep> {
+        self.steps.clone()
+    }
+
+    /// Records that `rule` removed `narrowed` from patterns' digits
+    fn record_digits(&mut self, rule: Rule, narrowed: Vec<Narrowed<Vec<char>, u8>>) -> bool {
+        let changed = !narrowed.is_empty();
+        for (pattern, digits) in narrowed {
+            self.steps
+                .extend(DeductionStep::digits(rule, &pattern, digits));
+        }
+        changed
+    }
+
+    /// Records that `rule` removed `narrowed` from wires' segments
+    fn record_segments(&mut self, rule: Rule, narrowed: Vec<Narrowed<char, char>>) -> bool {
+        let changed = !narrowed.is_empty();
+        for (wire, segments) in narrowed {
+            self.steps
+                .extend(DeductionStep::segments(rule, wire, segments));
+        }
+        changed
+    }
+
+    fn pattern_reduce(&mut self) -> bool {
+        let mut narrowed = Vec::new();
+        for (pattern, possible_digits) in self.patterns.iter() {
+            let digits = &self.encoding.digits;
+            let possible_segments: HashSet<char> = possible_digits
+                .iter()
+                .flat_map(|&d| digits[d as usize].iter().copied())
+                .collect();
+
+            // Segments that could possibly be missing from the pattern
+            let possible_missing: HashSet<char> = self
+                .encoding
+                .segments
+                .iter()
+                .copied()
+                .filter(|c| {
+                    possible_digits
+                        .iter()
+                        .any(|&d| !digits[d as usize].contains(c))
+                })
+                .collect();
+
+            debug!(
+                "Looking at {} -> {:?}, segments {:?}",
+                pattern.iter().collect::<String>(),
+                possible_digits,
+                possible_segments
+            );
+            narrowed.extend(self.rewiring.retain_all(|segment, w| {
+                if pattern.contains(segment) {
+                    // e.g. 'f' above.
+                    // This segment is used by the digit, so one of the wires intended for the current digit
+                    // must map to this segment; segment 'f' could only be matched by 'a', 'b', or 'c' digit
+                    // So segment 'b'
+                    possible_segments.contains(w)
+                } else if possible_digits.len() == 1 {
+                    // This digit is known, and this segment is not lit up during this digit.
+                    // Thus, it can't be any of the wires for this digit.
+                    !possible_segments.contains(w)
+                } else {
+                    // This segment is not used by the pattern, so it can only be attached to a wire that is not used
+                    // by all the possible digits
+                    possible_missing.contains(w)
+                }
+            }));
+        }
+
+        for (segment, wires) in &narrowed {
+            debug!("  segment {segment}: ruled out {wires:?}");
+        }
+        self.record_segments(Rule::PatternSegments, narrowed)
+    }
+
+    fn wire_reduce(&mut self) -> bool {
+        // If any wire is known, then its not a possible match for any other segment
+        let narrowed = self.rewiring.exclude_known();
+        self.record_segments(Rule::KnownWire, narrowed)
+    }
+
+    /// Whether there's a pattern for every digit
+    pub fn complete(&self) -> bool {
+        self.patterns.len() == self.encoding.digits.len()
+    }
+
+    // For any pattern that could only be one digit, remove that digit from all other patterns,
+    // and any digit only one pattern could be is that pattern's - if every digit has a pattern
+    fn pattern_singles_reduce(&mut self) -> bool {
+        let loners = self.patterns.exclude_known();
+        let singles = if self.complete() {
+            self.patterns.hidden_singles()
+        } else {
+            Vec::new()
+        };
+        self.record_digits(Rule::SingleDigit, loners)
+            | self.record_digits(Rule::SingleDigit, singles)
+    }
+
+    // For any wire that has only one possible segment, that segment must be that wire
+    fn wire_singles_reduce(&mut self) -> bool {
+        let narrowed = self.rewiring.hidden_singles();
+        self.record_segments(Rule::SingleWire, narrowed)
+    }
+
+    fn solve_known_wire_patterns(&mut self) -> bool {
+        let mut known = Vec::new();
+        'outer: for (pattern, digits) in self.patterns.iter() {
+            if digits.len() == 1 {
+                continue;
+            }
+
+            let mut wires: Vec<char> = Vec::new();
+            for c in pattern {
+                let Some(&wire) = self.rewiring.known(c) else {
+                    continue 'outer;
+                };
+                wires.push(wire);
+            }
+
+            // So we know exactly what digit this is, so we know exactly what digit this should be.
+            wires.sort();
+
+            // No digit, if the wiring so far is impossible
+            let Some(d) = self.encoding.digits.iter().position(|s| *s == wires) else {
+                continue;
+            };
+            known.push((pattern.clone(), d as u8));
+        }
+
+        let narrowed = known
+            .into_iter()
+            .map(|(pattern, d)| (pattern.clone(), self.patterns.assign(pattern, d)))
+            .collect();
+        self.record_digits(Rule::KnownPattern, narrowed)
+    }
+
+    // Determine which pattern is 3, and use that to determine segments b, e, and f.
+    // More generally: of any three digits lighting as many segments, one may
+    // be the only one whose two others leave out segments that don't overlap,
+    // as 3 is of 2, 3 and 5. Wiring doesn't change which segments overlap, so
+    // the pattern that's the odd one out in the same way is that digit.
+    fn solve_three(&mut self) -> bool {
+        if !self.complete() {
+            // Needs a pattern for every digit lighting as many segments
+            return false;
+        }
+
+        let wires = &self.encoding.segments;
+        let left_out = |lit: &[char]| -> HashSet<char> {
+            wires.iter().copied().filter(|c| !lit.contains(c)).collect()
+        };
+        // The index of the only one of three whose others are disjoint
+        let odd_one_out = |sets: &[HashSet<char>]| -> Option<usize> {
+            let found: Vec<usize> = (0..3)
+                .filter(|&ix| sets[(ix + 1) % 3].is_disjoint(&sets[(ix + 2) % 3]))
+                .collect();
+            match found[..] {
+                [ix] => Some(ix),
+                _ => None,
+            }
+        };
+
+        let lengths: BTreeSet<usize> = self.encoding.digits.iter().map(Vec::len).collect();
+        let mut assigned = Vec::new();
+        for len in lengths {
+            let digits: Vec<u8> = (0..self.encoding.digits.len() as u8)
+                .filter(|&d| self.encoding.digits[d as usize].len() == len)
+                .collect();
+            if digits.len() != 3 {
+                continue;
+            }
+            let missing: Vec<_> = digits
+                .iter()
+                .map(|&d| left_out(&self.encoding.digits[d as usize]))
+                .collect();
+            let Some(odd) = odd_one_out(&missing) else {
+                continue;
+            };
+            let digit = digits[odd];
+
+            let pats: Vec<Vec<char>> = self
+                .patterns
+                .vars()
+                .filter(|p| p.len() == len)
+                .cloned()
+                .collect();
+            if pats.iter().any(|p| self.patterns.known(p) == Some(&digit)) {
+                // Already know which one it is
+                continue;
+            }
+            if pats.len() != 3 {
+                debug!("Not enough {len}-patterns");
+                continue;
+            }
+
+            let missing: Vec<_> = pats.iter().map(|p| left_out(p)).collect();
+            let Some(ix) = odd_one_out(&missing) else {
+                debug!("The {len}-patterns don't pick out a {digit}");
+                continue;
+            };
+            let possible = self.patterns.get(&pats[ix]).unwrap();
+            if possible.len() <= 1 || !possible.contains(&digit) {
+                debug!("The {len}-pattern for {digit} can't be {digit}");
+                continue;
+            }
+            assigned.push((pats[ix].clone(), digit));
+        }
+
+        let changed = !assigned.is_empty();
+        for (pattern, digit) in assigned {
+            let removed = self.patterns.assign(pattern.clone(), digit);
+            self.steps
+                .extend(DeductionStep::digits(Rule::Three, &pattern, removed));
+        }
+        changed
+    }
+
+    pub fn simplify(&mut self) {
+        let rounds = constraints::propagate(
+            self,
+            &[
+                Self::pattern_reduce,
+                Self::pattern_singles_reduce,
+                Self::wire_reduce,
+                Self::wire_singles_reduce,
+                Self::solve_known_wire_patterns,
+            ],
+            // It turns out - this isn't needed, the above cover all cases
+            &[Self::solve_three],
+        );
+        debug!("Deduction took {rounds} rounds");
+    }
+
+    /// Works out the wiring: first by deduction with [`Possibilities::simplify`],
+    /// then, if that gets stuck, by searching the wirings left with
+    /// [`Possibilities::brute_force`]. Returns whether every pattern is known
+    /// to be a different digit.
+    pub fn solve(&mut self) -> bool {
+        self.simplify();
+        if !self.all_distinct() {
+            debug!("Deduction got stuck; searching the wirings left");
+            self.brute_force();
+        }
+        self.all_distinct()
+    }
+
+    /// Whether every pattern is known, and no two are the same digit
+    fn all_distinct(&self) -> bool {
+        let digits: HashSet<u8> = self
+            .patterns
+            .vars()
+            .filter_map(|p| self.patterns.known(p).copied())
+            .collect();
+        self.all_known() && digits.len() == self.patterns.len()
+    }
+
+    /// Searches every wiring the wires' possible segments still allow for
+    /// those that make each pattern a different digit and each output a
+    /// digit: all `n!` ways of connecting the `n` wires to segments (7! for
+    /// the usual display) if nothing has been deduced yet. The patterns and
+    /// wires every wiring found agrees on are set to match. Returns whether
+    /// any wiring was found.
+    pub fn brute_force(&mut self) -> bool {
+        let readings = self.readings();
+        self.settle(&readings);
+        !readings.is_empty()
+    }
+
+    /// Settles each pattern and wire that all of `readings` agree on
+    fn settle(&mut self, readings: &[(Vec<char>, Vec<u8>)]) {
+        let Some((first, _)) = readings.first() else {
+            return;
+        };
+        let agreed: Vec<(Vec<char>, u8)> = self
+            .patterns
+            .vars()
+            .filter_map(|pattern| {
+                let digit = self.digit_with(first, pattern)?;
+                readings
+                    .iter()
+                    .all(|(wiring, _)| self.digit_with(wiring, pattern) == Some(digit))
+                    .then(|| (pattern.clone(), digit))
+            })
+            .collect();
+        let narrowed = agreed
+            .into_iter()
+            .map(|(pattern, digit)| (pattern.clone(), self.patterns.assign(pattern, digit)))
+            .collect();
+        self.record_digits(Rule::BruteForce, narrowed);
+
+        let narrowed = self
+            .encoding
+            .segments
+            .iter()
+            .enumerate()
+            .filter(|&(ix, _)| readings.iter().all(|(wiring, _)| wiring[ix] == first[ix]))
+            .map(|(ix, &wire)| (wire, self.rewiring.assign(wire, first[ix])))
+            .collect();
+        self.record_segments(Rule::BruteForce, narrowed);
+    }
+
+    /// The digit `lit` shows if the encoding's `i`th wire lights
+    /// `wiring[i]`, if any
+    fn digit_with(&self, wiring: &[char], lit: &[char]) -> Option<u8> {
+        let wires = &self.encoding.segments;
+        let mut segments = lit
+            .iter()
+            .map(|w| Some(wiring[wires.iter().position(|c| c == w)?]))
+            .collect::<Option<Vec<char>>>()?;
+        segments.sort();
+        let digit = self.encoding.digits.iter().position(|s| *s == segments)?;
+        Some(digit as u8)
+    }
+
+    /// Every wiring the wires' possible segments still allow, as in
+    /// [`Possibilities::digit_with`], extending `wiring` a wire at a time
+    fn wirings(&self, wiring: &mut Vec<char>, found: &mut Vec<Vec<char>>) {
+        let Some(wire) = self.encoding.segments.get(wiring.len()) else {
+            found.push(wiring.clone());
+            return;
+        };
+        for &segment in self.rewiring.get(wire).into_iter().flatten() {
+            if wiring.contains(&segment) {
+                continue;
+            }
+            wiring.push(segment);
+            self.wirings(wiring, found);
+            wiring.pop();
+        }
+    }
+
+    /// The wirings still possible that make each pattern a different digit
+    /// and each output a digit, each with the digits the outputs show
+    fn readings(&self) -> Vec<(Vec<char>, Vec<u8>)> {
+        let mut wirings = Vec::new();
+        self.wirings(&mut Vec::new(), &mut wirings);
+        info!(target: stats::TARGET, wirings_searched = wirings.len());
+
+        wirings
+            .into_iter()
+            .filter_map(|wiring| {
+                let digits = self
+                    .patterns
+                    .vars()
+                    .map(|pattern| self.digit_with(&wiring, pattern))
+                    .collect::<Option<HashSet<u8>>>()?;
+                if digits.len() != self.patterns.len() {
+                    return None;
+                }
+                let outputs = self
+                    .outputs
+                    .iter()
+                    .map(|output| self.digit_with(&wiring, output))
+                    .collect::<Option<Vec<u8>>>()?;
+                Some((wiring, outputs))
+            })
+            .collect()
+    }
+
+    /// `digits` read as a number, in base the number of digits
+    fn value(&self, digits: &[u8]) -> u64 {
+        let base = self.encoding.digits.len() as u64;
+        digits.iter().fold(0, |value, &d| value * base + d as u64)
+    }
+
+    /// Every value the outputs could read, in order, searching the wirings
+    /// still possible for those that make each pattern a different digit and
+    /// each output a digit
+    pub fn output_values(&self) -> Vec<u64> {
+        let values: BTreeSet<u64> = self
+            .readings()
+            .iter()
+            .map(|(_, digits)| self.value(digits))
+            .collect();
+        values.into_iter().collect()
+    }
+
+    /// Reads the outputs: deduces what it can, then searches the wirings left
+    /// as [`Possibilities::brute_force`] does. That's usually just the one,
+    /// but an entry missing some patterns may leave several.
+    pub fn decode(&mut self) -> Result<Decoded, Undecodable> {
+        self.simplify();
+        let readings = self.readings();
+        self.settle(&readings);
+        let values: BTreeSet<u64> = readings
+            .iter()
+            .map(|(_, digits)| self.value(digits))
+            .collect();
+        match values.len() {
+            0 => return Err(Undecodable::NoWiring),
+            1 => {}
+            _ => return Err(Undecodable::Ambiguous(values.into_iter().collect())),
+        }
+
+        let (_, digits) = &readings[0];
+        // Only the wires every reading agrees on
+        let wiring = self
+            .encoding
+            .segments
+            .iter()
+            .filter_map(|wire| Some((*wire, *self.rewiring.known(wire)?)))
+            .collect();
+        Ok(Decoded {
+            wiring,
+            digits: digits.clone(),
+            value: self.value(digits),
+        })
+    }
+
+    pub fn all_known(&self) -> bool {
+        self.patterns.all_known()
+    }
+
+    pub fn lookup(&self, pattern: &str) -> Option<u8> {
+        let mut pattern = pattern.chars().collect::<Vec<char>>();
+        pattern.sort();
+        let pattern = pattern;
+
+        self.patterns.known(&pattern).copied()
+    }
+
+    pub fn solve_outputs(&self) -> Option<u64> {
+        let digits = self
+            .outputs
+            .iter()
+            .map(|output| self.patterns.known(output).copied())
+            .collect::<Option<Vec<u8>>>()?;
+        Some(self.value(&digits))
+    }
+
+    /// The segments each output lights up, going through the wiring rather
+    /// than the digits; `None` unless every wire it uses is known
+    pub fn output_segments(&self) -> Option<Vec<Vec<char>>> {
+        self.outputs
+            .iter()
+            .map(|output| {
+                let mut lit = output
+                    .iter()
+                    .map(|w| self.rewiring.known(w).copied())
+                    .collect::<Option<Vec<char>>>()?;
+                lit.sort();
+                Some(lit)
+            })
+            .collect()
+    }
+
+    /// The outputs drawn as a seven-segment display, as [`render`] does;
+    /// `None` unless every wire the outputs use is known
+    pub fn render(&self) -> Option<String> {
+        Some(render(&self.output_segments()?))
+    }
+}
+
+/// Draws digits as three lines of seven-segment ASCII art, given the segments
+/// each lights up. Segments are laid out as in the puzzle, with `a` at the
+/// top, `d` in the middle and `g` at the bottom; any others aren't drawn.
+///
+/// ```text
+///  _   _   _
+/// |_|  _| |_| |_|
+/// |_|  _|  _|   |
+/// ```
+pub fn render(digits: &[Vec<char>]) -> String {
+    // The character for each segment in each row, or ' ' for a gap
+    const ROWS: [[(char, char); 3]; 3] = [
+        [(' ', ' '), ('a', '_'), (' ', ' ')],
+        [('b', '|'), ('d', '_'), ('c', '|')],
+        [('e', '|'), ('g', '_'), ('f', '|')],
+    ];
+
+    let mut lines = Vec::with_capacity(3);
+    for row in ROWS {
+        let cells: Vec<String> = digits
+            .iter()
+            .map(|lit| {
+                row.iter()
+                    .map(|&(segment, c)| if lit.contains(&segment) { c } else { ' ' })
+                    .collect()
+            })
+            .collect();
+        lines.push(cells.join(" ").trim_end().to_owned());
+    }
+    lines.join("\n") + "\n"
+}
+
+/// Solves both parts of the puzzle for the given input
+pub fn solve(input: &str) -> anyhow::Result<Solved> {
+    let connections = info_span!("parse")
+        .in_scope(|| Connections::read(input.as_bytes(), &Encoding::decimal()))?;
+
+    let count: usize =
+        info_span!("part1").in_scope(|| connections.iter().map(|c| c.simples()).sum());
+
+    let total = info_span!("part2").in_scope(|| output_sum(&connections, &Encoding::decimal()))?;
+
+    Ok(Solved::new(count, total))
+}
+
+/// Works out the wiring of each entry and decodes its outputs, in order
+pub fn decode_all(
+    connections: &[Connections],
+    encoding: &Encoding,
+) -> Vec<anyhow::Result<Decoded>> {
+    parallel::map(connections, |entry| Ok(entry.decode_with(encoding)?))
+}
+
+/// The sum of every entry's decoded outputs. Errors with the first entry
+/// that can't be decoded.
+pub fn output_sum(connections: &[Connections], encoding: &Encoding) -> anyhow::Result<u64> {
+    let mut total = 0;
+    for (ix, output) in decode_all(connections, encoding).into_iter().enumerate() {
+        total += output.with_context(|| format!("entry {}", ix + 1))?.value;
+    }
+    Ok(total)
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// Tests
+
+#[cfg(test)]
+mod tests {
+    use test_log::test;
+
+    use crate::parse;
+
+    #[allow(unused_imports)]
+    use super::*;
+
+    const EXAMPLE: &str = r###"
+        be cfbegad cbdgef fgaecd cgeb fdcge agebfd fecdb fabcd edb | fdgacbe cefdb cefbgd gcbe
+        edbfga begcd cbg gc gcadebf fbgde acbgfd abcde gfcbed gfec | fcgedb cgb dgebacf gc
+        fgaebd cg bdaec gdafb agbcfd gdcbef bgcad gfac gcb cdgabef | cg cg fdcagb cbg
+        fbegcd cbd adcefb dageb afcb bc aefdc ecdab fgdeca fcdbega | efabcd cedba gadfec cb
+        aecbfdg fbg gf bafeg dbefa fcge gcbea fcaegb dgceab fcbdga | gecf egdcabf bgf bfgea
+        fgeab ca afcebg bdacfeg cfaedg gcfdb baec bfadeg bafgc acf | gebdcfa ecba ca fadegcb
+        dbcfg fgd bdegcaf fgec aegbdf ecdfab fbedc dacgb gdcebf gf | cefg dcbef fcge gbcadfe
+        bdfegc cbegaf gecbf dfcage bdacg ed bedf ced adcbefg gebcd | ed bcgafe cdgba cbgef
+        egadfb cdbfeg cegd fecab cgb gbdefca cg fgcdab egfdb bfceg | gbdfcae bgc cg cgb
+        gcafb gcf dcaebfg ecagb gf abcdeg gaef cafbge fdbac fegbdc | fgae cfgab fg bagce
+    "###;
+
+    const EXAMPLE_OUTPUTS: [u64; 10] = [8394, 9781, 1197, 9361, 4873, 8418, 4548, 1625, 8717, 4315];
+
+    #[test]
+    fn test_basic() {
+        let connections: Vec<Connections> = parse::buffer(EXAMPLE.as_bytes()).unwrap();
+        let count: usize = connections.iter().map(|c| c.simples()).sum();
+        assert_eq!(count, 26);
+    }
+
+    #[test]
+    fn test_simplify() {
+        let connections: Vec<Connections> = parse::buffer(EXAMPLE.as_bytes()).unwrap();
+        let mut possibilities = Possibilities::new(&connections[0]);
+        possibilities.simplify();
+
+        println!("{:?}", possibilities);
+        assert!(possibilities.all_known());
+        assert_eq!(possibilities.lookup("acedgfb"), Some(8));
+    }
+
+    #[test]
+    fn test_full_example() {
+        let connections: Vec<Connections> = parse::buffer(EXAMPLE.as_bytes()).unwrap();
+        assert_eq!(connections.len(), EXAMPLE_OUTPUTS.len());
+
+        let mut output_sum = 0;
+        for (c, &out) in connections.iter().zip(EXAMPLE_OUTPUTS.iter()) {
+            let mut possibilities = Possibilities::new(c);
+            possibilities.simplify();
+
+            // println!("{:?}", possibilities);
+            assert!(possibilities.all_known());
+            let output = possibilities.solve_outputs().unwrap();
+
+            assert_eq!(output, out);
+            output_sum += output;
+        }
+
+        assert_eq!(output_sum, 61229);
+    }
+
+    #[test]
+    fn test_explain() {
+        let connections: Vec<Connections> = parse::buffer(EXAMPLE.as_bytes()).unwrap();
+        for c in &connections {
+            let mut possibilities = Possibilities::new(c);
+            assert!(possibilities.explain().is_empty());
+            assert!(possibilities.solve());
+
+            let steps = possibilities.explain();
+            assert!(!steps.is_empty());
+            // Nothing ruled out is the final answer
+            for step in &steps {
+                assert_ne!(step.rule, Rule::BruteForce);
+                match &step.removed {
+                    Removed::Digits { pattern, digits } => {
+                        let digit = possibilities.lookup(pattern).unwrap();
+                        assert!(!digits.contains(&digit), "{step}");
+                    }
+                    Removed::Segments { wire, segments } => {
+                        let known = possibilities.rewiring.get(wire).unwrap();
+                        assert!(segments.iter().all(|s| !known.contains(s)), "{step}");
+                    }
+                }
+            }
+        }
+
+        let step = DeductionStep {
+            rule: Rule::SingleDigit,
+            removed: Removed::Digits {
+                pattern: "bcdef".to_owned(),
+                digits: vec![2, 5],
+            },
+        };
+        assert_eq!(step.to_string(), "single digit: bcdef isn't 2 or 5");
+        let step = DeductionStep {
+            rule: Rule::BruteForce,
+            removed: Removed::Segments {
+                wire: 'a',
+                segments: vec!['b', 'f'],
+            },
+        };
+        assert_eq!(step.to_string(), "brute force: wire a doesn't light b or f");
+    }
+
+    /// An entry for `encoding` with segment `s` wired to `wiring[s]`, showing
+    /// `outputs`
+    fn scrambled(encoding: &Encoding, wiring: &str, outputs: [usize; 4]) -> Connections {
+        let wire = |segments: &Vec<char>| -> String {
+            segments
+                .iter()
+                .map(|c| {
+                    let ix = encoding.segments().iter().position(|s| s == c).unwrap();
+                    wiring.chars().nth(ix).unwrap()
+                })
+                .collect()
+        };
+        let digits = encoding.digits();
+        // Patterns in a different order than the digits
+        let patterns = digits.iter().rev().map(wire).join(" ");
+        let outputs = outputs.iter().map(|&d| wire(&digits[d])).join(" ");
+        Connections::parse_with(&format!("{patterns} | {outputs}"), encoding).unwrap()
+    }
+
+    #[test]
+    fn test_output_sum() {
+        let decimal = Encoding::decimal();
+        let mut connections = Connections::read(EXAMPLE.as_bytes(), &decimal).unwrap();
+        let decoded: Vec<u64> = decode_all(&connections, &decimal)
+            .into_iter()
+            .map(|d| d.unwrap().value)
+            .collect();
+        assert_eq!(decoded, EXAMPLE_OUTPUTS);
+        assert_eq!(output_sum(&connections, &decimal).unwrap(), 61229);
+
+        // Many entries, split across threads with the parallel feature
+        let many: Vec<Connections> = connections.iter().cycle().take(200).cloned().collect();
+        assert_eq!(output_sum(&many, &decimal).unwrap(), 61229 * 20);
+
+        connections[6] = "be cfbegad cbdgef fgaecd cgeb fdcge agebfd fecdb fabcd ed \
+            | fdgacbe cefdb cefbgd gcbe"
+            .parse()
+            .unwrap();
+        let err = output_sum(&connections, &decimal).unwrap_err();
+        assert_eq!(
+            format!("{err:#}"),
+            "entry 7: No wiring makes every pattern a digit"
+        );
+        assert!(decode_all(&[], &decimal).is_empty());
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let line = EXAMPLE.trim().lines().next().unwrap().trim();
+        let entry: Connections = line.parse().unwrap();
+        let spaced = line.replace(' ', "  ").replace("|", " | ");
+        assert_eq!(spaced.parse::<Connections>().unwrap(), entry);
+
+        let error = |s: &str| format!("{:#}", s.parse::<Connections>().unwrap_err());
+        for (s, problem) in [
+            (
+                "be cfbegad cbdgef fgaecd cgeb fdcge agebfd fecdb fabcd edb",
+                "in separator",
+            ),
+            (
+                "be cfbegad cbdgef fgaecd cgeb fdcge agebfd fecdb fabcd edb fdgacbe",
+                "in separator",
+            ),
+            (
+                "be cfbegad cbdgef fgaecd cgeb fdcge agebfh fecdb fabcd edb | fdgacbe cefdb",
+                "in pattern",
+            ),
+            (
+                "be cfbegad cbdgef fgaecd cgeb fdcge agebfd fecdb fabcd edb | fdgacbe ce-db",
+                "in output",
+            ),
+            (
+                "be cfbegad cbdgef fgaecd cgeb fdcge agebfd fecdb fabcd edb |",
+                "in outputs",
+            ),
+            ("| fdgacbe cefdb cefbgd gcbe", "in patterns"),
+        ] {
+            let message = error(s);
+            assert!(message.contains(problem), "{s:?}: {message}");
+        }
+
+        let replaced = |from: &str, to: &str| error(&line.replacen(from, to, 1));
+        assert_eq!(
+            replaced("be ", "a be "),
+            "Expected at most 10 patterns, found 11"
+        );
+        // Too few is fine
+        assert!(line.replacen("be ", "", 1).parse::<Connections>().is_ok());
+        assert_eq!(replaced(" gcbe", ""), "Expected 4 outputs, found 3");
+        assert_eq!(
+            replaced("fabcd", "bcdef"),
+            "Pattern \"bcdef\" appears twice"
+        );
+        assert_eq!(
+            replaced("cbdgef", "cbdgeff"),
+            "Pattern \"cbdgeff\" repeats a segment"
+        );
+        assert_eq!(
+            replaced("| fdgacbe", "| fdgacbb"),
+            "Output \"fdgacbb\" repeats a segment"
+        );
+
+        let err = Connections::read(
+            format!("\n{line}\n{line} |\n").as_bytes(),
+            &Encoding::decimal(),
+        )
+        .unwrap_err();
+        assert!(format!("{err:#}").starts_with("line 3: "), "{err:#}");
+    }
+
+    #[test]
+    fn test_render() {
+        let connections: Vec<Connections> = parse::buffer(EXAMPLE.as_bytes()).unwrap();
+        let mut possibilities = Possibilities::new(&connections[0]);
+        assert_eq!(possibilities.render(), None);
+        assert!(possibilities.solve());
+
+        assert_eq!(possibilities.solve_outputs(), Some(8394));
+        assert_eq!(
+            possibilities.render().unwrap(),
+            " _   _   _\n\
+             |_|  _| |_| |_|\n\
+             |_|  _|  _|   |\n"
+        );
+
+        let hex = Encoding::hex();
+        assert_eq!(
+            render(&hex.digits()[10..]),
+            " _       _       _   _\n\
+             |_| |_  |    _| |_  |_\n\
+             | | |_| |_  |_| |_  |\n"
+        );
+    }
+
+    #[test]
+    fn test_encodings() {
+        assert_eq!(Encoding::default(), Encoding::decimal());
+        assert_eq!(Encoding::decimal().unique_lengths(), [2, 3, 4, 7]);
+        assert_eq!(Encoding::hex().unique_lengths(), [2, 3, 7]);
+        assert_eq!(Encoding::hex().digits().len(), 16);
+        assert_eq!(Encoding::hex().segments(), Encoding::decimal().segments());
+
+        assert!(Encoding::new(["ab", "ba"]).is_err());
+        assert!(Encoding::new(["ab", ""]).is_err());
+        assert!(Encoding::new(["ab", "C"]).is_err());
+        assert!(Encoding::new(["ab"]).is_err());
+
+        let connections: Vec<Connections> = parse::buffer(EXAMPLE.as_bytes()).unwrap();
+        let read = Connections::read(EXAMPLE.as_bytes(), &Encoding::decimal()).unwrap();
+        assert_eq!(read, connections);
+        // Decimal entries are hex entries missing the letters
+        let read = Connections::read(EXAMPLE.as_bytes(), &Encoding::hex()).unwrap();
+        assert_eq!(read, connections);
+        let mut possibilities = Possibilities::with_encoding(&read[0], &Encoding::hex());
+        assert!(!possibilities.complete());
+        assert_eq!(possibilities.decode().map(|d| d.value), Ok(0x8394));
+    }
+
+    #[test]
+    fn test_hex() {
+        let hex = Encoding::hex();
+        let entry = scrambled(&hex, "cfgabde", [0xb, 0xe, 0xe, 0xf]);
+        assert_eq!(entry.simples_with(&hex), 0);
+        let mut possibilities = Possibilities::with_encoding(&entry, &hex);
+        assert!(possibilities.solve());
+        assert_eq!(possibilities.solve_outputs(), Some(0xbeef));
+
+        let entry = scrambled(&hex, "gdbface", [1, 0xa, 8, 0xc]);
+        assert_eq!(entry.simples_with(&hex), 2);
+        let mut possibilities = Possibilities::with_encoding(&entry, &hex);
+        assert!(possibilities.brute_force());
+        assert_eq!(possibilities.solve_outputs(), Some(0x1a8c));
+    }
+
+    #[test]
+    fn test_nine_segments() {
+        // A 3x3 grid of segments, showing 0 to 7
+        let grid = Encoding::new([
+            "abcdefghi",
+            "adg",
+            "abcghi",
+            "abcefgi",
+            "adefi",
+            "cfi",
+            "aei",
+            "abcdgi",
+        ])
+        .unwrap();
+        assert_eq!(grid.segments().len(), 9);
+        let entry = scrambled(&grid, "ihgfedcba", [7, 0, 3, 1]);
+        let mut possibilities = Possibilities::with_encoding(&entry, &grid);
+        assert!(possibilities.solve());
+        assert_eq!(possibilities.solve_outputs(), Some(0o7031));
+    }
+
+    #[test]
+    fn test_solve_three() {
+        let entry: Connections = "acedgfb cdfbe gcdfa fbcad dab cefabd cdfgeb eafb cagedb ab \
+            | cdfeb fcadb cdfeb cdbaf"
+            .parse()
+            .unwrap();
+        let mut possibilities = Possibilities::new(&entry);
+        assert!(possibilities.solve_three());
+        assert_eq!(possibilities.lookup("fbcad"), Some(3));
+        assert_eq!(possibilities.explain()[0].rule, Rule::Three);
+        assert!(!possibilities.solve_three());
+
+        // The usual digits on nine segments, with two more every digit lights
+        let wider = Encoding::new(
+            Encoding::decimal()
+                .digits()
+                .iter()
+                .map(|d| d.iter().chain(&['h', 'i']).collect::<String>()),
+        )
+        .unwrap();
+        let entry = scrambled(&wider, "ihgfedcba", [3, 2, 5, 3]);
+        let mut possibilities = Possibilities::with_encoding(&entry, &wider);
+        assert!(possibilities.solve_three());
+        assert_eq!(possibilities.lookup(&entry.outputs[0]), Some(3));
+        assert_eq!(possibilities.lookup(&entry.outputs[1]), None);
+
+        // The hex digits have six five-segment digits, but of the
+        // four-segment 4, C and F, F is the odd one out
+        let hex = Encoding::hex();
+        let entry = scrambled(&hex, "cfgabde", [0xf, 3, 4, 0xc]);
+        let mut possibilities = Possibilities::with_encoding(&entry, &hex);
+        assert!(possibilities.solve_three());
+        assert_eq!(possibilities.lookup(&entry.outputs[0]), Some(0xf));
+        assert_eq!(possibilities.lookup(&entry.outputs[1]), None);
+        assert_eq!(possibilities.lookup(&entry.outputs[2]), None);
+    }
+
+    #[test]
+    fn test_brute_force() {
+        let connections: Vec<Connections> = parse::buffer(EXAMPLE.as_bytes()).unwrap();
+        for (c, &out) in connections.iter().zip(EXAMPLE_OUTPUTS.iter()) {
+            let mut deduced = Possibilities::new(c);
+            deduced.simplify();
+
+            let mut brute = Possibilities::new(c);
+            assert!(brute.brute_force());
+            assert!(brute.all_known());
+            assert_eq!(brute.patterns, deduced.patterns);
+            assert_eq!(brute.rewiring, deduced.rewiring);
+            assert_eq!(brute.solve_outputs(), Some(out));
+            assert!(brute.explain().iter().all(|s| s.rule == Rule::BruteForce));
+        }
+
+        // Two patterns for 1 and none for 7: no wiring works
+        let broken: Connections = "be cfbegad cbdgef fgaecd cgeb fdcge agebfd fecdb fabcd ed \
+            | fdgacbe cefdb cefbgd gcbe"
+            .parse()
+            .unwrap();
+        let mut possibilities = Possibilities::new(&broken);
+        assert!(!possibilities.brute_force());
+        assert!(!possibilities.solve());
+    }
+
+    #[test]
+    fn test_incomplete() {
+        let connections: Vec<Connections> = parse::buffer(EXAMPLE.as_bytes()).unwrap();
+        for (c, &out) in connections.iter().zip(EXAMPLE_OUTPUTS.iter()) {
+            // Without any one pattern, the rest still settle the wiring
+            for ix in 0..c.patterns.len() {
+                let mut missing = c.clone();
+                missing.patterns.remove(ix);
+                let mut possibilities = Possibilities::new(&missing);
+                assert!(!possibilities.complete());
+                let decoded = possibilities.decode();
+                assert_eq!(decoded.map(|d| d.value), Ok(out), "{missing:?}");
+            }
+        }
+
+        // 1, 7 and 8 leave c and f interchangeable, so the five-segment output
+        // could be 5 or 2
+        let entry: Connections = "cf acf abcdefg | cf acf abdfg abcdefg".parse().unwrap();
+        let mut possibilities = Possibilities::new(&entry);
+        let err = possibilities.decode().unwrap_err();
+        assert_eq!(err, Undecodable::Ambiguous(vec![1728, 1758]));
+        assert_eq!(err.to_string(), "The outputs could read 1728 or 1758");
+        assert_eq!(possibilities.output_values(), vec![1728, 1758]);
+
+        // Three five-segment patterns, but no 3 among them
+        let entry: Connections = "abcde abcdf abcdg | ab ab ab ab".parse().unwrap();
+        assert_eq!(entry.decode(), Err(Undecodable::NoWiring));
+
+        // A 4 whose segments are all in the 1
+        let entry: Connections = "cf acf bc | cf acf cf cf".parse().unwrap();
+        let mut possibilities = Possibilities::new(&entry);
+        assert_eq!(possibilities.decode(), Err(Undecodable::NoWiring));
+    }
+
+    #[test]
+    fn test_decoded() {
+        let entry: Connections = "acedgfb cdfbe gcdfa fbcad dab cefabd cdfgeb eafb cagedb ab \
+            | cdfeb fcadb cdfeb cdbaf"
+            .parse()
+            .unwrap();
+        let decoded = entry.decode().unwrap();
+        assert_eq!(decoded.digits, vec![5, 3, 5, 3]);
+        assert_eq!(decoded.value, 5353);
+        let wiring: HashMap<char, char> = "deafgbc".chars().zip("abcdefg".chars()).collect();
+        assert_eq!(decoded.wiring, wiring);
+        assert_eq!(
+            decoded.render(&Encoding::decimal()),
+            " _   _   _   _\n\
+             |_   _| |_   _|\n \
+              _|  _|  _|  _|\n"
+        );
+
+        let json = serde_json::to_string(&decoded).unwrap();
+        assert_eq!(serde_json::from_str::<Decoded>(&json).unwrap(), decoded);
+
+        // Patterns for 1, 4, 7 and 8 only settle wire a
+        let entry: Connections = "cf acf abcdefg bcdf | cf acf cf abcdefg".parse().unwrap();
+        let decoded = entry.decode().unwrap();
+        assert_eq!(decoded.value, 1718);
+        assert_eq!(decoded.wiring, HashMap::from([('a', 'a')]));
+
+        assert_eq!(
+            "cf acf abcdefg | cf acf abdfg abcdefg"
+                .parse::<Connections>()
+                .unwrap()
+                .decode(),
+            Err(Undecodable::Ambiguous(vec![1728, 1758]))
+        );
+    }
+
+    #[test]
+    fn test_bad_five_patterns() {
+        // No 3 among the five-segment patterns, and every pattern the same digit
+        for line in [
+            "abcde abcdf abcdg | ab ab ab ab",
+            "ab abc abcd abcdefg abcde abcdf abcdg abcdef abcdeg abcdfg | ab ab ab ab",
+            "ab ac ad ae af ag bc bd be bf | ab ab ab ab",
+        ] {
+            let entry: Connections = line.parse().unwrap();
+            let mut possibilities = Possibilities::new(&entry);
+            assert!(!possibilities.solve(), "{line}");
+            assert!(entry.decode().is_err(), "{line}");
+        }
+    }
+}
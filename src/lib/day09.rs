@@ -0,0 +1,411 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::io::BufRead;
+use std::str::FromStr;
+
+use anyhow::{anyhow, ensure};
+use parse_display::Display;
+use tracing::info_span;
+
+use crate::parse;
+use crate::render::Canvas;
+use crate::solution::Solved;
+use crate::union_find::UnionFind;
+
+/// Which locations count as neighbors
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Display, clap::ValueEnum)]
+#[display(style = "kebab-case")]
+pub enum Neighborhood {
+    /// The 4 locations up, down, left and right, as in the puzzle
+    #[default]
+    VonNeumann,
+    /// Those 4, and the 4 diagonals
+    Moore,
+}
+
+impl Neighborhood {
+    /// The offsets to each neighbor
+    pub fn offsets(self) -> &'static [(isize, isize)] {
+        match self {
+            Neighborhood::VonNeumann => &[(-1, 0), (1, 0), (0, -1), (0, 1)],
+            Neighborhood::Moore => &[
+                (-1, -1),
+                (-1, 0),
+                (-1, 1),
+                (0, -1),
+                (0, 1),
+                (1, -1),
+                (1, 0),
+                (1, 1),
+            ],
+        }
+    }
+}
+
+/// Labels a basin; basins are numbered from 0 in the order of their first
+/// location
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct BasinId(pub usize);
+
+/// One row of a heightmap, as read from a line
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Row(Vec<u8>);
+
+impl FromStr for Row {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut row = Vec::with_capacity(s.len());
+        for c in s.chars() {
+            match c {
+                '0'..='9' => {
+                    row.push(c.to_digit(10).unwrap() as u8);
+                }
+
+                _ => return Err(anyhow!("Invalid character: {c}")),
+            }
+        }
+        Ok(Row(row))
+    }
+}
+
+/// A heightmap, or anything else with a value per location. Location
+/// `(x, y)` is row `x`, column `y`; the rows are stored one after another.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Grid<T = u8> {
+    cells: Vec<T>,
+    width: usize,
+    height: usize,
+}
+
+impl<T: Copy> Grid<T> {
+    /// Index into `cells` of a location, if it's in the grid
+    fn index(&self, x: isize, y: isize) -> Option<usize> {
+        if x < 0 || y < 0 || x as usize >= self.height || y as usize >= self.width {
+            return None;
+        }
+        Some(x as usize * self.width + y as usize)
+    }
+
+    pub fn get(&self, x: isize, y: isize) -> Option<T> {
+        self.index(x, y).map(|ix| self.cells[ix])
+    }
+
+    /// Every location and its value, row by row
+    pub fn locations(&self) -> impl Iterator<Item = (usize, usize, T)> + '_ {
+        // max(1), as chunks can't be empty; with no columns there are no cells
+        self.cells
+            .chunks(self.width.max(1))
+            .enumerate()
+            .flat_map(|(x, row)| row.iter().enumerate().map(move |(y, &v)| (x, y, v)))
+    }
+
+    /// Returns an iterator over the neighbors of the given location
+    pub fn neighbors(
+        &self,
+        x: isize,
+        y: isize,
+        neighborhood: Neighborhood,
+    ) -> impl Iterator<Item = (isize, isize, T)> + '_ {
+        neighborhood
+            .offsets()
+            .iter()
+            .map(move |&(dx, dy)| (x + dx, y + dy))
+            .flat_map(|(nx, ny)| self.get(nx, ny).map(|n| (nx, ny, n)))
+    }
+
+    /// Number of rows
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Number of columns
+    pub fn width(&self) -> usize {
+        self.width
+    }
+}
+
+impl Grid {
+    /// Reads a heightmap with a row per line, skipping blank lines. Every row
+    /// must be the same width.
+    pub fn read(buf: impl BufRead) -> anyhow::Result<Self> {
+        let mut cells = Vec::new();
+        let mut width = None;
+        let mut height = 0;
+        for row in parse::lines::<_, Row>(buf) {
+            let Row(row) = row?;
+            let expected = *width.get_or_insert(row.len());
+            ensure!(
+                row.len() == expected,
+                "Row {} has {} locations, expected {expected}",
+                height + 1,
+                row.len()
+            );
+            cells.extend(row);
+            height += 1;
+        }
+        Ok(Grid {
+            cells,
+            width: width.unwrap_or_default(),
+            height,
+        })
+    }
+
+    /// Returns a list of (x, y, value) tuples for all the minima in the grid.
+    pub fn minima(&self, neighborhood: Neighborhood) -> Vec<(usize, usize, u8)> {
+        self.locations()
+            .filter(|&(x, y, value)| {
+                self.neighbors(x as isize, y as isize, neighborhood)
+                    .all(|(_, _, n)| n > value)
+            })
+            .collect()
+    }
+
+    /// Returns the sum of the risk levels of all the minima in the grid
+    pub fn risk_sum(&self, neighborhood: Neighborhood) -> i64 {
+        self.minima(neighborhood)
+            .iter()
+            .map(|&(_, _, v)| v as i64 + 1)
+            .sum()
+    }
+
+    /// Groups every location that isn't a 9 with its neighbors, so each
+    /// basin ends up as one set
+    pub fn basins(&self, neighborhood: Neighborhood) -> UnionFind<(usize, usize)> {
+        let mut basins = UnionFind::new();
+        for (x, y, value) in self.locations() {
+            if value == 9 {
+                continue;
+            }
+            basins.insert((x, y));
+            // Neighbors before this one are already in, and will be
+            // joined to those after when we get to them
+            for (nx, ny, n) in self.neighbors(x as isize, y as isize, neighborhood) {
+                let before = (nx as usize, ny as usize) < (x, y);
+                if before && n != 9 {
+                    basins.union((x, y), (nx as usize, ny as usize));
+                }
+            }
+        }
+        basins
+    }
+
+    /// The size of the basin around each minimum, in the order of `minima`.
+    /// Basins with a flat bottom have no minimum, and are left out; see
+    /// [`Grid::all_basin_sizes`].
+    pub fn basin_sizes(&self, neighborhood: Neighborhood) -> Vec<usize> {
+        let mut basins = self.basins(neighborhood);
+        self.minima(neighborhood)
+            .iter()
+            .map(|&(x, y, _)| basins.set_size(&(x, y)))
+            .collect()
+    }
+
+    /// The size of every basin, in the order of their first location, found
+    /// in one pass over the grid
+    pub fn all_basin_sizes(&self, neighborhood: Neighborhood) -> Vec<usize> {
+        self.basins(neighborhood).set_sizes()
+    }
+
+    /// The product of the sizes of the three largest basins
+    pub fn basin_max_product(&self, neighborhood: Neighborhood) -> i64 {
+        let mut sizes = self.all_basin_sizes(neighborhood);
+        sizes.sort_unstable();
+
+        sizes.iter().rev().take(3).map(|&n| n as i64).product()
+    }
+
+    /// The basin each location is in, or `None` for the 9s between them
+    pub fn basin_labels(&self, neighborhood: Neighborhood) -> Grid<Option<BasinId>> {
+        let mut basins = self.basins(neighborhood);
+        let mut ids: HashMap<(usize, usize), BasinId> = HashMap::new();
+        let cells = self
+            .locations()
+            .map(|(x, y, _)| {
+                let &root = basins.find(&(x, y))?;
+                let next = BasinId(ids.len());
+                Some(*ids.entry(root).or_insert(next))
+            })
+            .collect();
+        Grid {
+            cells,
+            width: self.width,
+            height: self.height,
+        }
+    }
+
+    /// A canvas of the heightmap, with the 9s separating basins left blank
+    pub fn canvas(&self) -> Canvas {
+        // The canvas's x is our column
+        Canvas::from_fn(self.width, self.height, |x, y| {
+            match self.cells[y * self.width + x] {
+                9 => 0,
+                h => h as usize + 1,
+            }
+        })
+    }
+}
+
+/// Letters for basins in [`Grid::basin_labels`] maps, repeating as needed
+const BASIN_LETTERS: &[u8] = b"abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ";
+
+impl Grid<Option<BasinId>> {
+    /// A canvas with each basin its own label, for [`Palette::categorical`],
+    /// and the 9s between them left blank
+    ///
+    /// [`Palette::categorical`]: crate::render::Palette::categorical
+    pub fn canvas(&self) -> Canvas {
+        // The canvas's x is our column
+        Canvas::from_fn(self.width, self.height, |x, y| {
+            self.cells[y * self.width + x].map_or(0, |BasinId(id)| id + 1)
+        })
+    }
+}
+
+/// A map of the basins, a letter each, with `.` for the 9s between them
+impl fmt::Display for Grid<Option<BasinId>> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for row in self.cells.chunks(self.width.max(1)) {
+            for label in row {
+                let c = match label {
+                    Some(BasinId(id)) => BASIN_LETTERS[id % BASIN_LETTERS.len()] as char,
+                    None => '.',
+                };
+                write!(f, "{c}")?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+/// Solves both parts of the puzzle for the given input
+pub fn solve(input: &str) -> anyhow::Result<Solved> {
+    let grid = info_span!("parse").in_scope(|| Grid::read(input.as_bytes()))?;
+    let risk = info_span!("part1").in_scope(|| grid.risk_sum(Neighborhood::VonNeumann));
+    let product = info_span!("part2").in_scope(|| grid.basin_max_product(Neighborhood::VonNeumann));
+    Ok(Solved::new(risk, product))
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// Tests
+
+#[cfg(test)]
+mod tests {
+    use test_log::test;
+
+    #[allow(unused_imports)]
+    use super::*;
+
+    const EXAMPLE: &str = r###"
+        2199943210
+        3987894921
+        9856789892
+        8767896789
+        9899965678
+    "###;
+
+    #[test]
+    fn test_basic() {
+        let grid = Grid::read(EXAMPLE.as_bytes()).unwrap();
+        assert_eq!((grid.width(), grid.height()), (10, 5));
+
+        let minima: Vec<u8> = grid
+            .minima(Neighborhood::VonNeumann)
+            .iter()
+            .map(|&(_, _, v)| v)
+            .collect();
+
+        assert_eq!(minima, vec![1, 0, 5, 5]);
+        assert_eq!(grid.risk_sum(Neighborhood::VonNeumann), 15);
+    }
+
+    #[test]
+    fn test_basins() {
+        let grid = Grid::read(EXAMPLE.as_bytes()).unwrap();
+        let sizes = grid.basin_sizes(Neighborhood::VonNeumann);
+        assert_eq!(sizes, vec![3, 9, 14, 9]);
+        assert_eq!(grid.basins(Neighborhood::VonNeumann).set_count(), 4);
+        assert_eq!(
+            grid.all_basin_sizes(Neighborhood::VonNeumann),
+            vec![3, 9, 14, 9]
+        );
+        assert_eq!(grid.basin_max_product(Neighborhood::VonNeumann), 1134);
+    }
+
+    #[test]
+    fn test_flat_basins() {
+        // Neither basin has a single lowest point
+        let grid = Grid::read("1191\n9999\n5599\n5599\n".as_bytes()).unwrap();
+        assert!(grid
+            .minima(Neighborhood::VonNeumann)
+            .iter()
+            .all(|&(_, _, v)| v == 1));
+        assert_eq!(grid.basin_sizes(Neighborhood::VonNeumann), vec![1]);
+        assert_eq!(
+            grid.all_basin_sizes(Neighborhood::VonNeumann),
+            vec![2, 1, 4]
+        );
+        assert_eq!(grid.basin_max_product(Neighborhood::VonNeumann), 8);
+    }
+
+    #[test]
+    fn test_basin_labels() {
+        let grid = Grid::read(EXAMPLE.as_bytes()).unwrap();
+        let labels = grid.basin_labels(Neighborhood::VonNeumann);
+        assert_eq!((labels.width(), labels.height()), (10, 5));
+        assert_eq!(labels.get(0, 0), Some(Some(BasinId(0))));
+        assert_eq!(labels.get(0, 2), Some(None));
+        assert_eq!(labels.get(4, 9), Some(Some(BasinId(3))));
+        assert_eq!(
+            labels.to_string(),
+            "aa...bbbbb\n\
+             a.ccc.b.bb\n\
+             .ccccc.d.b\n\
+             ccccc.ddd.\n\
+             .c...ddddd\n"
+        );
+
+        let canvas = labels.canvas();
+        assert_eq!(
+            (canvas.get(0, 0), canvas.get(2, 0), canvas.get(9, 4)),
+            (1, 0, 4)
+        );
+    }
+
+    #[test]
+    fn test_moore() {
+        let grid = Grid::read(EXAMPLE.as_bytes()).unwrap();
+        let minima = grid.minima(Neighborhood::Moore);
+        assert_eq!(minima, vec![(0, 1, 1), (0, 9, 0), (2, 2, 5), (4, 6, 5)]);
+        assert_eq!(grid.risk_sum(Neighborhood::Moore), 15);
+        assert_eq!(grid.neighbors(0, 0, Neighborhood::Moore).count(), 3);
+        assert_eq!(grid.neighbors(1, 1, Neighborhood::Moore).count(), 8);
+
+        // The diagonals join every basin into one
+        assert_eq!(grid.all_basin_sizes(Neighborhood::Moore), vec![35]);
+        assert_eq!(grid.basin_sizes(Neighborhood::Moore), vec![35; 4]);
+        assert_eq!(grid.basin_max_product(Neighborhood::Moore), 35);
+    }
+
+    #[test]
+    fn test_read() {
+        let grid = Grid::read("\n  123\n\n456\n".as_bytes()).unwrap();
+        assert_eq!((grid.width(), grid.height()), (3, 2));
+        assert_eq!(grid.get(1, 2), Some(6));
+        assert_eq!(grid.get(2, 0), None);
+        assert_eq!(grid.get(0, 3), None);
+        assert_eq!(grid.get(-1, 0), None);
+        let values: Vec<u8> = grid.locations().map(|(_, _, v)| v).collect();
+        assert_eq!(values, vec![1, 2, 3, 4, 5, 6]);
+
+        let err = Grid::read("123\n4567\n".as_bytes()).unwrap_err();
+        assert_eq!(err.to_string(), "Row 2 has 4 locations, expected 3");
+        assert!(Grid::read("12a\n".as_bytes()).is_err());
+
+        let empty = Grid::read("".as_bytes()).unwrap();
+        assert_eq!((empty.width(), empty.height()), (0, 0));
+        assert_eq!(empty.locations().count(), 0);
+        assert_eq!(empty.basin_labels(Neighborhood::VonNeumann).to_string(), "");
+    }
+}
@@ -0,0 +1,335 @@
+use std::fmt::{self, Display};
+
+use anyhow::ensure;
+use serde::{Deserialize, Serialize};
+use tracing::{debug, info_span};
+
+use crate::bits::BitReader;
+use crate::parse;
+use crate::solution::Solved;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct Literal(u64);
+
+impl Literal {
+    /// Reads a literal's value, in groups of 4 bits each prefixed by a
+    /// continuation bit
+    pub fn decode(reader: &mut BitReader) -> anyhow::Result<Literal> {
+        let mut n = 0u64;
+        for groups in 1.. {
+            ensure!(
+                groups <= 16,
+                "Literal too long at position {}",
+                reader.position()
+            );
+            let more = reader.read_bit()?;
+            n = (n << 4) | reader.read_bits(4)?;
+            if !more {
+                break;
+            }
+        }
+
+        Ok(Literal(n))
+    }
+}
+
+impl Display for Literal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct Packet {
+    pub version: u8,
+    pub payload: Payload,
+}
+
+/// Reads a packet's 3-bit version and 3-bit type
+pub fn decode_header(reader: &mut BitReader) -> anyhow::Result<(u8, u8)> {
+    Ok((reader.read_bits(3)? as u8, reader.read_bits(3)? as u8))
+}
+
+impl Packet {
+    /// Reads one packet, including any sub-packets, leaving the reader just
+    /// past its end
+    pub fn decode(reader: &mut BitReader) -> anyhow::Result<Packet> {
+        let (version, typ) = decode_header(reader)?;
+        if typ == 4 {
+            return Ok(Packet {
+                version,
+                payload: Payload::Literal(Literal::decode(reader)?),
+            });
+        }
+
+        // It's an operator
+        let mut components = Vec::new();
+        if reader.read_bit()? {
+            // sub-packets
+            let n = reader.read_bits(11)?;
+            debug!("Operator (sub-packets): {version} {typ} {n}");
+            for _ in 0..n {
+                components.push(Packet::decode(reader)?);
+            }
+        } else {
+            let n = reader.read_bits(15)? as usize;
+            debug!("Operator (bits):        {version} {typ} {n}");
+            ensure!(
+                n <= reader.remaining(),
+                "Operator at position {} needs {n} bits, only {} left",
+                reader.position(),
+                reader.remaining()
+            );
+            let end = reader.position() + n;
+            while reader.position() < end {
+                components.push(Packet::decode(reader)?);
+            }
+            ensure!(
+                reader.position() == end,
+                "Sub-packets overran their operator's length at position {end}"
+            );
+        }
+
+        Ok(Packet {
+            version,
+            payload: Payload::Operator(Operator { typ, components }),
+        })
+    }
+
+    pub fn version_sum(&self) -> u64 {
+        self.version as u64
+            + match &self.payload {
+                Payload::Literal(_) => 0,
+                Payload::Operator(o) => o.components.iter().map(|c| c.version_sum()).sum(),
+            }
+    }
+
+    pub fn evaluate(&self) -> i64 {
+        let (t, c) = match self.payload {
+            Payload::Literal(Literal(n)) => return n as i64,
+            Payload::Operator(Operator {
+                typ: t,
+                components: ref c,
+            }) => (t, c),
+        };
+
+        let mut inner_values = c.iter().map(|c| c.evaluate());
+        let (l, r) = match t {
+            0 => return inner_values.sum(),
+            1 => return inner_values.product(),
+            2 => return inner_values.min().unwrap_or(0),
+            3 => return inner_values.max().unwrap_or(0),
+            5..=7 => (inner_values.next().unwrap(), inner_values.next().unwrap()),
+            _ => panic!("Invalid operator type: {}", t),
+        };
+
+        let found = match t {
+            5 => l > r,
+            6 => l < r,
+            7 => l == r,
+            _ => unreachable!(),
+        };
+
+        found as i64
+    }
+}
+
+impl Display for Packet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "P{}:{}", self.version, self.payload)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum Payload {
+    Literal(Literal),
+    Operator(Operator),
+}
+
+impl Display for Payload {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Payload::Literal(l) => write!(f, "L{}", l),
+            Payload::Operator(o) => write!(f, "O{}", o),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct Operator {
+    typ: u8,
+    components: Vec<Packet>,
+}
+
+impl Display for Operator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:[", self.typ)?;
+        for (ix, c) in self.components.iter().enumerate() {
+            if ix > 0 {
+                write!(f, ",")?;
+            }
+            write!(f, "{}", c)?;
+        }
+
+        write!(f, "]")
+    }
+}
+
+/// Solves both parts of the puzzle for the given input
+pub fn solve(input: &str) -> anyhow::Result<Solved> {
+    let mut reader: BitReader = info_span!("parse").in_scope(|| parse::strict(input))?;
+    let packet = info_span!("decode").in_scope(|| Packet::decode(&mut reader))?;
+    let vs = info_span!("part1").in_scope(|| packet.version_sum());
+    let value = info_span!("part2").in_scope(|| packet.evaluate());
+    Ok(Solved::new(vs, value))
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// Tests
+
+#[cfg(test)]
+mod tests {
+    use test_log::test;
+
+    #[allow(unused_imports)]
+    use super::*;
+
+    #[test]
+    fn test_basic() {
+        let example = r"D2FE28";
+        let mut reader: BitReader = example.parse().unwrap();
+        let (v, t) = decode_header(&mut reader).unwrap();
+        assert_eq!((v, t), (6, 4));
+        let lit = Literal::decode(&mut reader).unwrap();
+        assert_eq!(lit, Literal(2021));
+        assert_eq!(reader.position(), 21);
+        assert!(reader.remainder_zero());
+
+        // 17 groups of 4 bits is too long for a u64
+        let mut reader = BitReader::new([0xFF; 12]);
+        let err = Literal::decode(&mut reader).unwrap_err();
+        assert!(err.to_string().contains("too long"), "{err}");
+    }
+
+    #[test]
+    fn test_packets() {
+        let example2 = r"38006F45291200";
+        let mut reader: BitReader = example2.parse().unwrap();
+
+        let pkt = Packet::decode(&mut reader).unwrap();
+        assert!(reader.remainder_zero());
+        assert_eq!(
+            pkt,
+            Packet {
+                version: 1,
+                payload: Payload::Operator(Operator {
+                    typ: 6,
+                    components: vec![
+                        Packet {
+                            version: 6,
+                            payload: Payload::Literal(Literal(10))
+                        },
+                        Packet {
+                            version: 2,
+                            payload: Payload::Literal(Literal(20))
+                        }
+                    ]
+                })
+            }
+        );
+
+        let packet_str = format!("{}", pkt);
+        assert_eq!(packet_str, "P1:O6:[P6:L10,P2:L20]");
+
+        let example3 = r"EE00D40C823060";
+        let mut reader: BitReader = example3.parse().unwrap();
+
+        let pkt = Packet::decode(&mut reader).unwrap();
+        assert!(reader.remainder_zero());
+        assert_eq!(
+            pkt,
+            Packet {
+                version: 7,
+                payload: Payload::Operator(Operator {
+                    typ: 3,
+                    components: vec![
+                        Packet {
+                            version: 2,
+                            payload: Payload::Literal(Literal(1))
+                        },
+                        Packet {
+                            version: 4,
+                            payload: Payload::Literal(Literal(2))
+                        },
+                        Packet {
+                            version: 1,
+                            payload: Payload::Literal(Literal(3))
+                        }
+                    ]
+                })
+            }
+        );
+
+        let packet_str = format!("{}", pkt);
+        assert_eq!(packet_str, "P7:O3:[P2:L1,P4:L2,P1:L3]");
+
+        let example4 = r"8A004A801A8002F478";
+        let mut reader: BitReader = example4.parse().unwrap();
+
+        let pkt = Packet::decode(&mut reader).unwrap();
+        assert!(reader.remainder_zero());
+        let packet_str = format!("{}", pkt);
+        assert_eq!(packet_str, "P4:O2:[P1:O2:[P5:O2:[P6:L15]]]");
+        assert_eq!(pkt.version_sum(), 16);
+
+        let example4 = r"620080001611562C8802118E34";
+        let mut reader: BitReader = example4.parse().unwrap();
+        let pkt = Packet::decode(&mut reader).unwrap();
+        assert!(reader.remainder_zero());
+        assert_eq!(pkt.version_sum(), 12);
+
+        let example4 = r"C0015000016115A2E0802F182340";
+        let mut reader: BitReader = example4.parse().unwrap();
+        let pkt = Packet::decode(&mut reader).unwrap();
+        assert!(reader.remainder_zero());
+        assert_eq!(pkt.version_sum(), 23);
+
+        let example4 = r"A0016C880162017C3686B18A3D4780";
+        let mut reader: BitReader = example4.parse().unwrap();
+        let pkt = Packet::decode(&mut reader).unwrap();
+        assert!(reader.remainder_zero());
+        assert_eq!(pkt.version_sum(), 31);
+    }
+
+    #[test]
+    fn test_evaluate() {
+        let examples: Vec<(&str, i64)> = vec![
+            ("C200B40A82", 3),
+            ("04005AC33890", 54),
+            ("880086C3E88112", 7),
+            ("CE00C43D881120", 9),
+            ("D8005AC2A8F0", 1),
+            ("F600BC2D8F", 0),
+            ("9C005AC2F8F0", 0),
+            ("9C0141080250320F1802104A08", 1),
+        ];
+
+        for (n, &(s, expected)) in examples.iter().enumerate() {
+            let mut reader: BitReader = s.parse().unwrap();
+            let pkt = Packet::decode(&mut reader).unwrap();
+            assert!(reader.remainder_zero());
+            assert_eq!(pkt.evaluate(), expected, "Failed example {n}: {s}");
+        }
+    }
+
+    #[test]
+    fn test_json() {
+        let mut reader: BitReader = "9C0141080250320F1802104A08".parse().unwrap();
+        let pkt = Packet::decode(&mut reader).unwrap();
+
+        let json = serde_json::to_string(&pkt).unwrap();
+        let reloaded: Packet = serde_json::from_str(&json).unwrap();
+        assert_eq!(reloaded, pkt);
+        assert_eq!(reloaded.evaluate(), 1);
+    }
+}
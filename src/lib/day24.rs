@@ -0,0 +1,592 @@
+use std::fmt::Display;
+use std::str::FromStr;
+
+use anyhow::{anyhow, ensure, Context};
+use tracing::{debug, info_span};
+
+use crate::nom::strict;
+use crate::parse;
+use crate::solution::Solved;
+
+mod parser {
+    use crate::nom::*;
+
+    use super::{Instruction, Operand, Program, Register};
+
+    pub fn register(input: &str) -> IResult<'_, Register> {
+        alt((
+            value(Register::W, char('w')),
+            value(Register::X, char('x')),
+            value(Register::Y, char('y')),
+            value(Register::Z, char('z')),
+        ))(input)
+    }
+
+    pub fn operand(input: &str) -> IResult<'_, Operand> {
+        alt((map(register, Operand::Register), map(int, Operand::Value)))(input)
+    }
+
+    fn binary<'a>(
+        name: &'static str,
+        f: fn(Register, Operand) -> Instruction,
+    ) -> impl FnMut(&'a str) -> IResult<'a, Instruction> {
+        map(
+            tuple((tag(name), char(' '), register, char(' '), operand)),
+            move |(_, _, r, _, o)| f(r, o),
+        )
+    }
+
+    pub fn instruction(input: &str) -> IResult<'_, Instruction> {
+        alt((
+            map(preceded(tag("inp "), register), Instruction::Inp),
+            binary("add", Instruction::Add),
+            binary("mul", Instruction::Mul),
+            binary("div", Instruction::Div),
+            binary("mod", Instruction::Mod),
+            binary("eql", Instruction::Eql),
+        ))(input)
+    }
+
+    pub fn program(input: &str) -> IResult<'_, Program> {
+        map(
+            delimited(ws, separated_list1(newline_ws, instruction), ws),
+            Program,
+        )(input)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Register {
+    W,
+    X,
+    Y,
+    Z,
+}
+
+impl Register {
+    fn index(self) -> usize {
+        match self {
+            Register::W => 0,
+            Register::X => 1,
+            Register::Y => 2,
+            Register::Z => 3,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Operand {
+    Register(Register),
+    Value(i64),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Instruction {
+    Inp(Register),
+    Add(Register, Operand),
+    Mul(Register, Operand),
+    Div(Register, Operand),
+    Mod(Register, Operand),
+    Eql(Register, Operand),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Program(Vec<Instruction>);
+
+impl FromStr for Program {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        strict(s, parser::program)
+    }
+}
+
+impl Program {
+    /// Runs the program on a fresh ALU with the given inputs, returning the
+    /// final register state.
+    pub fn run<I: IntoIterator<Item = i64>>(&self, inputs: I) -> anyhow::Result<Alu> {
+        let mut alu = Alu::default();
+        alu.run(&self.0, inputs)?;
+        Ok(alu)
+    }
+
+    /// Splits the program into blocks, each starting with an `inp` instruction.
+    pub fn blocks(&self) -> Vec<&[Instruction]> {
+        let mut blocks = Vec::new();
+        let mut start = 0;
+        for (ix, inst) in self.0.iter().enumerate() {
+            if ix > start && matches!(inst, Instruction::Inp(_)) {
+                blocks.push(&self.0[start..ix]);
+                start = ix;
+            }
+        }
+        if start < self.0.len() {
+            blocks.push(&self.0[start..]);
+        }
+
+        blocks
+    }
+
+    /// Returns true if the given model number leaves 0 in the z register.
+    pub fn validate(&self, digits: &[i64]) -> anyhow::Result<bool> {
+        let alu = self.run(digits.iter().copied())?;
+        Ok(alu.get(Register::Z) == 0)
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Alu {
+    registers: [i64; 4],
+}
+
+impl Alu {
+    pub fn get(&self, register: Register) -> i64 {
+        self.registers[register.index()]
+    }
+
+    fn value(&self, operand: Operand) -> i64 {
+        match operand {
+            Operand::Register(r) => self.get(r),
+            Operand::Value(n) => n,
+        }
+    }
+
+    /// `op` of `r` and `o`, or an error if it overflows
+    fn checked(
+        &self,
+        instruction: Instruction,
+        r: Register,
+        o: Operand,
+        op: fn(i64, i64) -> Option<i64>,
+    ) -> anyhow::Result<i64> {
+        let (a, b) = (self.get(r), self.value(o));
+        op(a, b).ok_or_else(|| anyhow!("Overflow with {a} and {b} in {instruction:?}"))
+    }
+
+    pub fn execute(
+        &mut self,
+        instruction: Instruction,
+        inputs: &mut impl Iterator<Item = i64>,
+    ) -> anyhow::Result<()> {
+        let (r, result) = match instruction {
+            Instruction::Inp(r) => (r, inputs.next().ok_or(anyhow!("Ran out of input"))?),
+            Instruction::Add(r, o) => (r, self.checked(instruction, r, o, i64::checked_add)?),
+            Instruction::Mul(r, o) => (r, self.checked(instruction, r, o, i64::checked_mul)?),
+            Instruction::Div(r, o) => {
+                if self.value(o) == 0 {
+                    return Err(anyhow!("Division by zero in {instruction:?}"));
+                }
+                // Only i64::MIN / -1 overflows
+                (r, self.checked(instruction, r, o, i64::checked_div)?)
+            }
+            Instruction::Mod(r, o) => {
+                let (a, b) = (self.get(r), self.value(o));
+                if a < 0 || b <= 0 {
+                    return Err(anyhow!("Invalid modulus {a} % {b} in {instruction:?}"));
+                }
+                (r, a % b)
+            }
+            Instruction::Eql(r, o) => (r, (self.get(r) == self.value(o)) as i64),
+        };
+
+        self.registers[r.index()] = result;
+        Ok(())
+    }
+
+    pub fn run<I: IntoIterator<Item = i64>>(
+        &mut self,
+        instructions: &[Instruction],
+        inputs: I,
+    ) -> anyhow::Result<()> {
+        let mut inputs = inputs.into_iter();
+        for &instruction in instructions {
+            self.execute(instruction, &mut inputs)?;
+        }
+        Ok(())
+    }
+}
+
+impl Display for Alu {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let [w, x, y, z] = self.registers;
+        write!(f, "w={w} x={x} y={y} z={z}")
+    }
+}
+
+// The MONAD program is made of 14 near-identical blocks, each of which differ
+// only in three constants:
+//
+//     inp w
+//     mul x 0
+//     add x z
+//     mod x 26
+//     div z {divisor}
+//     add x {check}
+//     eql x w
+//     eql x 0
+//     mul y 0
+//     add y 25
+//     mul y x
+//     add y 1
+//     mul z y
+//     mul y 0
+//     add y w
+//     add y {offset}
+//     mul y x
+//     add z y
+//
+// Treating z as a stack of base-26 digits, blocks with divisor 1 always push
+// `w + offset`, and blocks with divisor 26 pop the top value and push again
+// unless `popped + check == w`. For z to end at 0, every pop must match its
+// push, which pairs up the digits.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Block {
+    pub divisor: i64,
+    pub check: i64,
+    pub offset: i64,
+}
+
+impl Block {
+    pub fn from_instructions(instructions: &[Instruction]) -> anyhow::Result<Self> {
+        use Instruction::*;
+        use Operand::Value as V;
+        use Register::*;
+
+        let reg = Operand::Register;
+        if instructions.len() != 18 {
+            return Err(anyhow!(
+                "Expected 18 instructions in block, found {}",
+                instructions.len()
+            ));
+        }
+
+        let (divisor, check, offset) = match (instructions[4], instructions[5], instructions[15]) {
+            (Div(Z, V(d)), Add(X, V(c)), Add(Y, V(o))) => (d, c, o),
+            (d, c, o) => return Err(anyhow!("Unexpected block constants: {d:?}, {c:?}, {o:?}")),
+        };
+
+        let expected = [
+            Inp(W),
+            Mul(X, V(0)),
+            Add(X, reg(Z)),
+            Mod(X, V(26)),
+            Div(Z, V(divisor)),
+            Add(X, V(check)),
+            Eql(X, reg(W)),
+            Eql(X, V(0)),
+            Mul(Y, V(0)),
+            Add(Y, V(25)),
+            Mul(Y, reg(X)),
+            Add(Y, V(1)),
+            Mul(Z, reg(Y)),
+            Mul(Y, V(0)),
+            Add(Y, reg(W)),
+            Add(Y, V(offset)),
+            Mul(Y, reg(X)),
+            Add(Z, reg(Y)),
+        ];
+
+        for (ix, (&found, &exp)) in instructions.iter().zip(expected.iter()).enumerate() {
+            if found != exp {
+                return Err(anyhow!(
+                    "Instruction {ix}: expected {exp:?}, found {found:?}"
+                ));
+            }
+        }
+
+        if divisor == 1 && check <= 9 {
+            return Err(anyhow!(
+                "Push block has check {check} that could match a digit"
+            ));
+        }
+        if divisor != 1 && divisor != 26 {
+            return Err(anyhow!("Unexpected divisor {divisor}"));
+        }
+
+        Ok(Block {
+            divisor,
+            check,
+            offset,
+        })
+    }
+}
+
+/// A constraint of the form `digits[second] == digits[first] + delta`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Constraint {
+    pub first: usize,
+    pub second: usize,
+    pub delta: i64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Monad {
+    pub blocks: Vec<Block>,
+    pub constraints: Vec<Constraint>,
+}
+
+impl Monad {
+    pub fn new(program: &Program) -> anyhow::Result<Self> {
+        let blocks = program
+            .blocks()
+            .into_iter()
+            .map(Block::from_instructions)
+            .collect::<anyhow::Result<Vec<Block>>>()?;
+
+        let mut stack: Vec<(usize, i64)> = Vec::new();
+        let mut constraints = Vec::new();
+        for (ix, block) in blocks.iter().enumerate() {
+            if block.divisor == 1 {
+                stack.push((ix, block.offset));
+                continue;
+            }
+
+            let (first, offset) = stack
+                .pop()
+                .ok_or(anyhow!("Block {ix} pops from an empty stack"))?;
+            let constraint = Constraint {
+                first,
+                second: ix,
+                delta: offset + block.check,
+            };
+            debug!("Found constraint {constraint:?}");
+            constraints.push(constraint);
+        }
+
+        if !stack.is_empty() {
+            return Err(anyhow!(
+                "Unbalanced program: {} unmatched pushes",
+                stack.len()
+            ));
+        }
+
+        Ok(Monad {
+            blocks,
+            constraints,
+        })
+    }
+
+    fn solve(&self, largest: bool) -> Option<Vec<i64>> {
+        let mut digits = vec![0; self.blocks.len()];
+        for &Constraint {
+            first,
+            second,
+            delta,
+        } in &self.constraints
+        {
+            if delta.abs() > 8 {
+                return None;
+            }
+
+            let d1 = if largest {
+                9.min(9 - delta)
+            } else {
+                1.max(1 - delta)
+            };
+            digits[first] = d1;
+            digits[second] = d1 + delta;
+        }
+
+        Some(digits)
+    }
+
+    /// Returns the digits of the largest valid model number, if any
+    pub fn largest(&self) -> Option<Vec<i64>> {
+        self.solve(true)
+    }
+
+    /// Returns the digits of the smallest valid model number, if any
+    pub fn smallest(&self) -> Option<Vec<i64>> {
+        self.solve(false)
+    }
+}
+
+pub fn to_number(digits: &[i64]) -> i64 {
+    digits.iter().fold(0, |n, &d| n * 10 + d)
+}
+
+/// Solves both parts of the puzzle for the given input
+pub fn solve(input: &str) -> anyhow::Result<Solved> {
+    let program = info_span!("parse").in_scope(|| parse::strict::<Program>(input))?;
+    let monad =
+        info_span!("analyze").in_scope(|| Monad::new(&program).context("analyzing MONAD"))?;
+
+    let largest = info_span!("part1").in_scope(|| monad.largest());
+    let largest = largest.context("No valid model number found")?;
+    let smallest = info_span!("part2").in_scope(|| monad.smallest());
+    let smallest = smallest.context("No valid model number found")?;
+    for digits in [&largest, &smallest] {
+        ensure!(
+            program.validate(digits)?,
+            "Model number {} was rejected by the program",
+            to_number(digits)
+        );
+    }
+    Ok(Solved::new(to_number(&largest), to_number(&smallest)))
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// Tests
+
+#[cfg(test)]
+mod tests {
+    use itertools::Itertools;
+    use test_log::test;
+
+    #[allow(unused_imports)]
+    use super::*;
+
+    const NEGATE: &str = r###"
+        inp x
+        mul x -1
+    "###;
+
+    const TRIPLE: &str = r###"
+        inp z
+        inp x
+        mul z 3
+        eql z x
+    "###;
+
+    const BINARY: &str = r###"
+        inp w
+        add z w
+        mod z 2
+        div w 2
+        add y w
+        mod y 2
+        div w 2
+        add x w
+        mod x 2
+        div w 2
+        mod w 2
+    "###;
+
+    #[test]
+    fn test_parse() {
+        let program: Program = NEGATE.parse().unwrap();
+        assert_eq!(
+            program.0,
+            vec![
+                Instruction::Inp(Register::X),
+                Instruction::Mul(Register::X, Operand::Value(-1))
+            ]
+        );
+
+        let program: Program = TRIPLE.parse().unwrap();
+        assert_eq!(program.0.len(), 4);
+        assert_eq!(
+            program.0[3],
+            Instruction::Eql(Register::Z, Operand::Register(Register::X))
+        );
+
+        assert!("inp q".parse::<Program>().is_err());
+        assert!("add x".parse::<Program>().is_err());
+    }
+
+    #[test]
+    fn test_run() {
+        let program: Program = NEGATE.parse().unwrap();
+        assert_eq!(program.run([7]).unwrap().get(Register::X), -7);
+
+        let program: Program = TRIPLE.parse().unwrap();
+        assert_eq!(program.run([2, 6]).unwrap().get(Register::Z), 1);
+        assert_eq!(program.run([2, 7]).unwrap().get(Register::Z), 0);
+
+        let program: Program = BINARY.parse().unwrap();
+        let alu = program.run([0b1011]).unwrap();
+        assert_eq!(alu.to_string(), "w=1 x=0 y=1 z=1");
+
+        assert!(program.run([]).is_err());
+        let program: Program = "inp x\ndiv x 0".parse().unwrap();
+        assert!(program.run([1]).is_err());
+        let program: Program = "inp x\nmod x 3".parse().unwrap();
+        assert!(program.run([-1]).is_err());
+
+        // Overflow is an error, not a panic
+        let program: Program = "inp x\nadd x 1".parse().unwrap();
+        assert!(program.run([i64::MAX]).is_err());
+        assert_eq!(program.run([-1]).unwrap().get(Register::X), 0);
+        let program: Program = "inp x\nmul x x\nmul x x\nmul x x".parse().unwrap();
+        assert!(program.run([1 << 8]).is_err());
+        assert_eq!(program.run([-2]).unwrap().get(Register::X), 256);
+        let program: Program = "inp x\ndiv x -1".parse().unwrap();
+        assert!(program.run([i64::MIN]).is_err());
+        assert_eq!(program.run([5]).unwrap().get(Register::X), -5);
+    }
+
+    fn monad_block(divisor: i64, check: i64, offset: i64) -> String {
+        format!(
+            "inp w\nmul x 0\nadd x z\nmod x 26\ndiv z {divisor}\nadd x {check}\neql x w\n\
+             eql x 0\nmul y 0\nadd y 25\nmul y x\nadd y 1\nmul z y\nmul y 0\nadd y w\n\
+             add y {offset}\nmul y x\nadd z y\n"
+        )
+    }
+
+    #[test]
+    fn test_blocks() {
+        let source = monad_block(1, 11, 6) + &monad_block(26, -3, 4);
+        let program: Program = source.parse().unwrap();
+        assert_eq!(program.blocks().len(), 2);
+
+        let monad = Monad::new(&program).unwrap();
+        assert_eq!(
+            monad.blocks[0],
+            Block {
+                divisor: 1,
+                check: 11,
+                offset: 6
+            }
+        );
+        assert_eq!(
+            monad.constraints,
+            vec![Constraint {
+                first: 0,
+                second: 1,
+                delta: 3
+            }]
+        );
+
+        let broken: Program = (monad_block(1, 11, 6) + "add z 1\n").parse().unwrap();
+        assert!(Monad::new(&broken).is_err());
+
+        let unbalanced: Program = monad_block(1, 11, 6).parse().unwrap();
+        assert!(Monad::new(&unbalanced).is_err());
+    }
+
+    #[test]
+    fn test_solve_matches_brute_force() {
+        let source = [
+            monad_block(1, 12, 7),
+            monad_block(1, 10, 2),
+            monad_block(26, -5, 13),
+            monad_block(26, -10, 9),
+        ]
+        .concat();
+        let program: Program = source.parse().unwrap();
+        let monad = Monad::new(&program).unwrap();
+
+        let valid: Vec<Vec<i64>> = (0..4)
+            .map(|_| 1..=9i64)
+            .multi_cartesian_product()
+            .filter(|digits| program.validate(digits).unwrap())
+            .collect();
+        assert!(!valid.is_empty());
+
+        let largest = monad.largest().unwrap();
+        let smallest = monad.smallest().unwrap();
+        assert_eq!(&largest, valid.last().unwrap());
+        assert_eq!(&smallest, valid.first().unwrap());
+        assert_eq!(to_number(&largest), 9966);
+        assert_eq!(to_number(&smallest), 4411);
+    }
+
+    #[test]
+    fn test_unsolvable() {
+        let source = monad_block(1, 12, 9) + &monad_block(26, 5, 0);
+        let program: Program = source.parse().unwrap();
+        let monad = Monad::new(&program).unwrap();
+        assert_eq!(monad.largest(), None);
+        assert_eq!(monad.smallest(), None);
+    }
+}
@@ -0,0 +1,296 @@
+use std::fmt::Display;
+use std::str::FromStr;
+
+use anyhow::anyhow;
+use tracing::{debug, info_span};
+
+use crate::parse;
+use crate::solution::Solved;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Cell {
+    Empty,
+    East,
+    South,
+}
+
+impl Cell {
+    pub fn char(self) -> char {
+        match self {
+            Cell::Empty => '.',
+            Cell::East => '>',
+            Cell::South => 'v',
+        }
+    }
+}
+
+impl TryFrom<char> for Cell {
+    type Error = anyhow::Error;
+
+    fn try_from(c: char) -> Result<Self, Self::Error> {
+        match c {
+            '.' => Ok(Cell::Empty),
+            '>' => Ok(Cell::East),
+            'v' => Ok(Cell::South),
+            _ => Err(anyhow!("Unexpected character '{c}'")),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Seafloor {
+    width: usize,
+    height: usize,
+    cells: Vec<Cell>,
+    // Scratch space for the next state, so that moves are simultaneous
+    buffer: Vec<Cell>,
+}
+
+impl FromStr for Seafloor {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut cells = Vec::new();
+        let mut width = 0;
+        let mut height = 0;
+        for line in s.lines() {
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+
+            let row = line
+                .chars()
+                .map(Cell::try_from)
+                .collect::<anyhow::Result<Vec<Cell>>>()?;
+            if height > 0 && row.len() != width {
+                return Err(anyhow!(
+                    "Row {height} has length {}, expected {width}",
+                    row.len()
+                ));
+            }
+            width = row.len();
+            height += 1;
+            cells.extend(row);
+        }
+
+        if cells.is_empty() {
+            return Err(anyhow!("Empty seafloor"));
+        }
+
+        Ok(Seafloor {
+            width,
+            height,
+            buffer: cells.clone(),
+            cells,
+        })
+    }
+}
+
+impl Seafloor {
+    pub fn get(&self, x: usize, y: usize) -> Cell {
+        self.cells[y * self.width + x]
+    }
+
+    // Moves every cucumber of the given herd that has space in front of it,
+    // returning how many moved
+    fn move_herd(&mut self, herd: Cell) -> usize {
+        let (dx, dy) = match herd {
+            Cell::East => (1, 0),
+            Cell::South => (0, 1),
+            Cell::Empty => return 0,
+        };
+
+        self.buffer.copy_from_slice(&self.cells);
+        let mut moved = 0;
+        for y in 0..self.height {
+            for x in 0..self.width {
+                if self.get(x, y) != herd {
+                    continue;
+                }
+                let nx = (x + dx) % self.width;
+                let ny = (y + dy) % self.height;
+                if self.get(nx, ny) != Cell::Empty {
+                    continue;
+                }
+                self.buffer[y * self.width + x] = Cell::Empty;
+                self.buffer[ny * self.width + nx] = herd;
+                moved += 1;
+            }
+        }
+
+        std::mem::swap(&mut self.cells, &mut self.buffer);
+        moved
+    }
+
+    /// Take a step: first the east-facing herd moves, then the south-facing
+    /// herd. Returns the number of sea cucumbers that moved.
+    pub fn step(&mut self) -> usize {
+        self.move_herd(Cell::East) + self.move_herd(Cell::South)
+    }
+
+    /// Steps until nothing moves, returning the number of the first step on
+    /// which no sea cucumber moved.
+    pub fn settle(&mut self) -> usize {
+        self.settle_with(|_, _| {})
+    }
+
+    /// Like `settle`, but calls `on_step` with the step number and the new
+    /// state after each step.
+    pub fn settle_with(&mut self, mut on_step: impl FnMut(usize, &Self)) -> usize {
+        for n in 1.. {
+            let moved = self.step();
+            debug!("Step {n}: {moved} moved");
+            on_step(n, self);
+            if moved == 0 {
+                return n;
+            }
+        }
+        unreachable!()
+    }
+}
+
+impl Display for Seafloor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for row in self.cells.chunks(self.width) {
+            for &cell in row {
+                write!(f, "{}", cell.char())?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+/// Solves the puzzle for the given input; day 25 has only one part
+pub fn solve(input: &str) -> anyhow::Result<Solved> {
+    let mut seafloor = info_span!("parse").in_scope(|| parse::strict::<Seafloor>(input))?;
+    let steps = info_span!("part1").in_scope(|| seafloor.settle());
+    Ok(Solved::part1_only(steps))
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// Tests
+
+#[cfg(test)]
+mod tests {
+    use test_log::test;
+
+    #[allow(unused_imports)]
+    use super::*;
+
+    fn trimmed(s: &str) -> String {
+        s.trim()
+            .lines()
+            .map(|l| format!("{}\n", l.trim()))
+            .collect()
+    }
+
+    #[test]
+    fn test_line() {
+        let mut seafloor: Seafloor = "...>>>>>...".parse().unwrap();
+        assert_eq!(seafloor.step(), 1);
+        assert_eq!(seafloor.to_string(), "...>>>>.>..\n");
+        assert_eq!(seafloor.step(), 2);
+        assert_eq!(seafloor.to_string(), "...>>>.>.>.\n");
+    }
+
+    #[test]
+    fn test_wrap() {
+        let mut seafloor: Seafloor = r###"
+            ..........
+            .>v....v..
+            .......>..
+            ..........
+        "###
+        .parse()
+        .unwrap();
+
+        seafloor.step();
+        let expected = r###"
+            ..........
+            .>........
+            ..v....v>.
+            ..........
+        "###;
+        assert_eq!(seafloor.to_string(), trimmed(expected));
+
+        let mut seafloor: Seafloor = r###"
+            ...>...
+            .......
+            ......>
+            v.....>
+            ......>
+            .......
+            ..vvv..
+        "###
+        .parse()
+        .unwrap();
+
+        for _ in 0..4 {
+            seafloor.step();
+        }
+        let expected = r###"
+            >......
+            ..v....
+            ..>.v..
+            .>.v...
+            ...>...
+            .......
+            v......
+        "###;
+        assert_eq!(seafloor.to_string(), trimmed(expected));
+    }
+
+    const EXAMPLE: &str = r###"
+        v...>>.vv>
+        .vv>>.vv..
+        >>.>v>...v
+        >>v>>.>.v.
+        v>v.vv.v..
+        >.>>..v...
+        .vv..>.>v.
+        v.v..>>v.v
+        ....v..v.>
+    "###;
+
+    #[test]
+    fn test_example() {
+        let mut seafloor: Seafloor = EXAMPLE.parse().unwrap();
+        seafloor.step();
+        let expected = r###"
+            ....>.>v.>
+            v.v>.>v.v.
+            >v>>..>v..
+            >>v>v>.>.v
+            .>v.v...v.
+            v>>.>vvv..
+            ..v...>>..
+            vv...>>vv.
+            >.v.v..v.v
+        "###;
+        assert_eq!(seafloor.to_string(), trimmed(expected));
+
+        let mut seafloor: Seafloor = EXAMPLE.parse().unwrap();
+        assert_eq!(seafloor.settle(), 58);
+        let expected = r###"
+            ..>>v>vv..
+            ..v.>>vv..
+            ..>>v>>vv.
+            ..>>>>>vv.
+            v......>vv
+            v>v....>>v
+            vvv.....>>
+            >vv......>
+            .>v.vv.v..
+        "###;
+        assert_eq!(seafloor.to_string(), trimmed(expected));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!("..x..".parse::<Seafloor>().is_err());
+        assert!("...\n..".parse::<Seafloor>().is_err());
+        assert!("\n\n".parse::<Seafloor>().is_err());
+    }
+}
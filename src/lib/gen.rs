@@ -0,0 +1,929 @@
+//! Random but valid puzzle inputs, for stress-testing the solutions.
+//!
+//! Each day's generator takes a size, whose meaning depends on the day (see
+//! `GENERATORS`), and a seed; the same size and seed always produce the same
+//! input.
+
+use std::collections::BTreeSet;
+use std::fmt::Write;
+use std::ops::RangeInclusive;
+
+use anyhow::{anyhow, ensure};
+
+use crate::day11::Cavern;
+use crate::day19::Vector;
+use crate::{ocr, parse};
+
+/// A small, fast, seedable random number generator (SplitMix64).
+///
+/// Not suitable for anything but generating test data.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rng(u64);
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Rng(seed)
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// A number in `0..n`
+    pub fn below(&mut self, n: usize) -> usize {
+        assert!(n > 0, "Empty range");
+        (self.next_u64() % n as u64) as usize
+    }
+
+    /// A number in the (inclusive) range
+    pub fn range(&mut self, range: RangeInclusive<i64>) -> i64 {
+        let (lo, hi) = (*range.start(), *range.end());
+        assert!(lo <= hi, "Empty range {lo}..={hi}");
+        let span = (hi - lo) as u64 + 1;
+        lo + (self.next_u64() % span) as i64
+    }
+
+    /// True with probability `p`
+    pub fn chance(&mut self, p: f64) -> bool {
+        let unit = (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64;
+        unit < p
+    }
+
+    pub fn choose<'a, T>(&mut self, items: &'a [T]) -> &'a T {
+        &items[self.below(items.len())]
+    }
+
+    pub fn shuffle<T>(&mut self, items: &mut [T]) {
+        for ix in (1..items.len()).rev() {
+            items.swap(ix, self.below(ix + 1));
+        }
+    }
+}
+
+/// An input generator for one day
+#[derive(Debug, Clone)]
+pub struct Generator {
+    pub day: u8,
+    /// What `size` controls
+    pub size_meaning: &'static str,
+    pub default_size: usize,
+    /// The sizes that produce valid inputs
+    pub sizes: RangeInclusive<usize>,
+    generate: fn(&mut Rng, usize) -> String,
+}
+
+impl Generator {
+    /// Generates an input, using the default size if none is given
+    pub fn generate(&self, size: Option<usize>, seed: u64) -> anyhow::Result<String> {
+        let size = size.unwrap_or(self.default_size);
+        ensure!(
+            self.sizes.contains(&size),
+            "Day {} needs a size ({}) in {}..={}, not {size}",
+            self.day,
+            self.size_meaning,
+            self.sizes.start(),
+            self.sizes.end()
+        );
+        Ok((self.generate)(&mut Rng::new(seed), size))
+    }
+}
+
+macro_rules! generators {
+    ($($day:literal => $f:ident, $meaning:literal, $default:literal, $sizes:expr);* $(;)?) => {
+        &[$(Generator {
+            day: $day,
+            size_meaning: $meaning,
+            default_size: $default,
+            sizes: $sizes,
+            generate: $f,
+        }),*]
+    };
+}
+
+/// Every day's generator, in order
+pub static GENERATORS: &[Generator] = generators![
+    1 => day01, "depth readings", 2000, 0..=1_000_000;
+    2 => day02, "commands", 1000, 0..=1_000_000;
+    3 => day03, "distinct 12-bit numbers", 1000, 1..=4096;
+    4 => day04, "boards", 100, 1..=10_000;
+    5 => day05, "lines", 500, 0..=100_000;
+    6 => day06, "fish", 300, 0..=1_000_000;
+    7 => day07, "crabs", 1000, 1..=1_000_000;
+    8 => day08, "displays", 200, 0..=100_000;
+    9 => day09, "grid side", 100, 5..=2000;
+    10 => day10, "lines", 100, 1..=100_000;
+    11 => day11, "grid side", 10, 2..=30;
+    12 => day12, "small caves", 6, 1..=12;
+    13 => day13, "dots", 800, 1..=100_000;
+    14 => day14, "template length", 20, 2..=100_000;
+    15 => day15, "grid side", 100, 1..=1000;
+    16 => day16, "packets", 300, 1..=100_000;
+    17 => day17, "x velocity scale", 17, 2..=1000;
+    18 => day18, "numbers", 100, 1..=10_000;
+    19 => day19, "scanners", 30, 1..=200;
+    20 => day20, "image side", 100, 1..=1000;
+    21 => day21, "unused", 1, 0..=usize::MAX;
+    22 => day22, "cuboids", 420, 1..=10_000;
+    23 => day23, "unused", 1, 0..=usize::MAX;
+    24 => day24, "unused", 14, 0..=usize::MAX;
+    25 => day25, "grid side", 137, 2..=2000;
+];
+
+/// Looks up the generator for a day
+pub fn find(day: u8) -> Option<&'static Generator> {
+    GENERATORS.iter().find(|g| g.day == day)
+}
+
+/// Generates an input for `day`; see `Generator::generate`
+pub fn generate(day: u8, size: Option<usize>, seed: u64) -> anyhow::Result<String> {
+    find(day)
+        .ok_or_else(|| anyhow!("No generator for day {day}"))?
+        .generate(size, seed)
+}
+
+/// A grid of random digits, one row per line
+fn digit_grid(rng: &mut Rng, width: usize, height: usize, digits: RangeInclusive<i64>) -> String {
+    let mut s = String::with_capacity((width + 1) * height);
+    for _ in 0..height {
+        for _ in 0..width {
+            write!(s, "{}", rng.range(digits.clone())).unwrap();
+        }
+        s.push('\n');
+    }
+    s
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// Generators
+
+fn day01(rng: &mut Rng, size: usize) -> String {
+    let mut depth = rng.range(100..=200);
+    let mut s = String::new();
+    for _ in 0..size {
+        writeln!(s, "{depth}").unwrap();
+        depth = (depth + rng.range(-10..=20)).max(0);
+    }
+    s
+}
+
+fn day02(rng: &mut Rng, size: usize) -> String {
+    let mut depth = 0;
+    let mut s = String::new();
+    for _ in 0..size {
+        let n = rng.range(1..=9);
+        let command = match rng.below(3) {
+            0 => "forward",
+            1 if depth >= n => {
+                depth -= n;
+                "up"
+            }
+            _ => {
+                depth += n;
+                "down"
+            }
+        };
+        writeln!(s, "{command} {n}").unwrap();
+    }
+    s
+}
+
+/// Whether filtering by the most (or least) common bit leaves exactly one
+/// number, as the life support rating needs
+fn rating_exists(numbers: &[u16], most_common: bool) -> bool {
+    let mut left = numbers.to_vec();
+    for bit in (0..12).rev() {
+        if left.len() <= 1 {
+            break;
+        }
+        let ones = left.iter().filter(|&&n| n >> bit & 1 == 1).count();
+        let keep = (2 * ones >= left.len()) == most_common;
+        left.retain(|&n| (n >> bit & 1 == 1) == keep);
+    }
+    left.len() == 1
+}
+
+fn day03(rng: &mut Rng, size: usize) -> String {
+    // Numbers must be distinct, and both ratings must exist; the puzzle
+    // inputs never leave a filter with nothing to keep
+    let mut numbers: Vec<u16> = (0..4096).collect();
+    loop {
+        rng.shuffle(&mut numbers);
+        let chosen = &numbers[..size];
+        if rating_exists(chosen, true) && rating_exists(chosen, false) {
+            return chosen.iter().map(|n| format!("{n:012b}\n")).collect();
+        }
+    }
+}
+
+fn day04(rng: &mut Rng, size: usize) -> String {
+    let mut draws: Vec<i64> = (0..100).collect();
+    rng.shuffle(&mut draws);
+    let draws: Vec<String> = draws.iter().map(|n| n.to_string()).collect();
+    let mut s = draws.join(",");
+    s.push('\n');
+
+    for _ in 0..size {
+        let mut numbers: Vec<i64> = (0..100).collect();
+        rng.shuffle(&mut numbers);
+        s.push('\n');
+        for row in numbers[..25].chunks(5) {
+            let row: Vec<String> = row.iter().map(|n| format!("{n:>2}")).collect();
+            writeln!(s, "{}", row.join(" ")).unwrap();
+        }
+    }
+    s
+}
+
+fn day05(rng: &mut Rng, size: usize) -> String {
+    let mut s = String::new();
+    for _ in 0..size {
+        let (x1, y1) = (rng.range(0..=989), rng.range(0..=989));
+        let length = rng.range(0..=989 - x1.max(y1));
+        let (dx, dy) = match rng.below(3) {
+            0 => (length, 0),
+            1 => (0, length),
+            _ => (length, length),
+        };
+        let (mut start, mut end) = ((x1, y1), (x1 + dx, y1 + dy));
+        if rng.chance(0.5) {
+            std::mem::swap(&mut start, &mut end);
+        }
+        if dx != 0 && dy != 0 && rng.chance(0.5) {
+            // Flip to a diagonal going the other way
+            std::mem::swap(&mut start.1, &mut end.1);
+        }
+        writeln!(s, "{},{} -> {},{}", start.0, start.1, end.0, end.1).unwrap();
+    }
+    s
+}
+
+fn comma_separated(values: impl IntoIterator<Item = i64>) -> String {
+    let values: Vec<String> = values.into_iter().map(|n| n.to_string()).collect();
+    format!("{}\n", values.join(","))
+}
+
+fn day06(rng: &mut Rng, size: usize) -> String {
+    comma_separated((0..size).map(|_| rng.range(1..=5)))
+}
+
+fn day07(rng: &mut Rng, size: usize) -> String {
+    // Skewed towards small positions, like the puzzle inputs
+    comma_separated((0..size).map(|_| {
+        let n = rng.range(0..=2000);
+        n * n / 2000
+    }))
+}
+
+// Segments lit for each digit, with segments labelled a-g
+const SEGMENTS: [&str; 10] = [
+    "abcefg", "cf", "acdeg", "acdfg", "bcdf", "abdfg", "abdefg", "acf", "abcdefg", "abcdfg",
+];
+
+fn day08(rng: &mut Rng, size: usize) -> String {
+    let mut s = String::new();
+    for _ in 0..size {
+        let mut wiring: Vec<char> = "abcdefg".chars().collect();
+        rng.shuffle(&mut wiring);
+        let wire = |digit: usize, rng: &mut Rng| {
+            let mut wires: Vec<char> = SEGMENTS[digit]
+                .chars()
+                .map(|c| wiring[(c as u8 - b'a') as usize])
+                .collect();
+            rng.shuffle(&mut wires);
+            wires.into_iter().collect::<String>()
+        };
+
+        let mut patterns: Vec<String> = (0..10).map(|d| wire(d, rng)).collect();
+        rng.shuffle(&mut patterns);
+        let outputs: Vec<String> = (0..4).map(|_| wire(rng.below(10), rng)).collect();
+        writeln!(s, "{} | {}", patterns.join(" "), outputs.join(" ")).unwrap();
+    }
+    s
+}
+
+fn day09(rng: &mut Rng, size: usize) -> String {
+    digit_grid(rng, size, size, 0..=9)
+}
+
+const BRACKETS: [(char, char); 4] = [('(', ')'), ('[', ']'), ('{', '}'), ('<', '>')];
+
+fn day10(rng: &mut Rng, size: usize) -> String {
+    // Part two takes the median, so there must be an odd number of
+    // incomplete lines
+    let mut incomplete: Vec<bool> = (0..size).map(|_| rng.chance(0.5)).collect();
+    if incomplete.iter().filter(|&&b| b).count() % 2 == 0 {
+        incomplete[0] = !incomplete[0];
+    }
+
+    let mut s = String::new();
+    for is_incomplete in incomplete {
+        let length = rng.range(20..=110) as usize;
+        let corrupt_at = (!is_incomplete).then(|| rng.below(length));
+        let mut stack: Vec<char> = Vec::new();
+        let mut line = String::new();
+        for ix in 0..length {
+            if let Some(&closer) = stack.last() {
+                if corrupt_at.is_some_and(|c| ix >= c) {
+                    let wrong: Vec<char> = BRACKETS
+                        .iter()
+                        .map(|&(_, c)| c)
+                        .filter(|&c| c != closer)
+                        .collect();
+                    line.push(*rng.choose(&wrong));
+                    break;
+                }
+                if rng.chance(0.45) {
+                    line.push(closer);
+                    stack.pop();
+                    continue;
+                }
+            }
+            let &(opener, closer) = rng.choose(&BRACKETS);
+            line.push(opener);
+            stack.push(closer);
+        }
+        writeln!(s, "{line}").unwrap();
+    }
+    s
+}
+
+fn day11(rng: &mut Rng, size: usize) -> String {
+    // Not every grid synchronizes; keep trying until one does
+    loop {
+        let grid = digit_grid(rng, size, size, 0..=9);
+        let mut cavern: Cavern = parse::buffer(grid.as_bytes()).unwrap();
+        if (0..10_000).any(|_| cavern.step() == size * size) {
+            return grid;
+        }
+    }
+}
+
+fn day12(rng: &mut Rng, size: usize) -> String {
+    fn name(rng: &mut Rng, upper: bool, taken: &mut BTreeSet<String>) -> String {
+        loop {
+            let base = if upper { b'A' } else { b'a' };
+            let name: String = (0..2)
+                .map(|_| (base + rng.below(26) as u8) as char)
+                .collect();
+            if taken.insert(name.clone()) {
+                return name;
+            }
+        }
+    }
+
+    // Big caves are never connected to each other, or there would be
+    // infinitely many paths
+    let mut taken = BTreeSet::new();
+    let small: Vec<String> = (0..size).map(|_| name(rng, false, &mut taken)).collect();
+    let big: Vec<String> = (0..size.div_ceil(2))
+        .map(|_| name(rng, true, &mut taken))
+        .collect();
+    let caves: Vec<&String> = small.iter().chain(&big).collect();
+
+    let mut edges = BTreeSet::new();
+    let via = rng.choose(&caves);
+    edges.insert(("start".to_owned(), via.to_string()));
+    edges.insert((via.to_string(), "end".to_owned()));
+    for end in ["start", "end"] {
+        for _ in 0..rng.range(1..=2) {
+            edges.insert((end.to_owned(), rng.choose(&caves).to_string()));
+        }
+    }
+    for cave in &small {
+        for _ in 0..rng.range(1..=3) {
+            let other = rng.choose(&caves);
+            if other != &cave {
+                edges.insert((cave.clone(), other.to_string()));
+            }
+        }
+    }
+
+    let mut lines: Vec<String> = edges
+        .into_iter()
+        .map(|(a, b)| {
+            if rng.chance(0.5) {
+                format!("{a}-{b}")
+            } else {
+                format!("{b}-{a}")
+            }
+        })
+        .collect();
+    rng.shuffle(&mut lines);
+    lines.iter().map(|l| format!("{l}\n")).collect()
+}
+
+fn day13(rng: &mut Rng, size: usize) -> String {
+    // Start from a message and unfold it, mirroring each dot across the fold
+    // at random, so that folding it back up reveals the message
+    let letters: Vec<char> = ocr::letters().collect();
+    let pixels: BTreeSet<(i64, i64)> = loop {
+        let message: String = (0..8).map(|_| *rng.choose(&letters)).collect();
+        let pixels = ocr::draw(&message).unwrap();
+        // Letters are read from the leftmost dot, so the first letter can't
+        // start with a blank column (like I)
+        if pixels.iter().any(|&(x, _)| x == 0) {
+            break pixels.into_iter().collect();
+        }
+    };
+    let pixels: Vec<(i64, i64)> = pixels.into_iter().collect();
+
+    let (mut width, mut height) = (8 * 5 - 1, ocr::GLYPH_HEIGHT);
+    let mut unfolds = Vec::new();
+    for ix in 0..12 {
+        let along_x = ix % 2 == 0 || rng.chance(0.3);
+        let line = if along_x { width } else { height } + rng.range(0..=2);
+        if along_x {
+            width = 2 * line + 1;
+        } else {
+            height = 2 * line + 1;
+        }
+        unfolds.push((along_x, line));
+    }
+
+    // Every pixel of the message gets at least one dot
+    let mut points = BTreeSet::new();
+    for ix in 0..size.max(pixels.len()) {
+        let (mut x, mut y) = pixels[ix % pixels.len()];
+        for &(along_x, line) in &unfolds {
+            if rng.chance(0.5) {
+                if along_x {
+                    x = 2 * line - x;
+                } else {
+                    y = 2 * line - y;
+                }
+            }
+        }
+        points.insert((x, y));
+    }
+
+    let mut points: Vec<(i64, i64)> = points.into_iter().collect();
+    rng.shuffle(&mut points);
+    let mut s = String::new();
+    for (x, y) in points {
+        writeln!(s, "{x},{y}").unwrap();
+    }
+    s.push('\n');
+    for &(along_x, line) in unfolds.iter().rev() {
+        let axis = if along_x { 'x' } else { 'y' };
+        writeln!(s, "fold along {axis}={line}").unwrap();
+    }
+    s
+}
+
+fn day14(rng: &mut Rng, size: usize) -> String {
+    let elements: Vec<char> = "BCFHKNOPSV".chars().collect();
+    let template: String = (0..size).map(|_| *rng.choose(&elements)).collect();
+    let mut s = format!("{template}\n\n");
+    for &a in &elements {
+        for &b in &elements {
+            writeln!(s, "{a}{b} -> {}", rng.choose(&elements)).unwrap();
+        }
+    }
+    s
+}
+
+fn day15(rng: &mut Rng, size: usize) -> String {
+    digit_grid(rng, size, size, 1..=9)
+}
+
+/// Appends the low `n` bits of `value` to `bits`, most significant first
+fn push_bits(bits: &mut Vec<bool>, value: u64, n: usize) {
+    bits.extend((0..n).rev().map(|ix| (value >> ix) & 1 == 1));
+}
+
+/// Encodes a random packet made of `count` packets in total, returning its
+/// value as well. Values are kept small enough that products can't overflow.
+fn packet(rng: &mut Rng, count: usize, bits: &mut Vec<bool>) -> i64 {
+    push_bits(bits, rng.below(8) as u64, 3);
+    if count == 1 {
+        let value = rng.range(0..=5000);
+        push_bits(bits, 4, 3);
+        let nibbles = (64 - (value as u64).leading_zeros() as usize)
+            .div_ceil(4)
+            .max(1);
+        for ix in (0..nibbles).rev() {
+            bits.push(ix > 0);
+            push_bits(bits, (value >> (4 * ix)) as u64 & 0xF, 4);
+        }
+        return value;
+    }
+
+    // Comparisons take exactly two sub-packets. They are kept rare, as their
+    // 0 or 1 results would otherwise wipe out most of the products above them.
+    let remaining = count - 1;
+    let mut typ = if remaining >= 2 && rng.chance(0.1) {
+        *rng.choose(&[5, 6, 7])
+    } else {
+        *rng.choose(&[0, 1, 2, 3])
+    };
+    let children = if typ >= 5 {
+        2
+    } else {
+        rng.range(1..=remaining.min(5) as i64) as usize
+    };
+
+    let mut sizes = vec![1; children];
+    for _ in children..remaining {
+        sizes[rng.below(children)] += 1;
+    }
+
+    let mut body = Vec::new();
+    let mut values = Vec::with_capacity(children);
+    for size in sizes {
+        values.push(packet(rng, size, &mut body));
+    }
+
+    let product = values.iter().try_fold(1i64, |p, &v| {
+        p.checked_mul(v).filter(|&p| p > 0 && p < 1 << 40)
+    });
+    if typ == 1 && product.is_none() {
+        typ = 0;
+    }
+    // Likewise a minimum would pass a comparison's result straight up
+    if typ == 2 && values.iter().any(|&v| v <= 1) {
+        typ = 3;
+    }
+
+    push_bits(bits, typ as u64, 3);
+    if body.len() < 1 << 15 && rng.chance(0.5) {
+        bits.push(false);
+        push_bits(bits, body.len() as u64, 15);
+    } else {
+        bits.push(true);
+        push_bits(bits, children as u64, 11);
+    }
+    bits.extend(body);
+
+    match typ {
+        0 => values.iter().sum(),
+        1 => product.unwrap(),
+        2 => *values.iter().min().unwrap(),
+        3 => *values.iter().max().unwrap(),
+        5 => (values[0] > values[1]) as i64,
+        6 => (values[0] < values[1]) as i64,
+        _ => (values[0] == values[1]) as i64,
+    }
+}
+
+fn day16(rng: &mut Rng, size: usize) -> String {
+    let mut bits = Vec::new();
+    packet(rng, size, &mut bits);
+    while bits.len() % 4 != 0 {
+        bits.push(false);
+    }
+
+    let mut s: String = bits
+        .chunks(4)
+        .map(|nibble| {
+            let n = nibble.iter().fold(0, |n, &b| n * 2 + b as u32);
+            char::from_digit(n, 16).unwrap().to_ascii_uppercase()
+        })
+        .collect();
+    s.push('\n');
+    s
+}
+
+fn day17(rng: &mut Rng, size: usize) -> String {
+    // The highest shot drops straight down into the target, so some x
+    // velocity must come to rest inside it
+    let vx = rng.range(2..=size as i64);
+    let rest = vx * (vx + 1) / 2;
+    let x1 = (rest - rng.range(0..=vx)).max(1);
+    let x2 = rest + rng.range(0..=vx);
+    let y2 = -rng.range(2..=5 * size as i64);
+    let y1 = y2 - rng.range(0..=size as i64);
+    format!("target area: x={x1}..{x2}, y={y1}..{y2}\n")
+}
+
+fn snailfish(rng: &mut Rng, depth: usize) -> String {
+    // Pairs nested inside four pairs would explode, so they are left out
+    if depth < 4 && (depth == 0 || rng.chance(0.6)) {
+        let left = snailfish(rng, depth + 1);
+        let right = snailfish(rng, depth + 1);
+        format!("[{left},{right}]")
+    } else {
+        rng.range(0..=9).to_string()
+    }
+}
+
+fn day18(rng: &mut Rng, size: usize) -> String {
+    (0..size).map(|_| snailfish(rng, 0) + "\n").collect()
+}
+
+fn day19(rng: &mut Rng, size: usize) -> String {
+    // Scanners form a chain, each one in range of the last, and each pair of
+    // neighbours shares at least 12 beacons
+    const RANGE: i64 = 1000;
+    let mut scanners = vec![[0i64; 3]];
+    while scanners.len() < size {
+        let last = scanners[scanners.len() - 1];
+        scanners.push(last.map(|c| c + rng.range(-1100..=1100)));
+    }
+
+    let mut beacons = BTreeSet::new();
+    let mut place = |rng: &mut Rng, lo: [i64; 3], hi: [i64; 3], count: usize| {
+        let mut placed = 0;
+        while placed < count {
+            let b: Vec<i64> = (0..3).map(|ix| rng.range(lo[ix]..=hi[ix])).collect();
+            if beacons.insert((b[0], b[1], b[2])) {
+                placed += 1;
+            }
+        }
+    };
+    for pair in scanners.windows(2) {
+        let lo: [i64; 3] = std::array::from_fn(|ix| pair[0][ix].max(pair[1][ix]) - RANGE);
+        let hi: [i64; 3] = std::array::from_fn(|ix| pair[0][ix].min(pair[1][ix]) + RANGE);
+        let shared = rng.range(12..=14) as usize;
+        place(rng, lo, hi, shared);
+    }
+    for s in &scanners {
+        place(rng, s.map(|c| c - RANGE), s.map(|c| c + RANGE), 10);
+    }
+
+    let mut out = String::new();
+    for (id, s) in scanners.iter().enumerate() {
+        writeln!(out, "--- scanner {id} ---").unwrap();
+        let rotation = rng.below(24);
+        let mut seen: Vec<String> = beacons
+            .iter()
+            .map(|&(x, y, z)| (x - s[0], y - s[1], z - s[2]))
+            .filter(|&(x, y, z)| x.abs() <= RANGE && y.abs() <= RANGE && z.abs() <= RANGE)
+            .map(|(x, y, z)| {
+                let v = Vector(x, y, z);
+                let Vector(x, y, z) = if id == 0 { v } else { v.rotations()[rotation] };
+                format!("{x},{y},{z}\n")
+            })
+            .collect();
+        rng.shuffle(&mut seen);
+        out.extend(seen);
+        out.push('\n');
+    }
+    out
+}
+
+fn day20(rng: &mut Rng, size: usize) -> String {
+    let mut algorithm: Vec<char> = (0..512)
+        .map(|_| if rng.chance(0.5) { '#' } else { '.' })
+        .collect();
+    // If the dark background lights up, it must go dark again next step, or
+    // there would be infinitely many lit pixels
+    if algorithm[0] == '#' {
+        algorithm[511] = '.';
+    }
+
+    let mut s: String = algorithm.into_iter().collect();
+    s.push_str("\n\n");
+    for _ in 0..size {
+        let row: String = (0..size)
+            .map(|_| if rng.chance(0.5) { '#' } else { '.' })
+            .collect();
+        writeln!(s, "{row}").unwrap();
+    }
+    s
+}
+
+fn day21(rng: &mut Rng, _size: usize) -> String {
+    format!(
+        "Player 1 starting position: {}\nPlayer 2 starting position: {}\n",
+        rng.range(1..=10),
+        rng.range(1..=10)
+    )
+}
+
+fn day22(rng: &mut Rng, size: usize) -> String {
+    // Like the puzzle input, the first few cuboids are inside the
+    // initialization region, and the rest are much larger
+    let mut s = String::new();
+    for ix in 0..size {
+        let on = ix == 0 || rng.chance(0.6);
+        let (bound, extent) = if ix < 20 { (50, 50) } else { (100_000, 40_000) };
+        let ranges: Vec<String> = (0..3)
+            .map(|_| {
+                let lo = rng.range(-bound..=bound - 1);
+                let hi = (lo + rng.range(0..=extent)).min(bound);
+                format!("{lo}..{hi}")
+            })
+            .collect();
+        let state = if on { "on" } else { "off" };
+        writeln!(s, "{state} x={},y={},z={}", ranges[0], ranges[1], ranges[2]).unwrap();
+    }
+    s
+}
+
+fn day23(rng: &mut Rng, _size: usize) -> String {
+    let mut amphipods: Vec<char> = "AABBCCDD".chars().collect();
+    rng.shuffle(&mut amphipods);
+    let room = |row: &[char]| {
+        let letters: Vec<String> = row.iter().map(|c| c.to_string()).collect();
+        letters.join("#")
+    };
+    format!(
+        "#############\n#...........#\n###{}###\n  #{}#\n  #########\n",
+        room(&amphipods[..4]),
+        room(&amphipods[4..])
+    )
+}
+
+fn day24(rng: &mut Rng, _size: usize) -> String {
+    // MONAD pushes and pops base-26 digits of z; each pop must match a
+    // push, so a valid model number exists when every matched pair of digits
+    // differs by at most 8
+    const PAIRS: usize = 7;
+    let mut stack: Vec<i64> = Vec::new();
+    let mut pushes = 0;
+    let mut blocks: Vec<(i64, i64, i64)> = Vec::new();
+    while blocks.len() < 2 * PAIRS {
+        let offset = rng.range(1..=16);
+        if pushes < PAIRS && (stack.is_empty() || rng.chance(0.5)) {
+            pushes += 1;
+            stack.push(offset);
+            blocks.push((1, rng.range(10..=16), offset));
+        } else {
+            let pushed = stack.pop().unwrap();
+            let delta = rng.range(-8..=8);
+            blocks.push((26, delta - pushed, offset));
+        }
+    }
+
+    let mut s = String::new();
+    for (divisor, check, offset) in blocks {
+        write!(
+            s,
+            "inp w\nmul x 0\nadd x z\nmod x 26\ndiv z {divisor}\nadd x {check}\neql x w\n\
+             eql x 0\nmul y 0\nadd y 25\nmul y x\nadd y 1\nmul z y\nmul y 0\nadd y w\n\
+             add y {offset}\nmul y x\nadd z y\n"
+        )
+        .unwrap();
+    }
+    s
+}
+
+fn day25(rng: &mut Rng, size: usize) -> String {
+    let mut s = String::new();
+    for _ in 0..size {
+        let row: String = (0..size)
+            .map(|_| match rng.below(10) {
+                0..=2 => '>',
+                3..=5 => 'v',
+                _ => '.',
+            })
+            .collect();
+        writeln!(s, "{row}").unwrap();
+    }
+    s
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// Tests
+
+#[cfg(test)]
+mod tests {
+    use test_log::test;
+
+    #[allow(unused_imports)]
+    use super::*;
+
+    use std::collections::BTreeMap;
+
+    use crate::bits::BitReader;
+    use crate::day16::Packet;
+    use crate::day19::Regions;
+    use crate::solution;
+
+    /// How many lines there are of each length
+    fn line_counts(s: &str) -> BTreeMap<usize, usize> {
+        let mut counts = BTreeMap::new();
+        for line in s.lines() {
+            *counts.entry(line.len()).or_default() += 1;
+        }
+        counts
+    }
+
+    #[test]
+    fn test_rng() {
+        let mut rng = Rng::new(7);
+        let first: Vec<u64> = (0..5).map(|_| rng.next_u64()).collect();
+        let mut rng = Rng::new(7);
+        let second: Vec<u64> = (0..5).map(|_| rng.next_u64()).collect();
+        assert_eq!(first, second);
+
+        for _ in 0..1000 {
+            assert!((-3..=3).contains(&rng.range(-3..=3)));
+            assert!(rng.below(4) < 4);
+        }
+
+        let mut items: Vec<usize> = (0..20).collect();
+        rng.shuffle(&mut items);
+        assert_ne!(items, (0..20).collect::<Vec<_>>());
+        items.sort();
+        assert_eq!(items, (0..20).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_registry() {
+        for (ix, generator) in GENERATORS.iter().enumerate() {
+            assert_eq!(generator.day as usize, ix + 1);
+            assert!(generator.sizes.contains(&generator.default_size));
+        }
+        assert!(generate(3, Some(5000), 0).is_err());
+        assert!(generate(26, None, 0).is_err());
+        assert_eq!(
+            generate(9, Some(20), 3).unwrap(),
+            generate(9, Some(20), 3).unwrap()
+        );
+        assert_ne!(
+            generate(9, Some(20), 3).unwrap(),
+            generate(9, Some(20), 4).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_shapes() {
+        let grid = generate(15, Some(12), 1).unwrap();
+        assert_eq!(line_counts(&grid), BTreeMap::from([(12, 12)]));
+
+        let report = generate(3, Some(50), 1).unwrap();
+        assert_eq!(line_counts(&report), BTreeMap::from([(12, 50)]));
+    }
+
+    // Each day's solution accepts its generated input. Day 23 is left out, as
+    // it is slow in debug builds.
+    #[test]
+    fn test_solvable() {
+        let sizes = [
+            (1, 100),
+            (2, 100),
+            (3, 100),
+            (4, 10),
+            (5, 50),
+            (6, 20),
+            (7, 50),
+            (8, 20),
+            (9, 20),
+            (10, 21),
+            (11, 10),
+            (12, 4),
+            (13, 200),
+            (14, 10),
+            (15, 20),
+            (16, 50),
+            (17, 10),
+            (18, 10),
+            (19, 4),
+            (20, 10),
+            (21, 1),
+            (22, 40),
+            (24, 14),
+            (25, 20),
+        ];
+        for seed in 0..3 {
+            for &(day, size) in &sizes {
+                let input = generate(day, Some(size), seed).unwrap();
+                let solved = (solution::find(day).unwrap().solve)(&input);
+                assert!(
+                    solved.is_ok(),
+                    "Day {day}, seed {seed} failed: {:?}\n{input}",
+                    solved
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_day16_packets() {
+        // day16 draws from the RNG exactly as packet does, so the same seed
+        // gives the same packet
+        let mut trivial = 0;
+        for seed in 0..20 {
+            let value = packet(&mut Rng::new(seed), 40, &mut Vec::new());
+            let hex = day16(&mut Rng::new(seed), 40);
+            let mut reader: BitReader = hex.parse().unwrap();
+            let parsed = Packet::decode(&mut reader).unwrap();
+            assert_eq!(parsed.evaluate(), value, "seed {seed}");
+            if value <= 1 {
+                trivial += 1;
+            }
+        }
+        // Comparisons are allowed, but shouldn't flatten most answers to 0 or 1
+        assert!(trivial < 5, "{trivial} of 20 packets evaluated to 0 or 1");
+    }
+
+    #[test]
+    fn test_day13_letters() {
+        let input = generate(13, Some(300), 5).unwrap();
+        let solved = (solution::find(13).unwrap().solve)(&input).unwrap();
+        let letters = solved.part2.unwrap();
+        assert_eq!(letters.len(), 8);
+    }
+
+    #[test]
+    fn test_day19_overlaps() {
+        let input = generate(19, Some(5), 2).unwrap();
+        let regions: Regions = input.parse().unwrap();
+        assert_eq!(regions.reduce(12).unwrap().scanners.len(), 5);
+    }
+}
@@ -0,0 +1,254 @@
+use std::ops::RangeInclusive;
+
+use serde::{Deserialize, Serialize};
+
+/// A closed range of integers, as written in puzzle inputs like `x=10..12`.
+/// A range with `start > end` is empty.
+pub type Interval = RangeInclusive<i64>;
+
+/// Set operations on [`Interval`]s
+pub trait IntervalExt: Sized {
+    /// Number of integers in the range, or 0 if it is empty
+    fn size(&self) -> u64;
+
+    /// The integers in both ranges, if any
+    fn intersect(&self, other: &Self) -> Option<Self>;
+
+    /// The two ranges as one, if they overlap or are adjacent
+    fn union(&self, other: &Self) -> Option<Self>;
+
+    /// The parts of this range not in `other`: none, one, or two pieces, in
+    /// order
+    fn subtract(&self, other: &Self) -> Vec<Self>;
+
+    /// Whether every integer in `other` is also in this range. An empty
+    /// `other` is contained in anything.
+    fn contains_range(&self, other: &Self) -> bool;
+
+    /// The closest value to `value` within the range. Panics if the range is
+    /// empty.
+    fn clamp(&self, value: i64) -> i64;
+}
+
+impl IntervalExt for Interval {
+    fn size(&self) -> u64 {
+        if self.is_empty() {
+            return 0;
+        }
+        self.end().abs_diff(*self.start()) + 1
+    }
+
+    fn intersect(&self, other: &Self) -> Option<Self> {
+        let overlap = *self.start().max(other.start())..=*self.end().min(other.end());
+        (!overlap.is_empty()).then_some(overlap)
+    }
+
+    fn union(&self, other: &Self) -> Option<Self> {
+        if self.is_empty() {
+            return Some(other.clone());
+        }
+        if other.is_empty() {
+            return Some(self.clone());
+        }
+        // Touching ranges like 1..=3 and 4..=6 merge too
+        let (first, second) = if self.start() <= other.start() {
+            (self, other)
+        } else {
+            (other, self)
+        };
+        if second.start().saturating_sub(1) > *first.end() {
+            return None;
+        }
+        Some(*first.start()..=*first.end().max(second.end()))
+    }
+
+    fn subtract(&self, other: &Self) -> Vec<Self> {
+        let overlap = match self.intersect(other) {
+            None if self.is_empty() => return vec![],
+            None => return vec![self.clone()],
+            Some(overlap) => overlap,
+        };
+
+        let mut pieces = Vec::with_capacity(2);
+        if self.start() < overlap.start() {
+            pieces.push(*self.start()..=overlap.start() - 1);
+        }
+        if overlap.end() < self.end() {
+            pieces.push(overlap.end() + 1..=*self.end());
+        }
+        pieces
+    }
+
+    fn contains_range(&self, other: &Self) -> bool {
+        other.is_empty() || (self.start() <= other.start() && other.end() <= self.end())
+    }
+
+    fn clamp(&self, value: i64) -> i64 {
+        assert!(!self.is_empty(), "Can't clamp to an empty range {self:?}");
+        value.clamp(*self.start(), *self.end())
+    }
+}
+
+/// Merges ranges into the fewest disjoint, sorted ranges covering the same
+/// integers
+pub fn merge(ranges: impl IntoIterator<Item = Interval>) -> Vec<Interval> {
+    let mut ranges: Vec<Interval> = ranges.into_iter().filter(|r| !r.is_empty()).collect();
+    ranges.sort_by_key(|r| (*r.start(), *r.end()));
+
+    let mut merged: Vec<Interval> = Vec::with_capacity(ranges.len());
+    for range in ranges {
+        match merged.last_mut().and_then(|last| last.union(&range)) {
+            Some(joined) => *merged.last_mut().unwrap() = joined,
+            None => merged.push(range),
+        }
+    }
+    merged
+}
+
+/// An axis-aligned box of integer points, inclusive on every side
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Cuboid {
+    pub x: Interval,
+    pub y: Interval,
+    pub z: Interval,
+}
+
+impl Cuboid {
+    pub fn new(x: Interval, y: Interval, z: Interval) -> Self {
+        Cuboid { x, y, z }
+    }
+
+    /// A cube spanning `range` along every axis
+    pub fn cube(range: Interval) -> Self {
+        Cuboid::new(range.clone(), range.clone(), range)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.x.is_empty() || self.y.is_empty() || self.z.is_empty()
+    }
+
+    /// Number of points inside
+    pub fn volume(&self) -> u64 {
+        self.x.size() * self.y.size() * self.z.size()
+    }
+
+    pub fn contains(&self, (x, y, z): (i64, i64, i64)) -> bool {
+        self.x.contains(&x) && self.y.contains(&y) && self.z.contains(&z)
+    }
+
+    /// Whether every point of `other` is inside this cuboid
+    pub fn contains_cuboid(&self, other: &Self) -> bool {
+        other.is_empty()
+            || (self.x.contains_range(&other.x)
+                && self.y.contains_range(&other.y)
+                && self.z.contains_range(&other.z))
+    }
+
+    pub fn intersect(&self, other: &Self) -> Option<Self> {
+        Some(Cuboid {
+            x: self.x.intersect(&other.x)?,
+            y: self.y.intersect(&other.y)?,
+            z: self.z.intersect(&other.z)?,
+        })
+    }
+
+    /// The parts of this cuboid not in `other`, as at most 6 disjoint cuboids
+    pub fn subtract(&self, other: &Self) -> Vec<Self> {
+        let Some(overlap) = self.intersect(other) else {
+            if self.is_empty() {
+                return vec![];
+            }
+            return vec![self.clone()];
+        };
+
+        // Slabs on either side in x take the full y and z extent; then
+        // within the overlap's x, slabs in y; then within that, slabs in z.
+        let mut pieces = Vec::with_capacity(6);
+        for x in self.x.subtract(&overlap.x) {
+            pieces.push(Cuboid::new(x, self.y.clone(), self.z.clone()));
+        }
+        for y in self.y.subtract(&overlap.y) {
+            pieces.push(Cuboid::new(overlap.x.clone(), y, self.z.clone()));
+        }
+        for z in self.z.subtract(&overlap.z) {
+            pieces.push(Cuboid::new(overlap.x.clone(), overlap.y.clone(), z));
+        }
+        pieces
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// Tests
+
+#[cfg(test)]
+mod tests {
+    use test_log::test;
+
+    #[allow(unused_imports)]
+    use super::*;
+
+    #[test]
+    fn test_intervals() {
+        assert_eq!((1..=3).size(), 3);
+        #[allow(clippy::reversed_empty_ranges)]
+        let empty: Interval = 3..=1;
+        assert_eq!(empty.size(), 0);
+        assert_eq!((i64::MIN..=i64::MAX - 1).size(), u64::MAX);
+
+        assert_eq!((1..=5).intersect(&(3..=8)), Some(3..=5));
+        assert_eq!((1..=5).intersect(&(5..=8)), Some(5..=5));
+        assert_eq!((1..=5).intersect(&(6..=8)), None);
+
+        assert_eq!((1..=3).union(&(4..=6)), Some(1..=6));
+        assert_eq!((4..=6).union(&(1..=5)), Some(1..=6));
+        assert_eq!((1..=3).union(&(5..=6)), None);
+        assert_eq!((1..=3).union(&empty), Some(1..=3));
+
+        assert_eq!((1..=10).subtract(&(4..=6)), vec![1..=3, 7..=10]);
+        assert_eq!((1..=10).subtract(&(0..=6)), vec![7..=10]);
+        assert_eq!((1..=10).subtract(&(8..=20)), vec![1..=7]);
+        assert_eq!((1..=10).subtract(&(0..=20)), vec![]);
+        assert_eq!((1..=10).subtract(&(11..=20)), vec![1..=10]);
+
+        assert!((1..=10).contains_range(&(1..=10)));
+        assert!(!(1..=10).contains_range(&(0..=5)));
+        assert!((1..=10).contains_range(&empty));
+
+        assert_eq!((1..=10).clamp(-5), 1);
+        assert_eq!((1..=10).clamp(5), 5);
+        assert_eq!((1..=10).clamp(50), 10);
+    }
+
+    #[test]
+    fn test_merge() {
+        let merged = merge([8..=9, 1..=3, 2..=5, 11..=12, 6..=6]);
+        assert_eq!(merged, vec![1..=6, 8..=9, 11..=12]);
+        assert_eq!(merge([]), vec![]);
+    }
+
+    #[test]
+    fn test_cuboids() {
+        let a = Cuboid::cube(10..=12);
+        let b = Cuboid::cube(11..=13);
+        assert_eq!(a.volume(), 27);
+        assert_eq!(a.intersect(&b), Some(Cuboid::cube(11..=12)));
+        assert_eq!(a.intersect(&Cuboid::cube(13..=14)), None);
+        assert!(a.contains((10, 11, 12)));
+        assert!(!a.contains((10, 11, 13)));
+        assert!(Cuboid::cube(-50..=50).contains_cuboid(&a));
+        assert!(!a.contains_cuboid(&b));
+
+        let pieces = a.subtract(&b);
+        assert_eq!(pieces.iter().map(Cuboid::volume).sum::<u64>(), 27 - 8);
+        for (ix, p) in pieces.iter().enumerate() {
+            assert!(a.contains_cuboid(p));
+            assert_eq!(p.intersect(&b), None);
+            for q in &pieces[ix + 1..] {
+                assert_eq!(p.intersect(q), None);
+            }
+        }
+
+        assert_eq!(a.subtract(&Cuboid::cube(0..=1)), vec![a.clone()]);
+        assert_eq!(a.subtract(&Cuboid::cube(0..=20)), vec![]);
+    }
+}
@@ -1,2 +1,46 @@
+pub mod animate;
+pub mod bits;
+pub mod cache;
+pub mod cancel;
+pub mod config;
+pub mod constraints;
+pub mod count;
+pub mod day01;
+pub mod day02;
+pub mod day03;
+pub mod day04;
+pub mod day05;
+pub mod day06;
+pub mod day07;
+pub mod day08;
+pub mod day09;
+pub mod day10;
+pub mod day11;
+pub mod day12;
+pub mod day13;
+pub mod day14;
+pub mod day15;
+pub mod day16;
+pub mod day17;
+pub mod day18;
+pub mod day19;
+pub mod day20;
+pub mod day21;
+pub mod day22;
+pub mod day23;
+pub mod day24;
+pub mod day25;
+pub mod gen;
+pub mod intervals;
 pub mod nom;
+pub mod ocr;
+pub mod parallel;
 pub mod parse;
+pub mod render;
+#[cfg(feature = "serve")]
+pub mod serve;
+pub mod solution;
+pub mod stats;
+pub mod table;
+pub mod trace;
+pub mod union_find;
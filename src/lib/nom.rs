@@ -2,8 +2,9 @@ use std::ops::Deref;
 
 pub use nom::branch::alt;
 pub use nom::bytes::complete::tag;
-pub use nom::character::complete::{char, digit1, one_of};
-pub use nom::combinator::{all_consuming, map, map_res, opt, recognize, value};
+pub use nom::character::complete::{char, digit1, none_of, one_of};
+pub use nom::combinator::{all_consuming, cut, map, map_res, opt, peek, recognize, value, verify};
+pub use nom::error::context;
 // #[cfg(not(debug_assertions))]
 // pub use nom::error::Error as NomError;
 // #[cfg(debug_assertions)]
@@ -28,6 +29,20 @@ pub fn simplify<'a, V>(input: &'a str, r: Result<(&'a str, V), ErrorRef>) -> any
     }
 }
 
+/// Runs `parser` over `input`, erroring if anything but whitespace is left
+/// over, rather than silently ignoring it
+pub fn strict<'a, V>(
+    input: &'a str,
+    mut parser: impl FnMut(&'a str) -> IResult<'a, V>,
+) -> anyhow::Result<V> {
+    let (rest, v) = match parser(input) {
+        Ok(parsed) => parsed,
+        Err(e) => return simplify(input, Err(e)),
+    };
+    crate::parse::ensure_consumed(input, rest)?;
+    Ok(v)
+}
+
 // #[cfg(debug_assertions)]
 pub fn convert_error<I: Deref<Target = str>>(
     input: I,
@@ -49,25 +64,25 @@ fn convert_simple_error<
 }
 
 // Matches whitespace: ' ', '\n'
-pub fn ws(input: &str) -> IResult<&str> {
+pub fn ws(input: &str) -> IResult<'_, &str> {
     recognize(many0(one_of(" \n")))(input)
 }
 
 // Matches a newline with optional whitespace after it
-pub fn newline_ws(input: &str) -> IResult<&str> {
+pub fn newline_ws(input: &str) -> IResult<'_, &str> {
     recognize(pair(char('\n'), many0(char(' '))))(input)
 }
 
 // Matches 0 or more newlines
-pub fn newlines0(input: &str) -> IResult<&str> {
+pub fn newlines0(input: &str) -> IResult<'_, &str> {
     recognize(many0(newline_ws))(input)
 }
 // Matches 1 or more newlines
-pub fn newlines1(input: &str) -> IResult<&str> {
+pub fn newlines1(input: &str) -> IResult<'_, &str> {
     recognize(many1(newline_ws))(input)
 }
 
 // Matches an integer
-pub fn int(input: &str) -> IResult<i64> {
+pub fn int(input: &str) -> IResult<'_, i64> {
     map_res(recognize(pair(opt(char('-')), digit1)), str::parse::<i64>)(input)
 }
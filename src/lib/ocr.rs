@@ -0,0 +1,176 @@
+use std::collections::HashSet;
+
+use anyhow::anyhow;
+
+/// Width of a glyph, in pixels
+pub const GLYPH_WIDTH: i64 = 4;
+/// Height of a glyph, in pixels
+pub const GLYPH_HEIGHT: i64 = 6;
+// Glyphs are separated by a single blank column
+const GLYPH_PITCH: i64 = GLYPH_WIDTH + 1;
+
+// The standard 4x6 letters used in Advent of Code puzzle output
+const GLYPHS: [(char, &str); 18] = [
+    ('A', ".##.#..##..######..##..#"),
+    ('B', "###.#..####.#..##..####."),
+    ('C', ".##.#..##...#...#..#.##."),
+    ('E', "#####...###.#...#...####"),
+    ('F', "#####...###.#...#...#..."),
+    ('G', ".##.#..##...#.###..#.###"),
+    ('H', "#..##..######..##..##..#"),
+    ('I', ".###..#...#...#...#..###"),
+    ('J', "..##...#...#...##..#.##."),
+    ('K', "#..##.#.##..#.#.#.#.#..#"),
+    ('L', "#...#...#...#...#...####"),
+    ('O', ".##.#..##..##..##..#.##."),
+    ('P', "###.#..##..####.#...#..."),
+    ('R', "###.#..##..####.#.#.#..#"),
+    ('S', ".####...#....##....####."),
+    ('U', "#..##..##..##..##..#.##."),
+    ('Y', "#...#....#.#..#...#...#."),
+    ('Z', "####...#..#..#..#...####"),
+];
+
+fn glyph_pattern(points: &HashSet<(i64, i64)>, x0: i64, y0: i64) -> String {
+    (0..GLYPH_HEIGHT)
+        .flat_map(|y| (0..GLYPH_WIDTH).map(move |x| (x, y)))
+        .map(|(x, y)| {
+            if points.contains(&(x0 + x, y0 + y)) {
+                '#'
+            } else {
+                '.'
+            }
+        })
+        .collect()
+}
+
+/// Recognize a single glyph from a 24-character pattern of `#` and `.`, read
+/// row by row.
+pub fn recognize_glyph(pattern: &str) -> Option<char> {
+    GLYPHS.iter().find(|&&(_, p)| p == pattern).map(|&(c, _)| c)
+}
+
+/// Recognize a row of letters drawn by a set of lit `(x, y)` points.
+///
+/// Points are shifted so that the top-left lit point is at the origin, and then
+/// read as 4x6 glyphs separated by single blank columns.
+pub fn recognize<I: IntoIterator<Item = (i64, i64)>>(points: I) -> anyhow::Result<String> {
+    let points: HashSet<(i64, i64)> = points.into_iter().collect();
+    let (x0, x1) = match (
+        points.iter().map(|&(x, _)| x).min(),
+        points.iter().map(|&(x, _)| x).max(),
+    ) {
+        (Some(x0), Some(x1)) => (x0, x1),
+        _ => return Ok(String::new()),
+    };
+    let y0 = points.iter().map(|&(_, y)| y).min().unwrap_or_default();
+    let y1 = points.iter().map(|&(_, y)| y).max().unwrap_or_default();
+    if y1 - y0 >= GLYPH_HEIGHT {
+        return Err(anyhow!(
+            "Points span {} rows, expected at most {GLYPH_HEIGHT}",
+            y1 - y0 + 1
+        ));
+    }
+
+    let count = (x1 - x0) / GLYPH_PITCH + 1;
+    (0..count)
+        .map(|ix| {
+            let pattern = glyph_pattern(&points, x0 + ix * GLYPH_PITCH, y0);
+            recognize_glyph(&pattern).ok_or_else(|| anyhow!("Unrecognized glyph {ix}: {pattern}"))
+        })
+        .collect()
+}
+
+/// The letters with known glyphs
+pub fn letters() -> impl Iterator<Item = char> {
+    GLYPHS.iter().map(|&(c, _)| c)
+}
+
+/// Draws a row of letters as lit `(x, y)` points, starting at the origin; the
+/// inverse of `recognize`.
+pub fn draw(text: &str) -> anyhow::Result<HashSet<(i64, i64)>> {
+    let mut points = HashSet::new();
+    for (ix, c) in text.chars().enumerate() {
+        let &(_, pattern) = GLYPHS
+            .iter()
+            .find(|&&(g, _)| g == c)
+            .ok_or_else(|| anyhow!("No glyph for {c:?}"))?;
+        let x0 = ix as i64 * GLYPH_PITCH;
+        for (n, p) in pattern.chars().enumerate() {
+            if p == '#' {
+                let n = n as i64;
+                points.insert((x0 + n % GLYPH_WIDTH, n / GLYPH_WIDTH));
+            }
+        }
+    }
+    Ok(points)
+}
+
+#[cfg(test)]
+mod tests {
+    use test_log::test;
+
+    use super::*;
+
+    fn points(drawing: &str) -> Vec<(i64, i64)> {
+        drawing
+            .trim()
+            .lines()
+            .enumerate()
+            .flat_map(|(y, line)| {
+                line.trim()
+                    .chars()
+                    .enumerate()
+                    .filter(|&(_, c)| c == '#')
+                    .map(move |(x, _)| (x as i64, y as i64))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_glyphs() {
+        for &(c, pattern) in &GLYPHS {
+            assert_eq!(pattern.len(), (GLYPH_WIDTH * GLYPH_HEIGHT) as usize);
+            assert_eq!(recognize_glyph(pattern), Some(c));
+        }
+        assert_eq!(recognize_glyph("########################"), None);
+    }
+
+    #[test]
+    fn test_recognize() {
+        let drawing = r###"
+            #..#.###..####.###.
+            #..#.#..#....#.#..#
+            ####.#..#...#..#..#
+            #..#.###...#...###.
+            #..#.#....#....#.#.
+            #..#.#....####.#..#
+        "###;
+        assert_eq!(recognize(points(drawing)).unwrap(), "HPZR");
+
+        // Offsets don't matter
+        let shifted = points(drawing).into_iter().map(|(x, y)| (x + 7, y - 3));
+        assert_eq!(recognize(shifted).unwrap(), "HPZR");
+
+        assert_eq!(recognize(vec![]).unwrap(), "");
+    }
+
+    #[test]
+    fn test_draw() {
+        let text: String = letters().collect();
+        assert_eq!(recognize(draw(&text).unwrap()).unwrap(), text);
+        assert!(draw("hi").is_err());
+    }
+
+    #[test]
+    fn test_unrecognized() {
+        let drawing = r###"
+            #####
+            #...#
+            #...#
+            #...#
+            #####
+        "###;
+        assert!(recognize(points(drawing)).is_err());
+    }
+}
@@ -0,0 +1,51 @@
+//! Work spread across threads with rayon when built with the `parallel`
+//! feature, and done in order on the current thread without it. Either way
+//! the results are the same, in the same order.
+
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+
+/// How many pieces to split work into: one per thread, or one without the
+/// `parallel` feature
+#[cfg(not(feature = "parallel"))]
+pub fn threads() -> usize {
+    1
+}
+
+/// How many pieces to split work into: one per thread in rayon's pool
+#[cfg(feature = "parallel")]
+pub fn threads() -> usize {
+    rayon::current_num_threads()
+}
+
+/// Applies `f` to every item, in order
+#[cfg(not(feature = "parallel"))]
+pub fn map<T: Sync, U: Send>(items: &[T], f: impl Fn(&T) -> U + Sync + Send) -> Vec<U> {
+    items.iter().map(f).collect()
+}
+
+/// Applies `f` to every item, in order, across rayon's thread pool
+#[cfg(feature = "parallel")]
+pub fn map<T: Sync, U: Send>(items: &[T], f: impl Fn(&T) -> U + Sync + Send) -> Vec<U> {
+    items.par_iter().map(f).collect()
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// Tests
+
+#[cfg(test)]
+mod tests {
+    use test_log::test;
+
+    #[allow(unused_imports)]
+    use super::*;
+
+    #[test]
+    fn test_map() {
+        let items: Vec<u64> = (0..1000).collect();
+        let squares = map(&items, |&n| n * n);
+        assert_eq!(squares, items.iter().map(|n| n * n).collect::<Vec<_>>());
+        assert!(map(&[] as &[u64], |&n| n).is_empty());
+        assert!(threads() >= 1);
+    }
+}
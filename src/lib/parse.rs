@@ -3,8 +3,43 @@ use std::io::BufRead;
 use std::iter::FromIterator;
 use std::str::FromStr;
 
-use log::debug;
-use log::warn;
+use anyhow::bail;
+use tracing::debug;
+use tracing::warn;
+
+/// Parse a whole puzzle input, ignoring surrounding whitespace.
+///
+/// This is the entry point for days that parse their input as a single
+/// `FromStr` type. Those implementations must reject anything left over after
+/// the puzzle's own content: nom-based ones through [`crate::nom::strict`],
+/// and others through [`ensure_consumed`].
+pub fn strict<T>(input: &str) -> anyhow::Result<T>
+where
+    T: FromStr,
+    T::Err: Into<anyhow::Error>,
+{
+    T::from_str(input.trim()).map_err(Into::into)
+}
+
+/// Errors if `rest`, the unparsed end of `input`, has anything but whitespace
+/// in it, pointing out the line where the unexpected content starts.
+pub fn ensure_consumed(input: &str, rest: &str) -> anyhow::Result<()> {
+    let trailing = rest.trim_start();
+    if trailing.is_empty() {
+        return Ok(());
+    }
+
+    let offset = input.len().saturating_sub(trailing.len());
+    let line = input[..offset].matches('\n').count() + 1;
+    let snippet: String = trailing
+        .lines()
+        .next()
+        .unwrap_or_default()
+        .chars()
+        .take(40)
+        .collect();
+    bail!("Unexpected trailing input on line {line}: {snippet:?}")
+}
 
 /// Parse a series of items from lines in a buffer.
 ///
@@ -16,25 +51,77 @@ where
     Item::Err: Into<anyhow::Error> + Display,
     F: FromIterator<Item>,
 {
-    buf.lines()
-        .filter_map(|rl| match rl {
-            Err(e) => {
-                warn!("  Error getting line: {}", e);
-                Some(Err(e.into()))
-            }
-            Ok(l) => {
-                let trimmed = l.trim();
-                if trimmed.is_empty() {
-                    None
-                } else {
-                    let fd = Item::from_str(trimmed);
-                    match fd {
-                        Ok(ref i) => debug!("  Parsed line '{}' -> {:?}", trimmed, i),
-                        Err(ref e) => warn!("  Error parsing line '{}': {}", trimmed, e),
-                    }
-                    Some(fd.map_err(|e| e.into()))
+    lines(buf).collect()
+}
+
+/// Parse items from lines in a buffer one at a time, as [`buffer`] does, for
+/// inputs too big to hold in memory.
+pub fn lines<B, Item>(buf: B) -> impl Iterator<Item = anyhow::Result<Item>>
+where
+    B: BufRead,
+    Item: Debug + FromStr,
+    Item::Err: Into<anyhow::Error> + Display,
+{
+    buf.lines().filter_map(|rl| match rl {
+        Err(e) => {
+            warn!("  Error getting line: {}", e);
+            Some(Err(e.into()))
+        }
+        Ok(l) => {
+            let trimmed = l.trim();
+            if trimmed.is_empty() {
+                None
+            } else {
+                let fd = Item::from_str(trimmed);
+                match fd {
+                    Ok(ref i) => debug!("  Parsed line '{}' -> {:?}", trimmed, i),
+                    Err(ref e) => warn!("  Error parsing line '{}': {}", trimmed, e),
                 }
+                Some(fd.map_err(|e| e.into()))
             }
-        })
-        .collect()
+        }
+    })
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// Tests
+
+#[cfg(test)]
+mod tests {
+    use test_log::test;
+
+    #[allow(unused_imports)]
+    use super::*;
+
+    #[test]
+    fn test_strict() {
+        let n: i64 = strict("  42\n").unwrap();
+        assert_eq!(n, 42);
+        assert!(strict::<i64>("42\nxyz").is_err());
+    }
+
+    #[test]
+    fn test_lines() {
+        let mut items = lines::<_, i64>("1\n\n  2 \nx\n3\n".as_bytes());
+        assert_eq!(items.next().unwrap().unwrap(), 1);
+        assert_eq!(items.next().unwrap().unwrap(), 2);
+        assert!(items.next().unwrap().is_err());
+        assert_eq!(items.next().unwrap().unwrap(), 3);
+        assert!(items.next().is_none());
+    }
+
+    #[test]
+    fn test_ensure_consumed() {
+        let input = "1,2\n3,4\n\n";
+        assert!(ensure_consumed(input, &input[3..]).is_err());
+        ensure_consumed(input, "\n\n").unwrap();
+        ensure_consumed(input, "").unwrap();
+
+        let input = "1,2\n3,4\nxyz\n";
+        let err = ensure_consumed(input, &input[7..]).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Unexpected trailing input on line 3: \"xyz\""
+        );
+    }
 }
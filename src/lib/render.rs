@@ -0,0 +1,307 @@
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+use anyhow::{anyhow, Context};
+use tracing::debug;
+
+pub type Rgb = [u8; 3];
+
+/// Maps cell values to colors. A value of 0 is always the background.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Palette {
+    pub background: Rgb,
+    pub colors: Vec<Rgb>,
+    // If true, values beyond the end of `colors` wrap around; otherwise they
+    // saturate at the last color
+    pub cycle: bool,
+}
+
+impl Palette {
+    /// White on black, for on/off images
+    pub fn mono() -> Self {
+        Palette {
+            background: [0, 0, 0],
+            colors: vec![[255, 255, 255]],
+            cycle: false,
+        }
+    }
+
+    /// Dark blue through red to yellow, for counts and heights
+    pub fn heat() -> Self {
+        Palette {
+            background: [0, 0, 32],
+            colors: vec![
+                [64, 0, 128],
+                [128, 0, 128],
+                [192, 0, 64],
+                [255, 0, 0],
+                [255, 96, 0],
+                [255, 160, 0],
+                [255, 224, 0],
+                [255, 255, 128],
+                [255, 255, 255],
+            ],
+            cycle: false,
+        }
+    }
+
+    /// Distinct colors for labels, repeating as needed
+    pub fn categorical() -> Self {
+        Palette {
+            background: [0, 0, 0],
+            colors: vec![
+                [230, 25, 75],
+                [60, 180, 75],
+                [255, 225, 25],
+                [0, 130, 200],
+                [245, 130, 48],
+                [145, 30, 180],
+                [70, 240, 240],
+                [240, 50, 230],
+                [210, 245, 60],
+                [250, 190, 212],
+                [0, 128, 128],
+                [220, 190, 255],
+            ],
+            cycle: true,
+        }
+    }
+
+    pub fn color(&self, value: usize) -> Rgb {
+        if value == 0 || self.colors.is_empty() {
+            return self.background;
+        }
+
+        let ix = value - 1;
+        if self.cycle {
+            self.colors[ix % self.colors.len()]
+        } else {
+            self.colors[ix.min(self.colors.len() - 1)]
+        }
+    }
+}
+
+/// A grid of cell values, to be colored by a `Palette` when rendered.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Canvas {
+    width: usize,
+    height: usize,
+    cells: Vec<usize>,
+}
+
+impl Canvas {
+    pub fn new(width: usize, height: usize) -> Self {
+        Canvas {
+            width,
+            height,
+            cells: vec![0; width * height],
+        }
+    }
+
+    pub fn from_fn(width: usize, height: usize, f: impl Fn(usize, usize) -> usize) -> Self {
+        let mut canvas = Canvas::new(width, height);
+        for y in 0..height {
+            for x in 0..width {
+                canvas.set(x, y, f(x, y));
+            }
+        }
+        canvas
+    }
+
+    /// Builds a canvas just large enough to hold all the given `((x, y), value)`
+    /// points, shifted so that the minimum x and y are at the origin.
+    pub fn from_points<I: IntoIterator<Item = ((i64, i64), usize)>>(points: I) -> Self {
+        let points: Vec<((i64, i64), usize)> = points.into_iter().collect();
+        if points.is_empty() {
+            return Canvas::new(0, 0);
+        }
+
+        let x0 = points.iter().map(|&((x, _), _)| x).min().unwrap();
+        let x1 = points.iter().map(|&((x, _), _)| x).max().unwrap();
+        let y0 = points.iter().map(|&((_, y), _)| y).min().unwrap();
+        let y1 = points.iter().map(|&((_, y), _)| y).max().unwrap();
+
+        let mut canvas = Canvas::new((x1 - x0 + 1) as usize, (y1 - y0 + 1) as usize);
+        for ((x, y), value) in points {
+            canvas.set((x - x0) as usize, (y - y0) as usize, value);
+        }
+        canvas
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn get(&self, x: usize, y: usize) -> usize {
+        self.cells[y * self.width + x]
+    }
+
+    pub fn set(&mut self, x: usize, y: usize, value: usize) {
+        self.cells[y * self.width + x] = value;
+    }
+}
+
+/// Draws a `Canvas` to PNG or SVG, with each cell taking up `scale` x `scale`
+/// pixels.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Renderer {
+    pub palette: Palette,
+    pub scale: usize,
+}
+
+impl Renderer {
+    pub fn new(palette: Palette) -> Self {
+        Renderer { palette, scale: 1 }
+    }
+
+    pub fn with_scale(self, scale: usize) -> Self {
+        Renderer {
+            scale: scale.max(1),
+            ..self
+        }
+    }
+
+    /// Picks the largest scale at which the canvas fits within `size` pixels
+    /// in each direction (but always at least 1).
+    pub fn fit(self, canvas: &Canvas, size: usize) -> Self {
+        let longest = canvas.width.max(canvas.height).max(1);
+        self.with_scale(size / longest)
+    }
+
+    pub fn write_png<W: Write>(&self, canvas: &Canvas, w: W) -> anyhow::Result<()> {
+        let (width, height) = (canvas.width * self.scale, canvas.height * self.scale);
+        let mut encoder = png::Encoder::new(w, width as u32, height as u32);
+        encoder.set_color(png::ColorType::Rgb);
+        encoder.set_depth(png::BitDepth::Eight);
+        let mut writer = encoder.write_header()?;
+
+        let mut data = Vec::with_capacity(width * height * 3);
+        for y in 0..height {
+            for x in 0..width {
+                let value = canvas.get(x / self.scale, y / self.scale);
+                data.extend(self.palette.color(value));
+            }
+        }
+        writer.write_image_data(&data)?;
+        Ok(())
+    }
+
+    pub fn write_svg<W: Write>(&self, canvas: &Canvas, mut w: W) -> anyhow::Result<()> {
+        let (width, height) = (canvas.width * self.scale, canvas.height * self.scale);
+        let hex = |[r, g, b]: Rgb| format!("#{r:02x}{g:02x}{b:02x}");
+
+        writeln!(
+            w,
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="{width}" height="{height}" viewBox="0 0 {width} {height}">"#
+        )?;
+        writeln!(
+            w,
+            r#"<rect width="{width}" height="{height}" fill="{}"/>"#,
+            hex(self.palette.background)
+        )?;
+        for y in 0..canvas.height {
+            for x in 0..canvas.width {
+                let value = canvas.get(x, y);
+                if value == 0 {
+                    continue;
+                }
+                writeln!(
+                    w,
+                    r#"<rect x="{}" y="{}" width="{s}" height="{s}" fill="{}"/>"#,
+                    x * self.scale,
+                    y * self.scale,
+                    hex(self.palette.color(value)),
+                    s = self.scale,
+                )?;
+            }
+        }
+        writeln!(w, "</svg>")?;
+        Ok(())
+    }
+
+    /// Saves the canvas to a file, choosing the format from the extension
+    /// (`.png` or `.svg`).
+    pub fn save<P: AsRef<Path>>(&self, canvas: &Canvas, path: P) -> anyhow::Result<()> {
+        let path = path.as_ref();
+        let extension = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(|e| e.to_ascii_lowercase());
+
+        debug!(
+            "Rendering {}x{} canvas to {}",
+            canvas.width,
+            canvas.height,
+            path.display()
+        );
+        let file = File::create(path).with_context(|| format!("creating {}", path.display()))?;
+        let w = BufWriter::new(file);
+        match extension.as_deref() {
+            Some("png") => self.write_png(canvas, w),
+            Some("svg") => self.write_svg(canvas, w),
+            _ => Err(anyhow!(
+                "Unknown image format for {}; expected .png or .svg",
+                path.display()
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use test_log::test;
+
+    use super::*;
+
+    #[test]
+    fn test_palette() {
+        let heat = Palette::heat();
+        assert_eq!(heat.color(0), heat.background);
+        assert_eq!(heat.color(1), heat.colors[0]);
+        assert_eq!(heat.color(100), *heat.colors.last().unwrap());
+
+        let labels = Palette::categorical();
+        assert_eq!(labels.color(1), labels.color(labels.colors.len() + 1));
+    }
+
+    #[test]
+    fn test_points() {
+        let canvas = Canvas::from_points([((-1, 2), 1), ((1, 3), 2)]);
+        assert_eq!((canvas.width(), canvas.height()), (3, 2));
+        assert_eq!(canvas.get(0, 0), 1);
+        assert_eq!(canvas.get(2, 1), 2);
+        assert_eq!(canvas.get(1, 0), 0);
+    }
+
+    #[test]
+    fn test_png() {
+        let canvas = Canvas::from_fn(3, 2, |x, y| x + y);
+        let renderer = Renderer::new(Palette::mono()).with_scale(2);
+        let mut data = Vec::new();
+        renderer.write_png(&canvas, &mut data).unwrap();
+
+        let decoder = png::Decoder::new(data.as_slice());
+        let reader = decoder.read_info().unwrap();
+        let info = reader.info();
+        assert_eq!((info.width, info.height), (6, 4));
+    }
+
+    #[test]
+    fn test_svg() {
+        let canvas = Canvas::from_points([((0, 0), 1), ((4, 1), 1)]);
+        let renderer = Renderer::new(Palette::mono()).fit(&canvas, 50);
+        assert_eq!(renderer.scale, 10);
+
+        let mut data = Vec::new();
+        renderer.write_svg(&canvas, &mut data).unwrap();
+        let svg = String::from_utf8(data).unwrap();
+        assert!(svg.starts_with("<svg"));
+        assert!(svg.contains(r#"width="50" height="20""#));
+        assert_eq!(svg.matches("#ffffff").count(), 2);
+    }
+}
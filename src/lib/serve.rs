@@ -0,0 +1,177 @@
+//! A small HTTP server answering puzzle inputs, for `aoc serve`.
+//!
+//! `POST /solve/<day>` with the raw puzzle input as the body returns the
+//! answers as JSON:
+//!
+//! ```json
+//! {"day": 1, "part1": "7", "part2": "5"}
+//! ```
+//!
+//! Errors come back as `{"error": "..."}` with a 4xx or 5xx status.
+
+use std::io::Read;
+use std::net::IpAddr;
+
+use anyhow::anyhow;
+use serde::Serialize;
+use serde_json::json;
+use tracing::{info, info_span, warn};
+
+use crate::solution::{self, Solved};
+
+/// Largest request body accepted; real inputs are well under 100KiB
+const MAX_BODY: u64 = 16 << 20;
+
+/// A JSON response and its HTTP status
+#[derive(Debug, Clone, PartialEq)]
+pub struct Response {
+    pub status: u16,
+    pub body: serde_json::Value,
+}
+
+impl Response {
+    fn error(status: u16, message: impl std::fmt::Display) -> Self {
+        Response {
+            status,
+            body: json!({ "error": message.to_string() }),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct Answer<'a> {
+    day: u8,
+    #[serde(flatten)]
+    solved: &'a Solved,
+}
+
+/// Routes one request. `body` is the puzzle input for `/solve/<day>`.
+pub fn handle(method: &str, path: &str, body: &str) -> Response {
+    let Some(day) = path.strip_prefix("/solve/") else {
+        return Response::error(404, format!("No such endpoint: {path}"));
+    };
+    if method != "POST" {
+        return Response::error(405, format!("Use POST for {path}, not {method}"));
+    }
+    let Some(solution) = day.parse().ok().and_then(solution::find) else {
+        return Response::error(404, format!("No solution for day {day:?}"));
+    };
+
+    let _span = info_span!("solve", day = solution.day).entered();
+    match std::panic::catch_unwind(|| (solution.solve)(body)) {
+        Ok(Ok(solved)) => Response {
+            status: 200,
+            body: serde_json::to_value(Answer {
+                day: solution.day,
+                solved: &solved,
+            })
+            .unwrap(),
+        },
+        Ok(Err(e)) => Response::error(400, format!("{e:#}")),
+        Err(_) => Response::error(500, format!("{} panicked", solution.name())),
+    }
+}
+
+/// Serves requests on `addr:port` until the process is killed, solving them
+/// on a fixed pool of a thread per core. Requests beyond that wait their turn.
+pub fn serve(addr: IpAddr, port: u16) -> anyhow::Result<()> {
+    let server = tiny_http::Server::http((addr, port)).map_err(|e| anyhow!(e))?;
+    eprintln!("Listening on http://{}", server.server_addr());
+
+    let workers = std::thread::available_parallelism().map_or(1, |n| n.get());
+    let server = &server;
+    std::thread::scope(|scope| {
+        for _ in 0..workers {
+            scope.spawn(move || server.incoming_requests().for_each(respond));
+        }
+    });
+    Ok(())
+}
+
+/// Reads a request body of at most `limit` bytes; anything longer is an
+/// error response rather than cut short.
+fn read_body(reader: impl Read, limit: u64) -> Result<String, Response> {
+    let mut body = String::new();
+    // One byte over, to tell a body at the limit from one past it
+    match reader.take(limit + 1).read_to_string(&mut body) {
+        Ok(_) if body.len() as u64 > limit => Err(Response::error(
+            413,
+            format!("Request body is over the limit of {limit} bytes"),
+        )),
+        Ok(_) => Ok(body),
+        Err(e) => Err(Response::error(
+            400,
+            format!("Could not read request body: {e}"),
+        )),
+    }
+}
+
+fn respond(mut request: tiny_http::Request) {
+    let method = request.method().to_string();
+    let path = request.url().to_owned();
+
+    let response = match read_body(request.as_reader(), MAX_BODY) {
+        Ok(body) => handle(&method, &path, &body),
+        Err(response) => response,
+    };
+    info!("{method} {path} -> {}", response.status);
+
+    let header = "Content-Type: application/json"
+        .parse::<tiny_http::Header>()
+        .unwrap();
+    let reply = tiny_http::Response::from_string(response.body.to_string())
+        .with_status_code(response.status)
+        .with_header(header);
+    if let Err(e) = request.respond(reply) {
+        warn!("Could not respond to {method} {path}: {e}");
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// Tests
+
+#[cfg(test)]
+mod tests {
+    use test_log::test;
+
+    #[allow(unused_imports)]
+    use super::*;
+
+    const DAY01: &str = "199\n200\n208\n210\n200\n207\n240\n269\n260\n263\n";
+
+    #[test]
+    fn test_solve() {
+        let response = handle("POST", "/solve/1", DAY01);
+        assert_eq!(response.status, 200);
+        assert_eq!(
+            response.body,
+            json!({ "day": 1, "part1": "7", "part2": "5" })
+        );
+
+        assert_eq!(handle("POST", "/solve/01", DAY01), response);
+    }
+
+    #[test]
+    fn test_errors() {
+        assert_eq!(handle("POST", "/solve/26", DAY01).status, 404);
+        assert_eq!(handle("POST", "/solve/one", DAY01).status, 404);
+        assert_eq!(handle("POST", "/answers", DAY01).status, 404);
+        assert_eq!(handle("GET", "/solve/1", "").status, 405);
+
+        let response = handle("POST", "/solve/1", "199\nabc\n");
+        assert_eq!(response.status, 400);
+        assert!(response.body["error"].is_string());
+    }
+
+    #[test]
+    fn test_read_body() {
+        assert_eq!(read_body(DAY01.as_bytes(), 100).unwrap(), DAY01);
+        let limit = DAY01.len() as u64;
+        assert_eq!(read_body(DAY01.as_bytes(), limit).unwrap(), DAY01);
+        assert_eq!(
+            read_body(DAY01.as_bytes(), limit - 1).unwrap_err().status,
+            413
+        );
+        assert_eq!(read_body(&[0xff, 0xfe][..], 100).unwrap_err().status, 400);
+    }
+}
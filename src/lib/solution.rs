@@ -0,0 +1,329 @@
+use std::collections::BTreeMap;
+use std::fmt::Display;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::Instant;
+
+use anyhow::{anyhow, bail, Context};
+use parse_display::Display;
+use serde::{Deserialize, Serialize};
+use tracing::info_span;
+
+use crate::table::Table;
+use crate::trace::format_duration;
+use crate::{
+    day01, day02, day03, day04, day05, day06, day07, day08, day09, day10, day11, day12, day13,
+    day14, day15, day16, day17, day18, day19, day20, day21, day22, day23, day24, day25,
+};
+
+/// The answers to one day's puzzle
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Solved {
+    pub part1: String,
+    /// Empty for puzzles with only one part, like day 25
+    pub part2: Option<String>,
+}
+
+impl Solved {
+    pub fn new(part1: impl Display, part2: impl Display) -> Self {
+        Solved {
+            part1: part1.to_string(),
+            part2: Some(part2.to_string()),
+        }
+    }
+
+    pub fn part1_only(part1: impl Display) -> Self {
+        Solved {
+            part1: part1.to_string(),
+            part2: None,
+        }
+    }
+}
+
+/// Roughly how long a day takes on a real input, in a release build
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Display)]
+#[display(style = "lowercase")]
+pub enum Runtime {
+    /// Under 10ms
+    Fast,
+    /// Under 100ms
+    Moderate,
+    /// 100ms or more
+    Slow,
+}
+
+/// A puzzle solver, taking the full text of a puzzle input
+#[derive(Debug, Clone, Copy)]
+pub struct Solution {
+    pub day: u8,
+    /// The puzzle's name, e.g. "Sonar Sweep"
+    pub title: &'static str,
+    /// Whether `solve` answers part 2; false for day 25, which has none
+    pub part2: bool,
+    pub runtime: Runtime,
+    pub solve: fn(&str) -> anyhow::Result<Solved>,
+}
+
+impl Solution {
+    /// The binary and input name, e.g. `day07`
+    pub fn name(&self) -> String {
+        format!("day{:02}", self.day)
+    }
+
+    /// Runs the solution on each input in turn, returning a table of answers
+    /// and run times. A failure on one input is shown in its row, and does not
+    /// stop the others.
+    pub fn compare(&self, inputs: &[PathBuf]) -> Table {
+        let mut table = Table::new(["input", "part1", "part2", "time"]);
+        for path in inputs {
+            let name = path.display().to_string();
+            let input = match std::fs::read_to_string(path) {
+                Ok(input) => input,
+                Err(e) => {
+                    table.push([name, format!("error: {e}")]);
+                    continue;
+                }
+            };
+
+            let span = info_span!("solve", input = name.as_str());
+            let start = Instant::now();
+            let result = span.in_scope(|| (self.solve)(&input));
+            let elapsed = format_duration(start.elapsed());
+            match result {
+                Ok(solved) => {
+                    let part2 = solved.part2.unwrap_or_default();
+                    table.push([name, solved.part1, part2, elapsed]);
+                }
+                Err(e) => table.push([name, format!("error: {e:#}"), String::new(), elapsed]),
+            }
+        }
+        table
+    }
+}
+
+macro_rules! solutions {
+    (@part2) => { true };
+    (@part2 part1) => { false };
+    ($($day:literal => $module:ident($title:literal, $runtime:ident $(, $only:ident only)?)),* $(,)?) => {
+        &[$(Solution {
+            day: $day,
+            title: $title,
+            part2: solutions!(@part2 $($only)?),
+            runtime: Runtime::$runtime,
+            solve: $module::solve,
+        }),*]
+    };
+}
+
+/// Every implemented day, in order
+pub static SOLUTIONS: &[Solution] = solutions![
+    1 => day01("Sonar Sweep", Fast),
+    2 => day02("Dive!", Fast),
+    3 => day03("Binary Diagnostic", Fast),
+    4 => day04("Giant Squid", Fast),
+    5 => day05("Hydrothermal Venture", Moderate),
+    6 => day06("Lanternfish", Fast),
+    7 => day07("The Treachery of Whales", Fast),
+    8 => day08("Seven Segment Search", Fast),
+    9 => day09("Smoke Basin", Fast),
+    10 => day10("Syntax Scoring", Fast),
+    11 => day11("Dumbo Octopus", Fast),
+    12 => day12("Passage Pathing", Slow),
+    13 => day13("Transparent Origami", Fast),
+    14 => day14("Extended Polymerization", Fast),
+    15 => day15("Chiton", Slow),
+    16 => day16("Packet Decoder", Fast),
+    17 => day17("Trick Shot", Fast),
+    18 => day18("Snailfish", Moderate),
+    19 => day19("Beacon Scanner", Moderate),
+    20 => day20("Trench Map", Moderate),
+    21 => day21("Dirac Dice", Fast),
+    22 => day22("Reactor Reboot", Fast),
+    23 => day23("Amphipod", Slow),
+    24 => day24("Arithmetic Logic Unit", Fast),
+    25 => day25("Sea Cucumber", Moderate, part1 only),
+];
+
+/// Looks up the solution for a day
+pub fn find(day: u8) -> Option<&'static Solution> {
+    SOLUTIONS.iter().find(|s| s.day == day)
+}
+
+/// Looks up a solution by its binary name, e.g. `day07`
+pub fn by_name(name: &str) -> Option<&'static Solution> {
+    SOLUTIONS.iter().find(|s| s.name() == name)
+}
+
+/// Runs the day binary `name` on several inputs, printing a comparison table
+pub fn compare(name: &str, inputs: &[PathBuf]) -> anyhow::Result<()> {
+    let solution = by_name(name).ok_or_else(|| anyhow!("No solution registered for {name}"))?;
+    print!("{}", solution.compare(inputs));
+    Ok(())
+}
+
+/// Known-good answers, keyed by day, as loaded from an answers file:
+///
+/// ```toml
+/// [day01]
+/// part1 = 1121
+/// part2 = 1065
+///
+/// [day13]
+/// part1 = 720
+/// part2 = "AHPRPAUZ"
+/// ```
+///
+/// Answers may be written as strings or numbers. A missing `part2` is not
+/// checked.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Expected(pub BTreeMap<u8, Solved>);
+
+impl Expected {
+    pub fn from_file(path: &Path) -> anyhow::Result<Self> {
+        let s =
+            std::fs::read_to_string(path).with_context(|| format!("reading {}", path.display()))?;
+        s.parse()
+            .with_context(|| format!("parsing {}", path.display()))
+    }
+
+    pub fn get(&self, day: u8) -> Option<&Solved> {
+        self.0.get(&day)
+    }
+
+    /// Whether `solved` matches the expected answers for `day`; `None` if
+    /// there are no expected answers for that day.
+    pub fn check(&self, day: u8, solved: &Solved) -> Option<bool> {
+        let expected = self.get(day)?;
+        let part2_ok = expected.part2.is_none() || expected.part2 == solved.part2;
+        Some(expected.part1 == solved.part1 && part2_ok)
+    }
+}
+
+impl FromStr for Expected {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let table: BTreeMap<String, BTreeMap<String, toml::Value>> = toml::from_str(s)?;
+
+        let mut expected = BTreeMap::new();
+        for (name, parts) in table {
+            let day = name
+                .strip_prefix("day")
+                .and_then(|n| n.parse::<u8>().ok())
+                .ok_or_else(|| anyhow!("Expected a section like [day01], found [{name}]"))?;
+
+            let answer = |part: &str| -> anyhow::Result<Option<String>> {
+                match parts.get(part) {
+                    None => Ok(None),
+                    Some(toml::Value::String(s)) => Ok(Some(s.clone())),
+                    Some(toml::Value::Integer(n)) => Ok(Some(n.to_string())),
+                    Some(v) => Err(anyhow!(
+                        "[{name}] {part}: expected a string or integer, found {v}"
+                    )),
+                }
+            };
+            let part1 = answer("part1")?.ok_or_else(|| anyhow!("[{name}] is missing part1"))?;
+            let part2 = answer("part2")?;
+            if let Some(other) = parts.keys().find(|k| *k != "part1" && *k != "part2") {
+                bail!("[{name}]: unexpected key {other}");
+            }
+            expected.insert(day, Solved { part1, part2 });
+        }
+
+        Ok(Expected(expected))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use test_log::test;
+
+    use super::*;
+
+    #[test]
+    fn test_registry() {
+        for (ix, solution) in SOLUTIONS.iter().enumerate() {
+            assert_eq!(solution.day as usize, ix + 1);
+        }
+        assert_eq!(find(7).unwrap().name(), "day07");
+        assert!(find(26).is_none());
+        assert_eq!(by_name("day07").unwrap().day, 7);
+        assert!(by_name("template").is_none());
+
+        let day25 = find(25).unwrap();
+        assert_eq!((day25.title, day25.part2), ("Sea Cucumber", false));
+        assert_eq!(find(23).unwrap().runtime.to_string(), "slow");
+    }
+
+    #[test]
+    fn test_part2_metadata() {
+        for solution in SOLUTIONS {
+            let generator = crate::gen::find(solution.day).unwrap();
+            // Tiny day 25 grids can cycle forever; this one settles
+            let size = match solution.day {
+                25 => 10,
+                _ => (*generator.sizes.start()).max(2),
+            };
+            let input = generator.generate(Some(size), 0).unwrap();
+            let solved = (solution.solve)(&input).unwrap();
+            assert_eq!(
+                solved.part2.is_some(),
+                solution.part2,
+                "{} part2 metadata is wrong",
+                solution.name()
+            );
+        }
+    }
+
+    #[test]
+    fn test_solve() {
+        let solution = find(1).unwrap();
+        let solved =
+            (solution.solve)("199\n200\n208\n210\n200\n207\n240\n269\n260\n263\n").unwrap();
+        assert_eq!(solved, Solved::new(7, 5));
+
+        assert!((solution.solve)("199\nabc\n").is_err());
+    }
+
+    #[test]
+    fn test_trailing_input() {
+        for solution in SOLUTIONS {
+            let generator = crate::gen::find(solution.day).unwrap();
+            let input = generator
+                .generate(Some(*generator.sizes.start()), 0)
+                .unwrap();
+            let input = input.trim_end();
+            for bad in [format!("{input}\ngarbage!\n"), format!("{input}xyz\n")] {
+                assert!(
+                    (solution.solve)(&bad).is_err(),
+                    "{} accepted trailing input:\n{bad}",
+                    solution.name()
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_expected() {
+        let expected: Expected = r#"
+            [day01]
+            part1 = 7
+            part2 = "5"
+
+            [day25]
+            part1 = 58
+        "#
+        .parse()
+        .unwrap();
+
+        assert_eq!(expected.check(1, &Solved::new(7, 5)), Some(true));
+        assert_eq!(expected.check(1, &Solved::new(7, 6)), Some(false));
+        assert_eq!(expected.check(25, &Solved::new(58, "anything")), Some(true));
+        assert_eq!(expected.check(2, &Solved::new(1, 2)), None);
+
+        assert!("[dayone]\npart1 = 1".parse::<Expected>().is_err());
+        assert!("[day01]\npart2 = 1".parse::<Expected>().is_err());
+        assert!("[day01]\npart1 = 1.5".parse::<Expected>().is_err());
+        assert!("[day01]\npart1 = 1\npart3 = 2".parse::<Expected>().is_err());
+    }
+}
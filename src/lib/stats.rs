@@ -0,0 +1,362 @@
+//! Counters describing how much work a solver did: states expanded, queue
+//! sizes, hash map entries, and bytes allocated.
+//!
+//! Solvers report counters as `tracing` events with the target `"stats"`,
+//! once per search rather than once per step:
+//!
+//! ```
+//! # let (expanded, queue_peak) = (0usize, 0usize);
+//! tracing::info!(target: "stats", states_expanded = expanded, queue_peak);
+//! ```
+//!
+//! A [`StatsLayer`] adds these up for each top-level span (`parse`, `part1`,
+//! ...). Fields with `peak` in their name keep their largest value instead of
+//! being added, and fields ending in `bytes` are printed as sizes.
+//!
+//! Binaries that install [`TrackingAllocator`] as their global allocator also
+//! get `allocated_bytes` and `heap_peak_bytes` for each stage.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::fmt::Write;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+use tracing::field::{Field, Visit};
+use tracing::{span, Event, Subscriber};
+use tracing_subscriber::layer::Context;
+use tracing_subscriber::registry::LookupSpan;
+use tracing_subscriber::Layer;
+
+/// The `tracing` target of counter events
+pub const TARGET: &str = "stats";
+
+/// A stage name, and its counters in the order they were first recorded
+pub type Stage = (String, Vec<(String, u64)>);
+
+/// Counters recorded for each top-level span, in the order the spans started.
+#[must_use = "stats are reported when dropped"]
+#[derive(Debug, Clone, Default)]
+pub struct Stats {
+    stages: Arc<Mutex<Vec<Stage>>>,
+    report_on_drop: bool,
+}
+
+impl Stats {
+    pub(crate) fn new(report_on_drop: bool) -> Self {
+        Stats {
+            stages: Default::default(),
+            report_on_drop,
+        }
+    }
+
+    /// A layer recording into these stats
+    pub fn layer(&self) -> StatsLayer {
+        StatsLayer {
+            stages: Arc::clone(&self.stages),
+        }
+    }
+
+    pub fn stages(&self) -> Vec<Stage> {
+        self.stages.lock().unwrap().clone()
+    }
+
+    /// The value of counter `name` in stage `stage`, if it was recorded
+    pub fn get(&self, stage: &str, name: &str) -> Option<u64> {
+        let stages = self.stages.lock().unwrap();
+        let (_, counters) = stages.iter().find(|(s, _)| s == stage)?;
+        counters.iter().find(|(n, _)| n == name).map(|&(_, v)| v)
+    }
+
+    /// Formats the stats with one line per stage, like
+    /// `part1: states_expanded=1200, queue_peak=85, allocated_bytes=1.5MiB`
+    pub fn report(&self) -> String {
+        let mut s = String::new();
+        for (stage, counters) in self.stages() {
+            if counters.is_empty() {
+                continue;
+            }
+            if !s.is_empty() {
+                s.push('\n');
+            }
+            write!(s, "{stage}: ").unwrap();
+            for (ix, (name, value)) in counters.iter().enumerate() {
+                if ix > 0 {
+                    s.push_str(", ");
+                }
+                if name.ends_with("bytes") {
+                    write!(s, "{name}={}", format_bytes(*value)).unwrap();
+                } else {
+                    write!(s, "{name}={value}").unwrap();
+                }
+            }
+        }
+        s
+    }
+}
+
+impl Drop for Stats {
+    fn drop(&mut self) {
+        let report = self.report();
+        if self.report_on_drop && !report.is_empty() {
+            eprintln!("{report}");
+        }
+    }
+}
+
+fn record(counters: &mut Vec<(String, u64)>, name: &str, value: u64) {
+    let peak = name.contains("peak");
+    match counters.iter_mut().find(|(n, _)| n == name) {
+        Some((_, total)) if peak => *total = (*total).max(value),
+        Some((_, total)) => *total += value,
+        None => counters.push((name.to_owned(), value)),
+    }
+}
+
+/// Allocator totals when a top-level span started
+struct StageStart {
+    allocated: u64,
+}
+
+/// Adds up counter events for each top-level span.
+pub struct StatsLayer {
+    stages: Arc<Mutex<Vec<Stage>>>,
+}
+
+impl StatsLayer {
+    fn record(&self, stage: &str, name: &str, value: u64) {
+        let mut stages = self.stages.lock().unwrap();
+        let counters = match stages.iter().position(|(s, _)| s == stage) {
+            Some(ix) => &mut stages[ix].1,
+            None => {
+                stages.push((stage.to_owned(), Vec::new()));
+                &mut stages.last_mut().unwrap().1
+            }
+        };
+        record(counters, name, value);
+    }
+}
+
+impl<S> Layer<S> for StatsLayer
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn on_new_span(&self, _attrs: &span::Attributes<'_>, id: &span::Id, ctx: Context<'_, S>) {
+        let Some(span) = ctx.span(id) else {
+            return;
+        };
+        if span.parent().is_none() {
+            TrackingAllocator::reset_peak();
+            span.extensions_mut().insert(StageStart {
+                allocated: TrackingAllocator::allocated(),
+            });
+        }
+    }
+
+    fn on_event(&self, event: &Event<'_>, ctx: Context<'_, S>) {
+        if event.metadata().target() != TARGET {
+            return;
+        }
+        let stage = ctx
+            .event_scope(event)
+            .and_then(|scope| scope.from_root().next())
+            .map(|span| span.name())
+            .unwrap_or("main");
+
+        let mut visitor = CounterVisitor(Vec::new());
+        event.record(&mut visitor);
+        for (name, value) in visitor.0 {
+            self.record(stage, name, value);
+        }
+    }
+
+    fn on_close(&self, id: span::Id, ctx: Context<'_, S>) {
+        let Some(span) = ctx.span(&id) else {
+            return;
+        };
+        let Some(start) = span.extensions_mut().remove::<StageStart>() else {
+            return;
+        };
+        if !TrackingAllocator::installed() {
+            return;
+        }
+
+        let allocated = TrackingAllocator::allocated() - start.allocated;
+        self.record(span.name(), "allocated_bytes", allocated);
+        self.record(span.name(), "heap_peak_bytes", TrackingAllocator::peak());
+    }
+}
+
+/// Collects the integer fields of an event
+struct CounterVisitor(Vec<(&'static str, u64)>);
+
+impl Visit for CounterVisitor {
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        self.0.push((field.name(), value));
+    }
+
+    fn record_i64(&mut self, field: &Field, value: i64) {
+        self.0.push((field.name(), value.max(0) as u64));
+    }
+
+    fn record_debug(&mut self, _field: &Field, _value: &dyn std::fmt::Debug) {}
+}
+
+static ALLOCATIONS: AtomicU64 = AtomicU64::new(0);
+static ALLOCATED: AtomicU64 = AtomicU64::new(0);
+static IN_USE: AtomicU64 = AtomicU64::new(0);
+static PEAK: AtomicU64 = AtomicU64::new(0);
+
+/// A global allocator that counts bytes allocated, on top of the system
+/// allocator. Install it in a binary with
+///
+/// ```ignore
+/// #[global_allocator]
+/// static ALLOCATOR: TrackingAllocator = TrackingAllocator;
+/// ```
+pub struct TrackingAllocator;
+
+impl TrackingAllocator {
+    /// Whether anything has been allocated through this allocator, i.e.
+    /// whether it is installed
+    pub fn installed() -> bool {
+        ALLOCATIONS.load(Ordering::Relaxed) > 0
+    }
+
+    /// Total bytes allocated so far, including those since freed
+    pub fn allocated() -> u64 {
+        ALLOCATED.load(Ordering::Relaxed)
+    }
+
+    /// Bytes currently allocated
+    pub fn in_use() -> u64 {
+        IN_USE.load(Ordering::Relaxed)
+    }
+
+    /// The most bytes allocated at once since the last `reset_peak`
+    pub fn peak() -> u64 {
+        PEAK.load(Ordering::Relaxed)
+    }
+
+    pub fn reset_peak() {
+        PEAK.store(IN_USE.load(Ordering::Relaxed), Ordering::Relaxed);
+    }
+
+    fn track_alloc(size: usize) {
+        let size = size as u64;
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        ALLOCATED.fetch_add(size, Ordering::Relaxed);
+        let in_use = IN_USE.fetch_add(size, Ordering::Relaxed) + size;
+        PEAK.fetch_max(in_use, Ordering::Relaxed);
+    }
+
+    fn track_dealloc(size: usize) {
+        IN_USE.fetch_sub(size as u64, Ordering::Relaxed);
+    }
+}
+
+unsafe impl GlobalAlloc for TrackingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc(layout);
+        if !ptr.is_null() {
+            Self::track_alloc(layout.size());
+        }
+        ptr
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc_zeroed(layout);
+        if !ptr.is_null() {
+            Self::track_alloc(layout.size());
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout);
+        Self::track_dealloc(layout.size());
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let new_ptr = System.realloc(ptr, layout, new_size);
+        if !new_ptr.is_null() {
+            Self::track_dealloc(layout.size());
+            Self::track_alloc(new_size);
+        }
+        new_ptr
+    }
+}
+
+/// Formats a number of bytes with a binary unit: `512B`, `1.5KiB`, `40MiB`.
+pub fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["KiB", "MiB", "GiB", "TiB"];
+    if bytes < 1024 {
+        return format!("{bytes}B");
+    }
+
+    let mut value = bytes as f64;
+    let mut unit = "B";
+    for u in UNITS {
+        if value < 1024.0 {
+            break;
+        }
+        value /= 1024.0;
+        unit = u;
+    }
+
+    if value < 10.0 {
+        format!("{value:.1}{unit}")
+    } else {
+        format!("{value:.0}{unit}")
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// Tests
+
+#[cfg(test)]
+mod tests {
+    use test_log::test;
+    use tracing::{info, info_span};
+    use tracing_subscriber::layer::SubscriberExt;
+
+    #[allow(unused_imports)]
+    use super::*;
+
+    #[test]
+    fn test_format_bytes() {
+        assert_eq!(format_bytes(512), "512B");
+        assert_eq!(format_bytes(1536), "1.5KiB");
+        assert_eq!(format_bytes(40 << 20), "40MiB");
+        assert_eq!(format_bytes(3 << 30), "3.0GiB");
+    }
+
+    #[test]
+    fn test_stats() {
+        let stats = Stats::new(false);
+        let subscriber = tracing_subscriber::registry().with(stats.layer());
+        tracing::subscriber::with_default(subscriber, || {
+            info_span!("part1").in_scope(|| {
+                info!(target: TARGET, states_expanded = 10, queue_peak = 4);
+                info_span!("search").in_scope(|| {
+                    info!(target: TARGET, states_expanded = 5u64, queue_peak = 3usize);
+                });
+                // Events for other targets are ignored
+                info!(states_expanded = 100);
+            });
+            info_span!("part2").in_scope(|| {
+                info!(target: TARGET, entries = 7, note = "ignored");
+            });
+        });
+
+        assert_eq!(stats.get("part1", "states_expanded"), Some(15));
+        assert_eq!(stats.get("part1", "queue_peak"), Some(4));
+        assert_eq!(stats.get("part2", "entries"), Some(7));
+        assert_eq!(stats.get("part2", "note"), None);
+        // Not installed in tests, so there are no allocator counters
+        assert_eq!(stats.get("part1", "allocated_bytes"), None);
+        assert_eq!(
+            stats.report(),
+            "part1: states_expanded=15, queue_peak=4\npart2: entries=7"
+        );
+    }
+}
@@ -0,0 +1,95 @@
+use std::fmt::{self, Display, Write};
+
+/// A plain-text table with left-aligned, space-padded columns.
+///
+/// ```text
+/// day    part1  part2
+/// -----  -----  -----
+/// day01  1228   1257
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Table {
+    headers: Vec<String>,
+    rows: Vec<Vec<String>>,
+}
+
+impl Table {
+    pub fn new<S: Into<String>>(headers: impl IntoIterator<Item = S>) -> Self {
+        Table {
+            headers: headers.into_iter().map(Into::into).collect(),
+            rows: Vec::new(),
+        }
+    }
+
+    /// Adds a row; missing cells are left blank, and extra cells are ignored.
+    pub fn push<S: Into<String>>(&mut self, row: impl IntoIterator<Item = S>) {
+        let mut row: Vec<String> = row.into_iter().map(Into::into).collect();
+        row.resize(self.headers.len(), String::new());
+        self.rows.push(row);
+    }
+
+    pub fn len(&self) -> usize {
+        self.rows.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rows.is_empty()
+    }
+
+    fn widths(&self) -> Vec<usize> {
+        let mut widths: Vec<usize> = self.headers.iter().map(|h| h.chars().count()).collect();
+        for row in &self.rows {
+            for (width, cell) in widths.iter_mut().zip(row) {
+                *width = (*width).max(cell.chars().count());
+            }
+        }
+        widths
+    }
+}
+
+fn write_row(f: &mut fmt::Formatter<'_>, widths: &[usize], cells: &[String]) -> fmt::Result {
+    let mut line = String::new();
+    for (ix, (cell, &width)) in cells.iter().zip(widths).enumerate() {
+        if ix > 0 {
+            line.push_str("  ");
+        }
+        write!(line, "{cell:width$}")?;
+    }
+    writeln!(f, "{}", line.trim_end())
+}
+
+impl Display for Table {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let widths = self.widths();
+        write_row(f, &widths, &self.headers)?;
+        let rules: Vec<String> = widths.iter().map(|&w| "-".repeat(w)).collect();
+        write_row(f, &widths, &rules)?;
+        for row in &self.rows {
+            write_row(f, &widths, row)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use test_log::test;
+
+    use super::*;
+
+    #[test]
+    fn test_table() {
+        let mut table = Table::new(["day", "part1", "part2"]);
+        table.push(["day01", "1228", "1257"]);
+        table.push(["day25", "not-a-number"]);
+        assert_eq!(table.len(), 2);
+
+        let expected = "\
+day    part1         part2
+-----  ------------  -----
+day01  1228          1257
+day25  not-a-number
+";
+        assert_eq!(table.to_string(), expected);
+    }
+}
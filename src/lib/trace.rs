@@ -0,0 +1,213 @@
+use std::fmt::Write;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use tracing::span;
+use tracing::Subscriber;
+use tracing_subscriber::fmt::format::FmtSpan;
+use tracing_subscriber::layer::{Context, SubscriberExt};
+use tracing_subscriber::registry::LookupSpan;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::{EnvFilter, Layer};
+
+use crate::stats::Stats;
+
+// Command-line flags for logging and span timing; use with `#[clap(flatten)]`.
+#[derive(clap::Args, Debug, Clone, PartialEq, Eq)]
+pub struct TraceArgs {
+    /// Log every span's busy and idle time as it closes
+    #[clap(long)]
+    pub trace: bool,
+
+    /// Print the wall-clock time of each stage (parse, part1, part2, ...)
+    #[clap(long)]
+    pub time: bool,
+
+    /// Print each stage's search counters and memory use, where recorded
+    #[clap(long)]
+    pub stats: bool,
+}
+
+impl TraceArgs {
+    /// Installs the global subscriber, writing to stderr.
+    ///
+    /// Events are filtered by `RUST_LOG` as before. With `--trace`, the
+    /// default level is raised to `info`, and each span reports its busy and
+    /// idle time when it closes. With `--time`, the returned `Reports` prints
+    /// a one-line summary of the top-level spans when dropped, and with
+    /// `--stats`, the counters recorded in each of them.
+    pub fn init(&self) -> Reports {
+        let default = if self.trace { "info" } else { "error" };
+        let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new(default));
+
+        let events = if self.trace {
+            FmtSpan::CLOSE
+        } else {
+            FmtSpan::NONE
+        };
+
+        let timings = Timings::new(self.time);
+        let stats = Stats::new(self.stats);
+        let fmt = tracing_subscriber::fmt::layer()
+            .with_span_events(events)
+            .with_writer(std::io::stderr)
+            .with_filter(filter);
+        tracing_subscriber::registry()
+            .with(fmt)
+            .with(self.time.then(|| timings.layer()))
+            .with(self.stats.then(|| stats.layer()))
+            .init();
+
+        Reports { timings, stats }
+    }
+}
+
+/// What `TraceArgs::init` collects, printed to stderr when dropped: first
+/// the timings, then the stats.
+#[must_use = "reports are printed when dropped"]
+#[derive(Debug)]
+pub struct Reports {
+    pub timings: Timings,
+    pub stats: Stats,
+}
+
+/// Wall-clock durations of top-level spans, in the order they closed.
+///
+/// Spans with the same name are added together.
+#[must_use = "timings are reported when dropped"]
+#[derive(Debug, Clone, Default)]
+pub struct Timings {
+    stages: Arc<Mutex<Vec<(String, Duration)>>>,
+    report_on_drop: bool,
+}
+
+impl Timings {
+    fn new(report_on_drop: bool) -> Self {
+        Timings {
+            stages: Default::default(),
+            report_on_drop,
+        }
+    }
+
+    /// A layer recording into these timings
+    pub fn layer(&self) -> TimingLayer {
+        TimingLayer {
+            stages: Arc::clone(&self.stages),
+        }
+    }
+
+    pub fn stages(&self) -> Vec<(String, Duration)> {
+        self.stages.lock().unwrap().clone()
+    }
+
+    /// Formats the timings as `parse: 1.2ms, part1: 40ms, part2: 3.1s`
+    pub fn report(&self) -> String {
+        let mut s = String::new();
+        for (ix, (name, elapsed)) in self.stages().iter().enumerate() {
+            if ix > 0 {
+                s.push_str(", ");
+            }
+            write!(s, "{name}: {}", format_duration(*elapsed)).unwrap();
+        }
+        s
+    }
+}
+
+impl Drop for Timings {
+    fn drop(&mut self) {
+        let report = self.report();
+        if self.report_on_drop && !report.is_empty() {
+            eprintln!("{report}");
+        }
+    }
+}
+
+/// Records how long each top-level span was open.
+pub struct TimingLayer {
+    stages: Arc<Mutex<Vec<(String, Duration)>>>,
+}
+
+impl<S> Layer<S> for TimingLayer
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn on_new_span(&self, _attrs: &span::Attributes<'_>, id: &span::Id, ctx: Context<'_, S>) {
+        let Some(span) = ctx.span(id) else {
+            return;
+        };
+        if span.parent().is_none() {
+            span.extensions_mut().insert(Instant::now());
+        }
+    }
+
+    fn on_close(&self, id: span::Id, ctx: Context<'_, S>) {
+        let Some(span) = ctx.span(&id) else {
+            return;
+        };
+        let Some(&start) = span.extensions().get::<Instant>() else {
+            return;
+        };
+
+        let elapsed = start.elapsed();
+        let mut stages = self.stages.lock().unwrap();
+        match stages.iter_mut().find(|(name, _)| name == span.name()) {
+            Some((_, total)) => *total += elapsed,
+            None => stages.push((span.name().to_owned(), elapsed)),
+        }
+    }
+}
+
+/// Formats a duration with a unit suited to its size, and a decimal place
+/// for small values: `850ns`, `1.2ms`, `40ms`, `3.1s`.
+pub fn format_duration(d: Duration) -> String {
+    let nanos = d.as_nanos() as f64;
+    let (value, unit) = if nanos < 1e3 {
+        (nanos, "ns")
+    } else if nanos < 1e6 {
+        (nanos / 1e3, "µs")
+    } else if nanos < 1e9 {
+        (nanos / 1e6, "ms")
+    } else {
+        (nanos / 1e9, "s")
+    };
+
+    if value < 10.0 && unit != "ns" {
+        format!("{value:.1}{unit}")
+    } else {
+        format!("{value:.0}{unit}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tracing::info_span;
+
+    use super::*;
+
+    #[test]
+    fn test_format_duration() {
+        assert_eq!(format_duration(Duration::from_nanos(850)), "850ns");
+        assert_eq!(format_duration(Duration::from_micros(1200)), "1.2ms");
+        assert_eq!(format_duration(Duration::from_millis(40)), "40ms");
+        assert_eq!(format_duration(Duration::from_millis(3100)), "3.1s");
+        assert_eq!(format_duration(Duration::from_secs(125)), "125s");
+    }
+
+    #[test]
+    fn test_timings() {
+        let timings = Timings::new(false);
+        let subscriber = tracing_subscriber::registry().with(timings.layer());
+        tracing::subscriber::with_default(subscriber, || {
+            info_span!("parse").in_scope(|| {});
+            info_span!("part1").in_scope(|| {
+                // Nested spans are part of their parent's time
+                info_span!("inner").in_scope(|| {});
+            });
+            info_span!("part1").in_scope(|| {});
+        });
+
+        let names: Vec<String> = timings.stages().into_iter().map(|(n, _)| n).collect();
+        assert_eq!(names, vec!["parse", "part1"]);
+        assert!(timings.report().starts_with("parse: "));
+    }
+}
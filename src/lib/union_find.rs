@@ -0,0 +1,234 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+
+/// A disjoint-set forest over items of type `T`, for finding connected
+/// groups: items start out alone in their own set, and `union` joins sets.
+///
+/// Uses path compression and union by rank, so operations take nearly
+/// constant time.
+#[derive(Debug, Clone)]
+pub struct UnionFind<T> {
+    indices: HashMap<T, usize>,
+    items: Vec<T>,
+    parents: Vec<usize>,
+    ranks: Vec<u8>,
+    // Only kept up to date for roots
+    sizes: Vec<usize>,
+    sets: usize,
+}
+
+impl<T> Default for UnionFind<T> {
+    fn default() -> Self {
+        UnionFind {
+            indices: HashMap::new(),
+            items: Vec::new(),
+            parents: Vec::new(),
+            ranks: Vec::new(),
+            sizes: Vec::new(),
+            sets: 0,
+        }
+    }
+}
+
+impl<T: Hash + Eq + Clone> UnionFind<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Number of items, in any set
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    /// Number of disjoint sets
+    pub fn set_count(&self) -> usize {
+        self.sets
+    }
+
+    pub fn contains(&self, item: &T) -> bool {
+        self.indices.contains_key(item)
+    }
+
+    /// Adds `item` in a set of its own, if it isn't already present
+    pub fn insert(&mut self, item: T) {
+        self.index(item);
+    }
+
+    fn index(&mut self, item: T) -> usize {
+        if let Some(&ix) = self.indices.get(&item) {
+            return ix;
+        }
+        let ix = self.items.len();
+        self.indices.insert(item.clone(), ix);
+        self.items.push(item);
+        self.parents.push(ix);
+        self.ranks.push(0);
+        self.sizes.push(1);
+        self.sets += 1;
+        ix
+    }
+
+    fn root(&mut self, ix: usize) -> usize {
+        let mut root = ix;
+        while self.parents[root] != root {
+            root = self.parents[root];
+        }
+
+        // Point everything on the way directly at the root
+        let mut ix = ix;
+        while self.parents[ix] != root {
+            let next = self.parents[ix];
+            self.parents[ix] = root;
+            ix = next;
+        }
+        root
+    }
+
+    /// The representative item of `item`'s set, or `None` if `item` was never
+    /// added. Two items are in the same set exactly when they have the same
+    /// representative.
+    pub fn find(&mut self, item: &T) -> Option<&T> {
+        let &ix = self.indices.get(item)?;
+        let root = self.root(ix);
+        Some(&self.items[root])
+    }
+
+    /// Joins the sets containing `a` and `b`, adding either if missing.
+    /// Returns false if they were already in the same set.
+    pub fn union(&mut self, a: T, b: T) -> bool {
+        let a = self.index(a);
+        let b = self.index(b);
+        let (a, b) = (self.root(a), self.root(b));
+        if a == b {
+            return false;
+        }
+
+        // Hang the shallower tree under the deeper one
+        let (child, parent) = if self.ranks[a] < self.ranks[b] {
+            (a, b)
+        } else {
+            (b, a)
+        };
+        self.parents[child] = parent;
+        self.sizes[parent] += self.sizes[child];
+        if self.ranks[child] == self.ranks[parent] {
+            self.ranks[parent] += 1;
+        }
+        self.sets -= 1;
+        true
+    }
+
+    /// Whether `a` and `b` are both present and in the same set
+    pub fn connected(&mut self, a: &T, b: &T) -> bool {
+        match (self.indices.get(a), self.indices.get(b)) {
+            (Some(&a), Some(&b)) => self.root(a) == self.root(b),
+            _ => false,
+        }
+    }
+
+    /// Number of items in `item`'s set, or 0 if `item` was never added
+    pub fn set_size(&mut self, item: &T) -> usize {
+        match self.indices.get(item) {
+            Some(&ix) => {
+                let root = self.root(ix);
+                self.sizes[root]
+            }
+            None => 0,
+        }
+    }
+
+    /// The size of each set, ordered by their first item as in
+    /// [`UnionFind::sets`]
+    pub fn set_sizes(&mut self) -> Vec<usize> {
+        let mut seen = vec![false; self.items.len()];
+        let mut sizes = Vec::with_capacity(self.sets);
+        for ix in 0..self.items.len() {
+            let root = self.root(ix);
+            if !seen[root] {
+                seen[root] = true;
+                sizes.push(self.sizes[root]);
+            }
+        }
+        sizes
+    }
+
+    /// All the sets, each in insertion order, ordered by their first item
+    pub fn sets(&mut self) -> Vec<Vec<T>> {
+        let mut set_ixs: HashMap<usize, usize> = HashMap::new();
+        let mut sets: Vec<Vec<T>> = Vec::with_capacity(self.sets);
+        for ix in 0..self.items.len() {
+            let root = self.root(ix);
+            let set_ix = *set_ixs.entry(root).or_insert_with(|| {
+                sets.push(Vec::new());
+                sets.len() - 1
+            });
+            sets[set_ix].push(self.items[ix].clone());
+        }
+        sets
+    }
+}
+
+impl<T: Hash + Eq + Clone> FromIterator<T> for UnionFind<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut uf = UnionFind::new();
+        for item in iter {
+            uf.insert(item);
+        }
+        uf
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// Tests
+
+#[cfg(test)]
+mod tests {
+    use test_log::test;
+
+    #[allow(unused_imports)]
+    use super::*;
+
+    #[test]
+    fn test_union_find() {
+        let mut uf: UnionFind<&str> = ["a", "b", "c", "d", "e"].into_iter().collect();
+        assert_eq!((uf.len(), uf.set_count()), (5, 5));
+        assert_eq!(uf.find(&"c"), Some(&"c"));
+        assert_eq!(uf.find(&"z"), None);
+
+        assert!(uf.union("a", "b"));
+        assert!(uf.union("c", "d"));
+        assert!(uf.union("b", "d"));
+        assert!(!uf.union("a", "c"));
+        assert_eq!(uf.set_count(), 2);
+
+        assert!(uf.connected(&"a", &"d"));
+        assert!(!uf.connected(&"a", &"e"));
+        assert!(!uf.connected(&"a", &"z"));
+        let root = uf.find(&"a").copied();
+        assert_eq!(uf.find(&"d").copied(), root);
+        assert_eq!(uf.set_size(&"c"), 4);
+        assert_eq!(uf.set_size(&"e"), 1);
+        assert_eq!(uf.set_size(&"z"), 0);
+
+        // Union adds missing items
+        assert!(uf.union("e", "f"));
+        assert_eq!((uf.len(), uf.set_count()), (6, 2));
+        assert_eq!(uf.sets(), vec![vec!["a", "b", "c", "d"], vec!["e", "f"]]);
+        assert_eq!(uf.set_sizes(), vec![4, 2]);
+    }
+
+    #[test]
+    fn test_long_chain() {
+        let mut uf = UnionFind::new();
+        for n in 0..10_000 {
+            uf.union(n, n + 1);
+        }
+        assert_eq!(uf.set_count(), 1);
+        assert_eq!(uf.set_size(&0), 10_001);
+        assert!(uf.connected(&0, &10_000));
+    }
+}